@@ -0,0 +1,32 @@
+// This worker runs KeyVault SPHINCS+ signing off the main thread.
+// fips205 produces a signature in one synchronous call without exposing its WOTS+/FORS/hypertree phases,
+// so signing can't yield to the browser midway. The slow "s" variants can take seconds, which is why
+// signing belongs in a worker: IndexedDB and wasm are both available here, so `KeyVault.sign` runs unchanged.
+//
+// Request (structured clone):  { requestId, variant, password: Uint8Array, lockArgs: string, message: Uint8Array }
+// Response:                    { requestId, signature: Uint8Array } or { requestId, error: { message, code } },
+//                              `code` being the KeyVaultError code, e.g. "WRONG_PASSWORD", when there is one
+// Transfer `password.buffer` when posting so no copy of the password stays on the main thread.
+import __wbg_init, { KeyVault } from "./pkg/key_vault";
+
+const wasmReady = __wbg_init();
+
+self.addEventListener("message", async (event) => {
+  const { requestId, variant, password, lockArgs, message } = event.data;
+  try {
+    await wasmReady;
+    const keyVault = new KeyVault(variant);
+    try {
+      const signature = await keyVault.sign(password, lockArgs, message);
+      self.postMessage({ requestId, signature }, [signature.buffer]);
+    } finally {
+      keyVault.free();
+    }
+  } catch (error) {
+    // Error objects don't survive structured cloning with their `code`, so only plain fields are posted
+    const message = error instanceof Error ? error.message : String(error);
+    self.postMessage({ requestId, error: { message, code: error?.code } });
+  } finally {
+    password.fill(0);
+  }
+});
@@ -0,0 +1,165 @@
+//! Decoding of CKB addresses (RFC 0021 full format, bech32m encoded), so addresses pasted by users can be
+//! checked against stored accounts.
+
+use super::constants::{CKB_MAINNET_HRP, CKB_TESTNET_HRP};
+use super::types::CkbAddress;
+use hex::encode;
+#[cfg(test)]
+mod tests;
+
+/// The bech32 data part alphabet.
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+/// The constant a bech32m checksum must verify to (BIP-350).
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+/// Payload format byte of a full format address.
+const FULL_FORMAT: u8 = 0x00;
+/// Maximum length of an encoded address. CKB lifts the 90 character limit of BIP-173 to fit long lock args.
+const MAX_ADDRESS_LENGTH: usize = 1023;
+
+fn polymod(values: &[u8]) -> u32 {
+    const GEN: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk: u32 = 1;
+    for value in values {
+        let top = chk >> 25;
+        chk = (chk & 0x01ff_ffff) << 5 ^ *value as u32;
+        for (i, gen) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= gen;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut expanded: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    expanded.push(0);
+    expanded.extend(hrp.bytes().map(|b| b & 0x1f));
+    expanded
+}
+
+/// Regroups 5-bit values into bytes, rejecting non-zero or over-long padding.
+fn from_5bit(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let mut bytes = Vec::with_capacity(data.len() * 5 / 8);
+    for value in data {
+        acc = (acc << 5) | *value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || (acc << (8 - bits)) as u8 != 0 {
+        return Err("Invalid address: bad padding".to_string());
+    }
+    Ok(bytes)
+}
+
+/// Decodes a bech32m string into its human-readable part and payload bytes.
+fn decode_bech32m(address: &str) -> Result<(String, Vec<u8>), String> {
+    if address.len() > MAX_ADDRESS_LENGTH {
+        return Err("Invalid address: too long".to_string());
+    }
+    if address.bytes().any(|b| b.is_ascii_lowercase())
+        && address.bytes().any(|b| b.is_ascii_uppercase())
+    {
+        return Err("Invalid address: mixed case".to_string());
+    }
+    let address = address.to_ascii_lowercase();
+    let separator = address
+        .rfind('1')
+        .ok_or_else(|| "Invalid address: missing separator".to_string())?;
+    let (hrp, data) = (&address[..separator], &address[separator + 1..]);
+    if hrp.is_empty() || data.len() < 6 || hrp.bytes().any(|b| !(33..=126).contains(&b)) {
+        return Err("Invalid address: malformed".to_string());
+    }
+    let values = data
+        .bytes()
+        .map(|b| {
+            CHARSET
+                .iter()
+                .position(|c| *c == b)
+                .map(|p| p as u8)
+                .ok_or_else(|| format!("Invalid address: unexpected character '{}'", b as char))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    let mut checked = hrp_expand(hrp);
+    checked.extend(&values);
+    if polymod(&checked) != BECH32M_CONST {
+        return Err("Invalid address: checksum mismatch".to_string());
+    }
+    let payload = from_5bit(&values[..values.len() - 6])?;
+    Ok((hrp.to_string(), payload))
+}
+
+/// Name of a script hash type byte, as used in CKB JSON.
+fn hash_type_name(hash_type: u8) -> Result<&'static str, String> {
+    match hash_type {
+        0x00 => Ok("data"),
+        0x01 => Ok("type"),
+        0x02 => Ok("data1"),
+        0x04 => Ok("data2"),
+        other => Err(format!("Invalid address: unknown hash type {}", other)),
+    }
+}
+
+/// Decodes a full format CKB address into its network and lock script.
+///
+/// **Parameters**:
+/// - `address: &str` - The address, e.g. `ckb1qz...` on mainnet or `ckt1qz...` on testnet.
+/// - `expected_network: Option<&str>` - `"mainnet"` or `"testnet"` to refuse addresses of the other network.
+///
+/// **Returns**:
+/// - `Result<CkbAddress, String>` - The decoded address on success, or an error message if it is malformed,
+///   of a deprecated format or of another network.
+pub fn decode_ckb_address(
+    address: &str,
+    expected_network: Option<&str>,
+) -> Result<CkbAddress, String> {
+    let (hrp, payload) = decode_bech32m(address.trim())?;
+    let network = match hrp.as_str() {
+        CKB_MAINNET_HRP => "mainnet",
+        CKB_TESTNET_HRP => "testnet",
+        other => {
+            return Err(format!(
+                "Invalid address: unknown network prefix '{}'",
+                other
+            ))
+        }
+    };
+    if let Some(expected) = expected_network {
+        if expected != network {
+            return Err(format!(
+                "Wrong network: the address is a {} address, expected {}",
+                network, expected
+            ));
+        }
+    }
+    match payload.first() {
+        Some(&FULL_FORMAT) => {}
+        Some(format) => {
+            return Err(format!(
+                "Unsupported address format 0x{:02x}: only full format addresses are supported",
+                format
+            ))
+        }
+        None => return Err("Invalid address: empty payload".to_string()),
+    }
+    if payload.len() < 34 {
+        return Err("Invalid address: payload too short for a lock script".to_string());
+    }
+    Ok(CkbAddress {
+        network: network.to_string(),
+        code_hash: format!("0x{}", encode(&payload[1..33])),
+        hash_type: hash_type_name(payload[33])?.to_string(),
+        args: format!("0x{}", encode(&payload[34..])),
+    })
+}
@@ -0,0 +1,93 @@
+use super::*;
+
+const SECP256K1_CODE_HASH: &str = "0x9bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce8";
+
+fn to_5bit(data: &[u8]) -> Vec<u8> {
+  let mut acc: u32 = 0;
+  let mut bits: u32 = 0;
+  let mut values = Vec::new();
+  for byte in data {
+    acc = (acc << 8) | *byte as u32;
+    bits += 8;
+    while bits >= 5 {
+      bits -= 5;
+      values.push(((acc >> bits) & 0x1f) as u8);
+    }
+  }
+  if bits > 0 {
+    values.push(((acc << (5 - bits)) & 0x1f) as u8);
+  }
+  values
+}
+
+fn encode_bech32m(hrp: &str, payload: &[u8]) -> String {
+  let values = to_5bit(payload);
+  let mut checked = hrp_expand(hrp);
+  checked.extend(&values);
+  checked.extend([0u8; 6]);
+  let checksum = polymod(&checked) ^ BECH32M_CONST;
+  let mut address = format!("{}1", hrp);
+  for value in values.iter().copied().chain((0..6).map(|i| ((checksum >> (5 * (5 - i))) & 0x1f) as u8)) {
+    address.push(CHARSET[value as usize] as char);
+  }
+  address
+}
+
+fn full_address(hrp: &str, code_hash: &str, hash_type: u8, args: &[u8]) -> String {
+  let mut payload = vec![FULL_FORMAT];
+  payload.extend(hex::decode(code_hash.trim_start_matches("0x")).unwrap());
+  payload.push(hash_type);
+  payload.extend(args);
+  encode_bech32m(hrp, &payload)
+}
+
+#[test]
+fn test_decode_mainnet_rfc_vector() {
+  // Full format secp256k1 address of RFC 0021
+  let address = "ckb1qzda0cr08m85hc8jlnfp3zer7xulejywt49kt2rr0vthywaa50xwsqdnnw7qkdnnclfkg59uzn8umtfd2kwxceqxwquc4";
+  let decoded = decode_ckb_address(address, None).unwrap();
+  assert_eq!(
+    decoded,
+    CkbAddress {
+      network: "mainnet".to_string(),
+      code_hash: SECP256K1_CODE_HASH.to_string(),
+      hash_type: "type".to_string(),
+      args: "0xb39bbc0b3673c7d36450bc14cfcdad2d559c6c64".to_string(),
+    }
+  );
+  assert!(decode_ckb_address(&address.to_ascii_uppercase(), Some("mainnet")).is_ok());
+}
+
+#[test]
+fn test_decode_testnet_lock_address() {
+  let args = [0xabu8; 32];
+  let address = full_address(CKB_TESTNET_HRP, crate::constants::LOCK_SCRIPT_CODE_HASH, 0x02, &args);
+  let decoded = decode_ckb_address(&address, Some("testnet")).unwrap();
+  assert_eq!(decoded.network, "testnet");
+  assert_eq!(decoded.code_hash, crate::constants::LOCK_SCRIPT_CODE_HASH);
+  assert_eq!(decoded.hash_type, crate::constants::LOCK_SCRIPT_HASH_TYPE);
+  assert_eq!(decoded.args, format!("0x{}", encode(args)));
+}
+
+#[test]
+fn test_decode_rejects_wrong_network() {
+  let address = full_address(CKB_TESTNET_HRP, SECP256K1_CODE_HASH, 0x01, &[1u8; 20]);
+  let err = decode_ckb_address(&address, Some("mainnet")).unwrap_err();
+  assert!(err.starts_with("Wrong network"));
+  let foreign = full_address("bc", SECP256K1_CODE_HASH, 0x01, &[1u8; 20]);
+  assert!(decode_ckb_address(&foreign, None).unwrap_err().contains("unknown network prefix"));
+}
+
+#[test]
+fn test_decode_rejects_malformed() {
+  let address = "ckb1qzda0cr08m85hc8jlnfp3zer7xulejywt49kt2rr0vthywaa50xwsqdnnw7qkdnnclfkg59uzn8umtfd2kwxceqxwquc4";
+  // A single changed character breaks the checksum
+  let tampered = address.replacen("qzda0", "qzda2", 1);
+  assert!(decode_ckb_address(&tampered, None).unwrap_err().contains("checksum"));
+  assert!(decode_ckb_address("ckb1", None).is_err());
+  assert!(decode_ckb_address("not an address", None).is_err());
+  assert!(decode_ckb_address("ckb1qzda0cr08m85hc8jlnfp3zer7xulejywt49kt2rr0vthywaa50xwsqdnnw7qkdnnclfkg59uzn8umtfd2kwxceqxwqub4", None).is_err());
+  // Deprecated short format payload
+  let short = encode_bech32m(CKB_MAINNET_HRP, &[0x01, 0x00, 1, 2, 3]);
+  assert!(decode_ckb_address(&short, None).unwrap_err().starts_with("Unsupported address format 0x01"));
+}
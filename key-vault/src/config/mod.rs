@@ -0,0 +1,205 @@
+//! Runtime-configurable settings of the key vault, adjusted from JavaScript through `Util::set_*`.
+//! Every setting starts at its documented default from `constants`.
+
+use super::constants::{
+    DEFAULT_DB_TIMEOUT_MS, DEFAULT_DESCRIPTOR_BATCH_SIZE, DEFAULT_LOCKOUT_COOLDOWN_MS,
+    DEFAULT_MAX_ACCOUNTS, DEFAULT_MAX_FAILED_ATTEMPTS, ENC_SCRYPT, MAX_ENC_SCRYPT_LOG_N,
+    MAX_ENC_SCRYPT_MEMORY, MAX_ENC_SCRYPT_P, MIN_ENC_SCRYPT_LOG_N,
+};
+use super::types::ScryptParam;
+use scrypt::Params;
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+#[cfg(test)]
+mod tests;
+
+static MAX_ACCOUNTS: AtomicU32 = AtomicU32::new(DEFAULT_MAX_ACCOUNTS);
+static DB_TIMEOUT_MS: AtomicU32 = AtomicU32::new(DEFAULT_DB_TIMEOUT_MS);
+static DESCRIPTOR_BATCH_SIZE: AtomicU32 = AtomicU32::new(DEFAULT_DESCRIPTOR_BATCH_SIZE);
+static MAX_FAILED_ATTEMPTS: AtomicU32 = AtomicU32::new(DEFAULT_MAX_FAILED_ATTEMPTS);
+static LOCKOUT_COOLDOWN_MS: AtomicU32 = AtomicU32::new(DEFAULT_LOCKOUT_COOLDOWN_MS);
+static ENC_SCRYPT_LOG_N: AtomicU8 = AtomicU8::new(ENC_SCRYPT.log_n);
+static ENC_SCRYPT_R: AtomicU32 = AtomicU32::new(ENC_SCRYPT.r);
+static ENC_SCRYPT_P: AtomicU32 = AtomicU32::new(ENC_SCRYPT.p);
+
+/// The maximum number of accounts a wallet may hold or derive.
+pub fn max_accounts() -> u32 {
+    MAX_ACCOUNTS.load(Ordering::Relaxed)
+}
+
+/// Sets the maximum number of accounts a wallet may hold or derive.
+pub fn set_max_accounts(max: u32) {
+    MAX_ACCOUNTS.store(max, Ordering::Relaxed);
+}
+
+/// How long, in milliseconds, an IndexedDB operation may take before it fails with `StorageTimeout`.
+pub fn db_timeout_ms() -> u32 {
+    DB_TIMEOUT_MS.load(Ordering::Relaxed)
+}
+
+/// Sets how long, in milliseconds, an IndexedDB operation may take before it fails with `StorageTimeout`.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok on success, or an error message for 0, which would fail every operation. Nothing
+///   changes then.
+pub fn set_db_timeout_ms(timeout_ms: u32) -> Result<(), String> {
+    if timeout_ms == 0 {
+        return Err("The database timeout must be at least 1 ms".to_string());
+    }
+    DB_TIMEOUT_MS.store(timeout_ms, Ordering::Relaxed);
+    Ok(())
+}
+
+/// How many addresses an account descriptor covers, see `KeyVault::account_xpub_equivalent`.
+pub fn descriptor_batch_size() -> u32 {
+    DESCRIPTOR_BATCH_SIZE.load(Ordering::Relaxed)
+}
+
+/// Sets how many addresses an account descriptor covers.
+pub fn set_descriptor_batch_size(size: u32) {
+    DESCRIPTOR_BATCH_SIZE.store(size, Ordering::Relaxed);
+}
+
+/// How many wrong passwords in a row lock unlocking, see `utilities::check_unlock_allowed`. 0 disables the lockout.
+pub fn max_failed_attempts() -> u32 {
+    MAX_FAILED_ATTEMPTS.load(Ordering::Relaxed)
+}
+
+/// Sets how many wrong passwords in a row lock unlocking. 0 disables the lockout.
+pub fn set_max_failed_attempts(max: u32) {
+    MAX_FAILED_ATTEMPTS.store(max, Ordering::Relaxed);
+}
+
+/// How long, in milliseconds, unlocking stays locked after the last wrong password. 0 keeps it locked until the
+/// failed attempts are cleared.
+pub fn lockout_cooldown_ms() -> u32 {
+    LOCKOUT_COOLDOWN_MS.load(Ordering::Relaxed)
+}
+
+/// Sets how long, in milliseconds, unlocking stays locked after the last wrong password.
+pub fn set_lockout_cooldown_ms(cooldown_ms: u32) {
+    LOCKOUT_COOLDOWN_MS.store(cooldown_ms, Ordering::Relaxed);
+}
+
+/// The Scrypt parameters new encryptions derive their key with. Each payload stores its own, so changing them
+/// doesn't affect decryption of existing data.
+pub fn enc_scrypt() -> ScryptParam {
+    ScryptParam {
+        log_n: ENC_SCRYPT_LOG_N.load(Ordering::Relaxed),
+        r: ENC_SCRYPT_R.load(Ordering::Relaxed),
+        p: ENC_SCRYPT_P.load(Ordering::Relaxed),
+        len: ENC_SCRYPT.len,
+    }
+}
+
+/// Sets the Scrypt parameters new encryptions derive their key with.
+///
+/// **Parameters**:
+/// - `log_n: u8` - The log2 of the Scrypt cost N.
+/// - `r: u32` - The Scrypt block size.
+/// - `p: u32` - The Scrypt parallelism.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok on success, or an error message if the cost is below `MIN_ENC_SCRYPT_LOG_N`, above
+///   the bounds of `check_scrypt_cost` or rejected by Scrypt. Nothing changes then.
+pub fn set_enc_scrypt(log_n: u8, r: u32, p: u32) -> Result<(), String> {
+    if log_n < MIN_ENC_SCRYPT_LOG_N {
+        return Err(format!(
+            "Scrypt log_n must be at least {}, got {}",
+            MIN_ENC_SCRYPT_LOG_N, log_n
+        ));
+    }
+    check_scrypt_cost(log_n, r, p)?;
+    Params::new(log_n, r, p, ENC_SCRYPT.len)
+        .map_err(|e| format!("Invalid scrypt parameters: {:?}", e))?;
+    ENC_SCRYPT_LOG_N.store(log_n, Ordering::Relaxed);
+    ENC_SCRYPT_R.store(r, Ordering::Relaxed);
+    ENC_SCRYPT_P.store(p, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Checks Scrypt parameters against the highest cost the vault will run, so a crafted payload can't make decryption
+/// allocate gigabytes or spin for minutes.
+///
+/// **Parameters**:
+/// - `log_n: u8` - The log2 of the Scrypt cost N.
+/// - `r: u32` - The Scrypt block size.
+/// - `p: u32` - The Scrypt parallelism.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if within `MAX_ENC_SCRYPT_LOG_N`, `MAX_ENC_SCRYPT_P` and `MAX_ENC_SCRYPT_MEMORY`,
+///   or an error message otherwise.
+pub fn check_scrypt_cost(log_n: u8, r: u32, p: u32) -> Result<(), String> {
+    if log_n > MAX_ENC_SCRYPT_LOG_N {
+        return Err(format!(
+            "Scrypt log_n must be at most {}, got {}",
+            MAX_ENC_SCRYPT_LOG_N, log_n
+        ));
+    }
+    if p > MAX_ENC_SCRYPT_P {
+        return Err(format!(
+            "Scrypt p must be at most {}, got {}",
+            MAX_ENC_SCRYPT_P, p
+        ));
+    }
+    let memory = 128 * r as u64 * (1u64 << log_n);
+    if memory > MAX_ENC_SCRYPT_MEMORY {
+        return Err(format!(
+            "Scrypt parameters need {} bytes of memory, the maximum is {}",
+            memory, MAX_ENC_SCRYPT_MEMORY
+        ));
+    }
+    Ok(())
+}
+
+/// Restores every setting of this module to its default from `constants`.
+pub fn reset_defaults() {
+    set_max_accounts(DEFAULT_MAX_ACCOUNTS);
+    set_db_timeout_ms(DEFAULT_DB_TIMEOUT_MS).expect("Default database timeout is valid");
+    set_descriptor_batch_size(DEFAULT_DESCRIPTOR_BATCH_SIZE);
+    set_max_failed_attempts(DEFAULT_MAX_FAILED_ATTEMPTS);
+    set_lockout_cooldown_ms(DEFAULT_LOCKOUT_COOLDOWN_MS);
+    set_enc_scrypt(ENC_SCRYPT.log_n, ENC_SCRYPT.r, ENC_SCRYPT.p)
+        .expect("Default scrypt parameters are valid");
+}
+
+/// Computes the account index range `[start, end)` covered by the descriptor of `account`.
+///
+/// **Parameters**:
+/// - `account: u32` - The descriptor account number.
+/// - `batch_size: u32` - The number of addresses per account.
+///
+/// **Returns**:
+/// - `Result<(u32, u32), String>` - The index range on success, or an error message if the batch size is 0
+///   or the range does not fit in u32 indices.
+pub fn descriptor_index_range(account: u32, batch_size: u32) -> Result<(u32, u32), String> {
+    if batch_size == 0 {
+        return Err("Descriptor batch size must be at least 1".to_string());
+    }
+    let start = account as u64 * batch_size as u64;
+    let end = start + batch_size as u64;
+    if end > u32::MAX as u64 {
+        return Err(format!(
+            "Account {} is out of the derivation index range",
+            account
+        ));
+    }
+    Ok((start as u32, end as u32))
+}
+
+/// Checks that an operation touching account indices below `end` stays within `max` accounts.
+///
+/// **Parameters**:
+/// - `end: u64` - One past the highest account index the operation touches.
+/// - `max: u32` - The maximum number of accounts.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if within the limit, or an error message otherwise.
+pub fn check_account_limit(end: u64, max: u32) -> Result<(), String> {
+    if end > max as u64 {
+        return Err(format!(
+            "Account limit exceeded: the operation reaches {} accounts but the maximum is {}",
+            end, max
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use super::*;
+
+#[test]
+fn test_account_limit_enforced() {
+  assert!(check_account_limit(10_000, 10_000).is_ok());
+  let err = check_account_limit(10_001, 10_000).unwrap_err();
+  assert!(err.contains("Account limit exceeded"));
+  assert!(err.contains("10000"));
+}
+
+#[test]
+fn test_account_limit_does_not_overflow() {
+  let end = u32::MAX as u64 + u32::MAX as u64;
+  assert!(check_account_limit(end, DEFAULT_MAX_ACCOUNTS).is_err());
+}
+
+#[test]
+fn test_descriptor_index_range() {
+  assert_eq!(descriptor_index_range(0, 20), Ok((0, 20)));
+  assert_eq!(descriptor_index_range(3, 20), Ok((60, 80)));
+  assert!(descriptor_index_range(0, 0).is_err());
+  assert!(descriptor_index_range(u32::MAX, 2).unwrap_err().contains("out of the derivation index range"));
+}
+
+#[test]
+fn test_reset_defaults_restores_every_setting() {
+  set_max_accounts(5);
+  set_db_timeout_ms(1).unwrap();
+  set_descriptor_batch_size(7);
+  set_max_failed_attempts(3);
+  set_lockout_cooldown_ms(0);
+  set_enc_scrypt(10, 4, 2).unwrap();
+  reset_defaults();
+  assert_eq!(max_accounts(), DEFAULT_MAX_ACCOUNTS);
+  assert_eq!(db_timeout_ms(), DEFAULT_DB_TIMEOUT_MS);
+  assert_eq!(descriptor_batch_size(), DEFAULT_DESCRIPTOR_BATCH_SIZE);
+  assert_eq!(max_failed_attempts(), DEFAULT_MAX_FAILED_ATTEMPTS);
+  assert_eq!(lockout_cooldown_ms(), DEFAULT_LOCKOUT_COOLDOWN_MS);
+  assert_eq!(enc_scrypt(), ENC_SCRYPT);
+}
+
+#[test]
+fn test_set_enc_scrypt_rejects_invalid_params() {
+  let before = enc_scrypt();
+  assert!(set_enc_scrypt(0, 8, 1).is_err());
+  assert!(set_enc_scrypt(14, 0, 1).is_err());
+  assert!(set_enc_scrypt(MIN_ENC_SCRYPT_LOG_N - 1, 8, 1).unwrap_err().contains("at least"));
+  assert!(set_enc_scrypt(MAX_ENC_SCRYPT_LOG_N + 1, 1, 1).is_err());
+  assert_eq!(enc_scrypt(), before);
+}
+
+#[test]
+fn test_scrypt_cost_is_bounded() {
+  assert!(check_scrypt_cost(ENC_SCRYPT.log_n, ENC_SCRYPT.r, ENC_SCRYPT.p).is_ok());
+  assert!(check_scrypt_cost(MAX_ENC_SCRYPT_LOG_N + 1, 1, 1).unwrap_err().contains("log_n"));
+  assert!(check_scrypt_cost(14, 8, MAX_ENC_SCRYPT_P + 1).unwrap_err().contains("Scrypt p"));
+  // 128 * 64 * 2^20 bytes is 8 GiB
+  assert!(check_scrypt_cost(20, 64, 1).unwrap_err().contains("memory"));
+  assert!(check_scrypt_cost(20, u32::MAX, 1).is_err());
+}
+
+#[test]
+fn test_zero_db_timeout_is_rejected() {
+  let before = db_timeout_ms();
+  assert!(set_db_timeout_ms(0).unwrap_err().contains("at least 1 ms"));
+  assert_eq!(db_timeout_ms(), before);
+}
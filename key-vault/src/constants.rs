@@ -3,11 +3,51 @@ use super::types::ScryptParam;
 // Constants
 pub const SALT_LENGTH: usize = 16; // 128-bit salt
 pub const IV_LENGTH: usize = 12; // 96-bit IV for AES-GCM
+pub const AES_GCM_TAG_LENGTH: usize = 16; // 128-bit authentication tag appended to every ciphertext
+pub const CIPHER_PAYLOAD_VERSION: u8 = 2; // 0: legacy, no associated data. 1: bound to its DB record. 2: as 1, raw bytes
 pub const DB_NAME: &str = "quantum_purse";
+pub const DB_VERSION: u8 = 4; // 3: CHILD_KEYS_STORE gained the ACCOUNT_INDEX_NAME index, 4: the ACCOUNT_ID_INDEX_NAME one
 pub const SEED_PHRASE_KEY: &str = "seed_phrase";
 pub const SEED_PHRASE_STORE: &str = "seed_phrase_store";
+pub const SEALED_KEY: &str = "vault_sealed"; // Record in SEED_PHRASE_STORE, present while the vault is sealed
+pub const NEXT_INDEX_KEY: &str = "next_account_index"; // Record in SEED_PHRASE_STORE, the next unused derivation index
+pub const VERIFIER_KEY: &str = "password_verifier"; // Record in SEED_PHRASE_STORE, VERIFIER_PLAINTEXT encrypted with the password
+pub const VARIANT_KEY: &str = "sphincs_variant"; // Record in SEED_PHRASE_STORE, the SphincsVariant id the seed was set up with
+pub const SCHEME_KEY: &str = "derivation_single_scrypt"; // Record in SEED_PHRASE_STORE, whether new keys derive with one Scrypt run
+pub const VERIFIER_PLAINTEXT: &[u8] = b"quantum-purse password verifier";
 pub const CHILD_KEYS_STORE: &str = "child_keys_store";
+pub const ACCOUNT_INDEX_NAME: &str = "by_index"; // IndexedDB index of CHILD_KEYS_STORE over the account `index` field
+pub const ACCOUNT_ID_INDEX_NAME: &str = "by_account_id"; // Unique IndexedDB index of CHILD_KEYS_STORE over `account_id`
+pub const METADATA_STORE: &str = "encrypted_metadata_store";
+pub const FAILED_ATTEMPTS_KEY: &str = "failed_unlock_attempts"; // Record in METADATA_STORE, see `FailedAttempts`
+pub const MAX_METADATA_KEY_LENGTH: usize = 256; // bytes
+pub const MAX_METADATA_VALUE_LENGTH: usize = 64 * 1024; // bytes
+pub const MAX_ACCOUNT_LABEL_LENGTH: usize = 64; // characters
+pub const BACKUP_KEY: &str = "wallet_backup";
+pub const BLOB_KEY: &str = "app_blob"; // Associated data label of `Util::encrypt_blob` payloads
+pub const BACKUP_FORMAT_PLAIN: u8 = 0;
+pub const BACKUP_FORMAT_DEFLATE: u8 = 1;
+pub const MAX_BACKUP_INFLATED_SIZE: usize = 64 * 1024 * 1024; // bytes, far above a full wallet of 256f accounts
+pub const VAULT_MAGIC: [u8; 4] = *b"QPVT"; // Header of `export_vault` blobs
+pub const VAULT_FORMAT_VERSION: u8 = 1;
+pub const MAX_SIGNING_CONTEXT_LENGTH: usize = 255; // FIPS 205 limit on the context string of a signature
 pub const KDF_PATH_PREFIX: &str = "ckb/quantum-purse/sphincs-plus/";
+pub const SESSION_ID_LENGTH: usize = 32; // 256-bit unguessable session handle
+pub const DEFAULT_MAX_ACCOUNTS: u32 = 10_000; // Guards against runaway derivation loops
+pub const DEFAULT_DB_TIMEOUT_MS: u32 = 10_000; // Longest an IndexedDB operation may take before it is abandoned
+pub const LOCK_ARGS_PAGE_SIZE: u32 = 100; // Account records `get_all_sphincs_lock_args` deserializes at a time
+pub const DEFAULT_MAX_FAILED_ATTEMPTS: u32 = 10; // Wrong passwords in a row before unlocking is refused
+pub const DEFAULT_LOCKOUT_COOLDOWN_MS: u32 = 5 * 60 * 1000; // How long unlocking stays refused after the last failure
+pub const DEFAULT_DESCRIPTOR_BATCH_SIZE: u32 = 20; // Addresses per account descriptor, matching the usual BIP44 gap limit
+pub const DEFAULT_DERIVATION_LOG_N: u8 = 10; // Scrypt cost of the key derivation KDF, see `sphincs_keygen!`
+pub const KDF_CACHE_CAPACITY: usize = 32; // Scrypt-derived decryption keys kept in memory, see `kdf_cache`
+pub const MIN_DERIVATION_LOG_N: u8 = 4; // Lowest derivation cost accepted for slow devices
+pub const MAX_DERIVATION_LOG_N: u8 = 20;
+pub const WATCH_ONLY_INDEX_BASE: u32 = 1 << 31; // Watch-only accounts are numbered from here, apart from derived ones
+pub const MIN_ENC_SCRYPT_LOG_N: u8 = 10; // Lowest encryption cost `Util::set_kdf_params` accepts
+pub const MAX_ENC_SCRYPT_LOG_N: u8 = 20; // Highest encryption cost a payload may ask decryption for
+pub const MAX_ENC_SCRYPT_P: u32 = 16;
+pub const MAX_ENC_SCRYPT_MEMORY: u64 = 256 * 1024 * 1024; // Scrypt needs 128 * r * N bytes, keep within a browser tab
 
 /// Scrypt’s original paper suggests N = 16384 (log_n = 14) for interactive logins, but that’s for low-entropy passwords.
 /// QuantumPurse uses 256 bit high-entropy passwords together with the following scrypt param to protect data in DB.
@@ -25,3 +65,12 @@ pub const MULTISIG_RESERVED_FIELD_VALUE: u8 = 0x80;
 pub const REQUIRED_FIRST_N: u8 = 0x00;
 pub const THRESHOLD: u8 = 0x01;
 pub const PUBKEY_NUM: u8 = 0x01;
+/// The quantum resistant lock script the config above and the lock_args hashing are compatible with
+/// (cryptape/quantum-resistant-lock-script, commit bd5f76e8). Must match `SPHINCSPLUS_LOCK` of the web app.
+pub const LOCK_SCRIPT_CODE_HASH: &str =
+    "0x52ee8e71396abd2997f7f02697dd4c30c34d751ba7541db1817922b7add4a4a0";
+pub const LOCK_SCRIPT_HASH_TYPE: &str = "data1";
+
+// CKB address human-readable parts (RFC 0021)
+pub const CKB_MAINNET_HRP: &str = "ckb";
+pub const CKB_TESTNET_HRP: &str = "ckt";
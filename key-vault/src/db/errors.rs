@@ -6,6 +6,14 @@ use std::fmt;
 pub enum KeyVaultDBError {
     SerializationError(String),
     DatabaseError(String),
+    /// IndexedDB refused the write because the origin's storage quota is used up.
+    StorageQuotaExceeded(String),
+    /// The database was upgraded by a newer app version; this build refuses to touch it.
+    DatabaseVersionTooNew(String),
+    /// An IndexedDB operation didn't complete within `config::db_timeout_ms`.
+    StorageTimeout(String),
+    /// The vault was sealed with `KeyVault::seal`; every operation on wallet data is refused until `unseal`.
+    VaultSealed(String),
 }
 
 impl fmt::Display for KeyVaultDBError {
@@ -13,19 +21,36 @@ impl fmt::Display for KeyVaultDBError {
         match self {
             KeyVaultDBError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             KeyVaultDBError::DatabaseError(msg) => write!(f, "Database error: {}", msg),
+            KeyVaultDBError::StorageQuotaExceeded(msg) => {
+                write!(f, "Storage quota exceeded: {}", msg)
+            }
+            KeyVaultDBError::DatabaseVersionTooNew(msg) => {
+                write!(f, "Database version too new: {}", msg)
+            }
+            KeyVaultDBError::StorageTimeout(msg) => write!(f, "Storage timeout: {}", msg),
+            KeyVaultDBError::VaultSealed(msg) => write!(f, "Vault sealed: {}", msg),
         }
     }
 }
 
 impl KeyVaultDBError {
-    pub fn to_jsvalue(&self) -> wasm_bindgen::JsValue {
-        wasm_bindgen::JsValue::from_str(&self.to_string())
+    /// Maps an IndexedDB `DOMException` by its name, singling out `QuotaExceededError`.
+    pub fn from_dom_exception(name: &str, message: String) -> Self {
+        match name {
+            "QuotaExceededError" => KeyVaultDBError::StorageQuotaExceeded(message),
+            _ => KeyVaultDBError::DatabaseError(message),
+        }
     }
 }
 
 impl From<DBError> for KeyVaultDBError {
     fn from(e: DBError) -> Self {
-        KeyVaultDBError::DatabaseError(e.to_string())
+        match &e {
+            DBError::DomException(dom_err) => {
+                KeyVaultDBError::from_dom_exception(&dom_err.name(), e.to_string())
+            }
+            _ => KeyVaultDBError::DatabaseError(e.to_string()),
+        }
     }
 }
 
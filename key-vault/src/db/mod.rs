@@ -1,64 +1,361 @@
 mod errors;
+mod timeout;
 
-use super::types::{CipherPayload, SphincsPlusAccount};
-use crate::constants::{CHILD_KEYS_STORE, DB_NAME, SEED_PHRASE_KEY, SEED_PHRASE_STORE};
-use errors::KeyVaultDBError;
+use super::types::{
+    CipherPayload, FailedAttempts, SphincsPlusAccount, SphincsVariant, WalletBackup,
+};
+use crate::constants::{
+    ACCOUNT_ID_INDEX_NAME, ACCOUNT_INDEX_NAME, CHILD_KEYS_STORE, CIPHER_PAYLOAD_VERSION, DB_NAME, DB_VERSION,
+    FAILED_ATTEMPTS_KEY, METADATA_STORE, NEXT_INDEX_KEY, SCHEME_KEY, SEALED_KEY, SEED_PHRASE_KEY, SEED_PHRASE_STORE,
+    VARIANT_KEY, VERIFIER_KEY, WATCH_ONLY_INDEX_BASE,
+};
+use crate::utilities::{new_account_id, release_unlock_attempt, reserve_unlock_attempt};
+pub use errors::KeyVaultDBError;
 use indexed_db_futures::{
-    database::Database, error::Error as DBError, prelude::*, transaction::TransactionMode,
+    database::Database, error::Error as DBError, iter::ArrayMapIter, prelude::*,
+    transaction::TransactionMode,
 };
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+pub use timeout::yield_now;
+use timeout::with_timeout;
+use wasm_bindgen::JsValue;
+use web_sys::js_sys::Reflect;
+#[cfg(test)]
+mod tests;
 
 /// Opens the IndexedDB database, creating object stores if necessary.
 ///
 /// **Returns**:
-/// - `Result<Database, KeyVaultDBError>` - The opened database on success, or an error if the operation fails.
+/// - `Result<Database, KeyVaultDBError>` - The opened database on success, `DatabaseVersionTooNew` if a newer
+///   app version has upgraded the database, or an error if the operation fails.
 ///
 /// **Async**: Yes
 pub async fn open_db() -> Result<Database, KeyVaultDBError> {
-    Database::open(DB_NAME)
-        .with_version(1u8)
-        .with_on_blocked(|_event| Ok(()))
-        .with_on_upgrade_needed(|_event, db| {
-            if !db
-                .object_store_names()
-                .any(|name| name == SEED_PHRASE_STORE)
-            {
-                db.create_object_store(SEED_PHRASE_STORE).build()?;
+    with_timeout(async move {
+        // Set by the upgrade to version 4, after which the accounts stored without a local id get one
+        let backfill_ids = Rc::new(Cell::new(false));
+        let upgrade_backfill_ids = backfill_ids.clone();
+        let db = Database::open(DB_NAME)
+            .with_version(DB_VERSION)
+            .with_on_blocked(|_event| Ok(()))
+            .with_on_upgrade_needed(move |event, db| {
+                if !db
+                    .object_store_names()
+                    .any(|name| name == SEED_PHRASE_STORE)
+                {
+                    db.create_object_store(SEED_PHRASE_STORE).build()?;
+                }
+                if !db.object_store_names().any(|name| name == CHILD_KEYS_STORE) {
+                    db.create_object_store(CHILD_KEYS_STORE).build()?;
+                }
+                if !db.object_store_names().any(|name| name == METADATA_STORE) {
+                    db.create_object_store(METADATA_STORE).build()?;
+                }
+                // Version 3: index accounts by derivation index, for paged listing in index order
+                let store = event.transaction().object_store(CHILD_KEYS_STORE)?;
+                if !store.index_names().any(|name| name == ACCOUNT_INDEX_NAME) {
+                    store
+                        .create_index(ACCOUNT_INDEX_NAME, "index".into())
+                        .build()?;
+                }
+                // Version 4: look accounts up by local id without scanning, and keep ids unique. Accounts without
+                // an id aren't indexed, so the ones stored before get one once the database is open.
+                if !store.index_names().any(|name| name == ACCOUNT_ID_INDEX_NAME) {
+                    store
+                        .create_index(ACCOUNT_ID_INDEX_NAME, "account_id".into())
+                        .with_unique(true)
+                        .build()?;
+                    upgrade_backfill_ids.set(true);
+                }
+                Ok(())
+            })
+            .await;
+
+        match db {
+            Ok(db) => {
+                if backfill_ids.get() {
+                    backfill_account_ids(&db).await?;
+                }
+                Ok(db)
             }
-            if !db.object_store_names().any(|name| name == CHILD_KEYS_STORE) {
-                db.create_object_store(CHILD_KEYS_STORE).build()?;
+            Err(e) => {
+                // Opening fails when a newer build has upgraded the database past DB_VERSION. Probe the
+                // on-disk version to refuse with a clear error instead of a generic one.
+                if let Ok(current) = Database::open(DB_NAME).await {
+                    check_db_version(current.version(), DB_VERSION)?;
+                }
+                Err(KeyVaultDBError::DatabaseError(format!(
+                    "Failed to open IndexedDB: {}",
+                    e
+                )))
             }
-            Ok(())
-        })
-        .await
-        .map_err(|e| KeyVaultDBError::DatabaseError(format!("Failed to open IndexedDB: {}", e)))
+        }
+    })
+    .await
 }
 
-/// Stores the encrypted mnemonic phrase in the database.
+/// Gives every account record stored without a local id a random one, in one readwrite transaction, so all accounts
+/// are addressable through `get_account_by_id`. Records are patched as they are, so ones that don't deserialize
+/// (e.g. hex records awaiting `migrate_hex_accounts`) are completed too.
 ///
 /// **Parameters**:
-/// - `payload: CipherPayload` - The encrypted mnemonic phrase data to store.
+/// - `db: &Database` - The database instance to operate on.
 ///
 /// **Returns**:
-/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if storage fails.
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if the operation fails.
 ///
 /// **Async**: Yes
+async fn backfill_account_ids(db: &Database) -> Result<(), KeyVaultDBError> {
+    let tx = db
+        .transaction(CHILD_KEYS_STORE)
+        .with_mode(TransactionMode::Readwrite)
+        .build()?;
+    let store = tx.object_store(CHILD_KEYS_STORE)?;
+    let id_field = JsValue::from_str("account_id");
+    let iter: ArrayMapIter<JsValue> = store.get_all().await?;
+    for result in iter {
+        let record = result?;
+        let missing = Reflect::get(&record, &id_field)
+            .map(|id| id.is_undefined() || id.is_null())
+            .unwrap_or(false);
+        let lock_args = Reflect::get(&record, &JsValue::from_str("lock_args"))
+            .ok()
+            .and_then(|lock_args| lock_args.as_string());
+        let (true, Some(lock_args)) = (missing, lock_args) else {
+            continue;
+        };
+        let account_id = new_account_id().map_err(|e| {
+            KeyVaultDBError::DatabaseError(format!("Random generation error: {}", e))
+        })?;
+        // Setting a property on a deserialized record object can't fail
+        let _ = Reflect::set(&record, &id_field, &JsValue::from_str(&account_id));
+        store.put(&record).with_key(lock_args).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Opens the database for an operation on wallet data, refusing with `VaultSealed` while the vault is sealed.
 ///
-/// **Warning**: This method overwrites the existing mnemonic phrase in the database.
-pub async fn set_encrypted_mnemonic_seed(payload: CipherPayload) -> Result<(), KeyVaultDBError> {
+/// **Returns**:
+/// - `Result<Database, KeyVaultDBError>` - The opened database on success, `VaultSealed` if the vault is sealed,
+///   or an error if the operation fails.
+///
+/// **Async**: Yes
+async fn open_unsealed_db() -> Result<Database, KeyVaultDBError> {
     let db = open_db().await?;
+    check_not_sealed(read_sealed(&db).await?)?;
+    Ok(db)
+}
+
+/// Refuses with `VaultSealed` if `sealed` is set.
+pub fn check_not_sealed(sealed: bool) -> Result<(), KeyVaultDBError> {
+    if sealed {
+        return Err(KeyVaultDBError::VaultSealed(
+            "unseal it with the password to continue".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the persisted sealed flag; a missing record means unsealed.
+async fn read_sealed(db: &Database) -> Result<bool, KeyVaultDBError> {
     let tx = db
         .transaction(SEED_PHRASE_STORE)
-        .with_mode(TransactionMode::Readwrite)
+        .with_mode(TransactionMode::Readonly)
         .build()?;
     let store = tx.object_store(SEED_PHRASE_STORE)?;
+    let sealed: Option<JsValue> = store.get(SEALED_KEY).await?;
+    Ok(sealed.and_then(|value| value.as_bool()).unwrap_or(false))
+}
 
-    let js_value = serde_wasm_bindgen::to_value(&payload)?;
+/// Retrieves whether the vault is sealed.
+///
+/// **Returns**:
+/// - `Result<bool, KeyVaultDBError>` - Whether the vault is sealed, or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn is_sealed() -> Result<bool, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_db().await?;
+        read_sealed(&db).await
+    })
+    .await
+}
 
-    store.put(&js_value).with_key(SEED_PHRASE_KEY).await?;
-    tx.commit().await?;
+/// Persists the sealed flag, see `KeyVault::seal`.
+///
+/// **Parameters**:
+/// - `sealed: bool` - Whether the vault is sealed.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if storage fails.
+///
+/// **Async**: Yes
+pub async fn set_sealed(sealed: bool) -> Result<(), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_db().await?;
+        let tx = db
+            .transaction(SEED_PHRASE_STORE)
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(SEED_PHRASE_STORE)?;
+        if sealed {
+            store.put(&JsValue::TRUE).with_key(SEALED_KEY).await?;
+        } else {
+            store.delete(SEALED_KEY).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Checks that the on-disk database version is one this build understands. Older versions are fine,
+/// `open_db` upgrades them; a newer version means a newer app has changed the schema.
+///
+/// **Parameters**:
+/// - `on_disk: f64` - The version of the database on disk.
+/// - `supported: u8` - The newest version this build understands.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok if the version is supported, or `DatabaseVersionTooNew` otherwise.
+pub fn check_db_version(on_disk: f64, supported: u8) -> Result<(), KeyVaultDBError> {
+    if on_disk > supported as f64 {
+        return Err(KeyVaultDBError::DatabaseVersionTooNew(format!(
+            "found version {}, this build supports up to {}. Update the app before using this wallet",
+            on_disk, supported
+        )));
+    }
     Ok(())
 }
 
+/// Stores the encrypted mnemonic phrase and its password verifier in the database. The existence check and the
+/// writes run in one readwrite transaction; IndexedDB serializes overlapping readwrite transactions on the stores, so
+/// a concurrent init, import or account derivation can't slip in between them.
+///
+/// **Parameters**:
+/// - `payload: CipherPayload` - The encrypted mnemonic phrase data to store.
+/// - `verifier: CipherPayload` - The password verifier encrypted with the same password.
+/// - `variant: SphincsVariant` - The SPHINCS+ variant the wallet is set up with, see `get_stored_variant`.
+/// - `single_scrypt: bool` - The derivation scheme the wallet is set up with, see `get_stored_single_scrypt`.
+/// - `overwrite: bool` - Whether to replace an existing mnemonic phrase. Ignored while accounts are stored, as they
+///   would stay derived from the replaced phrase; clear the wallet first.
+///
+/// **Returns**:
+/// - `Result<bool, KeyVaultDBError>` - Whether the mnemonic phrase was written (false if one exists and either
+///   `overwrite` is false or accounts are stored), or an error if storage fails.
+///
+/// **Async**: Yes
+///
+/// **Warning**: With `overwrite`, this method overwrites the existing mnemonic phrase in the database.
+pub async fn set_encrypted_mnemonic_seed(
+    payload: CipherPayload,
+    verifier: CipherPayload,
+    variant: SphincsVariant,
+    single_scrypt: bool,
+    overwrite: bool,
+) -> Result<bool, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction([SEED_PHRASE_STORE, CHILD_KEYS_STORE])
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(SEED_PHRASE_STORE)?;
+        let accounts_store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let existing: Option<JsValue> = store.get(SEED_PHRASE_KEY).await?;
+        let account_count = accounts_store.count().await?;
+        if !should_restore_seed(existing.is_some(), account_count > 0, overwrite) {
+            return Ok(false);
+        }
+
+        let js_value = serde_wasm_bindgen::to_value(&payload)?;
+        store.put(&js_value).with_key(SEED_PHRASE_KEY).await?;
+        let js_value = serde_wasm_bindgen::to_value(&verifier)?;
+        store.put(&js_value).with_key(VERIFIER_KEY).await?;
+        store
+            .put(&JsValue::from(variant as u8))
+            .with_key(VARIANT_KEY)
+            .await?;
+        store
+            .put(&JsValue::from(single_scrypt))
+            .with_key(SCHEME_KEY)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    })
+    .await
+}
+
+/// Stores an encrypted mnemonic phrase restored from a blob, see `KeyVault::set_encrypted_seed_blob`. The existence
+/// check and the writes run in one readwrite transaction, as in `set_encrypted_mnemonic_seed`. The blob's password
+/// isn't known, so the verifier of the replaced phrase is deleted and passwords are checked against the phrase itself.
+///
+/// **Parameters**:
+/// - `payload: CipherPayload` - The encrypted mnemonic phrase.
+/// - `variant: SphincsVariant` - The SPHINCS+ variant the wallet is set up with, see `get_stored_variant`.
+/// - `single_scrypt: bool` - The derivation scheme the wallet is set up with, see `get_stored_single_scrypt`.
+/// - `overwrite: bool` - Whether to replace an existing mnemonic phrase. Ignored while accounts are stored, as they
+///   would stay derived from the replaced phrase.
+///
+/// **Returns**:
+/// - `Result<bool, KeyVaultDBError>` - Whether the mnemonic phrase was written (false if one exists and either
+///   `overwrite` is false or accounts are stored), or an error if storage fails.
+///
+/// **Async**: Yes
+pub async fn restore_encrypted_mnemonic_seed(
+    payload: CipherPayload,
+    variant: SphincsVariant,
+    single_scrypt: bool,
+    overwrite: bool,
+) -> Result<bool, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction([SEED_PHRASE_STORE, CHILD_KEYS_STORE])
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(SEED_PHRASE_STORE)?;
+        let accounts_store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let existing: Option<JsValue> = store.get(SEED_PHRASE_KEY).await?;
+        let account_count = accounts_store.count().await?;
+        if !should_restore_seed(existing.is_some(), account_count > 0, overwrite) {
+            return Ok(false);
+        }
+
+        let js_value = serde_wasm_bindgen::to_value(&payload)?;
+        store.put(&js_value).with_key(SEED_PHRASE_KEY).await?;
+        store.delete(VERIFIER_KEY).await?;
+        store
+            .put(&JsValue::from(variant as u8))
+            .with_key(VARIANT_KEY)
+            .await?;
+        store
+            .put(&JsValue::from(single_scrypt))
+            .with_key(SCHEME_KEY)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    })
+    .await
+}
+
+/// Decides whether a mnemonic phrase write may proceed, given whether one is already stored.
+pub fn should_write_seed(exists: bool, overwrite: bool) -> bool {
+    !exists || overwrite
+}
+
+/// Decides whether an imported or restored mnemonic phrase may be written. Overwriting is refused while accounts are
+/// stored, as they were derived from the phrase being replaced.
+pub fn should_restore_seed(exists: bool, has_accounts: bool, overwrite: bool) -> bool {
+    !exists || (overwrite && !has_accounts)
+}
+
 /// Retrieves the encrypted mnemonic phrase from the database.
 ///
 /// **Returns**:
@@ -66,7 +363,122 @@ pub async fn set_encrypted_mnemonic_seed(payload: CipherPayload) -> Result<(), K
 ///
 /// **Async**: Yes
 pub async fn get_encrypted_mnemonic_seed() -> Result<Option<CipherPayload>, KeyVaultDBError> {
-    let db = open_db().await?;
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        read_encrypted_mnemonic_seed(&db).await
+    })
+    .await
+}
+
+/// Retrieves the encrypted mnemonic phrase even while the vault is sealed, to check the password of `KeyVault::unseal`.
+///
+/// **Returns**:
+/// - `Result<Option<CipherPayload>, KeyVaultDBError>` - The encrypted mnemonic phrase if it exists, `None` if not found, or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_sealed_encrypted_mnemonic_seed() -> Result<Option<CipherPayload>, KeyVaultDBError>
+{
+    with_timeout(async move {
+        let db = open_db().await?;
+        read_encrypted_mnemonic_seed(&db).await
+    })
+    .await
+}
+
+/// Retrieves the SPHINCS+ variant the wallet was set up with. Readable while the vault is sealed, as it's no secret.
+///
+/// **Returns**:
+/// - `Result<Option<SphincsVariant>, KeyVaultDBError>` - The stored variant, `None` for wallets set up before it was
+///   recorded or restored from a backup, or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_stored_variant() -> Result<Option<SphincsVariant>, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_db().await?;
+        let tx = db
+            .transaction(SEED_PHRASE_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let stored: Option<JsValue> = tx.object_store(SEED_PHRASE_STORE)?.get(VARIANT_KEY).await?;
+        parse_stored_variant(stored.and_then(|v| v.as_f64()))
+    })
+    .await
+}
+
+/// Maps the stored variant record to its variant, refusing ids outside the 12 known ones.
+///
+/// **Parameters**:
+/// - `stored: Option<f64>` - The stored `SphincsVariant` id, `None` if there is no record.
+///
+/// **Returns**:
+/// - `Result<Option<SphincsVariant>, KeyVaultDBError>` - The variant, `None` if there is no record,
+///   or a `SerializationError` if the record is corrupted.
+pub fn parse_stored_variant(
+    stored: Option<f64>,
+) -> Result<Option<SphincsVariant>, KeyVaultDBError> {
+    stored
+        .map(|id| {
+            if id.fract() != 0.0 || !(0.0..=255.0).contains(&id) {
+                return Err(format!("Invalid SPHINCS+ variant id: {}", id));
+            }
+            SphincsVariant::try_from(id as u8)
+        })
+        .transpose()
+        .map_err(KeyVaultDBError::SerializationError)
+}
+
+/// Retrieves whether the wallet derives new keys with one Scrypt run, the scheme it was set up with, see
+/// `KeyVault::set_derivation_single_scrypt`.
+///
+/// **Returns**:
+/// - `Result<Option<bool>, KeyVaultDBError>` - The stored scheme, `None` for wallets set up before it was recorded
+///   or restored from a backup, or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_stored_single_scrypt() -> Result<Option<bool>, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(SEED_PHRASE_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let stored: Option<JsValue> = tx.object_store(SEED_PHRASE_STORE)?.get(SCHEME_KEY).await?;
+        stored
+            .map(|v| {
+                v.as_bool().ok_or_else(|| {
+                    KeyVaultDBError::SerializationError("Invalid derivation scheme record".to_string())
+                })
+            })
+            .transpose()
+    })
+    .await
+}
+
+/// Retrieves the password verifier stored with the mnemonic phrase.
+///
+/// **Returns**:
+/// - `Result<Option<CipherPayload>, KeyVaultDBError>` - The encrypted verifier if it exists, `None` if not found
+///   (no mnemonic, or one stored before verifiers were), or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_password_verifier() -> Result<Option<CipherPayload>, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        read_seed_store_payload(&db, VERIFIER_KEY).await
+    })
+    .await
+}
+
+async fn read_encrypted_mnemonic_seed(
+    db: &Database,
+) -> Result<Option<CipherPayload>, KeyVaultDBError> {
+    read_seed_store_payload(db, SEED_PHRASE_KEY).await
+}
+
+async fn read_seed_store_payload(
+    db: &Database,
+    key: &str,
+) -> Result<Option<CipherPayload>, KeyVaultDBError> {
     let tx = db
         .transaction(SEED_PHRASE_STORE)
         .with_mode(TransactionMode::Readonly)
@@ -74,55 +486,590 @@ pub async fn get_encrypted_mnemonic_seed() -> Result<Option<CipherPayload>, KeyV
     let store = tx.object_store(SEED_PHRASE_STORE)?;
 
     if let Some(js_value) = store
-        .get(SEED_PHRASE_KEY)
+        .get(key)
         .await
         .map_err(|e| KeyVaultDBError::DatabaseError(e.to_string()))?
     {
+        let hex = is_hex_payload(&js_value);
         let payload: CipherPayload = serde_wasm_bindgen::from_value(js_value)?;
+        if hex {
+            // Best effort, a failed rewrite is retried on the next read
+            let _ = migrate_hex_payload(db, SEED_PHRASE_STORE, key).await;
+        }
         Ok(Some(payload))
     } else {
         Ok(None)
     }
 }
 
-/// Stores a SPHINCS+ account to the database.
+/// Whether a stored `CipherPayload` still has hex string fields, as payloads before version 2 were written.
+fn is_hex_payload(payload: &JsValue) -> bool {
+    Reflect::get(payload, &JsValue::from_str("cipher_text"))
+        .map(|cipher_text| cipher_text.is_string())
+        .unwrap_or(false)
+}
+
+/// Whether a stored account record holds a private key payload with hex string fields.
+fn is_hex_account(record: &JsValue) -> bool {
+    Reflect::get(record, &JsValue::from_str("pri_enc"))
+        .map(|pri_enc| is_hex_payload(&pri_enc))
+        .unwrap_or(false)
+}
+
+/// Marks a payload read from a hex record as stored in raw bytes, as it is rewritten. Only the encoding changes,
+/// so legacy version 0 payloads, which carry no associated data, keep their version.
 ///
 /// **Parameters**:
-/// - `account: SphincsPlusAccount` - The SPHINCS+ account to store.
+/// - `payload: &mut CipherPayload` - The payload read from a hex record.
+pub fn upgrade_hex_payload(payload: &mut CipherPayload) {
+    if payload.version == 1 {
+        payload.version = CIPHER_PAYLOAD_VERSION;
+    }
+}
+
+/// Rewrites a `CipherPayload` record read with hex string fields in raw bytes, halving its size. The record is
+/// re-read in the write transaction and left alone if it changed since it was read.
+///
+/// **Parameters**:
+/// - `db: &Database` - The database instance to operate on.
+/// - `store_name: &str` - The object store holding the record.
+/// - `key: &str` - The key of the record.
 ///
 /// **Returns**:
-/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if storage fails.
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if the operation fails.
 ///
 /// **Async**: Yes
-pub async fn add_account(mut account: SphincsPlusAccount) -> Result<(), KeyVaultDBError> {
-    let db = open_db().await?;
+async fn migrate_hex_payload(
+    db: &Database,
+    store_name: &str,
+    key: &str,
+) -> Result<(), KeyVaultDBError> {
+    let tx = db
+        .transaction(store_name)
+        .with_mode(TransactionMode::Readwrite)
+        .build()?;
+    let store = tx.object_store(store_name)?;
+    let stored: Option<JsValue> = store.get(key).await?;
+    if let Some(js_value) = stored.filter(is_hex_payload) {
+        let mut payload: CipherPayload = serde_wasm_bindgen::from_value(js_value)?;
+        upgrade_hex_payload(&mut payload);
+        let js_value = serde_wasm_bindgen::to_value(&payload)?;
+        store.put(&js_value).with_key(key).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Rewrites the account records read with hex-encoded private key payloads in raw bytes, halving their size.
+/// Each record is re-read in the write transaction and left alone if it changed or was deleted since it was read.
+///
+/// **Parameters**:
+/// - `db: &Database` - The database instance to operate on.
+/// - `lock_args_list: Vec<String>` - The lock script arguments of the hex records.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if the operation fails.
+///
+/// **Async**: Yes
+async fn migrate_hex_accounts(
+    db: &Database,
+    lock_args_list: Vec<String>,
+) -> Result<(), KeyVaultDBError> {
+    if lock_args_list.is_empty() {
+        return Ok(());
+    }
     let tx = db
         .transaction(CHILD_KEYS_STORE)
         .with_mode(TransactionMode::Readwrite)
         .build()?;
     let store = tx.object_store(CHILD_KEYS_STORE)?;
-    let count = store.count().await?;
-    account.index = count as u32;
-    let js_value = serde_wasm_bindgen::to_value(&account)?;
-
-    match store.add(js_value).with_key(account.lock_args).build() {
-        Ok(_) => {
-            tx.commit().await?;
-            Ok(())
-        }
-        Err(e) => {
-            if let DBError::DomException(dom_err) = e {
-                if dom_err.name() == "ConstraintError" {
-                    // Key already exists, skip
-                    Ok(())
+    for lock_args in lock_args_list {
+        let stored: Option<JsValue> = store.get(lock_args.as_str()).await?;
+        let Some(js_value) = stored.filter(is_hex_account) else {
+            continue;
+        };
+        let mut account: SphincsPlusAccount = serde_wasm_bindgen::from_value(js_value)?;
+        if let Some(pri_enc) = account.pri_enc.as_mut() {
+            upgrade_hex_payload(pri_enc);
+        }
+        let js_value = serde_wasm_bindgen::to_value(&account)?;
+        store.put(&js_value).with_key(lock_args).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Stores a SPHINCS+ account to the database, at its given derivation index, and advances the persisted next
+/// derivation index past it in the same transaction.
+///
+/// **Parameters**:
+/// - `account: SphincsPlusAccount` - The SPHINCS+ account to store.
+///
+/// **Returns**:
+/// - `Result<bool, KeyVaultDBError>` - Whether the account was stored (false if its lock_args are already stored),
+///   or an error if storage fails.
+///
+/// **Async**: Yes
+pub async fn add_account(account: SphincsPlusAccount) -> Result<bool, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction([SEED_PHRASE_STORE, CHILD_KEYS_STORE])
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let seed_store = tx.object_store(SEED_PHRASE_STORE)?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+        let index = account.index;
+        let js_value = serde_wasm_bindgen::to_value(&account)?;
+
+        match store.add(js_value).with_key(account.lock_args).build() {
+            Ok(_) => {
+                let stored: Option<JsValue> = seed_store.get(NEXT_INDEX_KEY).await?;
+                let next_index = next_index_after(stored.and_then(|v| v.as_f64()), [index])?;
+                seed_store
+                    .put(&JsValue::from(next_index))
+                    .with_key(NEXT_INDEX_KEY)
+                    .await?;
+                tx.commit().await?;
+                Ok(true)
+            }
+            Err(e) => {
+                if let DBError::DomException(dom_err) = e {
+                    if dom_err.name() == "ConstraintError" {
+                        // Key already exists, skip
+                        Ok(false)
+                    } else {
+                        Err(KeyVaultDBError::from_dom_exception(
+                            &dom_err.name(),
+                            dom_err.to_string(),
+                        ))
+                    }
                 } else {
-                    Err(KeyVaultDBError::DatabaseError(dom_err.to_string()))
+                    Err(KeyVaultDBError::DatabaseError(e.to_string()))
                 }
-            } else {
-                Err(KeyVaultDBError::DatabaseError(e.to_string()))
             }
         }
+    })
+    .await
+}
+
+/// Stores several SPHINCS+ accounts in a single transaction, skipping those whose lock_args are already stored, and
+/// advances the persisted next derivation index past all of them. An error returns before `commit`, and the dropped
+/// transaction is aborted, so either every new account is stored or none is.
+///
+/// **Parameters**:
+/// - `accounts: Vec<SphincsPlusAccount>` - The SPHINCS+ accounts to store, at their given derivation index.
+///
+/// **Returns**:
+/// - `Result<u32, KeyVaultDBError>` - The number of accounts stored, or an error if storage fails.
+///
+/// **Async**: Yes
+pub async fn add_accounts(accounts: Vec<SphincsPlusAccount>) -> Result<u32, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction([SEED_PHRASE_STORE, CHILD_KEYS_STORE])
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let seed_store = tx.object_store(SEED_PHRASE_STORE)?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let indices: Vec<u32> = accounts.iter().map(|account| account.index).collect();
+        let mut added = 0;
+        for account in accounts {
+            let existing: Option<JsValue> = store.get(account.lock_args.as_str()).await?;
+            if existing.is_some() {
+                continue;
+            }
+            let js_value = serde_wasm_bindgen::to_value(&account)?;
+            store.put(&js_value).with_key(account.lock_args).await?;
+            added += 1;
+        }
+        let stored: Option<JsValue> = seed_store.get(NEXT_INDEX_KEY).await?;
+        let next_index = next_index_after(stored.and_then(|v| v.as_f64()), indices)?;
+        seed_store
+            .put(&JsValue::from(next_index))
+            .with_key(NEXT_INDEX_KEY)
+            .await?;
+        tx.commit().await?;
+        Ok(added)
+    })
+    .await
+}
+
+/// Computes the next unused derivation index: past both the persisted counter and every given index. Deleted
+/// accounts only live on in the counter, so their indices are never handed out again.
+///
+/// **Parameters**:
+/// - `stored: Option<f64>` - The persisted counter, `None` on databases written before it existed.
+/// - `indices: I` - The indices of the stored accounts. Watch-only indices, from `WATCH_ONLY_INDEX_BASE` up, are
+///   ignored as they aren't derived.
+///
+/// **Returns**:
+/// - `Result<u32, KeyVaultDBError>` - The next unused derivation index, or an error if it would reach the
+///   watch-only range.
+pub fn next_index_after<I: IntoIterator<Item = u32>>(
+    stored: Option<f64>,
+    indices: I,
+) -> Result<u32, KeyVaultDBError> {
+    let mut next = stored.map(|next| next as u32).unwrap_or(0);
+    for index in indices.into_iter().filter(|index| *index < WATCH_ONLY_INDEX_BASE) {
+        // Below WATCH_ONLY_INDEX_BASE, the addition can't overflow
+        next = next.max(index + 1);
+    }
+    if next >= WATCH_ONLY_INDEX_BASE {
+        return Err(KeyVaultDBError::DatabaseError(format!(
+            "Account index {} is out of the derivation index range",
+            next
+        )));
+    }
+    Ok(next)
+}
+
+/// Computes the index of the next watch-only account: past every watch-only index, starting at
+/// `WATCH_ONLY_INDEX_BASE`. Nothing is derived at these indices, so reusing the index of a deleted watch-only
+/// account is harmless.
+///
+/// **Parameters**:
+/// - `indices: I` - The indices of the stored accounts.
+///
+/// **Returns**:
+/// - `Result<u32, KeyVaultDBError>` - The next watch-only index, or an error if the watch-only range is used up.
+pub fn next_watch_only_index<I: IntoIterator<Item = u32>>(indices: I) -> Result<u32, KeyVaultDBError> {
+    match indices
+        .into_iter()
+        .filter(|index| *index >= WATCH_ONLY_INDEX_BASE)
+        .max()
+    {
+        None => Ok(WATCH_ONLY_INDEX_BASE),
+        Some(index) => index.checked_add(1).ok_or_else(|| {
+            KeyVaultDBError::DatabaseError("No watch-only account index left".to_string())
+        }),
+    }
+}
+
+/// Retrieves the derivation index of the next new account.
+///
+/// **Returns**:
+/// - `Result<u32, KeyVaultDBError>` - The next unused derivation index, or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn next_account_index() -> Result<u32, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction([SEED_PHRASE_STORE, CHILD_KEYS_STORE])
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let stored: Option<JsValue> = tx
+            .object_store(SEED_PHRASE_STORE)?
+            .get(NEXT_INDEX_KEY)
+            .await?;
+        let iter: ArrayMapIter<JsValue> = tx.object_store(CHILD_KEYS_STORE)?.get_all().await?;
+        let mut indices = Vec::new();
+        for result in iter {
+            indices.extend(record_index(&result?));
+        }
+        next_index_after(stored.and_then(|v| v.as_f64()), indices)
+    })
+    .await
+}
+
+/// Reads the `index` field of a raw account record, which unreadable records may still hold.
+fn record_index(record: &JsValue) -> Option<u32> {
+    Reflect::get(record, &JsValue::from_str("index"))
+        .ok()?
+        .as_f64()
+        .map(|index| index as u32)
+}
+
+/// Deletes the account stored under `lock_args`. The persisted next derivation index is advanced past it first,
+/// in the same transaction, so `gen_new_account` never derives the deleted index again.
+///
+/// **Parameters**:
+/// - `lock_args: &str` - The hex-encoded lock script's arguments of the account.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if the account doesn't exist or storage fails.
+///
+/// **Async**: Yes
+pub async fn delete_account(lock_args: &str) -> Result<(), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction([SEED_PHRASE_STORE, CHILD_KEYS_STORE])
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let seed_store = tx.object_store(SEED_PHRASE_STORE)?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let existing: Option<JsValue> = store.get(lock_args).await?;
+        if existing.is_none() {
+            return Err(KeyVaultDBError::DatabaseError(format!(
+                "Account {} not found",
+                lock_args
+            )));
+        }
+        let iter: ArrayMapIter<JsValue> = store.get_all().await?;
+        let mut indices = Vec::new();
+        for result in iter {
+            indices.extend(record_index(&result?));
+        }
+
+        let stored: Option<JsValue> = seed_store.get(NEXT_INDEX_KEY).await?;
+        let next_index = next_index_after(stored.and_then(|v| v.as_f64()), indices)?;
+        seed_store
+            .put(&JsValue::from(next_index))
+            .with_key(NEXT_INDEX_KEY)
+            .await?;
+        store.delete(lock_args).await?;
+        tx.commit().await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Deletes every watch-only account, the records stored without `pri_enc`, within a single transaction. Signing
+/// accounts, unreadable records and the next derivation index are left untouched.
+///
+/// **Returns**:
+/// - `Result<u32, KeyVaultDBError>` - The number of accounts deleted, or an error if storage fails.
+///
+/// **Async**: Yes
+pub async fn purge_watch_only() -> Result<u32, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let iter: ArrayMapIter<JsValue> = store.get_all().await?;
+        let mut records = Vec::new();
+        for result in iter {
+            records.push(serde_wasm_bindgen::from_value::<SphincsPlusAccount>(result?));
+        }
+        let (accounts, _) = partition_account_records(records);
+        let purged = watch_only_lock_args(&accounts);
+        for lock_args in &purged {
+            store.delete(lock_args.as_str()).await?;
+        }
+        tx.commit().await?;
+        Ok(purged.len() as u32)
+    })
+    .await
+}
+
+/// Picks the lock_args of the watch-only accounts, see `purge_watch_only`.
+pub fn watch_only_lock_args(accounts: &[SphincsPlusAccount]) -> Vec<String> {
+    accounts
+        .iter()
+        .filter(|account| account.is_watch_only())
+        .map(|account| account.lock_args.clone())
+        .collect()
+}
+
+/// Replaces the encrypted mnemonic phrase and all child accounts within a single transaction,
+/// so a failure leaves the previous seed and accounts untouched. An error returns before `commit`,
+/// and the dropped transaction is aborted, rolling back the writes made so far.
+///
+/// **Parameters**:
+/// - `payload: CipherPayload` - The new encrypted mnemonic phrase.
+/// - `verifier: CipherPayload` - The password verifier encrypted with the same password.
+/// - `accounts: Vec<SphincsPlusAccount>` - The accounts replacing every stored account, stored with their given index.
+/// - `read: Option<(Vec<u8>, Vec<String>)>` - The seed cipher text and account lock_args the replacement was computed
+///   from, if it was computed from the stored records. They are checked against the store inside the transaction.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if storage fails or the stored seed or accounts
+///   changed since `read`.
+///
+/// **Async**: Yes
+///
+/// **Note**: The next-index counter is kept, or raised past the given indices, never lowered.
+///
+/// **Warning**: This method overwrites the existing mnemonic phrase and removes all existing accounts.
+pub async fn replace_seed_and_accounts(
+    payload: CipherPayload,
+    verifier: CipherPayload,
+    accounts: Vec<SphincsPlusAccount>,
+    read: Option<(Vec<u8>, Vec<String>)>,
+) -> Result<(), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction([SEED_PHRASE_STORE, CHILD_KEYS_STORE])
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let seed_store = tx.object_store(SEED_PHRASE_STORE)?;
+        let accounts_store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        // An account added or a seed written since the records were read would otherwise be silently lost
+        if let Some((read_seed, read_lock_args)) = &read {
+            let stored_seed: Option<JsValue> = seed_store.get(SEED_PHRASE_KEY).await?;
+            let stored_seed = stored_seed
+                .map(serde_wasm_bindgen::from_value::<CipherPayload>)
+                .transpose()?;
+            let iter: ArrayMapIter<JsValue> = accounts_store.get_all().await?;
+            let mut stored_lock_args = Vec::new();
+            for result in iter {
+                stored_lock_args.push(record_lock_args(&result?).unwrap_or_default());
+            }
+            check_unchanged_since_read(
+                read_seed,
+                stored_seed.as_ref().map(|seed| seed.cipher_text.as_slice()),
+                read_lock_args,
+                &stored_lock_args,
+            )?;
+        }
+
+        let js_value = serde_wasm_bindgen::to_value(&payload)?;
+        seed_store.put(&js_value).with_key(SEED_PHRASE_KEY).await?;
+        let js_value = serde_wasm_bindgen::to_value(&verifier)?;
+        seed_store.put(&js_value).with_key(VERIFIER_KEY).await?;
+        // The counter carries over, so indices of accounts deleted before the replacement stay retired
+        let stored: Option<JsValue> = seed_store.get(NEXT_INDEX_KEY).await?;
+        let next_index = next_index_after(
+            stored.and_then(|v| v.as_f64()),
+            accounts.iter().map(|account| account.index),
+        )?;
+        accounts_store.clear()?;
+        seed_store
+            .put(&JsValue::from(next_index))
+            .with_key(NEXT_INDEX_KEY)
+            .await?;
+        for account in accounts {
+            let js_value = serde_wasm_bindgen::to_value(&account)?;
+            accounts_store
+                .put(&js_value)
+                .with_key(account.lock_args)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Checks that the stored seed and accounts are still those a replacement was computed from, see
+/// `replace_seed_and_accounts`. The order of the lock_args doesn't matter.
+pub fn check_unchanged_since_read(
+    read_seed: &[u8],
+    stored_seed: Option<&[u8]>,
+    read_lock_args: &[String],
+    stored_lock_args: &[String],
+) -> Result<(), KeyVaultDBError> {
+    let mut read_lock_args = read_lock_args.to_vec();
+    let mut stored_lock_args = stored_lock_args.to_vec();
+    read_lock_args.sort();
+    stored_lock_args.sort();
+    if stored_seed != Some(read_seed) || read_lock_args != stored_lock_args {
+        return Err(KeyVaultDBError::DatabaseError(
+            "The wallet changed while it was being re-encrypted; nothing was written".to_string(),
+        ));
     }
+    Ok(())
+}
+
+/// Reads the `lock_args` field of a raw account record, which unreadable records may still hold.
+fn record_lock_args(record: &JsValue) -> Option<String> {
+    Reflect::get(record, &JsValue::from_str("lock_args"))
+        .ok()?
+        .as_string()
+}
+
+/// Restores the mnemonic phrase, its password verifier and all child accounts from a vault export within a single
+/// transaction. The emptiness check and the writes share the transaction, so nothing can be stored in between.
+///
+/// **Parameters**:
+/// - `backup: WalletBackup` - The records to restore. Without a verifier, any stored one is removed, as it would
+///   belong to another password. The stored variant record is removed too, as the backup doesn't tell its variant.
+///   The next derivation index is restored from the backup, or recomputed from its accounts for older exports.
+/// - `overwrite: bool` - Whether to replace a stored mnemonic phrase or accounts.
+///
+/// **Returns**:
+/// - `Result<bool, KeyVaultDBError>` - Whether the records were restored (false if the database isn't empty and
+///   `overwrite` is false), or an error if storage fails.
+///
+/// **Async**: Yes
+///
+/// **Warning**: With `overwrite`, this method overwrites the existing mnemonic phrase and removes all existing accounts.
+pub async fn restore_vault(backup: WalletBackup, overwrite: bool) -> Result<bool, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction([SEED_PHRASE_STORE, CHILD_KEYS_STORE])
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let seed_store = tx.object_store(SEED_PHRASE_STORE)?;
+        let accounts_store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let existing: Option<JsValue> = seed_store.get(SEED_PHRASE_KEY).await?;
+        let account_count = accounts_store.count().await?;
+        if !should_write_seed(existing.is_some() || account_count > 0, overwrite) {
+            return Ok(false);
+        }
+
+        let js_value = serde_wasm_bindgen::to_value(&backup.seed)?;
+        seed_store.put(&js_value).with_key(SEED_PHRASE_KEY).await?;
+        match backup.verifier {
+            Some(verifier) => {
+                let js_value = serde_wasm_bindgen::to_value(&verifier)?;
+                seed_store.put(&js_value).with_key(VERIFIER_KEY).await?;
+            }
+            None => seed_store.delete(VERIFIER_KEY).await?,
+        }
+        seed_store.delete(VARIANT_KEY).await?;
+        seed_store.delete(SCHEME_KEY).await?;
+        accounts_store.clear()?;
+        let next_index = next_index_after(
+            backup.next_index.map(f64::from),
+            backup.accounts.iter().map(|account| account.index),
+        )?;
+        seed_store
+            .put(&JsValue::from(next_index))
+            .with_key(NEXT_INDEX_KEY)
+            .await?;
+        for account in backup.accounts {
+            let js_value = serde_wasm_bindgen::to_value(&account)?;
+            accounts_store
+                .put(&js_value)
+                .with_key(account.lock_args)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    })
+    .await
+}
+
+/// Overwrites the given child accounts, keyed by their lock_args, within a single transaction.
+/// An error returns before `commit`, and the dropped transaction is aborted, leaving every record untouched.
+///
+/// **Parameters**:
+/// - `accounts: Vec<SphincsPlusAccount>` - The updated accounts, stored with their given index.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if storage fails.
+///
+/// **Async**: Yes
+pub async fn put_accounts(accounts: Vec<SphincsPlusAccount>) -> Result<(), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+        for account in accounts {
+            let js_value = serde_wasm_bindgen::to_value(&account)?;
+            store.put(&js_value).with_key(account.lock_args).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    })
+    .await
 }
 
 /// Retrieves a child account by its public key from the database.
@@ -135,23 +1082,542 @@ pub async fn add_account(mut account: SphincsPlusAccount) -> Result<(), KeyVault
 ///
 /// **Async**: Yes
 pub async fn get_account(lock_args: &str) -> Result<Option<SphincsPlusAccount>, KeyVaultDBError> {
-    let db = open_db().await?;
-    let tx = db
-        .transaction(CHILD_KEYS_STORE)
-        .with_mode(TransactionMode::Readonly)
-        .build()?;
-    let store = tx.object_store(CHILD_KEYS_STORE)?;
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
 
-    if let Some(js_value) = store
-        .get(lock_args)
-        .await
-        .map_err(|e| KeyVaultDBError::DatabaseError(e.to_string()))?
-    {
-        let account: SphincsPlusAccount = serde_wasm_bindgen::from_value(js_value)?;
-        Ok(Some(account))
-    } else {
-        Ok(None)
+        if let Some(js_value) = store
+            .get(lock_args)
+            .await
+            .map_err(|e| KeyVaultDBError::DatabaseError(e.to_string()))?
+        {
+            let hex = is_hex_account(&js_value);
+            let account: SphincsPlusAccount = serde_wasm_bindgen::from_value(js_value)?;
+            if hex {
+                // Best effort, a failed rewrite is retried on the next read
+                let _ = migrate_hex_accounts(&db, vec![lock_args.to_string()]).await;
+            }
+            Ok(Some(account))
+        } else {
+            Ok(None)
+        }
+    })
+    .await
+}
+
+/// Retrieves the child accounts of several lock script arguments within a single readonly transaction.
+///
+/// **Parameters**:
+/// - `lock_args_list: &[String]` - The hex-encoded lock script arguments to look up.
+///
+/// **Returns**:
+/// - `Result<HashMap<String, SphincsPlusAccount>, KeyVaultDBError>` - The accounts found, by lock_args. Missing ones
+///   are absent from the map. An error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_accounts(
+    lock_args_list: &[String],
+) -> Result<HashMap<String, SphincsPlusAccount>, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let mut accounts = HashMap::new();
+        let mut hex_records = Vec::new();
+        for lock_args in lock_args_list {
+            if accounts.contains_key(lock_args) {
+                continue;
+            }
+            if let Some(js_value) = store
+                .get(lock_args.as_str())
+                .await
+                .map_err(|e| KeyVaultDBError::DatabaseError(e.to_string()))?
+            {
+                if is_hex_account(&js_value) {
+                    hex_records.push(lock_args.clone());
+                }
+                let account: SphincsPlusAccount = serde_wasm_bindgen::from_value(js_value)?;
+                accounts.insert(lock_args.clone(), account);
+            }
+        }
+        // Best effort, a failed rewrite is retried on the next read
+        let _ = migrate_hex_accounts(&db, hex_records).await;
+        Ok(accounts)
+    })
+    .await
+}
+
+/// Counts the child account records without reading them.
+///
+/// **Returns**:
+/// - `Result<u32, KeyVaultDBError>` - The number of stored account records on success, or an error if the operation fails.
+///
+/// **Async**: Yes
+pub async fn count_accounts() -> Result<u32, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+        Ok(store.count().await?)
+    })
+    .await
+}
+
+/// Retrieves all child accounts from the database, sorted by index.
+///
+/// **Returns**:
+/// - `Result<Vec<SphincsPlusAccount>, KeyVaultDBError>` - All stored accounts on success, or an error if retrieval fails
+///   or any record is unreadable.
+///
+/// **Async**: Yes
+pub async fn get_all_accounts() -> Result<Vec<SphincsPlusAccount>, KeyVaultDBError> {
+    let (accounts, unreadable) = get_all_account_records().await?;
+    if !unreadable.is_empty() {
+        return Err(KeyVaultDBError::SerializationError(format!(
+            "{} unreadable account record(s): {}",
+            unreadable.len(),
+            unreadable.join("; ")
+        )));
     }
+    Ok(accounts)
+}
+
+/// Retrieves all child accounts from the database, skipping records that fail to deserialize
+/// (e.g. written by an incompatible version) instead of failing on them.
+///
+/// **Returns**:
+/// - `Result<(Vec<SphincsPlusAccount>, Vec<String>), KeyVaultDBError>` - The readable accounts sorted by index and the
+///   deserialization errors of the unreadable records on success, or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_all_account_records(
+) -> Result<(Vec<SphincsPlusAccount>, Vec<String>), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let iter: ArrayMapIter<JsValue> = store.get_all().await?;
+        let mut records = Vec::new();
+        let mut hex_records = Vec::new();
+        for result in iter {
+            let record = result?;
+            let hex = is_hex_account(&record);
+            let account = serde_wasm_bindgen::from_value::<SphincsPlusAccount>(record);
+            if hex {
+                if let Ok(account) = &account {
+                    hex_records.push(account.lock_args.clone());
+                }
+            }
+            records.push(account);
+        }
+        // Best effort, a failed rewrite is retried on the next read
+        let _ = migrate_hex_accounts(&db, hex_records).await;
+        Ok(partition_account_records(records))
+    })
+    .await
+}
+
+/// Retrieves one page of account records in index order, walking a cursor over the `ACCOUNT_INDEX_NAME` index
+/// instead of loading and sorting every record.
+///
+/// **Parameters**:
+/// - `offset: u32` - The number of records to skip. This is a position, not an account index: indices may have gaps.
+/// - `limit: u32` - The maximum number of records to return.
+///
+/// **Returns**:
+/// - `Result<(Vec<SphincsPlusAccount>, Vec<String>), KeyVaultDBError>` - The readable accounts of the page and the
+///   deserialization errors of its unreadable records on success, or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_account_records_page(
+    offset: u32,
+    limit: u32,
+) -> Result<(Vec<SphincsPlusAccount>, Vec<String>), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+        let index = store.index(ACCOUNT_INDEX_NAME)?;
+
+        let mut records = Vec::new();
+        // An offset past the last record is an empty page, without advancing a cursor past the end
+        if limit > 0 && offset < index.count().await? {
+            if let Some(mut cursor) = index.open_cursor().await? {
+                if offset > 0 {
+                    cursor.advance_by(offset).await?;
+                }
+                while records.len() < limit as usize {
+                    let Some(record) = cursor.next_record::<JsValue>().await? else {
+                        break;
+                    };
+                    records.push(serde_wasm_bindgen::from_value::<SphincsPlusAccount>(record));
+                }
+            }
+        }
+        Ok(partition_account_records(records))
+    })
+    .await
+}
+
+/// Splits deserialized account records into the readable accounts, sorted by index, and the errors of the unreadable ones.
+///
+/// **Parameters**:
+/// - `records: I` - The deserialization result of every record.
+///
+/// **Returns**:
+/// - `(Vec<SphincsPlusAccount>, Vec<String>)` - The readable accounts and the deserialization errors.
+pub fn partition_account_records<I, E>(records: I) -> (Vec<SphincsPlusAccount>, Vec<String>)
+where
+    I: IntoIterator<Item = Result<SphincsPlusAccount, E>>,
+    E: std::fmt::Display,
+{
+    let mut accounts: Vec<SphincsPlusAccount> = Vec::new();
+    let mut unreadable: Vec<String> = Vec::new();
+    for record in records {
+        match record {
+            Ok(account) => accounts.push(account),
+            Err(e) => unreadable.push(e.to_string()),
+        }
+    }
+    accounts.sort_by_key(|account| account.index);
+    (accounts, unreadable)
+}
+
+/// Assigns a local account id to the account stored under `lock_args`.
+///
+/// **Parameters**:
+/// - `lock_args: &str` - The hex-encoded lock script's arguments of the account.
+/// - `account_id: &str` - The local id to assign.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if the account doesn't exist, the id is used by another account, or storage fails.
+///
+/// **Async**: Yes
+pub async fn set_account_id(lock_args: &str, account_id: &str) -> Result<(), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        // The unique index would refuse the write anyway, checked first for a clear error
+        let owner: Option<JsValue> = store.index(ACCOUNT_ID_INDEX_NAME)?.get(account_id).await?;
+        if let Some(owner) = owner {
+            let owner: SphincsPlusAccount = serde_wasm_bindgen::from_value(owner)?;
+            if owner.lock_args != lock_args {
+                return Err(KeyVaultDBError::DatabaseError(format!(
+                    "Account id {} is already in use",
+                    account_id
+                )));
+            }
+        }
+
+        let js_value: JsValue = store.get(lock_args).await?.ok_or_else(|| {
+            KeyVaultDBError::DatabaseError(format!("Account {} not found", lock_args))
+        })?;
+        let mut account: SphincsPlusAccount = serde_wasm_bindgen::from_value(js_value)?;
+        account.account_id = Some(account_id.to_string());
+        let js_value = serde_wasm_bindgen::to_value(&account)?;
+        store.put(&js_value).with_key(lock_args).await?;
+        tx.commit().await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Records whether on-chain discovery found history for the account stored under `lock_args`.
+///
+/// **Parameters**:
+/// - `lock_args: &str` - The hex-encoded lock script's arguments of the account.
+/// - `has_history: bool` - Whether the account has on-chain history.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if the account doesn't exist or storage fails.
+///
+/// **Async**: Yes
+pub async fn set_account_history_flag(
+    lock_args: &str,
+    has_history: bool,
+) -> Result<(), KeyVaultDBError> {
+    update_account(lock_args, |account| account.has_history = Some(has_history)).await
+}
+
+/// Sets or clears the user-editable label of a child account.
+///
+/// **Parameters**:
+/// - `lock_args: &str` - The hex-encoded lock script's arguments of the account.
+/// - `label: Option<String>` - The label, `None` to clear it.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if the account doesn't exist or storage fails.
+///
+/// **Async**: Yes
+pub async fn set_account_label(
+    lock_args: &str,
+    label: Option<String>,
+) -> Result<(), KeyVaultDBError> {
+    update_account(lock_args, |account| account.label = label).await
+}
+
+/// Reads, modifies and writes back one child account within a single readwrite transaction.
+async fn update_account<F: FnOnce(&mut SphincsPlusAccount)>(
+    lock_args: &str,
+    update: F,
+) -> Result<(), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let js_value: JsValue = store.get(lock_args).await?.ok_or_else(|| {
+            KeyVaultDBError::DatabaseError(format!("Account {} not found", lock_args))
+        })?;
+        let mut account: SphincsPlusAccount = serde_wasm_bindgen::from_value(js_value)?;
+        update(&mut account);
+        let js_value = serde_wasm_bindgen::to_value(&account)?;
+        store.put(&js_value).with_key(lock_args).await?;
+        tx.commit().await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Retrieves a child account by its local account id, through the unique `ACCOUNT_ID_INDEX_NAME` index.
+///
+/// **Parameters**:
+/// - `account_id: &str` - The local id of the retrieved child account.
+///
+/// **Returns**:
+/// - `Result<Option<SphincsPlusAccount>, KeyVaultDBError>` - The child account if found, `None` if not found, or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_account_by_id(
+    account_id: &str,
+) -> Result<Option<SphincsPlusAccount>, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(CHILD_KEYS_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let store = tx.object_store(CHILD_KEYS_STORE)?;
+
+        let stored: Option<JsValue> = store.index(ACCOUNT_ID_INDEX_NAME)?.get(account_id).await?;
+        match stored {
+            Some(js_value) => Ok(Some(serde_wasm_bindgen::from_value(js_value)?)),
+            None => Ok(None),
+        }
+    })
+    .await
+}
+
+/// Stores an encrypted metadata entry in the database.
+///
+/// **Parameters**:
+/// - `key: &str` - The metadata key.
+/// - `payload: CipherPayload` - The encrypted metadata value.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if storage fails.
+///
+/// **Async**: Yes
+///
+/// **Warning**: This method overwrites an existing entry under the same key.
+pub async fn set_encrypted_metadata(
+    key: &str,
+    payload: CipherPayload,
+) -> Result<(), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(METADATA_STORE)
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(METADATA_STORE)?;
+
+        let js_value = serde_wasm_bindgen::to_value(&payload)?;
+
+        store.put(&js_value).with_key(key).await?;
+        tx.commit().await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Retrieves an encrypted metadata entry from the database.
+///
+/// **Parameters**:
+/// - `key: &str` - The metadata key.
+///
+/// **Returns**:
+/// - `Result<Option<CipherPayload>, KeyVaultDBError>` - The encrypted value if it exists, `None` if not found, or an error if retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_encrypted_metadata(key: &str) -> Result<Option<CipherPayload>, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_unsealed_db().await?;
+        let tx = db
+            .transaction(METADATA_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let store = tx.object_store(METADATA_STORE)?;
+
+        if let Some(js_value) = store
+            .get(key)
+            .await
+            .map_err(|e| KeyVaultDBError::DatabaseError(e.to_string()))?
+        {
+            let payload: CipherPayload = serde_wasm_bindgen::from_value(js_value)?;
+            Ok(Some(payload))
+        } else {
+            Ok(None)
+        }
+    })
+    .await
+}
+
+/// Parses a stored wrong-password attempts record. A missing or unreadable record means none, so a damaged
+/// record can't lock the user out.
+fn parse_failed_attempts(stored: Option<JsValue>) -> FailedAttempts {
+    stored
+        .and_then(|value| serde_wasm_bindgen::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Retrieves the recorded wrong-password attempts.
+///
+/// **Returns**:
+/// - `Result<FailedAttempts, KeyVaultDBError>` - The attempts, zero if none are recorded, or an error if
+///   retrieval fails.
+///
+/// **Async**: Yes
+pub async fn get_failed_attempts() -> Result<FailedAttempts, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_db().await?;
+        let tx = db
+            .transaction(METADATA_STORE)
+            .with_mode(TransactionMode::Readonly)
+            .build()?;
+        let stored: Option<JsValue> = tx
+            .object_store(METADATA_STORE)?
+            .get(FAILED_ATTEMPTS_KEY)
+            .await?;
+        Ok(parse_failed_attempts(stored))
+    })
+    .await
+}
+
+/// Reserves an unlock attempt, see `utilities::reserve_unlock_attempt`. The lockout check and the count increment
+/// run in one readwrite transaction, so concurrent attempts are serialized and each one counts before it decrypts.
+///
+/// **Parameters**:
+/// - `max: u32` - How many wrong passwords in a row lock unlocking. 0 disables the lockout.
+/// - `cooldown_ms: u32` - How long unlocking stays locked after the last wrong password.
+/// - `now: f64` - The time of the attempt in milliseconds since the Unix epoch, 0 if unknown.
+///
+/// **Returns**:
+/// - `Result<Result<FailedAttempts, String>, KeyVaultDBError>` - The attempts including this one, or the lockout
+///   message if unlocking is locked (nothing is written then), or an error if storage fails.
+///
+/// **Async**: Yes
+pub async fn reserve_failed_attempt(
+    max: u32,
+    cooldown_ms: u32,
+    now: f64,
+) -> Result<Result<FailedAttempts, String>, KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_db().await?;
+        let tx = db
+            .transaction(METADATA_STORE)
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(METADATA_STORE)?;
+        let stored: Option<JsValue> = store.get(FAILED_ATTEMPTS_KEY).await?;
+        let attempts =
+            match reserve_unlock_attempt(&parse_failed_attempts(stored), max, cooldown_ms, now) {
+                Ok(attempts) => attempts,
+                Err(msg) => return Ok(Err(msg)),
+            };
+        store
+            .put(&serde_wasm_bindgen::to_value(&attempts)?)
+            .with_key(FAILED_ATTEMPTS_KEY)
+            .await?;
+        tx.commit().await?;
+        Ok(Ok(attempts))
+    })
+    .await
+}
+
+/// Gives back a reserved attempt that didn't test the password, see `utilities::release_unlock_attempt`. Read and
+/// written in one transaction, so attempts reserved meanwhile keep counting.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if storage fails.
+///
+/// **Async**: Yes
+pub async fn release_failed_attempt() -> Result<(), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_db().await?;
+        let tx = db
+            .transaction(METADATA_STORE)
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        let store = tx.object_store(METADATA_STORE)?;
+        let stored: Option<JsValue> = store.get(FAILED_ATTEMPTS_KEY).await?;
+        let attempts = release_unlock_attempt(&parse_failed_attempts(stored));
+        store
+            .put(&serde_wasm_bindgen::to_value(&attempts)?)
+            .with_key(FAILED_ATTEMPTS_KEY)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Clears the recorded wrong-password attempts, ending a lockout.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultDBError>` - Ok on success, or an error if storage fails.
+///
+/// **Async**: Yes
+pub async fn clear_failed_attempts() -> Result<(), KeyVaultDBError> {
+    with_timeout(async move {
+        let db = open_db().await?;
+        let tx = db
+            .transaction(METADATA_STORE)
+            .with_mode(TransactionMode::Readwrite)
+            .build()?;
+        tx.object_store(METADATA_STORE)?
+            .delete(FAILED_ATTEMPTS_KEY)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    })
+    .await
 }
 
 /// Clears a specific object store in the database.
@@ -165,27 +1631,36 @@ pub async fn get_account(lock_args: &str) -> Result<Option<SphincsPlusAccount>,
 ///
 /// **Async**: Yes
 pub async fn clear_object_store(db: &Database, store_name: &str) -> Result<(), KeyVaultDBError> {
-    let tx = db
-        .transaction(store_name)
-        .with_mode(TransactionMode::Readwrite)
-        .build()
-        .map_err(|e| {
+    with_timeout(async move {
+        let tx = db
+            .transaction(store_name)
+            .with_mode(TransactionMode::Readwrite)
+            .build()
+            .map_err(|e| {
+                KeyVaultDBError::DatabaseError(format!(
+                    "Error starting transaction for {}: {}",
+                    store_name, e
+                ))
+            })?;
+        let store = tx.object_store(store_name).map_err(|e| {
             KeyVaultDBError::DatabaseError(format!(
-                "Error starting transaction for {}: {}",
+                "Error getting object store {}: {}",
                 store_name, e
             ))
         })?;
-    let store = tx.object_store(store_name).map_err(|e| {
-        KeyVaultDBError::DatabaseError(format!("Error getting object store {}: {}", store_name, e))
-    })?;
-    store.clear().map_err(|e| {
-        KeyVaultDBError::DatabaseError(format!("Error clearing object store {}: {}", store_name, e))
-    })?;
-    tx.commit().await.map_err(|e| {
-        KeyVaultDBError::DatabaseError(format!(
-            "Error committing transaction for {}: {}",
-            store_name, e
-        ))
-    })?;
-    Ok(())
+        store.clear().map_err(|e| {
+            KeyVaultDBError::DatabaseError(format!(
+                "Error clearing object store {}: {}",
+                store_name, e
+            ))
+        })?;
+        tx.commit().await.map_err(|e| {
+            KeyVaultDBError::DatabaseError(format!(
+                "Error committing transaction for {}: {}",
+                store_name, e
+            ))
+        })?;
+        Ok(())
+    })
+    .await
 }
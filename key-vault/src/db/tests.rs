@@ -0,0 +1,249 @@
+use super::*;
+
+#[test]
+fn test_quota_exceeded_dom_exception_is_distinct() {
+  let err = KeyVaultDBError::from_dom_exception("QuotaExceededError", "quota".to_string());
+  assert!(matches!(err, KeyVaultDBError::StorageQuotaExceeded(_)));
+  assert_eq!(err.to_string(), "Storage quota exceeded: quota");
+
+  let err = KeyVaultDBError::from_dom_exception("DataError", "data".to_string());
+  assert!(matches!(err, KeyVaultDBError::DatabaseError(_)));
+}
+
+#[test]
+fn test_future_db_version_is_refused() {
+  let err = check_db_version(DB_VERSION as f64 + 1.0, DB_VERSION).unwrap_err();
+  assert!(matches!(err, KeyVaultDBError::DatabaseVersionTooNew(_)));
+  assert!(err.to_string().starts_with("Database version too new"));
+}
+
+#[test]
+fn test_current_and_older_db_versions_are_accepted() {
+  assert!(check_db_version(DB_VERSION as f64, DB_VERSION).is_ok());
+  assert!(check_db_version(1.0, DB_VERSION).is_ok());
+}
+
+#[test]
+fn test_stored_variant_record() {
+  assert!(matches!(parse_stored_variant(None), Ok(None)));
+  let id = SphincsVariant::Shake128F as u8 as f64;
+  assert!(matches!(parse_stored_variant(Some(id)), Ok(Some(SphincsVariant::Shake128F))));
+  assert!(matches!(parse_stored_variant(Some(1.0)), Err(KeyVaultDBError::SerializationError(_))));
+  assert!(parse_stored_variant(Some(48.5)).is_err());
+  assert!(parse_stored_variant(Some(-48.0)).is_err());
+}
+
+#[test]
+fn test_malformed_account_record_is_skipped() {
+  let account = |index: u32, lock_args: &str| {
+    serde_json::json!({
+      "index": index,
+      "lock_args": lock_args,
+      "pri_enc": { "salt": "00", "iv": "00", "cipher_text": "00", "version": 1 }
+    })
+  };
+  let records = vec![
+    account(2, "cc"),
+    serde_json::json!({ "index": "one", "lock_args": 7 }),
+    account(0, "aa"),
+  ];
+
+  let (accounts, unreadable) =
+    partition_account_records(records.into_iter().map(serde_json::from_value::<SphincsPlusAccount>));
+  let lock_args: Vec<&str> = accounts.iter().map(|account| account.lock_args.as_str()).collect();
+  assert_eq!(lock_args, vec!["aa", "cc"]);
+  assert_eq!(unreadable.len(), 1);
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+  use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+  fn noop_raw_waker() -> RawWaker {
+    fn clone(_: *const ()) -> RawWaker {
+      noop_raw_waker()
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    RawWaker::new(std::ptr::null(), &VTABLE)
+  }
+  let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+  let mut cx = Context::from_waker(&waker);
+  let mut future = std::pin::pin!(future);
+  loop {
+    if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+      return output;
+    }
+  }
+}
+
+#[test]
+fn test_blocked_transaction_times_out() {
+  // A blocked transaction never completes; the timer firing must end the wait.
+  let blocked = std::future::pending::<Result<(), KeyVaultDBError>>();
+  let err = block_on(timeout::race(blocked, std::future::ready(()), 10_000)).unwrap_err();
+  assert!(matches!(err, KeyVaultDBError::StorageTimeout(_)));
+  assert_eq!(err.to_string(), "Storage timeout: IndexedDB operation did not complete within 10000 ms");
+}
+
+#[test]
+fn test_completed_transaction_beats_timeout() {
+  let done = std::future::ready(Ok::<u32, KeyVaultDBError>(7));
+  let result = block_on(timeout::race(done, std::future::pending(), 10_000));
+  assert_eq!(result.unwrap(), 7);
+}
+
+#[test]
+fn test_timer_is_dropped_when_the_operation_completes() {
+  // Dropping the timer is what clears its `setTimeout`, see `timeout::Sleep`
+  use std::task::{Context, Poll};
+  struct Timer<'a>(&'a std::cell::Cell<bool>);
+  impl std::future::Future for Timer<'_> {
+    type Output = ();
+    fn poll(self: std::pin::Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+      Poll::Pending
+    }
+  }
+  impl Drop for Timer<'_> {
+    fn drop(&mut self) {
+      self.0.set(true);
+    }
+  }
+  let dropped = std::cell::Cell::new(false);
+  let done = std::future::ready(Ok::<u32, KeyVaultDBError>(7));
+  assert_eq!(block_on(timeout::race(done, Timer(&dropped), 10_000)).unwrap(), 7);
+  assert!(dropped.get());
+}
+
+#[test]
+fn test_seed_is_written_unless_one_exists() {
+  // The refusal itself is covered end to end by `storage::tests::test_vault_refuses_to_import_over_a_seed_unless_forced`
+  assert!(should_write_seed(false, false));
+  assert!(should_write_seed(false, true));
+  assert!(!should_write_seed(true, false));
+  assert!(should_write_seed(true, true));
+}
+
+#[test]
+fn test_restored_seed_never_replaces_one_with_accounts() {
+  assert!(should_restore_seed(false, false, false));
+  assert!(should_restore_seed(false, true, false));
+  assert!(!should_restore_seed(true, false, false));
+  assert!(should_restore_seed(true, false, true));
+  assert!(!should_restore_seed(true, true, true));
+}
+
+#[test]
+fn test_purge_picks_only_watch_only_accounts() {
+  let account = |index: u32, lock_args: &str, signing: bool| {
+    let mut record = serde_json::json!({ "index": index, "lock_args": lock_args, "pub_key": "00" });
+    if signing {
+      record["pri_enc"] = serde_json::json!({ "salt": "00", "iv": "00", "cipher_text": "00", "version": 1 });
+    }
+    serde_json::from_value::<SphincsPlusAccount>(record).unwrap()
+  };
+  let accounts = vec![
+    account(0, "aa", true),
+    account(WATCH_ONLY_INDEX_BASE, "bb", false),
+    account(1, "cc", true),
+    account(WATCH_ONLY_INDEX_BASE + 1, "dd", false),
+  ];
+  assert_eq!(watch_only_lock_args(&accounts), vec!["bb", "dd"]);
+  assert!(watch_only_lock_args(&accounts[..1]).is_empty());
+}
+
+#[test]
+fn test_sealed_vault_refuses_operations() {
+  let err = check_not_sealed(true).unwrap_err();
+  assert!(matches!(err, KeyVaultDBError::VaultSealed(_)));
+  assert!(err.to_string().starts_with("Vault sealed"));
+  assert!(check_not_sealed(false).is_ok());
+}
+
+#[test]
+fn test_paging_through_gapped_indices() {
+  // What a cursor over the by-index index yields: records in index order, with gaps left by the indices
+  let records: Vec<serde_json::Value> = [0u32, 2, 5, 9, 14]
+    .iter()
+    .map(|index| {
+      serde_json::json!({
+        "index": index,
+        "lock_args": format!("{:02x}", index),
+        "pri_enc": { "salt": "00", "iv": "00", "cipher_text": "00", "version": 1 }
+      })
+    })
+    .collect();
+  let page = |offset: usize, limit: usize| -> Vec<String> {
+    let window = records.iter().skip(offset).take(limit).cloned();
+    let (accounts, _) = partition_account_records(window.map(serde_json::from_value::<SphincsPlusAccount>));
+    accounts.into_iter().map(|account| account.lock_args).collect()
+  };
+  assert_eq!(page(0, 2), vec!["00", "02"]);
+  assert_eq!(page(2, 2), vec!["05", "09"]);
+  assert_eq!(page(4, 2), vec!["0e"]);
+  assert!(page(6, 2).is_empty());
+}
+
+#[test]
+fn test_next_index_is_never_reused() {
+  assert_eq!(next_index_after(None, []).unwrap(), 0);
+  assert_eq!(next_index_after(None, [0, 1, 2]).unwrap(), 3);
+  // Legacy databases have no counter; a gap left by a deleted account is not reused
+  assert_eq!(next_index_after(None, [0, 2]).unwrap(), 3);
+  // The highest account was deleted, only the counter remembers it
+  assert_eq!(next_index_after(Some(5.0), [0, 1]).unwrap(), 5);
+  assert_eq!(next_index_after(Some(2.0), [0, 1, 4]).unwrap(), 5);
+  // Three accounts created, the middle one deleted: the fourth gets index 3, not 2
+  let stored = next_index_after(None, [0, 1, 2]).unwrap();
+  assert_eq!(next_index_after(Some(stored as f64), [0, 2]).unwrap(), 3);
+}
+
+#[test]
+fn test_next_index_survives_replacing_the_accounts() {
+  // Accounts 0..=4 created and 4 deleted, then the password changed: the re-encrypted accounts 0..=3
+  // must not bring index 4 back
+  assert_eq!(next_index_after(Some(5.0), [0, 1, 2, 3]).unwrap(), 5);
+}
+
+#[test]
+fn test_watch_only_indices_are_apart_from_derivation_indices() {
+  let watch_only = WATCH_ONLY_INDEX_BASE;
+  // A watch-only account doesn't advance the derivation counter
+  assert_eq!(next_index_after(Some(2.0), [0, 1, watch_only]).unwrap(), 2);
+  assert_eq!(next_watch_only_index([0, 1]).unwrap(), watch_only);
+  assert_eq!(next_watch_only_index([0, watch_only, 1, watch_only + 4]).unwrap(), watch_only + 5);
+  assert!(next_watch_only_index([u32::MAX]).is_err());
+}
+
+#[test]
+fn test_last_derivation_index_does_not_overflow() {
+  assert_eq!(next_index_after(None, [WATCH_ONLY_INDEX_BASE - 2]).unwrap(), WATCH_ONLY_INDEX_BASE - 1);
+  let err = next_index_after(None, [0, WATCH_ONLY_INDEX_BASE - 1]).unwrap_err();
+  assert!(err.to_string().contains("out of the derivation index range"));
+}
+
+#[test]
+fn test_hex_payload_upgrade_keeps_associated_data_semantics() {
+  let payload = |version: u8| CipherPayload { salt: Vec::new(), iv: Vec::new(), cipher_text: Vec::new(), version, kdf: None };
+  let mut bound = payload(1);
+  upgrade_hex_payload(&mut bound);
+  assert_eq!(bound.version, CIPHER_PAYLOAD_VERSION);
+  let mut legacy = payload(0);
+  upgrade_hex_payload(&mut legacy);
+  assert_eq!(legacy.version, 0);
+}
+
+#[test]
+fn test_replacement_is_refused_when_the_store_changed_since_read() {
+  let seed = vec![1u8, 2, 3];
+  let read = vec!["aa".to_string(), "bb".to_string()];
+  assert!(check_unchanged_since_read(&seed, Some(&seed), &read, &["bb".to_string(), "aa".to_string()]).is_ok());
+  assert!(check_unchanged_since_read(&seed, Some(&seed), &[], &[]).is_ok());
+
+  let added = ["aa".to_string(), "bb".to_string(), "cc".to_string()];
+  assert!(matches!(
+    check_unchanged_since_read(&seed, Some(&seed), &read, &added),
+    Err(KeyVaultDBError::DatabaseError(_))
+  ));
+  assert!(check_unchanged_since_read(&seed, Some(&seed), &read, &read[..1]).is_err());
+  assert!(check_unchanged_since_read(&seed, Some(&[9u8, 9, 9]), &read, &read).is_err());
+  assert!(check_unchanged_since_read(&seed, None, &read, &read).is_err());
+}
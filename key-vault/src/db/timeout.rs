@@ -0,0 +1,137 @@
+use super::errors::KeyVaultDBError;
+use std::future::{poll_fn, Future};
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys::{Function, Promise, Reflect};
+
+/// Runs a database operation, giving up with `StorageTimeout` if it doesn't complete within the configured
+/// `config::db_timeout_ms`. Guards against IndexedDB transactions that hang on storage contention or other tabs.
+///
+/// **Parameters**:
+/// - `op: F` - The database operation.
+///
+/// **Returns**:
+/// - `Result<T, KeyVaultDBError>` - The operation's result, or `StorageTimeout` if the timeout passes first.
+///
+/// **Async**: Yes
+///
+/// **Note**: An abandoned IndexedDB transaction is not rolled back by the timeout itself; it aborts when
+/// dropped before committing, or completes later in the background.
+pub async fn with_timeout<T, F>(op: F) -> Result<T, KeyVaultDBError>
+where
+    F: Future<Output = Result<T, KeyVaultDBError>>,
+{
+    let timeout_ms = crate::config::db_timeout_ms();
+    race(op, sleep(timeout_ms), timeout_ms).await
+}
+
+/// Races `op` against `timer` - the `Promise.race` of `with_timeout`, split out so it runs with any timer.
+///
+/// **Parameters**:
+/// - `op: F` - The database operation.
+/// - `timer: S` - A future completing when the timeout passes.
+/// - `timeout_ms: u32` - The timeout, for the error message.
+///
+/// **Returns**:
+/// - `Result<T, KeyVaultDBError>` - The operation's result, or `StorageTimeout` if `timer` completes first.
+///
+/// **Async**: Yes
+pub async fn race<T, F, S>(op: F, timer: S, timeout_ms: u32) -> Result<T, KeyVaultDBError>
+where
+    F: Future<Output = Result<T, KeyVaultDBError>>,
+    S: Future<Output = ()>,
+{
+    let mut op = pin!(op);
+    let mut timer = pin!(timer);
+    poll_fn(|cx| {
+        if let Poll::Ready(result) = op.as_mut().poll(cx) {
+            return Poll::Ready(result);
+        }
+        if timer.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Err(KeyVaultDBError::StorageTimeout(format!(
+                "IndexedDB operation did not complete within {} ms",
+                timeout_ms
+            ))));
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+/// A pending global `setTimeout`, cleared when dropped so an operation that completes first doesn't leave its timer
+/// running. Nested `with_timeout` calls would otherwise pile up one live timer per finished operation.
+struct Sleep {
+    promise: JsFuture,
+    handle: Option<JsValue>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let fired = Pin::new(&mut self.promise).poll(cx).is_ready();
+        if fired {
+            self.handle = None;
+            return Poll::Ready(());
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let (Some(handle), Some(clear_timeout)) =
+            (self.handle.take(), global_function("clearTimeout"))
+        {
+            let _ = clear_timeout.call1(&JsValue::NULL, &handle);
+        }
+    }
+}
+
+/// Resolves after `ms` milliseconds through the global `setTimeout`, available in windows and workers alike.
+fn sleep(ms: u32) -> Sleep {
+    let mut handle = None;
+    let promise = Promise::new(&mut |resolve, _reject| {
+        if let Some(set_timeout) = global_function("setTimeout") {
+            handle = set_timeout
+                .call2(&JsValue::NULL, &resolve, &JsValue::from(ms))
+                .ok();
+        }
+    });
+    Sleep {
+        promise: JsFuture::from(promise),
+        handle,
+    }
+}
+
+/// Yields to the event loop through a zero-delay `setTimeout`, so a long computation lets the page render and run
+/// callbacks in between its steps. Resolves on the next microtask on hosts without `setTimeout`.
+///
+/// **Async**: Yes
+#[cfg(target_arch = "wasm32")]
+pub async fn yield_now() {
+    let promise = Promise::new(
+        &mut |resolve, _reject| match global_function("setTimeout") {
+            Some(set_timeout) => {
+                let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(0));
+            }
+            None => {
+                let _ = resolve.call0(&JsValue::NULL);
+            }
+        },
+    );
+    let _ = JsFuture::from(promise).await;
+}
+
+/// Outside wasm there is no event loop to yield to, so native tests run the computation straight through.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn yield_now() {}
+
+/// The global function `name`, e.g. `setTimeout`, if the host has one.
+fn global_function(name: &str) -> Option<Function> {
+    Reflect::get(&web_sys::js_sys::global(), &JsValue::from_str(name))
+        .ok()
+        .and_then(|f| f.dyn_into::<Function>().ok())
+}
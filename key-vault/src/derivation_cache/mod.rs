@@ -0,0 +1,94 @@
+//! Opt-in, bounded in-memory cache of derived SPHINCS+ key pairs, keyed by (seed hash, variant, index, KDF parameters).
+//! Each derivation costs three Scrypt runs, so workflows that re-derive the same indices
+//! (overlapping `try_gen_account_batch` ranges, recovery checks) skip the KDF on a hit.
+
+use super::secure_vec::SecureVec;
+use super::types::{DerivationParams, SphincsVariant};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+#[cfg(test)]
+mod tests;
+
+type CacheKey = ([u8; 32], SphincsVariant, u32, DerivationParams);
+
+/// Derived key pairs, evicted oldest first once `capacity` is reached. A capacity of 0 disables caching.
+/// Evicted and cleared entries are zeroized on drop.
+#[derive(Default)]
+pub struct DerivationCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, (SecureVec, SecureVec)>,
+    order: VecDeque<CacheKey>,
+}
+
+impl DerivationCache {
+    /// Sets the maximum number of cached key pairs, dropping the oldest entries beyond it.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.order.len() > capacity {
+            if let Some(key) = self.order.pop_front() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    /// Drops every cached key pair.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    /// Returns the cached key pair of `(seed, variant, index, params)`, or derives and caches it.
+    ///
+    /// **Parameters**:
+    /// - `seed: &[u8]` - The master seed. Only its hash is kept as part of the cache key.
+    /// - `variant: SphincsVariant` - The variant the key pair is derived for. The same seed and index derive a
+    ///   different key pair in each variant.
+    /// - `index: u32` - The index of the child key.
+    /// - `params: DerivationParams` - The derivation KDF parameters. Keys derived with different ones differ.
+    /// - `derive: F` - The derivation to run on a cache miss.
+    ///
+    /// **Returns**:
+    /// - `Result<(SecureVec, SecureVec), String>` - The public and private key on success, or an error message on failure.
+    pub fn get_or_derive<F>(
+        &mut self,
+        seed: &[u8],
+        variant: SphincsVariant,
+        index: u32,
+        params: DerivationParams,
+        derive: F,
+    ) -> Result<(SecureVec, SecureVec), String>
+    where
+        F: FnOnce(&[u8], u32) -> Result<(SecureVec, SecureVec), String>,
+    {
+        if self.capacity == 0 {
+            return derive(seed, index);
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"quantum-purse/derivation-cache/");
+        hasher.update(seed);
+        let key: CacheKey = (hasher.finalize().into(), variant, index, params);
+        if let Some((pub_key, pri_key)) = self.entries.get(&key) {
+            return Ok((
+                SecureVec::from_slice(pub_key),
+                SecureVec::from_slice(pri_key),
+            ));
+        }
+
+        let (pub_key, pri_key) = derive(seed, index)?;
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(
+            key,
+            (
+                SecureVec::from_slice(&pub_key),
+                SecureVec::from_slice(&pri_key),
+            ),
+        );
+        self.order.push_back(key);
+        Ok((pub_key, pri_key))
+    }
+}
@@ -0,0 +1,117 @@
+use super::*;
+use std::cell::Cell;
+
+const VARIANT: SphincsVariant = SphincsVariant::Sha2128F;
+
+fn cost(log_n: u8) -> DerivationParams {
+  DerivationParams { log_n, single_scrypt: false }
+}
+
+fn counting_derive(calls: &Cell<u32>) -> impl Fn(&[u8], u32) -> Result<(SecureVec, SecureVec), String> + '_ {
+  move |seed, index| {
+    calls.set(calls.get() + 1);
+    Ok((SecureVec::from_slice(&[seed[0], index as u8]), SecureVec::from_slice(&[index as u8; 4])))
+  }
+}
+
+#[test]
+fn test_cache_hit_skips_derivation() {
+  let calls = Cell::new(0);
+  let mut cache = DerivationCache::default();
+  cache.set_capacity(8);
+
+  let (pub_key, pri_key) = cache.get_or_derive(&[7; 64], VARIANT, 3, cost(10), counting_derive(&calls)).unwrap();
+  let (cached_pub_key, cached_pri_key) = cache.get_or_derive(&[7; 64], VARIANT, 3, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 1);
+  assert_eq!(&*pub_key, &*cached_pub_key);
+  assert_eq!(&*pri_key, &*cached_pri_key);
+
+  cache.get_or_derive(&[8; 64], VARIANT, 3, cost(10), counting_derive(&calls)).unwrap();
+  cache.get_or_derive(&[7; 64], VARIANT, 4, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 3);
+}
+
+#[test]
+fn test_cache_disabled_by_default() {
+  let calls = Cell::new(0);
+  let mut cache = DerivationCache::default();
+  cache.get_or_derive(&[7; 64], VARIANT, 0, cost(10), counting_derive(&calls)).unwrap();
+  cache.get_or_derive(&[7; 64], VARIANT, 0, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn test_cache_is_bounded_and_clearable() {
+  let calls = Cell::new(0);
+  let mut cache = DerivationCache::default();
+  cache.set_capacity(2);
+  for index in 0..3 {
+    cache.get_or_derive(&[7; 64], VARIANT, index, cost(10), counting_derive(&calls)).unwrap();
+  }
+  assert_eq!(cache.entries.len(), 2);
+
+  // Index 0 was evicted, index 2 is still cached
+  cache.get_or_derive(&[7; 64], VARIANT, 2, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 3);
+  cache.get_or_derive(&[7; 64], VARIANT, 0, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 4);
+
+  cache.clear();
+  assert!(cache.entries.is_empty());
+  cache.get_or_derive(&[7; 64], VARIANT, 2, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 5);
+}
+
+#[test]
+fn test_cache_separates_derivation_costs() {
+  let calls = Cell::new(0);
+  let mut cache = DerivationCache::default();
+  cache.set_capacity(8);
+  cache.get_or_derive(&[7; 64], VARIANT, 0, cost(10), counting_derive(&calls)).unwrap();
+  cache.get_or_derive(&[7; 64], VARIANT, 0, cost(4), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 2);
+  let single_scrypt = DerivationParams { single_scrypt: true, ..cost(10) };
+  cache.get_or_derive(&[7; 64], VARIANT, 0, single_scrypt, counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 3);
+}
+
+#[test]
+fn test_cache_separates_variants() {
+  let calls = Cell::new(0);
+  let mut cache = DerivationCache::default();
+  cache.set_capacity(8);
+  cache.get_or_derive(&[7; 64], VARIANT, 0, cost(10), counting_derive(&calls)).unwrap();
+  cache.get_or_derive(&[7; 64], SphincsVariant::Shake128F, 0, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 2);
+  cache.get_or_derive(&[7; 64], VARIANT, 0, cost(10), counting_derive(&calls)).unwrap();
+  cache.get_or_derive(&[7; 64], SphincsVariant::Shake128F, 0, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn test_cache_keeps_no_failed_derivation() {
+  let calls = Cell::new(0);
+  let mut cache = DerivationCache::default();
+  cache.set_capacity(8);
+  let failing = |_: &[u8], _: u32| -> Result<(SecureVec, SecureVec), String> { Err("derivation failed".to_string()) };
+  assert!(cache.get_or_derive(&[7; 64], VARIANT, 0, cost(10), failing).is_err());
+  assert!(cache.entries.is_empty());
+  cache.get_or_derive(&[7; 64], VARIANT, 0, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn test_shrinking_capacity_evicts_oldest() {
+  let calls = Cell::new(0);
+  let mut cache = DerivationCache::default();
+  cache.set_capacity(4);
+  for index in 0..4 {
+    cache.get_or_derive(&[7; 64], VARIANT, index, cost(10), counting_derive(&calls)).unwrap();
+  }
+  cache.set_capacity(1);
+  assert_eq!(cache.entries.len(), 1);
+  cache.get_or_derive(&[7; 64], VARIANT, 3, cost(10), counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 4);
+  cache.set_capacity(0);
+  assert!(cache.entries.is_empty());
+}
@@ -0,0 +1,155 @@
+//! The error type of the public `KeyVault` and `Util` API. It reaches JavaScript as an `Error` whose `code`
+//! property is stable across releases, so callers can branch on it instead of matching messages.
+
+use super::db::KeyVaultDBError;
+use std::fmt;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::js_sys::{Error, Reflect};
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug)]
+pub enum KeyVaultError {
+    /// No mnemonic phrase is stored yet.
+    MnemonicNotFound,
+    /// No account is stored under the given lock_args.
+    AccountNotFound(String),
+    /// Decryption with the given password failed on a well-formed payload: the password is wrong.
+    WrongPassword(String),
+    /// An encrypted payload is malformed, e.g. its salt or IV has the wrong length, so no password opens it.
+    CorruptedPayload(String),
+    /// Deriving a SPHINCS+ key pair failed.
+    KeyDerivation(String),
+    /// A database operation failed, see `KeyVaultDBError`.
+    Database(KeyVaultDBError),
+    /// An argument was rejected.
+    InvalidInput(String),
+    /// The `KeyVault` variant differs from the one the wallet was set up with.
+    VariantMismatch(String),
+    /// The account is watch-only: it holds a public key but no private key, so it can't sign.
+    WatchOnly(String),
+    /// Too many wrong passwords in a row; unlocking is refused until the cooldown ends or the attempts are cleared.
+    Locked(String),
+    /// Another operation conflicts with this one; retry once it completes.
+    Busy(String),
+    /// Anything else, e.g. a failure of the browser's crypto or an exception thrown by a callback.
+    Internal(String),
+}
+
+impl KeyVaultError {
+    /// The stable code JavaScript callers branch on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            KeyVaultError::MnemonicNotFound => "MNEMONIC_NOT_FOUND",
+            KeyVaultError::AccountNotFound(_) => "ACCOUNT_NOT_FOUND",
+            KeyVaultError::WrongPassword(_) => "WRONG_PASSWORD",
+            KeyVaultError::CorruptedPayload(_) => "CORRUPTED_PAYLOAD",
+            KeyVaultError::KeyDerivation(_) => "KEY_DERIVATION",
+            KeyVaultError::Database(KeyVaultDBError::VaultSealed(_)) => "VAULT_SEALED",
+            KeyVaultError::Database(KeyVaultDBError::StorageQuotaExceeded(_)) => {
+                "STORAGE_QUOTA_EXCEEDED"
+            }
+            KeyVaultError::Database(KeyVaultDBError::StorageTimeout(_)) => "STORAGE_TIMEOUT",
+            KeyVaultError::Database(KeyVaultDBError::DatabaseVersionTooNew(_)) => {
+                "DATABASE_VERSION_TOO_NEW"
+            }
+            KeyVaultError::Database(_) => "DATABASE",
+            KeyVaultError::InvalidInput(_) => "INVALID_INPUT",
+            KeyVaultError::VariantMismatch(_) => "VARIANT_MISMATCH",
+            KeyVaultError::WatchOnly(_) => "WATCH_ONLY",
+            KeyVaultError::Locked(_) => "LOCKED",
+            KeyVaultError::Busy(_) => "BUSY",
+            KeyVaultError::Internal(_) => "INTERNAL",
+        }
+    }
+}
+
+impl fmt::Display for KeyVaultError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyVaultError::MnemonicNotFound => write!(f, "Mnemonic phrase not found"),
+            KeyVaultError::AccountNotFound(lock_args) => {
+                write!(f, "Account {} not found", lock_args)
+            }
+            KeyVaultError::WrongPassword(msg) => write!(f, "Incorrect password: {}", msg),
+            KeyVaultError::CorruptedPayload(msg) => write!(f, "Corrupted payload: {}", msg),
+            KeyVaultError::KeyDerivation(msg) => write!(f, "Key derivation error: {}", msg),
+            KeyVaultError::Database(e) => write!(f, "{}", e),
+            KeyVaultError::VariantMismatch(msg) => write!(f, "SPHINCS+ variant mismatch: {}", msg),
+            KeyVaultError::WatchOnly(lock_args) => {
+                write!(f, "Account {} is watch-only and can't sign", lock_args)
+            }
+            KeyVaultError::Locked(msg) => write!(f, "Vault locked: {}", msg),
+            KeyVaultError::InvalidInput(msg)
+            | KeyVaultError::Busy(msg)
+            | KeyVaultError::Internal(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// Builds a JavaScript `Error` with the message and a `code` property.
+impl From<KeyVaultError> for JsValue {
+    fn from(e: KeyVaultError) -> Self {
+        let error = Error::new(&e.to_string());
+        // Setting a property on a fresh, extensible Error object can't fail
+        let _ = Reflect::set(
+            &error,
+            &JsValue::from_str("code"),
+            &JsValue::from_str(e.code()),
+        );
+        error.into()
+    }
+}
+
+/// Why `decrypt` failed. A payload is checked for well-formedness before decryption is attempted, so an
+/// authentication failure is left to mean a wrong password.
+#[derive(Debug, PartialEq)]
+pub enum DecryptError {
+    /// The payload is malformed.
+    CorruptedPayload(String),
+    /// The payload is well-formed but doesn't authenticate under the password.
+    WrongPassword(String),
+}
+
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecryptError::CorruptedPayload(msg) | DecryptError::WrongPassword(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<DecryptError> for KeyVaultError {
+    fn from(e: DecryptError) -> Self {
+        match e {
+            DecryptError::CorruptedPayload(msg) => KeyVaultError::CorruptedPayload(msg),
+            DecryptError::WrongPassword(msg) => KeyVaultError::WrongPassword(msg),
+        }
+    }
+}
+
+impl From<KeyVaultDBError> for KeyVaultError {
+    fn from(e: KeyVaultDBError) -> Self {
+        KeyVaultError::Database(e)
+    }
+}
+
+impl From<serde_wasm_bindgen::Error> for KeyVaultError {
+    fn from(e: serde_wasm_bindgen::Error) -> Self {
+        KeyVaultError::Internal(format!("Serialization error: {}", e))
+    }
+}
+
+/// A JavaScript exception, e.g. thrown by a progress callback.
+impl From<JsValue> for KeyVaultError {
+    fn from(e: JsValue) -> Self {
+        let msg = e
+            .as_string()
+            .or_else(|| {
+                e.dyn_ref::<Error>()
+                    .map(|error| String::from(error.message()))
+            })
+            .unwrap_or_else(|| format!("{:?}", e));
+        KeyVaultError::Internal(msg)
+    }
+}
@@ -0,0 +1,41 @@
+use super::*;
+
+#[test]
+fn test_error_codes_are_stable() {
+  assert_eq!(KeyVaultError::MnemonicNotFound.code(), "MNEMONIC_NOT_FOUND");
+  assert_eq!(KeyVaultError::AccountNotFound("aa".to_string()).code(), "ACCOUNT_NOT_FOUND");
+  assert_eq!(KeyVaultError::WrongPassword("x".to_string()).code(), "WRONG_PASSWORD");
+  assert_eq!(KeyVaultError::CorruptedPayload("x".to_string()).code(), "CORRUPTED_PAYLOAD");
+  assert_eq!(KeyVaultError::KeyDerivation("x".to_string()).code(), "KEY_DERIVATION");
+  assert_eq!(KeyVaultError::InvalidInput("x".to_string()).code(), "INVALID_INPUT");
+  assert_eq!(KeyVaultError::VariantMismatch("x".to_string()).code(), "VARIANT_MISMATCH");
+  assert_eq!(KeyVaultError::WatchOnly("aa".to_string()).code(), "WATCH_ONLY");
+  assert_eq!(KeyVaultError::Locked("x".to_string()).code(), "LOCKED");
+  assert_eq!(KeyVaultError::Busy("x".to_string()).code(), "BUSY");
+  assert_eq!(KeyVaultError::Internal("x".to_string()).code(), "INTERNAL");
+}
+
+#[test]
+fn test_database_errors_keep_their_kind() {
+  let sealed = KeyVaultError::from(KeyVaultDBError::VaultSealed("unseal first".to_string()));
+  assert_eq!(sealed.code(), "VAULT_SEALED");
+  assert_eq!(sealed.to_string(), "Vault sealed: unseal first");
+  assert_eq!(KeyVaultError::from(KeyVaultDBError::StorageTimeout("x".to_string())).code(), "STORAGE_TIMEOUT");
+  assert_eq!(KeyVaultError::from(KeyVaultDBError::DatabaseError("x".to_string())).code(), "DATABASE");
+}
+
+#[test]
+fn test_error_messages() {
+  assert_eq!(KeyVaultError::MnemonicNotFound.to_string(), "Mnemonic phrase not found");
+  assert_eq!(KeyVaultError::AccountNotFound("aa".to_string()).to_string(), "Account aa not found");
+  assert_eq!(KeyVaultError::WatchOnly("aa".to_string()).to_string(), "Account aa is watch-only and can't sign");
+  let rejected = KeyVaultError::InvalidInput("Password must not be empty".to_string());
+  assert_eq!(rejected.code(), "INVALID_INPUT");
+  assert_eq!(rejected.to_string(), "Password must not be empty");
+}
+
+#[test]
+fn test_decrypt_errors_keep_their_kind() {
+  assert_eq!(KeyVaultError::from(DecryptError::WrongPassword("x".to_string())).code(), "WRONG_PASSWORD");
+  assert_eq!(KeyVaultError::from(DecryptError::CorruptedPayload("x".to_string())).code(), "CORRUPTED_PAYLOAD");
+}
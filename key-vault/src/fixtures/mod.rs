@@ -0,0 +1,57 @@
+//! Deterministic accounts for downstream integration tests, derived from a fixed, public seed.
+//! Only built with the `test-fixtures` feature (and for this crate's own tests), never in production builds.
+//! The seed is public: never send funds to fixture accounts.
+
+use super::types::{DerivationParams, SphincsVariant};
+use super::KeyVault;
+use hex::encode;
+use serde::Serialize;
+#[cfg(test)]
+mod tests;
+
+/// The fixed test seed: the 96 bytes 0x00, 0x01, ..., 0x5f. Variants needing less entropy use its prefix.
+pub const FIXTURE_SEED: [u8; 96] = {
+    let mut seed = [0u8; 96];
+    let mut i = 0;
+    while i < 96 {
+        seed[i] = i as u8;
+        i += 1;
+    }
+    seed
+};
+
+/// A fixture account: its index, hex-encoded lock script arguments and hex-encoded SPHINCS+ public key.
+#[derive(Serialize, Debug)]
+pub struct FixtureAccount {
+    pub index: u32,
+    pub lock_args: String,
+    pub pub_key: String,
+}
+
+/// Derives the first `count` accounts of `variant` from `FIXTURE_SEED`, exactly as a wallet initialized
+/// with that seed would.
+///
+/// **Parameters**:
+/// - `variant: SphincsVariant` - The SPHINCS+ parameter set.
+/// - `count: u32` - The number of accounts, from index 0.
+///
+/// **Returns**:
+/// - `Result<Vec<FixtureAccount>, String>` - The accounts on success, or an error message on failure.
+pub fn fixture_accounts(
+    variant: SphincsVariant,
+    count: u32,
+) -> Result<Vec<FixtureAccount>, String> {
+    let vault = KeyVault::with_variant(variant);
+    let seed = &FIXTURE_SEED[..variant.bip39_compatible_entropy_size()];
+    (0..count)
+        .map(|index| {
+            let (pub_key, _) =
+                vault.derive_sphincs_key(seed, index, DerivationParams::default())?;
+            Ok(FixtureAccount {
+                index,
+                lock_args: encode(vault.get_lock_scrip_arg(&pub_key)),
+                pub_key: encode(&*pub_key),
+            })
+        })
+        .collect()
+}
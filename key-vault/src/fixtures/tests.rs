@@ -0,0 +1,22 @@
+use super::*;
+
+// Golden values, also produced by a wallet whose mnemonic encodes FIXTURE_SEED. Changing any of them
+// means derivation changed and existing wallets would no longer recover their accounts.
+#[test]
+fn test_sha2_128f_fixture_accounts() {
+  let accounts = fixture_accounts(SphincsVariant::Sha2128F, 2).unwrap();
+  assert_eq!(accounts[0].lock_args, "2c253f3eff927ab2c4cf3a10182d574960755398b6af055d9aa683f83c3a12b8");
+  assert_eq!(accounts[0].pub_key, "488ef28ae29aed5798b772ca061cce8d4caae171fd065bee7d43fda4724ba2e7");
+  assert_eq!(accounts[1].lock_args, "10e04aac4d6e327ca5369206122681168f1b9fe3feec05389815904fa83fa092");
+  assert_eq!(accounts[1].pub_key, "ba32ab041dea1a82e89d89de0f89be4f7bd6c9db6cd7cbfde712c580963468ae");
+}
+
+#[test]
+fn test_shake_192f_fixture_accounts() {
+  let accounts = fixture_accounts(SphincsVariant::Shake192F, 1).unwrap();
+  assert_eq!(accounts[0].lock_args, "0a45e2feb1a5c99013ce0d01d6d2d613209288eb4d9689e11df7e08aaa9484cf");
+  assert_eq!(
+    accounts[0].pub_key,
+    "0a4314e8e5c36793702ab18b3dc4da4528b56135bb8a889bd2b2715921bc9d50161c90bbff09b2229c5eb9d0d5b96e55"
+  );
+}
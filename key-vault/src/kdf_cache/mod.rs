@@ -0,0 +1,157 @@
+//! Bounded in-memory cache of Scrypt-derived decryption keys, keyed by an HMAC of (password, salt, Scrypt parameters)
+//! under a random key of the session. Every `decrypt` re-runs the password KDF, so decrypting the same payload again
+//! (the mnemonic seed on each `gen_new_account`, an account key on each `sign`) skips it on a hit. `encrypt` draws a
+//! fresh salt every time and never hits, so it bypasses the cache. Wasm runs on one thread, so the cache is a thread
+//! local shared by every `KeyVault`.
+
+use super::constants::KDF_CACHE_CAPACITY;
+use super::secure_vec::SecureVec;
+use super::types::ScryptParam;
+use super::utilities::get_random_bytes;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use zeroize::Zeroize;
+#[cfg(test)]
+mod tests;
+
+/// The HMAC-SHA256 of the password, salt and Scrypt parameters. Without the session key it can't be tested against
+/// password guesses, unlike a plain hash of the password.
+type CacheKey = [u8; 32];
+
+thread_local! {
+    static KDF_CACHE: RefCell<KdfCache> = RefCell::new(KdfCache::new(KDF_CACHE_CAPACITY));
+}
+
+/// Derived keys, evicted least recently used first once `capacity` is reached. A capacity of 0 disables caching.
+/// Evicted and cleared entries, cache keys included, are zeroized.
+pub struct KdfCache {
+    capacity: usize,
+    session_key: Option<SecureVec>,
+    entries: HashMap<CacheKey, SecureVec>,
+    order: VecDeque<CacheKey>,
+}
+
+impl KdfCache {
+    /// Creates an empty cache holding at most `capacity` keys. The session key is drawn on first use.
+    pub fn new(capacity: usize) -> Self {
+        KdfCache {
+            capacity,
+            session_key: None,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Drops every cached key, zeroizing the derived keys and the cache keys.
+    pub fn clear(&mut self) {
+        for (mut key, _) in self.entries.drain() {
+            key.zeroize();
+        }
+        for key in self.order.iter_mut() {
+            key.zeroize();
+        }
+        self.order.clear();
+    }
+
+    /// Computes the cache key of `(password, salt, param)` under the session key, drawing it on first use.
+    fn cache_key(&mut self, password: &[u8], salt: &[u8], param: &ScryptParam) -> Result<CacheKey, String> {
+        if self.session_key.is_none() {
+            let session_key = get_random_bytes(32).map_err(|e| format!("Random generation error: {}", e))?;
+            self.session_key = Some(session_key);
+        }
+        let session_key = self.session_key.as_ref().expect("Session key drawn above");
+        let mut mac = Hmac::<Sha256>::new_from_slice(session_key).expect("HMAC takes keys of any length");
+        // Length-prefixed, so no two inputs share an encoding
+        mac.update(&(password.len() as u64).to_le_bytes());
+        mac.update(password);
+        mac.update(&(salt.len() as u64).to_le_bytes());
+        mac.update(salt);
+        mac.update(&[param.log_n]);
+        mac.update(&param.r.to_le_bytes());
+        mac.update(&param.p.to_le_bytes());
+        mac.update(&(param.len as u64).to_le_bytes());
+        Ok(mac.finalize().into_bytes().into())
+    }
+
+    /// Returns the cached key of `(password, salt, param)`, or derives and caches it.
+    ///
+    /// **Parameters**:
+    /// - `password: &[u8]` - The password. Only the HMAC covering it is kept.
+    /// - `salt: &[u8]` - The salt of the payload. Only the HMAC covering it is kept.
+    /// - `param: &ScryptParam` - The Scrypt parameters. Keys derived with different parameters differ.
+    /// - `derive: F` - The derivation to run on a cache miss.
+    ///
+    /// **Returns**:
+    /// - `Result<SecureVec, String>` - The derived key on success, or an error message on failure.
+    pub fn get_or_derive<F>(
+        &mut self,
+        password: &[u8],
+        salt: &[u8],
+        param: &ScryptParam,
+        derive: F,
+    ) -> Result<SecureVec, String>
+    where
+        F: FnOnce() -> Result<SecureVec, String>,
+    {
+        if self.capacity == 0 {
+            return derive();
+        }
+
+        let mut key = self.cache_key(password, salt, param)?;
+        if let Some(scrypt_key) = self.entries.get(&key) {
+            let scrypt_key = SecureVec::from_slice(scrypt_key);
+            if let Some(mut used) = self
+                .order
+                .iter()
+                .position(|cached| cached == &key)
+                .and_then(|position| self.order.remove(position))
+            {
+                used.zeroize();
+            }
+            self.order.push_back(key);
+            key.zeroize();
+            return Ok(scrypt_key);
+        }
+
+        let scrypt_key = match derive() {
+            Ok(scrypt_key) => scrypt_key,
+            Err(e) => {
+                key.zeroize();
+                return Err(e);
+            }
+        };
+        if self.order.len() == self.capacity {
+            if let Some(mut oldest) = self.order.pop_front() {
+                if let Some((mut evicted, _)) = self.entries.remove_entry(&oldest) {
+                    evicted.zeroize();
+                }
+                oldest.zeroize();
+            }
+        }
+        self.entries.insert(key, SecureVec::from_slice(&scrypt_key));
+        self.order.push_back(key);
+        key.zeroize();
+        Ok(scrypt_key)
+    }
+}
+
+/// Returns the cached key of `(password, salt, param)` from the shared cache, or derives and caches it,
+/// see `KdfCache::get_or_derive`.
+pub fn get_or_derive<F>(
+    password: &[u8],
+    salt: &[u8],
+    param: &ScryptParam,
+    derive: F,
+) -> Result<SecureVec, String>
+where
+    F: FnOnce() -> Result<SecureVec, String>,
+{
+    KDF_CACHE.with(|cache| cache.borrow_mut().get_or_derive(password, salt, param, derive))
+}
+
+/// Drops every key of the shared cache.
+pub fn clear() {
+    KDF_CACHE.with(|cache| cache.borrow_mut().clear());
+}
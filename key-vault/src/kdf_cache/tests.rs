@@ -0,0 +1,84 @@
+use super::*;
+use std::cell::Cell;
+
+const PARAM: ScryptParam = ScryptParam { log_n: 4, r: 8, p: 1, len: 32 };
+
+fn counting_derive(calls: &Cell<u32>) -> impl FnOnce() -> Result<SecureVec, String> + '_ {
+  move || {
+    calls.set(calls.get() + 1);
+    Ok(SecureVec::from_slice(&[calls.get() as u8; 32]))
+  }
+}
+
+#[test]
+fn test_cache_hit_skips_derivation() {
+  let calls = Cell::new(0);
+  let mut cache = KdfCache::new(8);
+
+  let key = cache.get_or_derive(b"password", &[1; 16], &PARAM, counting_derive(&calls)).unwrap();
+  let cached_key = cache.get_or_derive(b"password", &[1; 16], &PARAM, counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 1);
+  assert_eq!(&*key, &*cached_key);
+
+  cache.get_or_derive(b"other", &[1; 16], &PARAM, counting_derive(&calls)).unwrap();
+  cache.get_or_derive(b"password", &[2; 16], &PARAM, counting_derive(&calls)).unwrap();
+  cache.get_or_derive(b"password", &[1; 16], &ScryptParam { log_n: 5, ..PARAM }, counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 4);
+}
+
+#[test]
+fn test_least_recently_used_key_is_evicted() {
+  let calls = Cell::new(0);
+  let mut cache = KdfCache::new(2);
+  cache.get_or_derive(b"password", &[0; 16], &PARAM, counting_derive(&calls)).unwrap();
+  cache.get_or_derive(b"password", &[1; 16], &PARAM, counting_derive(&calls)).unwrap();
+  // Using salt 0 again makes salt 1 the least recently used
+  cache.get_or_derive(b"password", &[0; 16], &PARAM, counting_derive(&calls)).unwrap();
+  cache.get_or_derive(b"password", &[2; 16], &PARAM, counting_derive(&calls)).unwrap();
+  assert_eq!(cache.entries.len(), 2);
+  assert_eq!(calls.get(), 3);
+
+  cache.get_or_derive(b"password", &[0; 16], &PARAM, counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 3);
+  cache.get_or_derive(b"password", &[1; 16], &PARAM, counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 4);
+
+  cache.clear();
+  assert!(cache.entries.is_empty());
+  cache.get_or_derive(b"password", &[1; 16], &PARAM, counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 5);
+}
+
+#[test]
+fn test_zero_capacity_disables_cache() {
+  let calls = Cell::new(0);
+  let mut cache = KdfCache::new(0);
+  cache.get_or_derive(b"password", &[1; 16], &PARAM, counting_derive(&calls)).unwrap();
+  cache.get_or_derive(b"password", &[1; 16], &PARAM, counting_derive(&calls)).unwrap();
+  assert_eq!(calls.get(), 2);
+  assert!(cache.entries.is_empty());
+}
+
+#[test]
+fn test_cache_keys_are_salted_per_session() {
+  let mut cache = KdfCache::new(8);
+  let mut other_session = KdfCache::new(8);
+  let key = cache.cache_key(b"password", &[1; 16], &PARAM).unwrap();
+  assert_eq!(cache.cache_key(b"password", &[1; 16], &PARAM).unwrap(), key);
+  // Another session keys the same password differently, so a cache key can't be matched against guesses offline
+  assert_ne!(other_session.cache_key(b"password", &[1; 16], &PARAM).unwrap(), key);
+  assert_ne!(cache.cache_key(b"password", &[1; 17], &PARAM).unwrap(), key);
+  assert_ne!(cache.cache_key(b"password\x01", &[1; 15], &PARAM).unwrap(), key);
+}
+
+#[test]
+fn test_clear_empties_the_cache() {
+  let calls = Cell::new(0);
+  let mut cache = KdfCache::new(2);
+  for salt in 0..3u8 {
+    cache.get_or_derive(b"password", &[salt; 16], &PARAM, counting_derive(&calls)).unwrap();
+  }
+  assert_eq!((cache.entries.len(), cache.order.len()), (2, 2));
+  cache.clear();
+  assert_eq!((cache.entries.len(), cache.order.len()), (0, 0));
+}
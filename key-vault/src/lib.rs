@@ -6,35 +6,51 @@
 //! the BIP39 mnemonic and derived SPHINCS+ private keys, is encrypted and stored in the browser via
 //! IndexedDB, with access authenticated by user-provided passwords.
 
-use bip39::{Language, Mnemonic};
-use ckb_fips205_utils::{
-    ckb_tx_message_all_from_mock_tx::{generate_ckb_tx_message_all_from_mock_tx, ScriptOrIndex},
-    Hasher,
-};
-use ckb_mock_tx_types::{MockTransaction, ReprMockTransaction};
+use ckb_mock_tx_types::MockTransaction;
+use derivation_cache::DerivationCache;
+use errors::{DecryptError, KeyVaultError};
 use fips205::{
     traits::{KeyGen, SerDes, Signer},
     *,
 };
 use hex::encode;
-use indexed_db_futures::{
-    error::Error as DBError, iter::ArrayMapIter, prelude::*, transaction::TransactionMode,
-};
+use serde::Serialize;
 use serde_wasm_bindgen;
+use session::SessionCache;
+use storage::{IndexedDbStorage, Storage};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
 use wasm_bindgen::{prelude::*, JsValue};
-use web_sys::js_sys::Uint8Array;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys::{Function, Promise, Uint8Array};
 use zeroize::Zeroize;
 
+mod address;
+mod config;
 mod constants;
 mod db;
+mod derivation_cache;
+mod errors;
+#[cfg(any(test, feature = "test-fixtures"))]
+mod fixtures;
+mod kdf_cache;
 mod macros;
+mod operation_lock;
+mod secure_string;
 mod secure_vec;
+mod session;
+mod storage;
 mod types;
 mod utilities;
+#[cfg(test)]
+mod tests;
 
 use crate::constants::{
-    CHILD_KEYS_STORE, KDF_PATH_PREFIX, MULTISIG_RESERVED_FIELD_VALUE, PUBKEY_NUM, REQUIRED_FIRST_N,
-    SEED_PHRASE_STORE, THRESHOLD,
+    CHILD_KEYS_STORE, DB_VERSION, KDF_PATH_PREFIX, LOCK_ARGS_PAGE_SIZE, METADATA_STORE,
+    MULTISIG_RESERVED_FIELD_VALUE, PUBKEY_NUM, REQUIRED_FIRST_N, SEED_PHRASE_STORE, THRESHOLD,
+    WATCH_ONLY_INDEX_BASE,
 };
 use secure_vec::SecureVec;
 use types::*;
@@ -47,24 +63,171 @@ use utilities::*;
 pub struct KeyVault {
     /// The one parameter set chosen for QuantumPurse KeyVault setup in all 12 NIST-approved SPHINCS+ FIPS205 variants
     pub variant: SphincsVariant,
+    /// Whether signing checks that the decrypted private key belongs to `variant` before signing. On by default.
+    /// The check is one lock script arguments hash, negligible next to a SPHINCS+ signature which takes thousands of
+    /// hash calls, see `check_key_variant`.
+    pub check_variant: bool,
+    /// Decrypted private keys of the unlocked signing session, see `unlock`. Registered, so `clear_database` ends it.
+    session: Rc<RefCell<SessionCache>>,
+    /// Derived key pairs kept for re-derivations of the same indices, see `set_derivation_cache_capacity`.
+    derivation_cache: RefCell<DerivationCache>,
+    /// How lock script arguments are hashed, see `set_lock_args_hash_config`.
+    lock_args_hash_config: LockArgsHashConfig,
+    /// The KDF cost new keys are derived with, see `set_derivation_log_n`.
+    derivation_params: DerivationParams,
+    /// Where the mnemonic phrase and accounts are read and written. IndexedDB, except for tests.
+    storage: Box<dyn Storage>,
 }
 
 #[wasm_bindgen]
 impl KeyVault {
     /// Constructs a new `KeyVault` to serve as a namespace in the output js interface.
     ///
+    /// **Parameters**:
+    /// - `variant: u8` - The `SphincsVariant` id. Taken as a plain number and validated here rather than trusting the
+    ///   enum marshalling, as the lock_args sign flag `variant << 1` is only meaningful for the 12 known ids.
+    ///
     /// **Returns**:
-    /// - `KeyVault` - A new instance of the struct.
+    /// - `Result<KeyVault, KeyVaultError>` - A new instance of the struct, or a JavaScript error if `variant` is not a known id.
     #[wasm_bindgen(constructor)]
-    pub fn new(variant: SphincsVariant) -> Self {
-        KeyVault { variant: variant }
+    pub fn new(variant: u8) -> Result<KeyVault, KeyVaultError> {
+        let variant = SphincsVariant::try_from(variant).map_err(KeyVaultError::InvalidInput)?;
+        Ok(KeyVault::with_variant(variant))
+    }
+
+    /// Constructs a `KeyVault` of an already valid variant, for Rust callers.
+    fn with_variant(variant: SphincsVariant) -> Self {
+        KeyVault::with_storage(variant, Box::new(IndexedDbStorage))
+    }
+
+    /// Constructs a `KeyVault` of an already valid variant on a given storage, e.g. an `InMemoryStorage` in tests.
+    fn with_storage(variant: SphincsVariant, storage: Box<dyn Storage>) -> Self {
+        KeyVault {
+            variant: variant,
+            check_variant: true,
+            session: SessionCache::registered(),
+            derivation_cache: RefCell::new(DerivationCache::default()),
+            lock_args_hash_config: LockArgsHashConfig::default(),
+            derivation_params: DerivationParams::default(),
+            storage,
+        }
+    }
+
+    /// Constructs a `KeyVault` with the SPHINCS+ variant of the stored accounts, so the caller doesn't have to
+    /// remember the variant separately from the keys. Each account's variant is detected by matching its lock script
+    /// arguments against those of every variant its decrypted private key length fits.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the private keys.
+    ///
+    /// **Returns**:
+    /// - `Result<KeyVault, KeyVaultError>` - A JavaScript Promise that resolves to the vault on success,
+    ///   or rejects with a JavaScript error if the store is empty, holds mixed variants, or an account's variant can't be detected.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Every account's private key is decrypted once, so this takes one Scrypt run per account.
+    #[wasm_bindgen]
+    pub async fn from_store(password: Uint8Array) -> Result<KeyVault, KeyVaultError> {
+        let password = SecureVec::from_uint8_array(&password);
+        let accounts = db::get_all_accounts().await.map_err(KeyVaultError::from)?;
+        let mut detected: Vec<(String, Option<SphincsVariant>)> = Vec::new();
+        // Watch-only accounts hold no private key to detect the variant from
+        for account in accounts
+            .into_iter()
+            .filter(|account| !account.is_watch_only())
+        {
+            let pri_key = Self::decrypt_private_key(&IndexedDbStorage, &password, &account).await?;
+            let variant = Self::detect_account_variant(&pri_key, &account.lock_args);
+            detected.push((account.lock_args, variant));
+        }
+        let variant = Self::common_variant(&detected).map_err(KeyVaultError::InvalidInput)?;
+        Ok(KeyVault::with_variant(variant))
+    }
+
+    /// Configures how lock script arguments are hashed, to target lock script versions other than the deployed one.
+    ///
+    /// **Parameters**:
+    /// - `personalization: Option<Vec<u8>>` - A 16-byte Blake2b personalization, or `undefined` for the default one.
+    /// - `prefix: Vec<u8>` - Extra bytes hashed before the lock script config. Empty for the default behavior.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - `undefined` on success, or a JavaScript error if the personalization isn't 16 bytes.
+    ///
+    /// **Async**: no
+    ///
+    /// **Warning**: Any non-default config produces different lock script arguments, thus different addresses,
+    /// from the same keys. Accounts stored under one config can't be found under another.
+    #[wasm_bindgen]
+    pub fn set_lock_args_hash_config(
+        &mut self,
+        personalization: Option<Vec<u8>>,
+        prefix: Vec<u8>,
+    ) -> Result<(), KeyVaultError> {
+        let personalization = personalization
+            .map(|bytes| {
+                <[u8; 16]>::try_from(bytes.as_slice()).map_err(|_| {
+                    KeyVaultError::InvalidInput("Personalization must be 16 bytes".to_string())
+                })
+            })
+            .transpose()?;
+        self.lock_args_hash_config = LockArgsHashConfig {
+            personalization,
+            prefix,
+        };
+        Ok(())
+    }
+
+    /// Sets the Scrypt cost (log_n) new keys are derived with. Each key takes three derivations (one with
+    /// `set_derivation_single_scrypt`), so on slow devices
+    /// lowering it from the default 10 cuts account generation and recovery time. Existing accounts keep the cost they
+    /// were derived with, which is stored alongside each of them.
+    ///
+    /// **Parameters**:
+    /// - `log_n: u8` - The Scrypt cost, from 4 to 20.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - `undefined` on success, or a JavaScript error if `log_n` is out of range.
+    ///
+    /// **Async**: no
+    ///
+    /// **Warning**: Keys derived at a different cost are different keys. Recovering a wallet from its seed phrase
+    /// needs the cost its accounts were created with. The security tradeoff is small: the seed carries 512/768 bits
+    /// of entropy, so the KDF cost only slows down each guess of an already infeasible search.
+    #[wasm_bindgen]
+    pub fn set_derivation_log_n(&mut self, log_n: u8) -> Result<(), KeyVaultError> {
+        check_derivation_log_n(log_n).map_err(KeyVaultError::InvalidInput)?;
+        self.derivation_params.log_n = log_n;
+        Ok(())
+    }
+
+    /// Sets whether new keys derive their three SPHINCS+ seed components from one Scrypt run, stretched to all three,
+    /// instead of one run each. Cuts the KDF time of account generation and recovery to a third, see
+    /// `bench_derivation_schemes` for timings. Existing accounts keep the scheme they were derived with, which is
+    /// stored alongside each of them. Off by default.
+    ///
+    /// The scheme is recorded with the wallet when its seed phrase is set up or imported, like the variant. Once
+    /// recorded, new accounts derive with the wallet's scheme whatever this setting, so set it before the seed.
+    ///
+    /// **Parameters**:
+    /// - `enabled: bool` - Whether to derive with one Scrypt run.
+    ///
+    /// **Async**: no
+    ///
+    /// **Warning**: The two schemes derive different keys from the same seed. Recovering a wallet from its seed phrase
+    /// needs the scheme its accounts were created with.
+    #[wasm_bindgen]
+    pub fn set_derivation_single_scrypt(&mut self, enabled: bool) {
+        self.derivation_params.single_scrypt = enabled;
     }
 
     /// To derive Sphincs key pair. One master mnemonic seed phrase can derive multiple child index-based sphincs+ key pairs on demand.
+    /// Served from the derivation cache when it is enabled.
     ///
     /// **Parameters**:
-    /// - `seed: &[u8]` - The master mnemonic seed phrase from which the child sphincs+ key is derived. MUST carry at least N*3 bytes of entropy or panics.
+    /// - `seed: &[u8]` - The master mnemonic seed phrase from which the child sphincs+ key is derived. Shorter than N*3 bytes is an error.
     /// - `index: u32` - The index of the child sphincs+ key to be derived.
+    /// - `params: DerivationParams` - The KDF parameters: the vault's `derivation_params` for new keys, the stored ones for existing accounts.
     ///
     /// **Returns**:
     /// - `Result<SecureVec, String>` - Scrypt key on success, or an error message on failure.
@@ -74,435 +237,3623 @@ impl KeyVault {
         &self,
         seed: &[u8],
         index: u32,
+        params: DerivationParams,
+    ) -> Result<(SecureVec, SecureVec), String> {
+        self.derivation_cache.borrow_mut().get_or_derive(
+            seed,
+            self.variant,
+            index,
+            params,
+            |seed, index| self.derive_sphincs_key_uncached(seed, index, params),
+        )
+    }
+
+    /// Derives the Sphincs key pairs of an index range and zeroizes the seed right after the last derivation,
+    /// so the full seed is not kept decrypted while the caller goes on encrypting, storing or awaiting IndexedDB.
+    ///
+    /// **Parameters**:
+    /// - `seed: &mut SecureVec` - The decrypted master seed. Zeroized before returning, on success and on failure.
+    /// - `indices: Range<u32>` - The indices of the child sphincs+ keys to be derived.
+    /// - `params: DerivationParams` - The KDF cost of the derivation.
+    /// - `f: FnMut(u32, SecureVec, SecureVec) -> Result<T, KeyVaultError>` - Called with the index, public key and private key
+    ///   of each derived pair. Keep it free of seed-independent slow work where possible, the seed lives until it returns.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<T>, KeyVaultError>` - The outputs of `f` in index order on success, or the first error.
+    fn derive_range_then_zeroize<T>(
+        &self,
+        seed: &mut SecureVec,
+        indices: Range<u32>,
+        params: DerivationParams,
+        mut f: impl FnMut(u32, SecureVec, SecureVec) -> Result<T, KeyVaultError>,
+    ) -> Result<Vec<T>, KeyVaultError> {
+        let derived = indices
+            .map(|index| {
+                let (pub_key, pri_key) = self
+                    .derive_sphincs_key(seed, index, params)
+                    .map_err(KeyVaultError::KeyDerivation)?;
+                f(index, pub_key, pri_key)
+            })
+            .collect();
+        seed.zeroize();
+        derived
+    }
+
+    /// Derives a Sphincs key pair without consulting the derivation cache, see `derive_sphincs_key`.
+    fn derive_sphincs_key_uncached(
+        &self,
+        seed: &[u8],
+        index: u32,
+        params: DerivationParams,
     ) -> Result<(SecureVec, SecureVec), String> {
         match self.variant {
-            SphincsVariant::Sha2128S => sphincs_keygen!(slh_dsa_sha2_128s::KG, slh_dsa_sha2_128s::N, seed, index),
-            SphincsVariant::Sha2128F => sphincs_keygen!(slh_dsa_sha2_128f::KG, slh_dsa_sha2_128f::N, seed, index),
-            SphincsVariant::Sha2192S => sphincs_keygen!(slh_dsa_sha2_192s::KG, slh_dsa_sha2_192s::N, seed, index),
-            SphincsVariant::Sha2192F => sphincs_keygen!(slh_dsa_sha2_192f::KG, slh_dsa_sha2_192f::N, seed, index),
-            SphincsVariant::Sha2256S => sphincs_keygen!(slh_dsa_sha2_256s::KG, slh_dsa_sha2_256s::N, seed, index),
-            SphincsVariant::Sha2256F => sphincs_keygen!(slh_dsa_sha2_256f::KG, slh_dsa_sha2_256f::N, seed, index),
-            SphincsVariant::Shake128S => sphincs_keygen!(slh_dsa_shake_128s::KG, slh_dsa_shake_128s::N, seed, index),
-            SphincsVariant::Shake128F => sphincs_keygen!(slh_dsa_shake_128f::KG, slh_dsa_shake_128f::N, seed, index),
-            SphincsVariant::Shake192S => sphincs_keygen!(slh_dsa_shake_192s::KG, slh_dsa_shake_192s::N, seed, index),
-            SphincsVariant::Shake192F => sphincs_keygen!(slh_dsa_shake_192f::KG, slh_dsa_shake_192f::N, seed, index),
-            SphincsVariant::Shake256S => sphincs_keygen!(slh_dsa_shake_256s::KG, slh_dsa_shake_256s::N, seed, index),
-            SphincsVariant::Shake256F => sphincs_keygen!(slh_dsa_shake_256f::KG, slh_dsa_shake_256f::N, seed, index),
+            SphincsVariant::Sha2128S => sphincs_keygen!(slh_dsa_sha2_128s::KG, slh_dsa_sha2_128s::N, seed, index, params),
+            SphincsVariant::Sha2128F => sphincs_keygen!(slh_dsa_sha2_128f::KG, slh_dsa_sha2_128f::N, seed, index, params),
+            SphincsVariant::Sha2192S => sphincs_keygen!(slh_dsa_sha2_192s::KG, slh_dsa_sha2_192s::N, seed, index, params),
+            SphincsVariant::Sha2192F => sphincs_keygen!(slh_dsa_sha2_192f::KG, slh_dsa_sha2_192f::N, seed, index, params),
+            SphincsVariant::Sha2256S => sphincs_keygen!(slh_dsa_sha2_256s::KG, slh_dsa_sha2_256s::N, seed, index, params),
+            SphincsVariant::Sha2256F => sphincs_keygen!(slh_dsa_sha2_256f::KG, slh_dsa_sha2_256f::N, seed, index, params),
+            SphincsVariant::Shake128S => sphincs_keygen!(slh_dsa_shake_128s::KG, slh_dsa_shake_128s::N, seed, index, params),
+            SphincsVariant::Shake128F => sphincs_keygen!(slh_dsa_shake_128f::KG, slh_dsa_shake_128f::N, seed, index, params),
+            SphincsVariant::Shake192S => sphincs_keygen!(slh_dsa_shake_192s::KG, slh_dsa_shake_192s::N, seed, index, params),
+            SphincsVariant::Shake192F => sphincs_keygen!(slh_dsa_shake_192f::KG, slh_dsa_shake_192f::N, seed, index, params),
+            SphincsVariant::Shake256S => sphincs_keygen!(slh_dsa_shake_256s::KG, slh_dsa_shake_256s::N, seed, index, params),
+            SphincsVariant::Shake256F => sphincs_keygen!(slh_dsa_shake_256f::KG, slh_dsa_shake_256f::N, seed, index, params),
+        }
+    }
+
+    /// Clears all data in the `seed_phrase_store`, `child_keys_store` and `encrypted_metadata_store` in IndexedDB,
+    /// and ends the signing sessions and clears the KDF cache of every `KeyVault` of this wasm instance.
+    /// Refused while another key vault operation of this wasm instance is in progress, and other operations are
+    /// refused while it runs, so a clear never interleaves with e.g. a `sign` or `gen_new_account`.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Another wasm instance, e.g. the signing worker or another tab, isn't excluded, see `operation_lock`.
+    /// Its in-flight operations see the stores either before or after the clear, as each runs in its own IndexedDB
+    /// transaction, and its sessions stay open until it is locked.
+    #[wasm_bindgen]
+    pub async fn clear_database() -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_exclusive()?;
+        session::close_all();
+        kdf_cache::clear();
+        Self::clear_stores(&IndexedDbStorage).await
+    }
+
+    /// Clears the seed phrase, child keys and metadata stores of a storage, see `clear_database`.
+    ///
+    /// **Parameters**:
+    /// - `storage: &dyn Storage` - The storage to clear.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok on success, or a `Database` error if a store can't be cleared.
+    async fn clear_stores(storage: &dyn Storage) -> Result<(), KeyVaultError> {
+        for store_name in [SEED_PHRASE_STORE, CHILD_KEYS_STORE, METADATA_STORE] {
+            storage
+                .clear_object_store(store_name)
+                .await
+                .map_err(KeyVaultError::from)?;
+        }
+        Ok(())
+    }
+
+    /// Retrieves all SPHINCS+ lock script arguments (processed public keys) from the database in derivation index order.
+    /// Unreadable account records are skipped, see `get_account_listing` to list them.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<String>, KeyVaultError>` - A JavaScript Promise that resolves to an array of hex-encoded SPHINCS+ lock script arguments on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn get_all_sphincs_lock_args() -> Result<Vec<String>, KeyVaultError> {
+        // Page by page, so only one page of records is deserialized at a time
+        let mut lock_args_array: Vec<String> = Vec::new();
+        let mut offset = 0;
+        loop {
+            let (lock_args, records_read) =
+                Self::lock_args_page(offset, LOCK_ARGS_PAGE_SIZE).await?;
+            lock_args_array.extend(lock_args);
+            if records_read < LOCK_ARGS_PAGE_SIZE {
+                return Ok(lock_args_array);
+            }
+            offset += records_read;
         }
     }
 
-    /// Clears all data in the `seed_phrase_store` and `child_keys_store` in IndexedDB.
+    /// Counts the stored accounts without loading or deserializing their records, e.g. to show an account counter.
+    ///
+    /// **Returns**:
+    /// - `Result<u32, KeyVaultError>` - A JavaScript Promise that resolves to the number of accounts on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Unreadable account records are counted too, unlike in `get_all_sphincs_lock_args`.
+    #[wasm_bindgen]
+    pub async fn account_count() -> Result<u32, KeyVaultError> {
+        db::count_accounts().await.map_err(KeyVaultError::from)
+    }
+
+    /// Retrieves one page of the SPHINCS+ lock script arguments, in the order of `get_all_sphincs_lock_args`, without
+    /// loading every account record. Meant for large wallets; `get_all_sphincs_lock_args` stays simpler for small ones.
+    /// An `offset` past the last account yields an empty page.
+    ///
+    /// **Parameters**:
+    /// - `offset: u32` - The number of accounts to skip. A position in the listing, not an account index.
+    /// - `limit: u32` - The maximum number of lock script arguments to return.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<String>, KeyVaultError>` - A JavaScript Promise that resolves to the hex-encoded lock script arguments of the page,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Like `get_all_sphincs_lock_args`, unreadable account records are skipped, so such a page may come back short.
+    #[wasm_bindgen]
+    pub async fn get_lock_args_page(offset: u32, limit: u32) -> Result<Vec<String>, KeyVaultError> {
+        let (lock_args, _) = Self::lock_args_page(offset, limit).await?;
+        Ok(lock_args)
+    }
+
+    /// Retrieves the display details of all accounts in derivation index order, like `get_all_sphincs_lock_args`
+    /// but with each account's index and label.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<JsValue>, KeyVaultError>` - A JavaScript Promise that resolves to an array of `{ index, lock_args, label }`
+    ///   objects on success, or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn get_all_accounts_meta() -> Result<Vec<JsValue>, KeyVaultError> {
+        let (accounts, _) = db::get_all_account_records()
+            .await
+            .map_err(KeyVaultError::from)?;
+        accounts
+            .iter()
+            .map(|account| {
+                serde_wasm_bindgen::to_value(&AccountMeta::from(account))
+                    .map_err(KeyVaultError::from)
+            })
+            .collect()
+    }
+
+    /// Lists the stored accounts like `get_all_sphincs_lock_args`, and also reports records that couldn't be read,
+    /// e.g. because an incompatible version wrote them. Unreadable records don't stop the listing.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - A JavaScript Promise that resolves to `{ lock_args, unreadable }` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn get_account_listing() -> Result<JsValue, KeyVaultError> {
+        let (accounts, unreadable) = db::get_all_account_records()
+            .await
+            .map_err(KeyVaultError::from)?;
+        let listing = AccountListing {
+            lock_args: accounts
+                .into_iter()
+                .map(|account| account.lock_args)
+                .collect(),
+            unreadable,
+        };
+        Ok(serde_wasm_bindgen::to_value(&listing)?)
+    }
+
+    /// Retrieves the derivation indices currently present in the account store.
+    /// Missing values between them are gaps in the derivation sequence, e.g. `[0, 1, 2, 5]` lacks 3 and 4.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<u32>, KeyVaultError>` - A JavaScript Promise that resolves to the sorted indices on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn stored_indices() -> Result<Vec<u32>, KeyVaultError> {
+        let accounts = db::get_all_accounts().await.map_err(KeyVaultError::from)?;
+        Ok(utilities::stored_indices(&accounts))
+    }
+
+    /// Reports the wallet's structural state for bug reports, without any secret material.
+    ///
+    /// Included: DB schema version, account count, derivation indices, accounts grouped by private key length with
+    /// the variants each length fits, whether a mnemonic is stored, cipher and KDF parameters, and the number of
+    /// stored payloads per `CipherPayload` version.
+    ///
+    /// Not included: the mnemonic, private keys, ciphertexts, salts, IVs, lock script arguments and account ids.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - A JavaScript Promise that resolves to the report object on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn diagnostic_report() -> Result<JsValue, KeyVaultError> {
+        let seed = db::get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?;
+        let accounts = db::get_all_accounts().await.map_err(KeyVaultError::from)?;
+        let report = utilities::diagnostic_report(DB_VERSION, seed.as_ref(), &accounts);
+        Ok(serde_wasm_bindgen::to_value(&report)?)
+    }
+
+    /// Computes a fingerprint of the stored account set - a hash over the sorted (index, lock_args) pairs.
+    /// Record it to later confirm that a restored or synced wallet holds exactly the same accounts. No secrets are involved.
+    ///
+    /// **Returns**:
+    /// - `Result<String, KeyVaultError>` - A JavaScript Promise that resolves to the hex-encoded checksum on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn accounts_checksum() -> Result<String, KeyVaultError> {
+        let accounts = db::get_all_accounts().await.map_err(KeyVaultError::from)?;
+        Ok(utilities::accounts_checksum(&accounts))
+    }
+
+    /// Assigns an app-chosen local id to an account, replacing the random one it was stored with. The id is a stable
+    /// handle for the account that survives operations regenerating its lock script arguments, while lock_args remain
+    /// the primary store key.
+    ///
+    /// **Parameters**:
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the account.
+    /// - `account_id: String` - The local id to assign. Must be unique across accounts.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error if the account doesn't exist or the id is taken.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn set_account_id(
+        lock_args: String,
+        account_id: String,
+    ) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        db::set_account_id(&lock_args, &account_id)
+            .await
+            .map_err(KeyVaultError::from)
+    }
+
+    /// Caches the result of an on-chain history lookup for an account, so gap-limit discovery in a later session
+    /// can skip re-scanning it. The flag is non-sensitive metadata, returned as `has_history` by `get_accounts`.
+    ///
+    /// **Parameters**:
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the account.
+    /// - `has_history: bool` - Whether the account has on-chain history.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error if the account doesn't exist.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn set_account_history_flag(
+        lock_args: String,
+        has_history: bool,
+    ) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        db::set_account_history_flag(&lock_args, has_history)
+            .await
+            .map_err(KeyVaultError::from)
+    }
+
+    /// Sets the user-editable label of an account, e.g. "Savings" or "Cold". The label is non-sensitive metadata.
+    ///
+    /// **Parameters**:
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the account.
+    /// - `label: String` - The label, at most 64 characters after trimming. An empty label clears it.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error if the account doesn't exist or the label is too long.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn set_account_label(lock_args: String, label: String) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let label = normalize_account_label(&label).map_err(KeyVaultError::InvalidInput)?;
+        db::set_account_label(&lock_args, label)
+            .await
+            .map_err(KeyVaultError::from)
+    }
+
+    /// Retrieves the user-editable label of an account.
+    ///
+    /// **Parameters**:
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the account.
+    ///
+    /// **Returns**:
+    /// - `Result<Option<String>, KeyVaultError>` - A JavaScript Promise that resolves to the label, `undefined` if the account
+    ///   has none, or rejects with a JavaScript error if the account doesn't exist.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn get_account_label(lock_args: String) -> Result<Option<String>, KeyVaultError> {
+        let account = db::get_account(&lock_args)
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or_else(|| KeyVaultError::AccountNotFound(lock_args.clone()))?;
+        Ok(account.label)
+    }
+
+    /// Retrieves the SPHINCS+ public key of an account, without any password.
+    ///
+    /// **Parameters**:
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the account.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - A JavaScript Promise that resolves to the public key, or rejects with a
+    ///   JavaScript error if the account doesn't exist or has no stored public key.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Legacy accounts store no public key until `backfill_pubkeys` runs.
+    #[wasm_bindgen]
+    pub async fn get_public_key(lock_args: String) -> Result<Uint8Array, KeyVaultError> {
+        let account = db::get_account(&lock_args)
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or_else(|| KeyVaultError::AccountNotFound(lock_args.clone()))?;
+        let pub_key = account.pub_key.ok_or_else(|| {
+            KeyVaultError::InvalidInput(format!(
+                "Account {} has no stored public key; run backfill_pubkeys first",
+                lock_args
+            ))
+        })?;
+        let pub_key = hex::decode(pub_key).map_err(|e| {
+            KeyVaultError::Internal(format!("Stored public key decode error: {}", e))
+        })?;
+        Ok(Uint8Array::from(pub_key.as_slice()))
+    }
+
+    /// Deletes one account. Its derivation index is never reused: `gen_new_account` continues after the highest
+    /// index ever stored, while `recover_accounts` can still bring the account back from the seed.
+    ///
+    /// **Parameters**:
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the account.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error if the account doesn't exist.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Warning**: The encrypted private key is removed from this device. Funds stay recoverable from the seed phrase.
+    #[wasm_bindgen]
+    pub async fn delete_account(lock_args: String) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        db::delete_account(&lock_args)
+            .await
+            .map_err(KeyVaultError::from)
+    }
+
+    /// Deletes every watch-only account at once, e.g. to stop monitoring cold wallet addresses, without touching the
+    /// signing accounts.
+    ///
+    /// **Returns**:
+    /// - `Result<u32, KeyVaultError>` - A JavaScript Promise that resolves to the number of accounts deleted on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Nothing is derived at watch-only indices, so the next derivation index doesn't change.
+    #[wasm_bindgen]
+    pub async fn purge_watch_only() -> Result<u32, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        db::purge_watch_only().await.map_err(KeyVaultError::from)
+    }
+
+    /// Exports one account for offline backup without the mnemonic. The private key stays encrypted under the wallet
+    /// password, so the exported object is safe to store on disk.
+    ///
+    /// **Parameters**:
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the account.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - A JavaScript Promise that resolves to the stored account record, including its
+    ///   `index` and encrypted `pri_enc`, or rejects with a JavaScript error if the account doesn't exist.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn export_account(lock_args: String) -> Result<JsValue, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let account = db::get_account(&lock_args)
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or_else(|| KeyVaultError::AccountNotFound(lock_args.clone()))?;
+        Ok(serde_wasm_bindgen::to_value(&account)?)
+    }
+
+    /// Imports an account exported with `export_account`. Its private key must be encrypted under this wallet's password
+    /// to be usable for signing.
+    ///
+    /// **Parameters**:
+    /// - `account: JsValue` - The account record returned by `export_account`.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success, or rejects with a
+    ///   JavaScript error if the record is malformed, its lock_args don't derive from its public key under this vault's
+    ///   variant, its index is taken or out of range, or an account with the same lock_args is already stored.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn import_account(&self, account: JsValue) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        self.check_stored_variant().await?;
+        let account: SphincsPlusAccount = serde_wasm_bindgen::from_value(account)
+            .map_err(|e| KeyVaultError::InvalidInput(format!("Invalid account record: {}", e)))?;
+        let accounts = self
+            .storage
+            .get_all_accounts()
+            .await
+            .map_err(KeyVaultError::from)?;
+        self.check_imported_account(&account, &accounts)?;
+        config::check_account_limit(accounts.len() as u64 + 1, config::max_accounts())
+            .map_err(KeyVaultError::InvalidInput)?;
+        self.add_new_account(account).await
+    }
+
+    /// Checks an imported account record against this vault and the stored accounts, see `import_account`.
+    ///
+    /// **Parameters**:
+    /// - `account: &SphincsPlusAccount` - The imported account.
+    /// - `stored: &[SphincsPlusAccount]` - The stored accounts.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok if the record is consistent, a `VariantMismatch` error if its public key is of
+    ///   another variant, or an `InvalidInput` error otherwise. A lock_args collision is left to the store.
+    fn check_imported_account(
+        &self,
+        account: &SphincsPlusAccount,
+        stored: &[SphincsPlusAccount],
+    ) -> Result<(), KeyVaultError> {
+        let pub_key = account
+            .pub_key
+            .as_deref()
+            .and_then(|pub_key| hex::decode(pub_key).ok())
+            .map(|pub_key| SecureVec::from_slice(&pub_key))
+            .ok_or_else(|| {
+                KeyVaultError::InvalidInput(
+                    "Account record has no valid public key to check its lock_args against".to_string(),
+                )
+            })?;
+        if pub_key.len() != self.variant.public_key_length() {
+            return Err(KeyVaultError::VariantMismatch(format!(
+                "the account's public key has {} bytes, {} keys have {}",
+                pub_key.len(),
+                self.variant,
+                self.variant.public_key_length()
+            )));
+        }
+        // The lock_args hash the variant's sign flag, so this also catches a variant of the same key length
+        if encode(self.get_lock_scrip_arg(&pub_key)) != account.lock_args {
+            return Err(KeyVaultError::InvalidInput(format!(
+                "Account {} does not match its public key under {}",
+                account.lock_args, self.variant
+            )));
+        }
+        let in_range = if account.is_watch_only() {
+            account.index >= WATCH_ONLY_INDEX_BASE
+        } else {
+            account.index < WATCH_ONLY_INDEX_BASE - 1
+        };
+        if !in_range {
+            return Err(KeyVaultError::InvalidInput(format!(
+                "Account index out of range: {}",
+                account.index
+            )));
+        }
+        if let Some(other) = stored
+            .iter()
+            .find(|other| other.index == account.index && other.lock_args != account.lock_args)
+        {
+            return Err(KeyVaultError::InvalidInput(format!(
+                "Account index {} is already used by {}",
+                account.index, other.lock_args
+            )));
+        }
+        Ok(())
+    }
+
+    /// Imports a watch-only account from a SPHINCS+ public key whose private key lives elsewhere, e.g. in a cold
+    /// wallet. The account shows up like any other, so its balance can be tracked, but it can't sign.
+    ///
+    /// **Parameters**:
+    /// - `public_key: Uint8Array` - The SPHINCS+ public key, of this vault's variant.
+    ///
+    /// **Returns**:
+    /// - `Result<String, KeyVaultError>` - A String Promise that resolves to the hex-encoded lock script arguments of the
+    ///   account on success, or rejects with a JavaScript error if the key length doesn't fit the variant or the
+    ///   account is already stored.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Watch-only accounts are numbered from `WATCH_ONLY_INDEX_BASE` (2^31) up, apart from derivation
+    /// indices, so `gen_new_account` never skips or derives at them. Signing with one fails with a `WATCH_ONLY` error,
+    /// while `get_public_key` works as for any account.
+    #[wasm_bindgen]
+    pub async fn import_watch_only(&self, public_key: Uint8Array) -> Result<String, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        self.check_stored_variant().await?;
+        let pub_key = SecureVec::from_uint8_array(&public_key);
+        if pub_key.len() != self.variant.public_key_length() {
+            return Err(KeyVaultError::InvalidInput(format!(
+                "Invalid public key length: expected {} bytes for {}, got {}",
+                self.variant.public_key_length(),
+                self.variant,
+                pub_key.len()
+            )));
+        }
+        let accounts = self
+            .storage
+            .get_all_accounts()
+            .await
+            .map_err(KeyVaultError::from)?;
+        config::check_account_limit(accounts.len() as u64 + 1, config::max_accounts())
+            .map_err(KeyVaultError::InvalidInput)?;
+        let index = db::next_watch_only_index(accounts.iter().map(|account| account.index))
+            .map_err(KeyVaultError::from)?;
+
+        let lock_args = encode(self.get_lock_scrip_arg(&pub_key));
+        let account_id = new_account_id()
+            .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
+        let account = SphincsPlusAccount {
+            index,
+            lock_args: lock_args.clone(),
+            pri_enc: None,
+            account_id: Some(account_id),
+            pub_key: Some(encode(&*pub_key)),
+            derivation: self.derivation_params,
+            has_history: None,
+            label: None,
+        };
+        self.add_new_account(account).await?;
+        Ok(lock_args)
+    }
+
+    /// Retrieves several accounts in one database round trip, e.g. the ones appearing in a transaction.
+    ///
+    /// **Parameters**:
+    /// - `lock_args_list: Vec<String>` - The hex-encoded lock script arguments of the accounts.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - A JavaScript Promise that resolves to an array of `{ index, lock_args, account_id, pub_key, has_history, label, watch_only }`
+    ///   objects in the requested order, with `null` for lock_args not in the store, or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn get_accounts(lock_args_list: Vec<String>) -> Result<JsValue, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let found = db::get_accounts(&lock_args_list)
+            .await
+            .map_err(KeyVaultError::from)?;
+        let summaries = accounts_in_requested_order(&lock_args_list, &found);
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_missing_as_null(true);
+        Ok(summaries.serialize(&serializer)?)
+    }
+
+    /// Looks up an account's lock script arguments by its local id.
+    ///
+    /// **Parameters**:
+    /// - `account_id: String` - The local id, as returned by `get_accounts` or assigned with `set_account_id`.
+    ///
+    /// **Returns**:
+    /// - `Result<Option<String>, KeyVaultError>` - A JavaScript Promise that resolves to the hex-encoded lock script arguments,
+    ///   `undefined` if no account carries the id, or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn get_lock_args_by_account_id(
+        account_id: String,
+    ) -> Result<Option<String>, KeyVaultError> {
+        let account = db::get_account_by_id(&account_id)
+            .await
+            .map_err(KeyVaultError::from)?;
+        Ok(account.map(|account| account.lock_args))
+    }
+
+    /// Initializes the mnemonic phrase by generating a BIP39 mnemonic, encrypting it with the provided password, and storing it in IndexedDB.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to encrypt the mnemonic.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Only effective when the mnemonic phrase is not yet set.
+    #[wasm_bindgen]
+    pub async fn init_seed_phrase(&self, password: Uint8Array) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        self.create_seed_phrase(&password).await
+    }
+
+    /// Generates, encrypts and stores a new mnemonic unless one is stored, see `init_seed_phrase`.
+    ///
+    /// **Parameters**:
+    /// - `password: &SecureVec` - The password used to encrypt the mnemonic.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok on success or if a mnemonic is stored, or an `InvalidInput` error if the
+    ///   password is empty.
+    async fn create_seed_phrase(&self, password: &SecureVec) -> Result<(), KeyVaultError> {
+        check_password_not_empty(password).map_err(KeyVaultError::InvalidInput)?;
+        let stored_seed = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?;
+        if stored_seed.is_some() {
+            debug!("\x1b[37;44m INFO \x1b[0m \x1b[1mkey-vault\x1b[0m: mnemonic phrase exists");
+            return Ok(());
+        }
+
+        let size = self.variant.bip39_compatible_entropy_size();
+        let entropy = get_random_bytes(size).unwrap();
+        let encrypted_seed = encrypt(password, entropy.as_ref(), &seed_aad())
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+        let verifier = make_password_verifier(password)
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+
+        // Re-checked atomically: a concurrent init or import may have stored a mnemonic since the check above.
+        let written = self
+            .storage
+            .set_encrypted_mnemonic_seed(
+                encrypted_seed,
+                verifier,
+                self.variant,
+                self.derivation_params.single_scrypt,
+                false,
+            )
+            .await
+            .map_err(KeyVaultError::from)?;
+        if !written {
+            debug!("\x1b[37;44m INFO \x1b[0m \x1b[1mkey-vault\x1b[0m: mnemonic phrase exists");
+        }
+        Ok(())
+    }
+
+    /// Stores a small app value (e.g. a setting or a note) encrypted with the password, in a store of its own.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to encrypt the value.
+    /// - `key: String` - The metadata key, 1 to 256 bytes. `failed_unlock_attempts` is reserved.
+    /// - `value: Uint8Array` - The value to encrypt, at most 64 KiB.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Warning**: This method overwrites an existing value under the same key.
+    #[wasm_bindgen]
+    pub async fn set_encrypted_metadata(
+        password: Uint8Array,
+        key: String,
+        value: Uint8Array,
+    ) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        let value = SecureVec::from_uint8_array(&value);
+        check_metadata_entry(&key, value.len()).map_err(KeyVaultError::InvalidInput)?;
+
+        let payload = encrypt(&password, &value, &record_aad(METADATA_STORE, &key))
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+        db::set_encrypted_metadata(&key, payload)
+            .await
+            .map_err(KeyVaultError::from)
+    }
+
+    /// Retrieves and decrypts an app value stored with `set_encrypted_metadata`.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the value.
+    /// - `key: String` - The metadata key.
+    ///
+    /// **Returns**:
+    /// - `Result<Option<Uint8Array>, KeyVaultError>` - A JavaScript Promise that resolves to the value, `undefined` if the key
+    ///   isn't set, or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Warning**: Proper zeroization of the returned value is the responsibility of the caller.
+    #[wasm_bindgen]
+    pub async fn get_encrypted_metadata(
+        password: Uint8Array,
+        key: String,
+    ) -> Result<Option<Uint8Array>, KeyVaultError> {
+        let password = SecureVec::from_uint8_array(&password);
+        check_metadata_entry(&key, 0).map_err(KeyVaultError::InvalidInput)?;
+        let payload = match db::get_encrypted_metadata(&key)
+            .await
+            .map_err(KeyVaultError::from)?
+        {
+            Some(payload) => payload,
+            None => return Ok(None),
+        };
+
+        let value = Self::decrypt_with_lockout(
+            &IndexedDbStorage,
+            &password,
+            payload,
+            &record_aad(METADATA_STORE, &key),
+        )
+        .await?;
+        Ok(Some(Uint8Array::from(value.as_ref())))
+    }
+
+    /// Generates a new SPHINCS+ account - a SPHINCS+ child account derived from the mnemonic phrase,
+    /// encrypts the private key with the password, and stores/appends it in IndexedDB.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the mnemonic phrase and encrypt the child private key.
+    ///
+    /// **Returns**:
+    /// - `Result<String, KeyVaultError>` - A String Promise that resolves to the hex-encoded SPHINCS+ lock argument (processed SPHINCS+ public key) of the account on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn gen_new_account(&self, password: Uint8Array) -> Result<String, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        self.derive_next_account(&password).await
+    }
+
+    /// Derives, encrypts and stores the account at the next derivation index, see `gen_new_account`.
+    ///
+    /// **Parameters**:
+    /// - `password: &SecureVec` - The password used to decrypt the mnemonic phrase and encrypt the child private key.
+    ///
+    /// **Returns**:
+    /// - `Result<String, KeyVaultError>` - The hex-encoded lock script arguments of the account on success, or an
+    ///   error on failure.
+    async fn derive_next_account(&self, password: &SecureVec) -> Result<String, KeyVaultError> {
+        self.check_stored_variant().await?;
+        check_password_not_empty(password).map_err(KeyVaultError::InvalidInput)?;
+
+        // Get and decrypt the mnemonic seed phrase
+        let payload = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        let index = self
+            .storage
+            .next_account_index()
+            .await
+            .map_err(KeyVaultError::from)?;
+        config::check_account_limit(index as u64 + 1, config::max_accounts())
+            .map_err(KeyVaultError::InvalidInput)?;
+        let params = self.new_key_params().await?;
+
+        // Decrypt the seed only after the last await before derivation, and drop it right after
+        let mut seed =
+            Self::decrypt_with_lockout(&*self.storage, password, payload, &seed_aad()).await?;
+        let (pub_key, pri_key) = self
+            .derive_range_then_zeroize(
+                &mut seed,
+                index..index + 1,
+                params,
+                |_, pub_key, pri_key| Ok((pub_key, pri_key)),
+            )?
+            .pop()
+            .ok_or_else(|| KeyVaultError::Internal("No key pair derived".to_string()))?;
+
+        // Calculate lock script args and encrypt corresponding private key
+        let lock_script_args = self.get_lock_scrip_arg(&pub_key);
+        let encrypted_pri = encrypt(password, &pri_key, &account_aad(&encode(lock_script_args)))
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+
+        // Store to DB
+        let account_id = new_account_id()
+            .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
+        let account = SphincsPlusAccount {
+            index,
+            lock_args: encode(lock_script_args),
+            pri_enc: Some(encrypted_pri),
+            account_id: Some(account_id),
+            pub_key: Some(encode(&*pub_key)),
+            derivation: params,
+            has_history: None,
+            label: None,
+        };
+
+        self
+            .storage
+            .add_account(account)
+            .await
+            .map_err(KeyVaultError::from)?;
+
+        Ok(encode(lock_script_args))
+    }
+
+    /// Imports a mnemonic by encrypting it with the provided password and storing it as the mnemonic phrase.
+    ///
+    /// **Parameters**:
+    /// - `seed_phrase: Uint8Array` - The mnemonic phrase as a UTF-8 encoded Uint8Array to import.
+    /// - `password: Uint8Array` - The password used to encrypt the mnemonic.
+    /// - `force: bool` - Whether to overwrite an existing mnemonic phrase. Without it, importing over an existing
+    ///   wallet is refused; the check and the write are atomic, so a concurrent init or import can't slip in between.
+    ///   Even with it, importing is refused while accounts are stored, as they would stay derived from the replaced
+    ///   phrase; clear the wallet first, see `clear_database`.
+    /// - `language: Option<MnemonicLanguage>` - The BIP39 wordlist of the seed phrase, English if not given.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Warning**: Handle the mnemonic in JavaScript side carefully. With `force`, the existing mnemonic phrase is
+    /// overwritten and unrecoverable without its backup.
+    #[wasm_bindgen]
+    pub async fn import_seed_phrase(
+        &self,
+        seed_phrase: Uint8Array,
+        password: Uint8Array,
+        force: bool,
+        language: Option<MnemonicLanguage>,
+    ) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        let seed_phrase = SecureVec::from_uint8_array(&seed_phrase);
+        self.store_seed_phrase(&password, &seed_phrase, force, language)
+            .await
+    }
+
+    /// Encrypts and stores an imported mnemonic, see `import_seed_phrase`.
+    ///
+    /// **Parameters**:
+    /// - `password: &SecureVec` - The password used to encrypt the mnemonic.
+    /// - `seed_phrase: &SecureVec` - The UTF-8 encoded mnemonic phrase.
+    /// - `force: bool` - Whether to overwrite an existing mnemonic phrase.
+    /// - `language: Option<MnemonicLanguage>` - The BIP39 wordlist of the seed phrase, English if not given.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok on success, or an `InvalidInput` error if the phrase is invalid or one is
+    ///   stored and `force` is false.
+    async fn store_seed_phrase(
+        &self,
+        password: &SecureVec,
+        seed_phrase: &SecureVec,
+        force: bool,
+        language: Option<MnemonicLanguage>,
+    ) -> Result<(), KeyVaultError> {
+        check_password_not_empty(password).map_err(KeyVaultError::InvalidInput)?;
+        let language = language.unwrap_or(MnemonicLanguage::English);
+
+        let seed_phrase_str = std::str::from_utf8(seed_phrase)
+            .map_err(|e| KeyVaultError::InvalidInput(format!("Invalid UTF-8: {}", e)))?;
+        let combined_entropy =
+            seed_phrase_entropy(seed_phrase_str, language).map_err(KeyVaultError::InvalidInput)?;
+
+        self.variant
+            .check_seed_entropy(combined_entropy.len())
+            .map_err(KeyVaultError::InvalidInput)?;
+
+        let encrypted_seed = encrypt(password, &combined_entropy, &seed_aad())
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+        let verifier = make_password_verifier(password)
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+        let written = self
+            .storage
+            .set_encrypted_mnemonic_seed(
+                encrypted_seed,
+                verifier,
+                self.variant,
+                self.derivation_params.single_scrypt,
+                force,
+            )
+            .await
+            .map_err(KeyVaultError::from)?;
+        if !written && force {
+            return Err(KeyVaultError::InvalidInput(
+                "Accounts are stored, clear the wallet before importing over its mnemonic phrase".to_string(),
+            ));
+        }
+        if !written {
+            return Err(KeyVaultError::InvalidInput(
+                "Mnemonic phrase already exists, import with force to overwrite it".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks whether a password is the wallet's password, without decrypting any key material, e.g. to validate it
+    /// on an unlock screen before running expensive operations.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password to check.
+    ///
+    /// **Returns**:
+    /// - `Result<bool, KeyVaultError>` - A JavaScript Promise that resolves to whether the password is correct,
+    ///   or rejects with a JavaScript error if no mnemonic phrase is stored or unlocking is locked. A wrong password
+    ///   counts towards the lockout, see `failed_attempts`.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Wallets created before the password verifier existed have none until their password is changed or
+    /// their seed is re-imported; for them the password is checked against the encrypted mnemonic phrase instead.
+    #[wasm_bindgen]
+    pub async fn check_password(password: Uint8Array) -> Result<bool, KeyVaultError> {
+        let password = SecureVec::from_uint8_array(&password);
+        let checked = if let Some(verifier) = db::get_password_verifier()
+            .await
+            .map_err(KeyVaultError::from)?
+        {
+            Self::with_lockout(&IndexedDbStorage, || {
+                check_password_verifier(&password, verifier)
+            })
+            .await
+        } else {
+            let payload = db::get_encrypted_mnemonic_seed()
+                .await
+                .map_err(KeyVaultError::from)?
+                .ok_or(KeyVaultError::MnemonicNotFound)?;
+            Self::decrypt_with_lockout(&IndexedDbStorage, &password, payload, &seed_aad())
+                .await
+                .map(|_| true)
+        };
+        match checked {
+            Err(KeyVaultError::WrongPassword(_)) => Ok(false),
+            checked => checked,
+        }
+    }
+
+    /// Retrieves how many wrong passwords were entered in a row, e.g. to warn the user before the lockout starts.
+    /// Every operation decrypting with the wallet password counts a wrong one and resets the count on success; a
+    /// corrupted record doesn't count.
+    ///
+    /// **Returns**:
+    /// - `Result<u32, KeyVaultError>` - A JavaScript Promise that resolves to the number of wrong passwords since the
+    ///   last successful unlock, or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Once the count reaches `Util::set_max_failed_attempts` (10 by default), those operations fail with a
+    /// `LOCKED` error until the cooldown of `Util::set_lockout_cooldown_ms` has passed or `clear_failed_attempts` is
+    /// called.
+    #[wasm_bindgen]
+    pub async fn failed_attempts() -> Result<u32, KeyVaultError> {
+        let attempts = db::get_failed_attempts()
+            .await
+            .map_err(KeyVaultError::from)?;
+        Ok(attempts.count)
+    }
+
+    /// Clears the count of wrong passwords, ending a lockout, e.g. after the user has confirmed their identity
+    /// some other way.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Warning**: The lockout slows down guessing through the app only. Anyone able to run code in the page can
+    /// call this, and the ciphertext in IndexedDB can be attacked offline regardless.
+    #[wasm_bindgen]
+    pub async fn clear_failed_attempts() -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        db::clear_failed_attempts()
+            .await
+            .map_err(KeyVaultError::from)
+    }
+
+    /// Exports the mnemonic phrase by decrypting it with the provided password.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the mnemonic.
+    /// - `language: Option<MnemonicLanguage>` - The BIP39 wordlist to render the mnemonic with, English if not given.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - A JavaScript Promise that resolves to the mnemonic as a UTF-8 encoded `Uint8Array` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Warning**: Exporting the mnemonic exposes it in JavaScript, which may pose a security risk.
+    /// Proper zeroization of exported seed phrase is the responsibility of the caller.
+    #[wasm_bindgen]
+    pub async fn export_seed_phrase(
+        password: Uint8Array,
+        language: Option<MnemonicLanguage>,
+    ) -> Result<Uint8Array, KeyVaultError> {
+        let password = SecureVec::from_uint8_array(&password);
+        let payload = db::get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+
+        let entropy =
+            Self::decrypt_with_lockout(&IndexedDbStorage, &password, payload, &seed_aad()).await?;
+        let language = language.unwrap_or(MnemonicLanguage::English);
+        let combined_mnemonics = standard_bip39_chunks(&entropy, language)
+            .map_err(KeyVaultError::Internal)?
+            .join(" ");
+
+        Ok(Uint8Array::from(combined_mnemonics.as_ref()))
+    }
+
+    /// Returns the stored encrypted mnemonic phrase as it is, for the user to back up externally. Unlike
+    /// `export_seed_phrase`, no password is needed and the seed is never decrypted: the blob is as safe at rest as
+    /// the database, and restoring it takes the password it was encrypted with, see `set_encrypted_seed_blob`.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - A JavaScript Promise that resolves to a `{ salt, iv, cipher_text, version, kdf }`
+    ///   object on success, or rejects with a JavaScript error if no mnemonic phrase is stored.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn get_encrypted_seed_blob() -> Result<JsValue, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let payload = db::get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        Ok(serde_wasm_bindgen::to_value(&payload)?)
+    }
+
+    /// Restores the encrypted mnemonic phrase from a blob made by `get_encrypted_seed_blob`. The wallet password
+    /// becomes the one the blob was encrypted with, and the wallet is recorded as set up with this vault's variant.
+    ///
+    /// **Parameters**:
+    /// - `blob: JsValue` - The blob, a `{ salt, iv, cipher_text, version, kdf }` object.
+    /// - `force: bool` - Whether to overwrite an existing mnemonic phrase. Refused while accounts are stored.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error if the blob is malformed, or a mnemonic phrase exists and either `force`
+    ///   is not set or accounts are stored.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: The blob is validated before anything is written. The password verifier can't be rebuilt without
+    /// the password, so passwords are checked against the mnemonic phrase itself until the password is changed.
+    ///
+    /// **Warning**: With `force`, the existing mnemonic phrase is overwritten and unrecoverable without its backup.
+    /// Delete the accounts derived from it first, or restore the whole wallet with `import_vault`.
+    #[wasm_bindgen]
+    pub async fn set_encrypted_seed_blob(&self, blob: JsValue, force: bool) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let payload: CipherPayload = serde_wasm_bindgen::from_value(blob)
+            .map_err(|e| KeyVaultError::InvalidInput(format!("Invalid seed blob: {}", e)))?;
+        check_seed_blob(&payload).map_err(KeyVaultError::InvalidInput)?;
+
+        let written = self
+            .storage
+            .restore_encrypted_mnemonic_seed(
+                payload,
+                self.variant,
+                self.derivation_params.single_scrypt,
+                force,
+            )
+            .await
+            .map_err(KeyVaultError::from)?;
+        if !written {
+            return Err(KeyVaultError::InvalidInput(
+                "Mnemonic phrase already exists, restore with force and no stored accounts to overwrite it"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Exports the whole wallet - the mnemonic seed phrase and all accounts - as one encrypted backup.
+    /// The stored records stay encrypted under the wallet password inside it, and the backup itself is encrypted again.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The wallet password, used to authenticate and to encrypt the backup.
+    /// - `compress: bool` - Whether to deflate the backup before encryption. Hex-encoded private keys of
+    ///   many (e.g. 256f) accounts compress to roughly half their size.
+    ///
+    /// **Returns**:
+    /// - `Result<String, KeyVaultError>` - A JavaScript Promise that resolves to the backup as a JSON string on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn export_backup(
+        password: Uint8Array,
+        compress: bool,
+    ) -> Result<String, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        let seed = db::get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        Self::decrypt_with_lockout(&IndexedDbStorage, &password, seed.clone(), &seed_aad()).await?;
+
+        let accounts = db::get_all_accounts().await.map_err(KeyVaultError::from)?;
+        let next_index = db::next_account_index()
+            .await
+            .map_err(KeyVaultError::from)?;
+        let backup = WalletBackup {
+            seed,
+            checksum: utilities::accounts_checksum(&accounts),
+            accounts,
+            verifier: None,
+            next_index: Some(next_index),
+        };
+        let payload = seal_backup(&password, &backup, compress).map_err(KeyVaultError::Internal)?;
+        serde_json::to_string(&payload)
+            .map_err(|e| KeyVaultError::Internal(format!("Serialization error: {}", e)))
+    }
+
+    /// Restores the whole wallet from a backup made by `export_backup`, replacing the mnemonic seed phrase and all accounts.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The wallet password at the time the backup was exported.
+    /// - `backup: String` - The backup JSON string.
+    /// - `force: bool` - Whether to overwrite a stored mnemonic phrase and accounts. Without it, restoring into a
+    ///   non-empty database is refused; the check and the restore are one transaction.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: The whole backup is decrypted and validated before anything is written, and the restore is a single
+    /// transaction, the variant and derivation scheme records included. A failure at any point leaves the current
+    /// wallet untouched.
+    ///
+    /// **Warning**: With `force`, the current mnemonic and accounts are overwritten.
+    #[wasm_bindgen]
+    pub async fn import_backup(
+        password: Uint8Array,
+        backup: String,
+        force: bool,
+    ) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        Self::restore_backup(&IndexedDbStorage, &password, &backup, force).await
+    }
+
+    /// Validates a backup and restores it into a storage, see `import_backup`.
+    ///
+    /// **Parameters**:
+    /// - `storage: &dyn Storage` - The storage to restore into.
+    /// - `password: &SecureVec` - The wallet password at the time the backup was exported.
+    /// - `backup: &str` - The backup JSON string.
+    /// - `force: bool` - Whether to overwrite a stored mnemonic phrase and accounts.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok on success, or an error on failure, in which case nothing was written.
+    async fn restore_backup(
+        storage: &dyn Storage,
+        password: &SecureVec,
+        backup: &str,
+        force: bool,
+    ) -> Result<(), KeyVaultError> {
+        let payload: CipherPayload = serde_json::from_str(backup)
+            .map_err(|e| KeyVaultError::InvalidInput(format!("Invalid backup: {}", e)))?;
+        let mut backup = open_backup(password, payload)?;
+        decrypt(password, backup.seed.clone(), &seed_aad())
+            .map_err(KeyVaultError::from)?;
+        config::check_account_limit(backup.accounts.len() as u64, config::max_accounts())
+            .map_err(KeyVaultError::InvalidInput)?;
+        assign_missing_account_ids(&mut backup.accounts)
+            .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
+        backup.verifier = Some(
+            make_password_verifier(password)
+                .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?,
+        );
+
+        debug!("\x1b[37;41m WARN \x1b[0m \x1b[1mkey-vault\x1b[0m: overwriting the mnemonic phrase and all accounts with a backup");
+        // The backup doesn't tell which variant its accounts are of, nor which scheme new accounts derive with, so
+        // the restore removes both records along with the rest
+        let restored = storage
+            .restore_vault(backup, force)
+            .await
+            .map_err(KeyVaultError::from)?;
+        if !restored {
+            return Err(KeyVaultError::InvalidInput(
+                "Wallet is not empty, import with force to overwrite it".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Exports the stored records - the encrypted mnemonic seed phrase, its password verifier and all accounts - as one
+    /// portable binary blob with a magic header and a checksum, see `import_vault`. Unlike `export_backup`, no password
+    /// is needed: every record stays encrypted under the wallet password, so the blob is as safe at rest as the database.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - A JavaScript Promise that resolves to the blob on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn export_vault() -> Result<Uint8Array, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let blob = Self::encode_stored_vault(&IndexedDbStorage).await?;
+        Ok(Uint8Array::from(blob.as_slice()))
+    }
+
+    /// Reads the records of a storage into a vault export, see `export_vault`.
+    ///
+    /// **Parameters**:
+    /// - `storage: &dyn Storage` - The storage to export.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<u8>, KeyVaultError>` - The blob on success, or an error on failure.
+    async fn encode_stored_vault(storage: &dyn Storage) -> Result<Vec<u8>, KeyVaultError> {
+        let seed = storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        let verifier = storage
+            .get_password_verifier()
+            .await
+            .map_err(KeyVaultError::from)?;
+        let accounts = storage
+            .get_all_accounts()
+            .await
+            .map_err(KeyVaultError::from)?;
+        let next_index = storage
+            .next_account_index()
+            .await
+            .map_err(KeyVaultError::from)?;
+        let backup = WalletBackup {
+            seed,
+            checksum: utilities::accounts_checksum(&accounts),
+            accounts,
+            verifier,
+            next_index: Some(next_index),
+        };
+        encode_vault(&backup).map_err(KeyVaultError::Internal)
+    }
+
+    /// Restores the stored records from a blob made by `export_vault`. The wallet password stays the one at export time.
+    ///
+    /// **Parameters**:
+    /// - `blob: Uint8Array` - The vault export.
+    /// - `force: bool` - Whether to overwrite a stored mnemonic phrase and accounts. Without it, importing into a
+    ///   non-empty database is refused; the check and the restore are one transaction.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: The header and checksum are validated before anything is written, and a failure leaves the current
+    /// wallet untouched.
+    ///
+    /// **Warning**: With `force`, the current mnemonic and accounts are overwritten.
+    #[wasm_bindgen]
+    pub async fn import_vault(blob: Uint8Array, force: bool) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        Self::decode_into_storage(&IndexedDbStorage, &blob.to_vec(), force).await
+    }
+
+    /// Validates a vault export and restores it into a storage, see `import_vault`.
+    ///
+    /// **Parameters**:
+    /// - `storage: &dyn Storage` - The storage to restore into.
+    /// - `blob: &[u8]` - The vault export.
+    /// - `force: bool` - Whether to overwrite a stored mnemonic phrase and accounts.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok on success, or an error on failure, in which case nothing was written.
+    async fn decode_into_storage(
+        storage: &dyn Storage,
+        blob: &[u8],
+        force: bool,
+    ) -> Result<(), KeyVaultError> {
+        let mut backup = decode_vault(blob).map_err(KeyVaultError::InvalidInput)?;
+        config::check_account_limit(backup.accounts.len() as u64, config::max_accounts())
+            .map_err(KeyVaultError::InvalidInput)?;
+        assign_missing_account_ids(&mut backup.accounts)
+            .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
+
+        let restored = storage
+            .restore_vault(backup, force)
+            .await
+            .map_err(KeyVaultError::from)?;
+        if !restored {
+            return Err(KeyVaultError::InvalidInput(
+                "Wallet is not empty, import with force to overwrite it".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Signs a message using the SPHINCS+ private key after decrypting it with the provided password.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the private key.
+    /// - `lock_args: String` - The hex-encoded lock script's arguments corresponding to the SPHINCS+ public key of the account that signs.
+    /// - `message: Uint8Array` - The message to be signed.
+    /// - `hedged: Option<bool>` - Whether to mix fresh randomness into the signature, true if not given. Pass false
+    ///   for deterministic signatures, e.g. reproducible test vectors: the same account and message then always give
+    ///   the same signature.
+    /// - `context: Option<Uint8Array>` - A FIPS 205 context string of at most 255 bytes binding the signature to an app
+    ///   or protocol, empty if not given. The signature then only verifies under the same context, see `verify`.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - The signature as a `Uint8Array` on success,
+    ///   or a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: The SPHINCS+ signature itself is computed in one synchronous call, as fips205 doesn't expose its
+    /// internal phases to yield between. For the slow "s" variants, run this in a Web Worker (see `signing_worker.js`)
+    /// to keep the UI responsive.
+    #[wasm_bindgen]
+    pub async fn sign(
+        &self,
+        password: Uint8Array,
+        lock_args: String,
+        message: Uint8Array,
+        hedged: Option<bool>,
+        context: Option<Uint8Array>,
+    ) -> Result<Uint8Array, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let context = context.map(|context| context.to_vec()).unwrap_or_default();
+        let password = SecureVec::from_uint8_array(&password);
+        // Copied straight from JS memory into a buffer zeroized on drop; personal messages may be sensitive
+        let message_vec = SecureVec::from_uint8_array(&message);
+        let signature = self
+            .sign_with_password(
+                &password,
+                &lock_args,
+                &message_vec,
+                hedged.unwrap_or(true),
+                &context,
+            )
+            .await?;
+        Ok(Uint8Array::from(signature.as_slice()))
+    }
+
+    /// Decrypts an account's private key and signs a message with it, see `sign`.
+    ///
+    /// **Parameters**:
+    /// - `password: &SecureVec` - The password used to decrypt the private key.
+    /// - `lock_args: &str` - The hex-encoded lock script's arguments of the account that signs.
+    /// - `message: &[u8]` - The message to be signed.
+    /// - `hedged: bool` - Whether to mix fresh randomness into the signature.
+    /// - `context: &[u8]` - The FIPS 205 context string.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<u8>, KeyVaultError>` - The signature on success, or an error on failure.
+    async fn sign_with_password(
+        &self,
+        password: &SecureVec,
+        lock_args: &str,
+        message: &[u8],
+        hedged: bool,
+        context: &[u8],
+    ) -> Result<Vec<u8>, KeyVaultError> {
+        // Checked before the Scrypt run, so a malformed context fails fast
+        check_signing_context(context).map_err(KeyVaultError::InvalidInput)?;
+        self.check_stored_variant().await?;
+        let account = self
+            .storage
+            .get_account(lock_args)
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or_else(|| KeyVaultError::AccountNotFound(lock_args.to_string()))?;
+
+        let pri_key = Self::decrypt_private_key(&*self.storage, password, &account).await?;
+        self.check_key_variant(&pri_key, lock_args)?;
+        self.sign_message_bytes(&pri_key, message, hedged, context)
+    }
+
+    /// Signs one message with several accounts, e.g. for a multi-input or multisig transaction. The accounts are loaded
+    /// in one database round trip, and each private key is zeroized right after its signature is made.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the private keys.
+    /// - `lock_args_list: Vec<String>` - The hex-encoded lock script's arguments of the signing accounts.
+    /// - `message: Uint8Array` - The message to be signed.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<Uint8Array>, KeyVaultError>` - One signature per account, in the order of `lock_args_list`, on success,
+    ///   or a JavaScript error naming the missing lock_args if any account is not stored. Nothing is signed then.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Every private key is encrypted under its own salt, so each account still costs one Scrypt run.
+    #[wasm_bindgen]
+    pub async fn sign_batch(
+        &self,
+        password: Uint8Array,
+        lock_args_list: Vec<String>,
+        message: Uint8Array,
+    ) -> Result<Vec<Uint8Array>, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        self.check_stored_variant().await?;
+        let password = SecureVec::from_uint8_array(&password);
+        let found = self
+            .storage
+            .get_accounts(&lock_args_list)
+            .await
+            .map_err(KeyVaultError::from)?;
+        let accounts = require_accounts(&lock_args_list, &found)
+            .map_err(KeyVaultError::InvalidInput)?;
+
+        let message_vec = SecureVec::from_uint8_array(&message);
+
+        let mut signatures = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            let pri_key = Self::decrypt_private_key(&*self.storage, &password, account).await?;
+            self.check_key_variant(&pri_key, &account.lock_args)?;
+            signatures.push(self.sign_message(&pri_key, &message_vec)?);
+            // `pri_key` is dropped, and so zeroized, before the next account is decrypted
+        }
+        Ok(signatures)
+    }
+
+    /// Verifies a signature made by one of the wallet's accounts, e.g. to confirm before broadcasting that a signature
+    /// will pass the on-chain lock script.
+    ///
+    /// **Parameters**:
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the signing account.
+    /// - `message: Uint8Array` - The signed message.
+    /// - `signature: Uint8Array` - Either the full lock signature returned by `sign`, whose embedded public key must belong
+    ///   to the account, or a raw SPHINCS+ signature, checked against the account's stored public key.
+    /// - `context: Option<Uint8Array>` - The FIPS 205 context string the signature was made under, empty if not given.
+    ///   A signature made under another context is invalid.
+    ///
+    /// **Returns**:
+    /// - `Result<bool, KeyVaultError>` - Whether the signature is valid for the account, or a JavaScript error if the account
+    ///   doesn't exist, the signature has neither expected length, a raw signature's account has no stored public key,
+    ///   or the context is longer than 255 bytes.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn verify(
+        &self,
+        lock_args: String,
+        message: Uint8Array,
+        signature: Uint8Array,
+        context: Option<Uint8Array>,
+    ) -> Result<bool, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let account = self
+            .storage
+            .get_account(&lock_args)
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or_else(|| KeyVaultError::AccountNotFound(lock_args.clone()))?;
+        let context = context.map(|context| context.to_vec()).unwrap_or_default();
+        self.verify_for_account(
+            &account,
+            &message.to_vec(),
+            &signature.to_vec(),
+            &context,
+        )
+        .map_err(KeyVaultError::InvalidInput)
+    }
+
+    /// Verifies `signature` over `message` under `context` for `account`, see `verify`.
+    fn verify_for_account(
+        &self,
+        account: &SphincsPlusAccount,
+        message: &[u8],
+        signature: &[u8],
+        context: &[u8],
+    ) -> Result<bool, String> {
+        let pk_len = self.variant.public_key_length();
+        if signature.len() == self.variant.witness_lock_length(true) {
+            // Multisig config, then param id with the sign flag, then the public key and the raw signature
+            let config = [
+                MULTISIG_RESERVED_FIELD_VALUE,
+                REQUIRED_FIRST_N,
+                THRESHOLD,
+                PUBKEY_NUM,
+            ];
+            let param_id_and_sign_flag: u8 = (self.variant << 1) | 1;
+            if signature[..4] != config || signature[4] != param_id_and_sign_flag {
+                return Ok(false);
+            }
+            let pub_key = &signature[5..5 + pk_len];
+            if encode(lock_args_from_pub_key(
+                self.variant,
+                &self.lock_args_hash_config,
+                pub_key,
+            )) != account.lock_args
+            {
+                return Ok(false);
+            }
+            return verify_signature_with_context(
+                self.variant,
+                pub_key,
+                message,
+                &signature[5 + pk_len..],
+                context,
+            );
+        }
+        if signature.len() == self.variant.signature_length() {
+            let pub_key = account.pub_key.as_ref().ok_or_else(|| {
+                format!(
+                    "Account {} has no stored public key to verify a raw signature against; run backfill_pubkeys or pass the full signature",
+                    account.lock_args
+                )
+            })?;
+            let pub_key = hex::decode(pub_key)
+                .map_err(|e| format!("Stored public key decode error: {}", e))?;
+            return verify_signature_with_context(self.variant, &pub_key, message, signature, context);
+        }
+        Err(format!(
+            "Invalid signature length: expected {} (full) or {} (raw) bytes for {:?}, got {}",
+            self.variant.witness_lock_length(true),
+            self.variant.signature_length(),
+            self.variant,
+            signature.len()
+        ))
+    }
+
+    /// Unlocks a signing session: decrypts the private keys of the given accounts once and keeps them in memory,
+    /// so `sign_with_session` can sign without the password and without re-running scrypt. Unlocking again ends the previous session.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the private keys.
+    /// - `lock_args: Vec<String>` - The hex-encoded lock script's arguments of the accounts to unlock.
+    /// - `timeout_ms: u32` - How long the session stays valid, in milliseconds.
+    ///
+    /// **Returns**:
+    /// - `Result<String, KeyVaultError>` - A JavaScript Promise that resolves to the opaque session id on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Warning**: Decrypted private keys stay in wasm memory until `lock` is called or the session times out.
+    #[wasm_bindgen]
+    pub async fn unlock(
+        &self,
+        password: Uint8Array,
+        lock_args: Vec<String>,
+        timeout_ms: u32,
+    ) -> Result<String, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        self.check_stored_variant().await?;
+        let now = now_ms();
+        if now == 0.0 {
+            return Err(KeyVaultError::Internal(
+                "Signing sessions need a clock to expire, but Date.now and performance.now are unavailable".to_string(),
+            ));
+        }
+        let password = SecureVec::from_uint8_array(&password);
+        let mut keys: HashMap<String, SecureVec> = HashMap::new();
+        for lock_args in lock_args {
+            let account = self
+                .storage
+                .get_account(&lock_args)
+                .await
+                .map_err(KeyVaultError::from)?
+                .ok_or_else(|| KeyVaultError::AccountNotFound(lock_args.clone()))?;
+            let pri_key = Self::decrypt_private_key(&*self.storage, &password, &account).await?;
+            self.check_key_variant(&pri_key, &lock_args)?;
+            keys.insert(lock_args, pri_key);
+        }
+        let session_id = self
+            .session
+            .borrow_mut()
+            .open(keys, now, timeout_ms as f64)
+            .map_err(KeyVaultError::Internal)?;
+        Ok(session_id)
+    }
+
+    /// Ends the signing session, zeroizing its decrypted private keys, and clears the derivation and KDF caches.
+    /// The session id is no longer accepted.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn lock(&self) {
+        self.session.borrow_mut().close();
+        self.derivation_cache.borrow_mut().clear();
+        kdf_cache::clear();
+    }
+
+    /// Drops the cached Scrypt-derived encryption keys, zeroizing them. Decrypting a payload caches the key derived
+    /// from the password and its salt, so decrypting it again, e.g. the mnemonic on the next `gen_new_account`,
+    /// skips the password KDF. The cache is also cleared by `lock`, `change_password` and `clear_database`.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn clear_kdf_cache() {
+        kdf_cache::clear();
+    }
+
+    /// Seals the vault: ends the signing session and persists a sealed flag, after which every operation on wallet
+    /// data, including read-only ones like `get_all_sphincs_lock_args`, fails with a "Vault sealed" error until
+    /// `unseal` is called with the password. Unlike `lock`, the seal survives page reloads.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: `clear_database` still works while sealed and removes the seal along with the wallet.
+    #[wasm_bindgen]
+    pub async fn seal(&self) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        self.lock();
+        self
+            .storage
+            .set_sealed(true)
+            .await
+            .map_err(KeyVaultError::from)?;
+        Ok(())
+    }
+
+    /// Unseals a vault sealed with `seal`, after checking the password by decrypting the mnemonic phrase.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the mnemonic.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error if the password is wrong or no mnemonic phrase is stored.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn unseal(&self, password: Uint8Array) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        let payload = self
+            .storage
+            .get_sealed_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        // Only a successful decryption proves the password; the seed itself is dropped right away
+        Self::decrypt_with_lockout(&*self.storage, &password, payload, &seed_aad()).await?;
+        self
+            .storage
+            .set_sealed(false)
+            .await
+            .map_err(KeyVaultError::from)?;
+        Ok(())
+    }
+
+    /// Checks whether the vault is sealed, see `seal`.
+    ///
+    /// **Returns**:
+    /// - `Result<bool, KeyVaultError>` - A JavaScript Promise that resolves to whether the vault is sealed,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn is_sealed() -> Result<bool, KeyVaultError> {
+        db::is_sealed().await.map_err(KeyVaultError::from)
+    }
+
+    /// Retrieves the SPHINCS+ variant the wallet was set up with by `init_seed_phrase` or `import_seed_phrase`, so the
+    /// UI can construct the `KeyVault` with it.
+    ///
+    /// **Returns**:
+    /// - `Result<Option<SphincsVariant>, KeyVaultError>` - A JavaScript Promise that resolves to the variant, `undefined`
+    ///   for wallets set up before it was recorded or restored from a backup, or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn stored_variant() -> Result<Option<SphincsVariant>, KeyVaultError> {
+        db::get_stored_variant().await.map_err(KeyVaultError::from)
+    }
+
+    /// Checks whether a mnemonic phrase is stored, so the UI can choose between the create/import and unlock flows.
+    ///
+    /// **Returns**:
+    /// - `Result<bool, KeyVaultError>` - A JavaScript Promise that resolves to whether a mnemonic phrase is stored,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Also answers while the vault is sealed, as a sealed wallet still exists.
+    #[wasm_bindgen]
+    pub async fn has_mnemonic() -> Result<bool, KeyVaultError> {
+        let payload = db::get_sealed_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?;
+        Ok(payload.is_some())
+    }
+
+    /// Enables caching of derived key pairs for workflows that re-derive the same indices, such as overlapping
+    /// `try_gen_account_batch` ranges. A hit skips the three Scrypt runs of a derivation. Off (capacity 0) by default.
+    ///
+    /// **Parameters**:
+    /// - `capacity: u32` - The maximum number of cached key pairs. 0 disables the cache and drops its entries.
+    ///
+    /// **Async**: no
+    ///
+    /// **Warning**: Cached private keys stay in wasm memory until evicted or `lock` is called.
+    #[wasm_bindgen]
+    pub fn set_derivation_cache_capacity(&self, capacity: u32) {
+        self.derivation_cache
+            .borrow_mut()
+            .set_capacity(capacity as usize);
+    }
+
+    /// Computes the lock script arguments of an m-of-n multisig lock over SPHINCS+ public keys of this vault's variant.
+    ///
+    /// **Parameters**:
+    /// - `pubkeys: Vec<Uint8Array>` - The SPHINCS+ public keys of the signers, in the order the lock script lists them.
+    /// - `require_first_n: u8` - How many of the first `pubkeys` must sign. At most `threshold`.
+    /// - `threshold: u8` - How many signatures unlock the script. Between 1 and the number of `pubkeys`.
+    ///
+    /// **Returns**:
+    /// - `Result<String, KeyVaultError>` - The hex-encoded lock script arguments on success,
+    ///   or a JavaScript error if the parameters are inconsistent or a public key has the wrong length.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn gen_multisig_lock_args(
+        &self,
+        pubkeys: Vec<Uint8Array>,
+        require_first_n: u8,
+        threshold: u8,
+    ) -> Result<String, KeyVaultError> {
+        let pub_keys: Vec<Vec<u8>> = pubkeys.iter().map(|key| key.to_vec()).collect();
+        let lock_args = multisig_lock_args(
+            self.variant,
+            &self.lock_args_hash_config,
+            &pub_keys,
+            require_first_n,
+            threshold,
+        )
+        .map_err(KeyVaultError::InvalidInput)?;
+        Ok(encode(lock_args))
+    }
+
+    /// Signs a message with a private key of the unlocked signing session.
+    ///
+    /// **Parameters**:
+    /// - `session_id: String` - The session id returned by `unlock`.
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the account that signs. Must have been unlocked.
+    /// - `message: Uint8Array` - The message to be signed.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - The signature as a `Uint8Array` on success,
+    ///   or a JavaScript error if the session is invalid, expired or locked.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn sign_with_session(
+        &self,
+        session_id: String,
+        lock_args: String,
+        message: Uint8Array,
+    ) -> Result<Uint8Array, KeyVaultError> {
+        let mut session = self.session.borrow_mut();
+        let pri_key = session.key(&session_id, &lock_args, now_ms())
+            .map_err(KeyVaultError::InvalidInput)?;
+        let message_vec = SecureVec::from_uint8_array(&message);
+        self.sign_message(pri_key, &message_vec)
+    }
+
+    /// Signs a message with an already decrypted SPHINCS+ private key of the configured variant, hedged.
+    ///
+    /// **Parameters**:
+    /// - `pri_key: &SecureVec` - The decrypted SPHINCS+ private key.
+    /// - `message_vec: &[u8]` - The message to be signed.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - The CKB quantum resistant lock signature on success,
+    ///   or a JavaScript error on failure.
+    fn sign_message(
+        &self,
+        pri_key: &SecureVec,
+        message_vec: &[u8],
+    ) -> Result<Uint8Array, KeyVaultError> {
+        let signature = self.sign_message_bytes(pri_key, message_vec, true, &[])?;
+        Ok(Uint8Array::from(signature.as_slice()))
+    }
+
+    /// Signs a message with an already decrypted SPHINCS+ private key of the configured variant.
+    ///
+    /// **Parameters**:
+    /// - `pri_key: &SecureVec` - The decrypted SPHINCS+ private key.
+    /// - `message_vec: &[u8]` - The message to be signed.
+    /// - `hedged: bool` - Whether to mix fresh randomness into the signature (FIPS 205 hedged signing). Without it,
+    ///   signing is deterministic: the same key and message always give the same signature.
+    /// - `context: &[u8]` - The FIPS 205 context string, empty for none. Checked with `check_signing_context`.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<u8>, KeyVaultError>` - The CKB quantum resistant lock signature on success, or an error on failure.
+    fn sign_message_bytes(
+        &self,
+        pri_key: &SecureVec,
+        message_vec: &[u8],
+        hedged: bool,
+        context: &[u8],
+    ) -> Result<Vec<u8>, KeyVaultError> {
+        check_signing_context(context).map_err(KeyVaultError::InvalidInput)?;
+        match self.variant {
+            SphincsVariant::Sha2128S => sphincs_sign!(slh_dsa_sha2_128s, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Sha2128F => sphincs_sign!(slh_dsa_sha2_128f, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Shake128S => sphincs_sign!(slh_dsa_shake_128s, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Shake128F => sphincs_sign!(slh_dsa_shake_128f, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Sha2192S => sphincs_sign!(slh_dsa_sha2_192s, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Sha2192F => sphincs_sign!(slh_dsa_sha2_192f, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Shake192S => sphincs_sign!(slh_dsa_shake_192s, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Shake192F => sphincs_sign!(slh_dsa_shake_192f, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Sha2256S => sphincs_sign!(slh_dsa_sha2_256s, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Sha2256F => sphincs_sign!(slh_dsa_sha2_256f, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Shake256S => sphincs_sign!(slh_dsa_shake_256s, pri_key, message_vec, self.variant, hedged, context),
+            SphincsVariant::Shake256F => sphincs_sign!(slh_dsa_shake_256f, pri_key, message_vec, self.variant, hedged, context),
+        }
+    }
+
+    /// Signs a CKB transaction whose inputs may be locked by different accounts of the wallet. Each signing plan entry
+    /// gets its own signature over the message all digest of its input's script group. Every account's private key is
+    /// decrypted once, no matter how many plan entries refer to it.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the private keys.
+    /// - `serialized_mock_tx: Uint8Array` - serialized CKB mock transaction.
+    /// - `signing_plan: Vec<JsValue>` - A list of `{ input_index, lock_args }` objects, one per input group to sign.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<Uint8Array>, KeyVaultError>` - The signatures, in signing plan order, on success,
+    ///   or a JavaScript error on failure, including when an entry's input is locked by another script.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn sign_transaction(
+        &self,
+        password: Uint8Array,
+        serialized_mock_tx: Uint8Array,
+        signing_plan: Vec<JsValue>,
+    ) -> Result<Vec<Uint8Array>, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        self.check_stored_variant().await?;
+        let password = SecureVec::from_uint8_array(&password);
+        let repr_mock_tx = parse_repr_mock_tx(&serialized_mock_tx.to_vec())
+            .map_err(KeyVaultError::InvalidInput)?;
+        let signing_plan = signing_plan
+            .into_iter()
+            .map(serde_wasm_bindgen::from_value::<SigningPlanEntry>)
+            .collect::<Result<Vec<_>, _>>()?;
+        // Checked before any Scrypt run, so a foreign input fails fast
+        for entry in &signing_plan {
+            check_input_lock_args(&repr_mock_tx, entry.input_index, &entry.lock_args)
+                .map_err(KeyVaultError::InvalidInput)?;
+        }
+
+        // Decrypted private keys by lock args, zeroized on drop
+        let mut pri_keys: HashMap<String, SecureVec> = HashMap::new();
+        for entry in &signing_plan {
+            if pri_keys.contains_key(&entry.lock_args) {
+                continue;
+            }
+            let account = self
+                .storage
+                .get_account(&entry.lock_args)
+                .await
+                .map_err(KeyVaultError::from)?
+                .ok_or_else(|| KeyVaultError::AccountNotFound(entry.lock_args.clone()))?;
+            let pri_key = Self::decrypt_private_key(&*self.storage, &password, &account).await?;
+            self.check_key_variant(&pri_key, &entry.lock_args)?;
+            pri_keys.insert(entry.lock_args.clone(), pri_key);
+        }
+
+        let signatures = self.sign_with_plan(&repr_mock_tx.into(), &signing_plan, &pri_keys)?;
+        Ok(signatures
+            .iter()
+            .map(|signature| Uint8Array::from(signature.as_slice()))
+            .collect())
+    }
+
+    /// Signs the script group of every signing plan entry with the already decrypted key of its account, see
+    /// `sign_transaction`.
+    ///
+    /// **Parameters**:
+    /// - `mock_tx: &MockTransaction` - The CKB mock transaction.
+    /// - `signing_plan: &[SigningPlanEntry]` - Which account signs the script group of which input.
+    /// - `pri_keys: &HashMap<String, SecureVec>` - The decrypted private keys by lock args.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<Vec<u8>>, KeyVaultError>` - The signatures, in signing plan order, on success, or an error on
+    ///   failure.
+    fn sign_with_plan(
+        &self,
+        mock_tx: &MockTransaction,
+        signing_plan: &[SigningPlanEntry],
+        pri_keys: &HashMap<String, SecureVec>,
+    ) -> Result<Vec<Vec<u8>>, KeyVaultError> {
+        signing_plan
+            .iter()
+            .map(|entry| {
+                let pri_key = pri_keys
+                    .get(&entry.lock_args)
+                    .ok_or_else(|| KeyVaultError::AccountNotFound(entry.lock_args.clone()))?;
+                let message = ckb_tx_message_all(mock_tx, entry.input_index)
+                    .map_err(KeyVaultError::InvalidInput)?;
+                self.sign_message_bytes(pri_key, &message, true, &[])
+            })
+            .collect()
+    }
+
+    /// Signs a CKB transaction with one account in a single call: computes the message all digest of the mock
+    /// transaction, the same one `Util::get_ckb_tx_message_all` returns, and signs it with the account's key.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the private key.
+    /// - `lock_args: String` - The hex-encoded lock script's arguments of the account that signs.
+    /// - `serialized_mock_tx: Uint8Array` - serialized CKB mock transaction.
+    /// - `input_index: usize` - Index of an input locked by the account's script group.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - The CKB quantum resistant lock signature on success,
+    ///   or a JavaScript error on failure, including when the input at `input_index` is locked by another script.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: For transactions whose inputs are locked by several accounts, use `sign_transaction` with a signing plan.
+    #[wasm_bindgen]
+    pub async fn sign_mock_transaction(
+        &self,
+        password: Uint8Array,
+        lock_args: String,
+        serialized_mock_tx: Uint8Array,
+        input_index: usize,
+    ) -> Result<Uint8Array, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        self.check_stored_variant().await?;
+        let password = SecureVec::from_uint8_array(&password);
+        // Computed before any Scrypt run, so a malformed transaction or a foreign input fails fast
+        let repr_mock_tx = parse_repr_mock_tx(&serialized_mock_tx.to_vec())
+            .map_err(KeyVaultError::InvalidInput)?;
+        check_input_lock_args(&repr_mock_tx, input_index, &lock_args)
+            .map_err(KeyVaultError::InvalidInput)?;
+        let message = ckb_tx_message_all(&repr_mock_tx.into(), input_index)
+            .map_err(KeyVaultError::InvalidInput)?;
+
+        let account = self
+            .storage
+            .get_account(&lock_args)
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or_else(|| KeyVaultError::AccountNotFound(lock_args.clone()))?;
+        let pri_key = Self::decrypt_private_key(&*self.storage, &password, &account).await?;
+        self.check_key_variant(&pri_key, &lock_args)?;
+        self.sign_message(&pri_key, &message)
+    }
+
+    /// Supporting wallet recovery - derives a list of lock script arguments (processed public keys) from the seed phrase starting from a given index.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the mnemonic.
+    /// - `start_index: u32` - The starting index for derivation.
+    /// - `count: u32` - The number of sequential lock scripts arguments to derive.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<String>, KeyVaultError>` - A list of lock script arguments on success,
+    ///   or a JavaScript error on failure.
+    #[wasm_bindgen]
+    pub async fn try_gen_account_batch(
+        &self,
+        password: Uint8Array,
+        start_index: u32,
+        count: u32,
+    ) -> Result<Vec<String>, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        self.check_stored_variant().await?;
+        config::check_account_limit(start_index as u64 + count as u64, config::max_accounts())
+            .map_err(KeyVaultError::InvalidInput)?;
+        let password = SecureVec::from_uint8_array(&password);
+        // Get and decrypt the mnemonic seed phrase
+        let payload = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        let params = self.new_key_params().await?;
+        let mut seed =
+            Self::decrypt_with_lockout(&*self.storage, &password, payload, &seed_aad()).await?;
+        let lock_args_array = self.derive_range_then_zeroize(
+            &mut seed,
+            start_index..(start_index + count),
+            params,
+            // Calculate lock script args
+            |_, pub_key, _| Ok(encode(self.get_lock_scrip_arg(&pub_key))),
+        )?;
+        Ok(lock_args_array)
+    }
+
+    /// Exports a watch-only public descriptor of an account, the closest equivalent of a BIP32 account xpub.
+    ///
+    /// An account covers a fixed window of `config::descriptor_batch_size()` consecutive derivation indices
+    /// (20 by default, see `Util::set_descriptor_batch_size`): account `n` covers indices `n * size` to `(n + 1) * size - 1`.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the mnemonic.
+    /// - `account: u32` - The descriptor account number.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - An `AccountDescriptor` object `{ account, variant, path_prefix, derivation, pubkeys }`
+    ///   on success, or a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Unlike a BIP32 xpub, the descriptor cannot derive further public keys. SPHINCS+ has no public child
+    /// derivation, so every address is derived from the seed up front and a watch-only wallet only sees the addresses
+    /// listed in `pubkeys`. Export the next account to watch more addresses. The descriptor reveals no private material.
+    #[wasm_bindgen]
+    pub async fn account_xpub_equivalent(
+        &self,
+        password: Uint8Array,
+        account: u32,
+    ) -> Result<JsValue, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let (start, end) =
+            config::descriptor_index_range(account, config::descriptor_batch_size())
+                .map_err(KeyVaultError::InvalidInput)?;
+        config::check_account_limit(end as u64, config::max_accounts())
+            .map_err(KeyVaultError::InvalidInput)?;
+        let password = SecureVec::from_uint8_array(&password);
+        let payload = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        let params = self.new_key_params().await?;
+        let seed =
+            Self::decrypt_with_lockout(&*self.storage, &password, payload, &seed_aad()).await?;
+        let descriptor = self.account_descriptor(&seed, account, start, end, params)
+            .map_err(KeyVaultError::KeyDerivation)?;
+        Ok(serde_wasm_bindgen::to_value(&descriptor)?)
+    }
+
+    /// Derives the public keys of the index range `[start, end)` into the descriptor of `account`, at the KDF parameters `params`.
+    fn account_descriptor(
+        &self,
+        seed: &[u8],
+        account: u32,
+        start: u32,
+        end: u32,
+        params: DerivationParams,
+    ) -> Result<AccountDescriptor, String> {
+        let mut pubkeys = Vec::new();
+        for index in start..end {
+            let (pub_key, _) = self.derive_sphincs_key(seed, index, params)?;
+            pubkeys.push(DescriptorKey {
+                index,
+                pub_key: encode(&*pub_key),
+                lock_args: encode(self.get_lock_scrip_arg(&pub_key)),
+            });
+        }
+        Ok(AccountDescriptor {
+            account,
+            variant: self.variant,
+            path_prefix: KDF_PATH_PREFIX.to_string(),
+            derivation: params,
+            pubkeys,
+        })
+    }
+
+    /// Audits stored accounts by re-deriving the lock script arguments of an index range from the seed and comparing
+    /// them to the ones stored at those indices. A mismatch points at a derivation bug, a variant mismatch or store corruption.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the mnemonic.
+    /// - `start_index: u32` - The first index to audit.
+    /// - `count: u32` - The number of indices to audit, stored or not.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - A JavaScript Promise that resolves to `[{ index, stored, rederived, matches }]`,
+    ///   with `stored` null for indices without an account, or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn audit_lock_args(
+        &self,
+        password: Uint8Array,
+        start_index: u32,
+        count: u32,
+    ) -> Result<JsValue, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        config::check_account_limit(start_index as u64 + count as u64, config::max_accounts())
+            .map_err(KeyVaultError::InvalidInput)?;
+        let password = SecureVec::from_uint8_array(&password);
+        let payload = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        let seed =
+            Self::decrypt_with_lockout(&*self.storage, &password, payload, &seed_aad()).await?;
+        let accounts = self
+            .storage
+            .get_all_accounts()
+            .await
+            .map_err(KeyVaultError::from)?;
+
+        let audit = self.audit_range(&seed, &accounts, start_index, count)
+            .map_err(KeyVaultError::KeyDerivation)?;
+        Ok(serde_wasm_bindgen::to_value(&audit)?)
+    }
+
+    /// Re-derives the lock script arguments of `count` indices from `start_index` and compares them to `accounts`.
+    ///
+    /// **Parameters**:
+    /// - `seed: &[u8]` - The decrypted master seed.
+    /// - `accounts: &[SphincsPlusAccount]` - The stored accounts.
+    /// - `start_index: u32` - The first index to audit.
+    /// - `count: u32` - The number of indices to audit.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<LockArgsAudit>, String>` - One entry per index on success, or an error message on failure.
+    fn audit_range(
+        &self,
+        seed: &[u8],
+        accounts: &[SphincsPlusAccount],
+        start_index: u32,
+        count: u32,
+    ) -> Result<Vec<LockArgsAudit>, String> {
+        // Watch-only accounts don't derive from the seed
+        let stored: HashMap<u32, &SphincsPlusAccount> = accounts
+            .iter()
+            .filter(|account| !account.is_watch_only())
+            .map(|account| (account.index, account))
+            .collect();
+        let mut audit: Vec<LockArgsAudit> = Vec::new();
+        for index in start_index..start_index.saturating_add(count) {
+            // Stored accounts are re-derived at the cost they were created with
+            let params = stored
+                .get(&index)
+                .map_or(self.derivation_params, |account| account.derivation);
+            let (pub_key, _) = self.derive_sphincs_key(seed, index, params)?;
+            let rederived = encode(self.get_lock_scrip_arg(&pub_key));
+            let stored = stored.get(&index).map(|account| account.lock_args.clone());
+            audit.push(LockArgsAudit {
+                index,
+                matches: stored.as_deref() == Some(rederived.as_str()),
+                stored,
+                rederived,
+            });
+        }
+        Ok(audit)
+    }
+
+    /// One-time migration for legacy accounts - re-derives the public key of every account stored without one,
+    /// at its stored index, and writes them back in a single transaction. Password-free features (verification,
+    /// address display) rely on the stored public keys.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the seed phrase.
+    ///
+    /// **Returns**:
+    /// - `Result<u32, KeyVaultError>` - The number of accounts backfilled on success, or a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Fails without writing anything if a re-derived key doesn't reproduce the stored lock_args,
+    /// e.g. when the vault was created with another SPHINCS+ variant.
+    #[wasm_bindgen]
+    pub async fn backfill_pubkeys(&self, password: Uint8Array) -> Result<u32, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        let payload = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        let seed =
+            Self::decrypt_with_lockout(&*self.storage, &password, payload, &seed_aad()).await?;
+        let accounts = self
+            .storage
+            .get_all_accounts()
+            .await
+            .map_err(KeyVaultError::from)?;
+
+        let backfilled = self.fill_missing_pub_keys(&seed, accounts)
+            .map_err(KeyVaultError::InvalidInput)?;
+        let count = backfilled.len() as u32;
+        if count > 0 {
+            self
+                .storage
+                .put_accounts(backfilled)
+                .await
+                .map_err(KeyVaultError::from)?;
+        }
+        Ok(count)
+    }
+
+    /// Re-derives the public keys of the `accounts` stored without one, checking each against its stored lock_args.
+    ///
+    /// **Parameters**:
+    /// - `seed: &[u8]` - The decrypted master seed.
+    /// - `accounts: Vec<SphincsPlusAccount>` - The stored accounts.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<SphincsPlusAccount>, String>` - The accounts that were missing a public key, now filled in,
+    ///   or an error message if any of them doesn't match its re-derived lock_args.
+    fn fill_missing_pub_keys(
+        &self,
+        seed: &[u8],
+        accounts: Vec<SphincsPlusAccount>,
+    ) -> Result<Vec<SphincsPlusAccount>, String> {
+        let mut backfilled = Vec::new();
+        for mut account in accounts
+            .into_iter()
+            .filter(|account| account.pub_key.is_none())
+        {
+            let (pub_key, _) = self
+                .derive_sphincs_key(seed, account.index, account.derivation)
+                .map_err(|e| format!("Key derivation error: {}", e))?;
+            if encode(self.get_lock_scrip_arg(&pub_key)) != account.lock_args {
+                return Err(format!(
+                    "Account {} does not match its re-derived lock_args",
+                    account.index
+                ));
+            }
+            account.pub_key = Some(encode(&*pub_key));
+            backfilled.push(account);
+        }
+        Ok(backfilled)
+    }
+
+    /// Estimates how long `recover_accounts` takes for `count` accounts by timing one full recovery cycle (key
+    /// derivation at the vault's derivation cost plus private key encryption) on a throwaway seed and scaling it by `count`.
+    ///
+    /// **Parameters**:
+    /// - `count: u32` - The number of accounts to recover.
+    ///
+    /// **Returns**:
+    /// - `Result<f64, KeyVaultError>` - The estimated duration in milliseconds on success,
+    ///   or a JavaScript error if no clock is available or the benchmark fails.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: The estimate leaves out IndexedDB writes and blocks the thread for one cycle, about a second at the default cost.
+    #[wasm_bindgen]
+    pub async fn estimate_recovery_time(&self, count: u32) -> Result<f64, KeyVaultError> {
+        if now_ms() == 0.0 {
+            return Err(KeyVaultError::Internal(
+                "Estimating recovery time needs a clock, but Date.now and performance.now are unavailable".to_string(),
+            ));
+        }
+        let cycle_ms = self.time_recovery_cycle(now_ms).map_err(KeyVaultError::Internal)?;
+        Ok(cycle_ms * count as f64)
+    }
+
+    /// Times one recovery cycle of `recover_accounts` with `clock`, in milliseconds, see `estimate_recovery_time`.
+    fn time_recovery_cycle(&self, clock: impl Fn() -> f64) -> Result<f64, String> {
+        let seed = get_random_bytes(self.variant.bip39_compatible_entropy_size())
+            .map_err(|e| e.to_string())?;
+        let password = get_random_bytes(32).map_err(|e| e.to_string())?;
+        let start = clock();
+        // Uncached, as recovery derives indices that were never derived before
+        let (pub_key, pri_key) =
+            self.derive_sphincs_key_uncached(&seed, 0, self.derivation_params)?;
+        let lock_args = encode(self.get_lock_scrip_arg(&pub_key));
+        encrypt(&password, &pri_key, &account_aad(&lock_args))?;
+        Ok((clock() - start).max(0.0))
+    }
+
+    /// Supporting wallet recovery - Recovers the wallet by deriving and storing private keys for the first N accounts.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the seed phrase.
+    /// - `count: u32` - The number of accounts to recover (from index 0 to count-1).
+    /// - `on_progress: Option<Function>` - Optional callback invoked as `on_progress(done, count)` after each account
+    ///   is derived and encrypted, before anything is stored. The recovery yields to the event loop after each account,
+    ///   so the page can render the progress. An exception thrown by the callback aborts the recovery.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<String>, KeyVaultError>` - A list of the recovered sphincs+ lock script arguments (processed public keys) on success, or a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Refused if the stored accounts don't derive from the current seed (e.g. another seed was imported over
+    /// it), as mixing accounts of two seeds in one store leaves some of them unsignable. Clear the database and recover instead.
+    /// The accounts are stored in a single transaction once all are ready, so a failure stores none of them. Each private
+    /// key is encrypted and dropped as soon as it's derived; the seed stays decrypted until the last one is encrypted.
+    #[wasm_bindgen]
+    pub async fn recover_accounts(
+        &self,
+        password: Uint8Array,
+        count: u32,
+        on_progress: Option<Function>,
+    ) -> Result<Vec<String>, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        self.recover_first_accounts(&password, count, |done, count| {
+            if let Some(callback) = &on_progress {
+                callback.call2(&JsValue::NULL, &JsValue::from(done), &JsValue::from(count))?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Derives, encrypts and stores the first `count` accounts, see `recover_accounts`.
+    ///
+    /// **Parameters**:
+    /// - `password: &SecureVec` - The password used to decrypt the seed phrase.
+    /// - `count: u32` - The number of accounts to recover (from index 0 to count-1).
+    /// - `on_progress: F` - Called as `on_progress(done, count)` after each account is derived and encrypted. An error
+    ///   aborts the recovery.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<String>, KeyVaultError>` - The recovered lock script arguments on success, or an error on failure.
+    async fn recover_first_accounts<F>(
+        &self,
+        password: &SecureVec,
+        count: u32,
+        mut on_progress: F,
+    ) -> Result<Vec<String>, KeyVaultError>
+    where
+        F: FnMut(u32, u32) -> Result<(), KeyVaultError>,
+    {
+        self.check_stored_variant().await?;
+        config::check_account_limit(count as u64, config::max_accounts())
+            .map_err(KeyVaultError::InvalidInput)?;
+        // Get and decrypt the mnemonic seed phrase
+        let payload = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        let accounts = self
+            .storage
+            .get_all_accounts()
+            .await
+            .map_err(KeyVaultError::from)?;
+        let params = self.new_key_params().await?;
+        let mut seed =
+            Self::decrypt_with_lockout(&*self.storage, password, payload, &seed_aad()).await?;
+        self.check_accounts_match_seed(&seed, &accounts).map_err(KeyVaultError::InvalidInput)?;
+        // Each private key is encrypted and dropped as soon as it's derived, so at most one is decrypted at a time
+        let mut new_accounts: Vec<SphincsPlusAccount> = Vec::new();
+        for index in 0..count {
+            let (pub_key, pri_key) = self
+                .derive_sphincs_key(&seed, index, params)
+                .map_err(KeyVaultError::KeyDerivation)?;
+            let lock_args = encode(self.get_lock_scrip_arg(&pub_key));
+            // Encrypt the private key with the lock script args as associated data
+            let encrypted_pri = encrypt(password, &pri_key, &account_aad(&lock_args))
+                .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+            let account_id = new_account_id()
+                .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
+            new_accounts.push(SphincsPlusAccount {
+                index,
+                lock_args,
+                pri_enc: Some(encrypted_pri),
+                account_id: Some(account_id),
+                pub_key: Some(encode(&*pub_key)),
+                derivation: params,
+                has_history: None,
+                label: None,
+            });
+            on_progress(index + 1, count)?;
+            // Let the page render and stay responsive before the next derivation blocks it again
+            db::yield_now().await;
+        }
+        seed.zeroize();
+        let lock_args_array: Vec<String> = new_accounts
+            .iter()
+            .map(|account| account.lock_args.clone())
+            .collect();
+
+        // Store to DB, all or nothing. Accounts already stored are kept as they are.
+        self
+            .storage
+            .add_accounts(new_accounts)
+            .await
+            .map_err(KeyVaultError::from)?;
+        Ok(lock_args_array)
+    }
+
+    /// Refuses to work with keys of another variant than the one the wallet was set up with: lock script arguments
+    /// and signatures of a mismatched `KeyVault` would be garbage. Skipped when `check_variant` is off.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok if the variants match or none is stored, or `VariantMismatch` otherwise.
+    async fn check_stored_variant(&self) -> Result<(), KeyVaultError> {
+        if !self.check_variant {
+            return Ok(());
+        }
+        match self
+            .storage
+            .get_stored_variant()
+            .await
+            .map_err(KeyVaultError::from)?
+        {
+            Some(stored) if stored != self.variant => Err(KeyVaultError::VariantMismatch(format!(
+                "the wallet was set up with {:?}, this KeyVault uses {:?}",
+                stored, self.variant
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuses to sign with a private key of another variant than `variant`. The key length only tells the security
+    /// level apart: the "s" and "f" variants of a level, SHA2 and SHAKE alike, share it. The account's lock script
+    /// arguments commit to its variant, so they are recomputed over the key's public half under `variant` and
+    /// compared with the stored ones. Skipped when `check_variant` is off.
+    ///
+    /// **Parameters**:
+    /// - `pri_key: &[u8]` - The decrypted SPHINCS+ private key.
+    /// - `lock_args: &str` - The hex-encoded lock script's arguments stored with the account.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok if the key belongs to `variant`, or `VariantMismatch` naming the account's
+    ///   variant when it can be detected.
+    fn check_key_variant(&self, pri_key: &[u8], lock_args: &str) -> Result<(), KeyVaultError> {
+        if !self.check_variant {
+            return Ok(());
+        }
+        self.variant
+            .check_private_key_length(pri_key.len())
+            .map_err(KeyVaultError::VariantMismatch)?;
+        let pub_key = SecureVec::from_slice(&pri_key[pri_key.len() / 2..]);
+        if encode(self.get_lock_scrip_arg(&pub_key)) == lock_args {
+            return Ok(());
+        }
+        Err(KeyVaultError::VariantMismatch(
+            match Self::detect_account_variant(pri_key, lock_args) {
+                Some(variant) => format!(
+                    "This account was created with a different SPHINCS+ variant; configure the wallet with variant {} to sign",
+                    variant
+                ),
+                None => format!(
+                    "Account {} doesn't match its private key under {}",
+                    lock_args, self.variant
+                ),
+            },
+        ))
+    }
+
+    /// The KDF parameters new keys are derived with: `derivation_params`, except for the scheme when the wallet
+    /// recorded the one it was set up with, so the accounts of a wallet never mix schemes.
+    ///
+    /// **Returns**:
+    /// - `Result<DerivationParams, KeyVaultError>` - The parameters on success, or a `Database` error.
+    async fn new_key_params(&self) -> Result<DerivationParams, KeyVaultError> {
+        let single_scrypt = self
+            .storage
+            .get_stored_single_scrypt()
+            .await
+            .map_err(KeyVaultError::from)?
+            .unwrap_or(self.derivation_params.single_scrypt);
+        Ok(DerivationParams {
+            single_scrypt,
+            ..self.derivation_params
+        })
+    }
+
+    /// Supporting wallet recovery - scans accounts from index 0 and stops after `gap_limit` consecutive accounts without
+    /// on-chain history, the way HD wallets discover their accounts. Only accounts up to the last used one are stored.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the seed phrase.
+    /// - `gap_limit: u32` - The number of consecutive unused accounts that ends the scan, at least 1.
+    /// - `is_used: Function` - Callback invoked as `is_used(lock_args)` for each derived account, returning (or resolving
+    ///   to) whether the account has on-chain history. An exception or rejection aborts the recovery.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<String>, KeyVaultError>` - The lock script arguments of the recovered accounts, from index 0 to the
+    ///   last used one (empty if none is used), on success, or a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Note**: Refused, like `recover_accounts`, if the stored accounts don't derive from the current seed. The seed stays
+    /// decrypted in wasm memory while `is_used` runs, and the scan never goes past `config::max_accounts`. Only public
+    /// keys are kept during the scan; the private keys of the recovered accounts are re-derived once it ends, so each
+    /// of them costs a second derivation unless the derivation cache is enabled.
+    #[wasm_bindgen]
+    pub async fn recover_with_gap_limit(
+        &self,
+        password: Uint8Array,
+        gap_limit: u32,
+        is_used: Function,
+    ) -> Result<Vec<String>, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        self.check_stored_variant().await?;
+        if gap_limit == 0 {
+            return Err(KeyVaultError::InvalidInput(
+                "Gap limit must be at least 1".to_string(),
+            ));
+        }
+        let password = SecureVec::from_uint8_array(&password);
+        let payload = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        let accounts = self
+            .storage
+            .get_all_accounts()
+            .await
+            .map_err(KeyVaultError::from)?;
+        let params = self.new_key_params().await?;
+        let mut seed =
+            Self::decrypt_with_lockout(&*self.storage, &password, payload, &seed_aad()).await?;
+        self.check_accounts_match_seed(&seed, &accounts).map_err(KeyVaultError::InvalidInput)?;
+
+        // Only public data is kept while the scan awaits `is_used`: private keys are re-derived for the used range only
+        let max_accounts = config::max_accounts();
+        let mut scanned: Vec<(String, SecureVec, bool)> = Vec::new();
+        let mut last_used: Option<u32> = None;
+        let mut index = 0;
+        while !gap_scan_done(index, last_used, gap_limit) && index < max_accounts {
+            let (pub_key, _) = self
+                .derive_sphincs_key(&seed, index, params)
+                .map_err(KeyVaultError::KeyDerivation)?;
+            let lock_args = encode(self.get_lock_scrip_arg(&pub_key));
+
+            let mut answer = is_used.call1(&JsValue::NULL, &JsValue::from_str(&lock_args))?;
+            if let Some(promise) = answer.dyn_ref::<Promise>() {
+                answer = JsFuture::from(promise.clone()).await?;
+            }
+            let used = answer.is_truthy();
+            if used {
+                last_used = Some(index);
+            }
+            scanned.push((lock_args, pub_key, used));
+            index += 1;
+        }
+
+        let recovered_count = last_used.map_or(0, |used| used + 1);
+        scanned.truncate(recovered_count as usize);
+        let pri_keys = self.derive_range_then_zeroize(
+            &mut seed,
+            0..recovered_count,
+            params,
+            |_, _, pri_key| Ok(pri_key),
+        )?;
+
+        let mut lock_args_array: Vec<String> = Vec::new();
+        let mut new_accounts: Vec<SphincsPlusAccount> = Vec::new();
+        for (index, ((lock_args, pub_key, used), pri_key)) in
+            scanned.into_iter().zip(pri_keys).enumerate()
+        {
+            let encrypted_pri = encrypt(&password, &pri_key, &account_aad(&lock_args))
+                .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+            let account_id = new_account_id()
+                .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
+            new_accounts.push(SphincsPlusAccount {
+                index: index as u32,
+                lock_args: lock_args.clone(),
+                pri_enc: Some(encrypted_pri),
+                account_id: Some(account_id),
+                pub_key: Some(encode(&*pub_key)),
+                derivation: params,
+                has_history: Some(used),
+                label: None,
+            });
+            lock_args_array.push(lock_args);
+        }
+
+        // Store to DB, all or nothing. Accounts already stored are kept as they are.
+        self
+            .storage
+            .add_accounts(new_accounts)
+            .await
+            .map_err(KeyVaultError::from)?;
+        Ok(lock_args_array)
+    }
+
+    /// Checks that the stored accounts derive from `seed` by re-deriving the lowest-index one, at its stored cost.
+    /// Watch-only accounts are left out, as they don't derive from the seed.
+    ///
+    /// **Parameters**:
+    /// - `seed: &[u8]` - The decrypted master seed.
+    /// - `accounts: &[SphincsPlusAccount]` - The stored accounts.
+    ///
+    /// **Returns**:
+    /// - `Result<(), String>` - Ok if there are no accounts or the sampled one matches, or an error message otherwise.
+    fn check_accounts_match_seed(
+        &self,
+        seed: &[u8],
+        accounts: &[SphincsPlusAccount],
+    ) -> Result<(), String> {
+        let Some(sample) = accounts
+            .iter()
+            .filter(|account| !account.is_watch_only())
+            .min_by_key(|account| account.index)
+        else {
+            return Ok(());
+        };
+        let (pub_key, _) = self
+            .derive_sphincs_key(seed, sample.index, sample.derivation)
+            .map_err(|e| format!("Key derivation error: {}", e))?;
+        if encode(self.get_lock_scrip_arg(&pub_key)) != sample.lock_args {
+            return Err(format!(
+                "Stored account {} does not derive from the current seed phrase. Clear the database and recover again to avoid mixing accounts of different seeds.",
+                sample.index
+            ));
+        }
+        Ok(())
+    }
+
+    /// Changes the wallet password by re-encrypting the mnemonic seed phrase and every account's private key.
+    /// Keys are not re-derived. Everything is written back in a single transaction, so a failure at any point
+    /// leaves the wallet on the old password. The transaction first checks that the seed and accounts are still those
+    /// that were re-encrypted, and writes nothing if another tab changed them meanwhile.
+    ///
+    /// **Parameters**:
+    /// - `old_password: Uint8Array` - The current password.
+    /// - `new_password: Uint8Array` - The new password.
+    /// - `on_progress: Option<Function>` - Optional callback invoked as `on_progress(done, total)` after each account
+    ///   is re-encrypted, before the final commit. An exception thrown by the callback aborts the operation.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - A JavaScript Promise that resolves to `undefined` on success,
+    ///   or rejects with a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    #[wasm_bindgen]
+    pub async fn change_password(
+        &self,
+        old_password: Uint8Array,
+        new_password: Uint8Array,
+        on_progress: Option<Function>,
+    ) -> Result<(), KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let old_password = SecureVec::from_uint8_array(&old_password);
+        let new_password = SecureVec::from_uint8_array(&new_password);
+        self.reencrypt_wallet(&old_password, &new_password, |done, total| match &on_progress {
+            Some(callback) => callback
+                .call2(&JsValue::NULL, &JsValue::from(done), &JsValue::from(total))
+                .map(|_| ())
+                .map_err(KeyVaultError::from),
+            None => Ok(()),
+        })
+        .await
+    }
+
+    /// Re-encrypts the mnemonic seed phrase and every account under a new password, see `change_password`.
+    ///
+    /// **Parameters**:
+    /// - `old_password: &SecureVec` - The current password.
+    /// - `new_password: &SecureVec` - The new password.
+    /// - `on_progress: F` - Called as `on_progress(done, total)` after each account is re-encrypted. An error aborts
+    ///   the operation.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok on success, or an error on failure, in which case nothing was written.
+    async fn reencrypt_wallet<F>(
+        &self,
+        old_password: &SecureVec,
+        new_password: &SecureVec,
+        on_progress: F,
+    ) -> Result<(), KeyVaultError>
+    where
+        F: FnMut(u32, u32) -> Result<(), KeyVaultError>,
+    {
+        check_password_not_empty(new_password).map_err(KeyVaultError::InvalidInput)?;
+
+        let payload = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        let read_seed = payload.cipher_text.clone();
+        // A wrong old password fails here, before anything is read or written. The decrypted seed is
+        // dropped, and so zeroized, as soon as it is re-encrypted.
+        let encrypted_seed = {
+            let seed =
+                Self::decrypt_with_lockout(&*self.storage, old_password, payload, &seed_aad())
+                    .await?;
+            encrypt(new_password, &seed, &seed_aad())
+                .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?
+        };
+
+        let mut accounts = self
+            .storage
+            .get_all_accounts()
+            .await
+            .map_err(KeyVaultError::from)?;
+        let read_lock_args: Vec<String> = accounts
+            .iter()
+            .map(|account| account.lock_args.clone())
+            .collect();
+        reencrypt_accounts(&mut accounts, old_password, new_password, on_progress)?;
+        let verifier = make_password_verifier(new_password)
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+
+        // Keys derived from the old password are of no further use
+        kdf_cache::clear();
+        // Re-checked inside the write transaction, as the records were read before the re-encryption
+        self
+            .storage
+            .replace_seed_and_accounts(
+                encrypted_seed,
+                verifier,
+                accounts,
+                Some((read_seed, read_lock_args)),
+            )
+            .await
+            .map_err(KeyVaultError::from)
+    }
+
+    /// Key rotation - replaces the mnemonic seed phrase with freshly generated entropy and re-derives every signing
+    /// account at its stored index from the new seed, so gaps left by deleted accounts are kept. The new accounts
+    /// have new addresses.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password used to decrypt the current mnemonic and encrypt the new seed and child keys.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<String>, KeyVaultError>` - A list of the new accounts' lock script arguments on success,
+    ///   or a JavaScript error on failure.
+    ///
+    /// **Async**: Yes
+    ///
+    /// **Warning**: The old mnemonic and all old accounts, watch-only ones included, are overwritten and can't be
+    /// recovered from this wallet afterwards.
+    /// Funds held by the old addresses MUST be swept to the new addresses (or elsewhere) before rotating.
+    ///
+    /// **Note**: Each new account keeps the local id, label and history flag of the account it replaces, so callers
+    /// keyed on `account_id` keep addressing the same logical account.
+    #[wasm_bindgen]
+    pub async fn rotate_seed(&self, password: Uint8Array) -> Result<Vec<String>, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        self.rotate_to_fresh_seed(&password).await
+    }
+
+    /// Replaces the mnemonic phrase with fresh entropy and re-derives the signing accounts, see `rotate_seed`.
+    ///
+    /// **Parameters**:
+    /// - `password: &SecureVec` - The password of the wallet.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<String>, KeyVaultError>` - The new accounts' lock script arguments on success, or an error on
+    ///   failure, in which case nothing was written.
+    async fn rotate_to_fresh_seed(&self, password: &SecureVec) -> Result<Vec<String>, KeyVaultError> {
+        self.check_stored_variant().await?;
+
+        // Authenticate against the current mnemonic seed phrase before touching anything
+        let payload = self
+            .storage
+            .get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+        Self::decrypt_with_lockout(&*self.storage, password, payload, &seed_aad()).await?;
+        // Watch-only accounts have no counterpart to re-derive
+        let old_accounts: Vec<SphincsPlusAccount> = self
+            .storage
+            .get_all_accounts()
+            .await
+            .map_err(KeyVaultError::from)?
+            .into_iter()
+            .filter(|account| !account.is_watch_only())
+            .collect();
+        let params = self.new_key_params().await?;
+
+        let size = self.variant.bip39_compatible_entropy_size();
+        let new_seed = get_random_bytes(size)
+            .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
+        let encrypted_seed = encrypt(password, &new_seed, &seed_aad())
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+
+        let mut accounts: Vec<SphincsPlusAccount> = Vec::new();
+        for old in old_accounts {
+            let (pub_key, pri_key) = self
+                .derive_sphincs_key(&new_seed, old.index, params)
+                .map_err(KeyVaultError::KeyDerivation)?;
+            let lock_script_args = self.get_lock_scrip_arg(&pub_key);
+            let encrypted_pri =
+                encrypt(password, &pri_key, &account_aad(&encode(lock_script_args)))
+                    .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+            accounts.push(SphincsPlusAccount {
+                index: old.index,
+                lock_args: encode(lock_script_args),
+                pri_enc: Some(encrypted_pri),
+                account_id: old.account_id,
+                pub_key: Some(encode(&*pub_key)),
+                derivation: params,
+                has_history: old.has_history,
+                label: old.label,
+            });
+        }
+        let lock_args_array: Vec<String> = accounts
+            .iter()
+            .map(|account| account.lock_args.clone())
+            .collect();
+        let verifier = make_password_verifier(password)
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+
+        debug!("\x1b[37;41m WARN \x1b[0m \x1b[1mkey-vault\x1b[0m: overwriting the mnemonic phrase and all accounts with a rotated seed");
+        self
+            .storage
+            .replace_seed_and_accounts(encrypted_seed, verifier, accounts, None)
+            .await
+            .map_err(KeyVaultError::from)?;
+        Ok(lock_args_array)
+    }
+
+    /// Stores an account that must not exist yet.
+    ///
+    /// **Parameters**:
+    /// - `account: SphincsPlusAccount` - The account to store.
+    ///
+    /// **Returns**:
+    /// - `Result<(), KeyVaultError>` - Ok on success, an `InvalidInput` error if its lock_args are already stored, or
+    ///   an error if storage fails.
+    ///
+    /// **Async**: Yes
+    async fn add_new_account(&self, account: SphincsPlusAccount) -> Result<(), KeyVaultError> {
+        let lock_args = account.lock_args.clone();
+        if !self
+            .storage
+            .add_account(account)
+            .await
+            .map_err(KeyVaultError::from)?
+        {
+            return Err(KeyVaultError::InvalidInput(format!(
+                "Account {} already exists",
+                lock_args
+            )));
+        }
+        Ok(())
+    }
+
+    /// Decrypts an account's private key.
+    ///
+    /// **Parameters**:
+    /// - `password: &[u8]` - The password the private key is encrypted under.
+    /// - `account: &SphincsPlusAccount` - The stored account.
+    ///
+    /// **Returns**:
+    /// - `Result<SecureVec, KeyVaultError>` - The private key on success, a `WatchOnly` error if the account holds
+    ///   no private key, or an error of `decrypt_with_lockout` if decryption fails.
+    async fn decrypt_private_key(
+        storage: &dyn Storage,
+        password: &[u8],
+        account: &SphincsPlusAccount,
+    ) -> Result<SecureVec, KeyVaultError> {
+        let payload = account
+            .pri_enc
+            .clone()
+            .ok_or_else(|| KeyVaultError::WatchOnly(account.lock_args.clone()))?;
+        Self::decrypt_with_lockout(storage, password, payload, &account_aad(&account.lock_args))
+            .await
+    }
+
+    /// Decrypts a payload stored under the wallet password, counting wrong passwords towards the failed-unlock
+    /// lockout, see `utilities::check_unlock_allowed`. Only authentication failures of well-formed payloads count,
+    /// so a corrupted record can't lock the user out.
+    ///
+    /// **Parameters**:
+    /// - `password: &[u8]` - The password the payload is encrypted under.
+    /// - `payload: CipherPayload` - The stored payload.
+    /// - `aad: &[u8]` - The associated data of the payload's record.
+    ///
+    /// **Returns**:
+    /// - `Result<SecureVec, KeyVaultError>` - The plaintext on success, a `Locked` error while unlocking is locked,
+    ///   or a `WrongPassword` or `CorruptedPayload` error if decryption fails.
+    async fn decrypt_with_lockout(
+        storage: &dyn Storage,
+        password: &[u8],
+        payload: CipherPayload,
+        aad: &[u8],
+    ) -> Result<SecureVec, KeyVaultError> {
+        Self::with_lockout(storage, || decrypt(password, payload, aad)).await
+    }
+
+    /// Runs a decryption under the failed-unlock lockout, see `decrypt_with_lockout`. The attempt is reserved, counted
+    /// as failed, before decrypting and cleared on success, so concurrent wrong passwords can't slip past the lockout.
+    ///
+    /// **Parameters**:
+    /// - `decrypt: F` - The decryption, failing with `DecryptError::WrongPassword` on a wrong password.
+    ///
+    /// **Returns**:
+    /// - `Result<T, KeyVaultError>` - The result of `decrypt` on success, a `Locked` error while unlocking is locked,
+    ///   or the error of `decrypt`.
+    async fn with_lockout<T, F>(storage: &dyn Storage, decrypt: F) -> Result<T, KeyVaultError>
+    where
+        F: FnOnce() -> Result<T, DecryptError>,
+    {
+        // Counted as failed before decrypting, in the same transaction as the lockout check
+        storage
+            .reserve_failed_attempt(
+                config::max_failed_attempts(),
+                config::lockout_cooldown_ms(),
+                now_ms(),
+            )
+            .await
+            .map_err(KeyVaultError::from)?
+            .map_err(KeyVaultError::Locked)?;
+
+        match decrypt() {
+            Ok(value) => {
+                storage
+                    .clear_failed_attempts()
+                    .await
+                    .map_err(KeyVaultError::from)?;
+                Ok(value)
+            }
+            Err(DecryptError::WrongPassword(msg)) => Err(KeyVaultError::WrongPassword(msg)),
+            Err(e) => {
+                // Not a wrong password, so the attempt doesn't count
+                storage
+                    .release_failed_attempt()
+                    .await
+                    .map_err(KeyVaultError::from)?;
+                Err(KeyVaultError::from(e))
+            }
+        }
+    }
+
+    /// Detects the SPHINCS+ variant of an account from its decrypted private key: the variant whose lock script
+    /// arguments, computed over the public key (the second half of the private key), equal the stored ones.
+    ///
+    /// **Parameters**:
+    /// - `pri_key: &[u8]` - The decrypted SPHINCS+ private key.
+    /// - `lock_args: &str` - The hex-encoded lock script's arguments stored with the account.
+    ///
+    /// **Returns**:
+    /// - `Option<SphincsVariant>` - The account's variant, or `None` if no variant matches.
+    fn detect_account_variant(pri_key: &[u8], lock_args: &str) -> Option<SphincsVariant> {
+        let pub_key = SecureVec::from_slice(&pri_key[pri_key.len() / 2..]);
+        SphincsVariant::ALL
+            .iter()
+            .copied()
+            .filter(|variant| variant.private_key_length() == pri_key.len())
+            .find(|variant| {
+                encode(KeyVault::with_variant(*variant).get_lock_scrip_arg(&pub_key)) == lock_args
+            })
+    }
+
+    /// Reduces the detected variants of all accounts to the one variant they share.
+    ///
+    /// **Parameters**:
+    /// - `detected: &[(String, Option<SphincsVariant>)]` - Each account's lock_args and detected variant.
+    ///
+    /// **Returns**:
+    /// - `Result<SphincsVariant, String>` - The common variant, or an error message if there are no accounts,
+    ///   an undetected account, or mixed variants.
+    fn common_variant(
+        detected: &[(String, Option<SphincsVariant>)],
+    ) -> Result<SphincsVariant, String> {
+        let mut variants: Vec<SphincsVariant> = Vec::new();
+        for (lock_args, variant) in detected {
+            let variant = variant.ok_or_else(|| {
+                format!(
+                    "Unable to detect the SPHINCS+ variant of account {}",
+                    lock_args
+                )
+            })?;
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+        match variants.as_slice() {
+            [] => Err("No accounts stored to detect the SPHINCS+ variant from".to_string()),
+            [variant] => Ok(*variant),
+            _ => Err(format!(
+                "Stored accounts use mixed SPHINCS+ variants: {}",
+                variants
+                    .iter()
+                    .map(|variant| variant.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Reads one page of account records in index order and extracts their lock script arguments, skipping
+    /// unreadable records.
+    ///
+    /// **Parameters**:
+    /// - `offset: u32` - The number of records to skip.
+    /// - `limit: u32` - The maximum number of records to read.
+    ///
+    /// **Returns**:
+    /// - `Result<(Vec<String>, u32), KeyVaultError>` - The lock script arguments of the readable records and the number of
+    ///   records read, readable or not, on success, or a JavaScript error on failure.
+    async fn lock_args_page(offset: u32, limit: u32) -> Result<(Vec<String>, u32), KeyVaultError> {
+        let (accounts, unreadable) = db::get_account_records_page(offset, limit)
+            .await
+            .map_err(KeyVaultError::from)?;
+        if !unreadable.is_empty() {
+            debug!(
+                "\x1b[37;41m WARN \x1b[0m \x1b[1mkey-vault\x1b[0m: skipped {} unreadable account record(s)",
+                unreadable.len()
+            );
+        }
+        let records_read = (accounts.len() + unreadable.len()) as u32;
+        let lock_args = accounts
+            .into_iter()
+            .map(|account| account.lock_args)
+            .collect();
+        Ok((lock_args, records_read))
+    }
+
+    /// Building CKB lockscript for SPHINCS+ public key
+    ///
+    /// **Parameters**:
+    /// - `public_key: &SecureVec` - The SPHINCS+ public key to be used in the lock script.
+    ///
+    /// **Returns**:
+    /// - `[u8; 32]` - The lock script arguments as a byte array.
+    fn get_lock_scrip_arg(&self, public_key: &SecureVec) -> [u8; 32] {
+        lock_args_from_pub_key(self.variant, &self.lock_args_hash_config, public_key)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+///  Key-vault utility functions
+////////////////////////////////////////////////////////////////////////////////
+#[wasm_bindgen]
+pub struct Util;
+
+#[wasm_bindgen]
+impl Util {
+    /// https://github.com/xxuejie/rfcs/blob/cighash-all/rfcs/0000-ckb-tx-message-all/0000-ckb-tx-message-all.md.
+    ///
+    /// **Parameters**:
+    /// - `serialized_mock_tx: Uint8Array` - serialized CKB mock transaction.
+    /// - `input_index: usize` - Index of an input locked by the script group being signed.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - The CKB transaction message all hash digest as a `Uint8Array` on success,
+    ///   or a JavaScript error on failure, e.g. if `input_index` is out of range.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn get_ckb_tx_message_all(
+        serialized_mock_tx: Uint8Array,
+        input_index: usize,
+    ) -> Result<Uint8Array, KeyVaultError> {
+        let mock_tx = parse_mock_tx(&serialized_mock_tx.to_vec())
+            .map_err(KeyVaultError::InvalidInput)?;
+        let message = ckb_tx_message_all(&mock_tx, input_index)
+            .map_err(KeyVaultError::InvalidInput)?;
+        Ok(Uint8Array::from(message.as_slice()))
+    }
+
+    /// Computes the CKB transaction message all digest of every lock script group, one per distinct lock script
+    /// among the inputs, in the order of their first input.
+    ///
+    /// **Parameters**:
+    /// - `serialized_mock_tx: Uint8Array` - serialized CKB mock transaction.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<Uint8Array>, KeyVaultError>` - One digest per lock script group on success,
+    ///   or a JavaScript error on failure.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn get_ckb_tx_message_all_for_all_groups(
+        serialized_mock_tx: Uint8Array,
+    ) -> Result<Vec<Uint8Array>, KeyVaultError> {
+        let repr_mock_tx = parse_repr_mock_tx(&serialized_mock_tx.to_vec())
+            .map_err(KeyVaultError::InvalidInput)?;
+        let group_inputs = lock_group_inputs(&repr_mock_tx);
+        let mock_tx: MockTransaction = repr_mock_tx.into();
+        group_inputs
+            .into_iter()
+            .map(|input_index| {
+                let message = ckb_tx_message_all(&mock_tx, input_index)
+                    .map_err(KeyVaultError::InvalidInput)?;
+                Ok(Uint8Array::from(message.as_slice()))
+            })
+            .collect()
+    }
+
+    /// Decodes and validates a full format CKB address, so the wallet can check whether a pasted address is one of its
+    /// accounts by comparing `args` with the stored lock_args.
+    ///
+    /// **Parameters**:
+    /// - `address: String` - The bech32m encoded address.
+    /// - `expected_network: Option<String>` - `"mainnet"` or `"testnet"` to refuse addresses of the other network.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - A `CkbAddress` object `{ network, code_hash, hash_type, args }` on success,
+    ///   or a JavaScript error if the address is malformed, of a deprecated format or of another network.
+    ///
+    /// **Async**: no
+    ///
+    /// **Note**: `args` is `0x`-prefixed while stored lock_args are not.
+    #[wasm_bindgen]
+    pub fn validate_ckb_address(
+        address: String,
+        expected_network: Option<String>,
+    ) -> Result<JsValue, KeyVaultError> {
+        let decoded = address::decode_ckb_address(&address, expected_network.as_deref())
+            .map_err(KeyVaultError::InvalidInput)?;
+        Ok(serde_wasm_bindgen::to_value(&decoded)?)
+    }
+
+    /// Verifies a raw SPHINCS+ signature against a public key, without any database state, e.g. for offline verifiers.
+    ///
+    /// **Parameters**:
+    /// - `variant: SphincsVariant` - The SPHINCS+ parameter set of the key and signature.
+    /// - `public_key: Uint8Array` - The SPHINCS+ public key, `PK_LEN` bytes of the variant.
+    /// - `message: Uint8Array` - The signed message.
+    /// - `signature: Uint8Array` - The raw SPHINCS+ signature.
+    ///
+    /// **Returns**:
+    /// - `Result<bool, KeyVaultError>` - Whether the signature is valid, or a JavaScript error if the public key is malformed.
+    ///
+    /// **Async**: no
+    ///
+    /// **Warning**: This doesn't check that the public key belongs to any address, see `verify_and_check_lock_args`.
+    #[wasm_bindgen]
+    pub fn verify_with_pubkey(
+        variant: SphincsVariant,
+        public_key: Uint8Array,
+        message: Uint8Array,
+        signature: Uint8Array,
+    ) -> Result<bool, KeyVaultError> {
+        verify_signature(
+            variant,
+            &public_key.to_vec(),
+            &message.to_vec(),
+            &signature.to_vec(),
+        )
+        .map_err(KeyVaultError::InvalidInput)
+    }
+
+    /// Fully checks an untrusted signature: verifies it against the public key, and independently checks that the
+    /// public key hashes to the expected lock_args. Verifying against a public key that doesn't belong to the claimed
+    /// address would accept signatures from anyone.
+    ///
+    /// **Parameters**:
+    /// - `variant: SphincsVariant` - The SPHINCS+ parameter set of the key and signature.
+    /// - `pub_key: Uint8Array` - The claimed SPHINCS+ public key.
+    /// - `message: Uint8Array` - The signed message.
+    /// - `signature: Uint8Array` - The raw SPHINCS+ signature.
+    /// - `expected_lock_args: String` - The hex-encoded lock script arguments of the claimed address, `0x` prefix optional.
+    ///
+    /// **Returns**:
+    /// - `Result<bool, KeyVaultError>` - True only if the signature verifies and the public key matches `expected_lock_args`,
+    ///   or a JavaScript error if the public key is malformed.
+    ///
+    /// **Async**: no
+    ///
+    /// **Note**: lock_args are recomputed with the default lock script hash, not a `set_lock_args_hash_config` override.
+    #[wasm_bindgen]
+    pub fn verify_and_check_lock_args(
+        variant: SphincsVariant,
+        pub_key: Uint8Array,
+        message: Uint8Array,
+        signature: Uint8Array,
+        expected_lock_args: String,
+    ) -> Result<bool, KeyVaultError> {
+        utilities::verify_and_check_lock_args(
+            variant,
+            &pub_key.to_vec(),
+            &message.to_vec(),
+            &signature.to_vec(),
+            &expected_lock_args,
+        )
+        .map_err(KeyVaultError::InvalidInput)
+    }
+
+    /// Encrypts an arbitrary app secret with the construction the vault stores keys with: AES-256-GCM under a
+    /// Scrypt-derived key with a fresh salt and IV, in the same `CipherPayload` format.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password to encrypt under. Must not be empty.
+    /// - `data: Uint8Array` - The secret to encrypt.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - The `CipherPayload` object `{ salt, iv, cipher_text, version, kdf }` on
+    ///   success, or a JavaScript error on failure.
+    ///
+    /// **Async**: no
+    ///
+    /// **Note**: Blobs are not interchangeable with vault records. Every payload is bound to its purpose through
+    /// associated data, so `decrypt_blob` opens only payloads made by `encrypt_blob`, and the vault opens none of
+    /// them. Otherwise `decrypt_blob` would hand out the seed or a private key from an exported record, skipping the
+    /// failed-attempt lockout.
+    #[wasm_bindgen]
+    pub fn encrypt_blob(password: Uint8Array, data: Uint8Array) -> Result<JsValue, KeyVaultError> {
+        let password_vec = SecureVec::from_uint8_array(&password);
+        let data_vec = SecureVec::from_uint8_array(&data);
+        let payload = encrypt(&password_vec, &data_vec, &blob_aad())
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+        Ok(serde_wasm_bindgen::to_value(&payload)?)
+    }
+
+    /// Decrypts an app secret encrypted with `encrypt_blob`. Vault records, e.g. from `export_account`, are refused,
+    /// see `encrypt_blob`.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - The password the secret was encrypted under.
+    /// - `payload: JsValue` - The `CipherPayload` object returned by `encrypt_blob`.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - The decrypted secret on success, or a JavaScript error if the payload
+    ///   is malformed or the password is wrong.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn decrypt_blob(
+        password: Uint8Array,
+        payload: JsValue,
+    ) -> Result<Uint8Array, KeyVaultError> {
+        let payload: CipherPayload = serde_wasm_bindgen::from_value(payload)
+            .map_err(|e| KeyVaultError::InvalidInput(format!("Invalid cipher payload: {}", e)))?;
+        let password_vec = SecureVec::from_uint8_array(&password);
+        let data =
+            decrypt(&password_vec, payload, &blob_aad()).map_err(KeyVaultError::from)?;
+        Ok(Uint8Array::from(&data[..]))
+    }
+
+    /// Computes the lock script arguments of an arbitrary SPHINCS+ public key, e.g. a co-signer's, the same way the
+    /// vault does for its own accounts.
+    ///
+    /// **Parameters**:
+    /// - `variant: SphincsVariant` - The SPHINCS+ parameter set of the key.
+    /// - `public_key: Uint8Array` - The SPHINCS+ public key.
+    ///
+    /// **Returns**:
+    /// - `Result<String, KeyVaultError>` - The hex-encoded lock script arguments on success, or a JavaScript error
+    ///   if the key length doesn't fit the variant.
+    ///
+    /// **Async**: no
+    ///
+    /// **Note**: lock_args are computed with the default lock script hash, not a `set_lock_args_hash_config` override.
+    #[wasm_bindgen]
+    pub fn compute_lock_args(
+        variant: SphincsVariant,
+        public_key: Uint8Array,
+    ) -> Result<String, KeyVaultError> {
+        utilities::compute_lock_args(variant, &public_key.to_vec())
+            .map_err(KeyVaultError::InvalidInput)
+    }
+
+    /// Builds the exact bytes hashed into the lock script arguments of an m-of-n multisig over SPHINCS+ public keys,
+    /// so a lock_args found in a transaction can be reconstructed and checked independently of the wallet. Hashing
+    /// them with `Hasher::script_args_hasher()` gives the lock script arguments. The layout is:
+    /// - byte 0: the reserved field, always 0x80;
+    /// - byte 1: `require_first_n`;
+    /// - byte 2: `threshold`;
+    /// - byte 3: the number of public keys;
+    /// - then per public key, in order: the sign flag `variant << 1` (1 byte), then the public key.
+    ///
+    /// **Parameters**:
+    /// - `variant: SphincsVariant` - The SPHINCS+ parameter set of all keys.
+    /// - `pubkeys: Vec<Uint8Array>` - The SPHINCS+ public keys of the signers, in the order the lock script lists them.
+    /// - `require_first_n: u8` - How many of the first `pubkeys` must sign. At most `threshold`.
+    /// - `threshold: u8` - How many signatures unlock the script. Between 1 and the number of `pubkeys`.
+    ///
+    /// **Returns**:
+    /// - `Result<Uint8Array, KeyVaultError>` - The preimage on success, or a JavaScript error if the parameters are
+    ///   inconsistent or a public key has the wrong length.
+    ///
+    /// **Async**: no
+    ///
+    /// **Note**: A single-key account uses `require_first_n` 0 and `threshold` 1. A `set_lock_args_hash_config`
+    /// prefix, if any, is hashed before these bytes and is not included.
+    #[wasm_bindgen]
+    pub fn build_lock_args_preimage(
+        variant: SphincsVariant,
+        pubkeys: Vec<Uint8Array>,
+        require_first_n: u8,
+        threshold: u8,
+    ) -> Result<Uint8Array, KeyVaultError> {
+        let pub_keys: Vec<Vec<u8>> = pubkeys.iter().map(|key| key.to_vec()).collect();
+        let preimage = lock_args_preimage(variant, &pub_keys, require_first_n, threshold)
+            .map_err(KeyVaultError::InvalidInput)?;
+        Ok(Uint8Array::from(preimage.as_slice()))
+    }
+
+    /// Returns the lock script parameters this build uses - the all-in-one config, each variant's param id and the
+    /// code hash of the compatible lock script - so integrators can assert the wallet is paired with the right script.
     ///
     /// **Returns**:
-    /// - `Result<(), JsValue>` - A JavaScript Promise that resolves to `undefined` on success,
-    ///   or rejects with a JavaScript error on failure.
+    /// - `Result<JsValue, KeyVaultError>` - The config object on success, or a JavaScript error on failure.
     ///
-    /// **Async**: Yes
+    /// **Async**: no
+    ///
+    /// **Warning**: Pairing the wallet with a lock script of a different config or code hash yields unspendable addresses.
     #[wasm_bindgen]
-    pub async fn clear_database() -> Result<(), JsValue> {
-        let db = db::open_db().await.map_err(|e| e.to_jsvalue())?;
-        db::clear_object_store(&db, SEED_PHRASE_STORE)
-            .await
-            .map_err(|e| e.to_jsvalue())?;
-        db::clear_object_store(&db, CHILD_KEYS_STORE)
-            .await
-            .map_err(|e| e.to_jsvalue())?;
-        Ok(())
+    pub fn lock_script_config() -> Result<JsValue, KeyVaultError> {
+        Ok(serde_wasm_bindgen::to_value(
+            &utilities::lock_script_config(),
+        )?)
     }
 
-    /// Retrieves all SPHINCS+ lock script arguments (processed public keys) from the database in the order they get inserted.
+    /// Wraps a signature blob into a molecule-serialized CKB `WitnessArgs`, ready to embed in the transaction's witnesses.
+    ///
+    /// **Parameters**:
+    /// - `signature_blob: Uint8Array` - The lock field, as returned by `sign`.
+    /// - `input_type: Option<Uint8Array>` - The optional input_type field.
+    /// - `output_type: Option<Uint8Array>` - The optional output_type field.
     ///
     /// **Returns**:
-    /// - `Result<Vec<String>, JsValue>` - A JavaScript Promise that resolves to an array of hex-encoded SPHINCS+ lock script arguments on success,
-    ///   or rejects with a JavaScript error on failure.
+    /// - `Result<Uint8Array, KeyVaultError>` - The serialized `WitnessArgs` on success, or a JavaScript error on failure.
     ///
-    /// **Async**: Yes
+    /// **Async**: no
     #[wasm_bindgen]
-    pub async fn get_all_sphincs_lock_args() -> Result<Vec<String>, JsValue> {
-        /// Error conversion helper
-        fn map_db_error<T>(result: Result<T, DBError>) -> Result<T, JsValue> {
-            result.map_err(|e| JsValue::from_str(&format!("Database error: {}", e)))
-        }
-
-        let db = db::open_db().await.map_err(|e| e.to_jsvalue())?;
-        let tx = map_db_error(
-            db.transaction(CHILD_KEYS_STORE)
-                .with_mode(TransactionMode::Readonly)
-                .build(),
-        )?;
-        let store = map_db_error(tx.object_store(CHILD_KEYS_STORE))?;
-
-        // Retrieve all accounts
-        let iter: ArrayMapIter<JsValue> = map_db_error(store.get_all().await)?;
-        let mut accounts: Vec<SphincsPlusAccount> = Vec::new();
-        for result in iter {
-            let js_value = map_db_error(result)?;
-            let account: SphincsPlusAccount = serde_wasm_bindgen::from_value(js_value)?;
-            accounts.push(account);
-        }
-
-        // Sort by index
-        accounts.sort_by_key(|account| account.index);
-
-        // Extract lock args in sorted order
-        let lock_args_array: Vec<String> = accounts
-            .into_iter()
-            .map(|account| account.lock_args)
-            .collect();
-
-        Ok(lock_args_array)
+    pub fn build_witness_args(
+        signature_blob: Uint8Array,
+        input_type: Option<Uint8Array>,
+        output_type: Option<Uint8Array>,
+    ) -> Result<Uint8Array, KeyVaultError> {
+        let lock = signature_blob.to_vec();
+        let input_type = input_type.map(|field| field.to_vec());
+        let output_type = output_type.map(|field| field.to_vec());
+        let serialized = witness_args(Some(&lock), input_type.as_deref(), output_type.as_deref());
+        Ok(Uint8Array::from(serialized.as_slice()))
     }
 
-    /// Initializes the mnemonic phrase by generating a BIP39 mnemonic, encrypting it with the provided password, and storing it in IndexedDB.
+    /// Primes the Scrypt and SPHINCS+ code paths so the user's first `gen_new_account`/`sign` doesn't pay
+    /// for wasm JIT warmup and first-touch allocation of the Scrypt buffers. Meant to be called during idle
+    /// time (e.g. from `requestIdleCallback`) after page load.
+    ///
+    /// It runs one throwaway key derivation, encryption and signature over random data; nothing is stored
+    /// and all throwaway material is zeroized on drop. This trades a bit of startup CPU for lower
+    /// first-action latency.
     ///
     /// **Parameters**:
-    /// - `password: Uint8Array` - The password used to encrypt the mnemonic.
+    /// - `variant: SphincsVariant` - The SPHINCS+ parameter set whose code paths should be primed.
     ///
     /// **Returns**:
-    /// - `Result<(), JsValue>` - A JavaScript Promise that resolves to `undefined` on success,
-    ///   or rejects with a JavaScript error on failure.
-    ///
-    /// **Async**: Yes
+    /// - `Result<(), KeyVaultError>` - `undefined` on success, or a JavaScript error on failure.
     ///
-    /// **Note**: Only effective when the mnemonic phrase is not yet set.
+    /// **Async**: no
     #[wasm_bindgen]
-    pub async fn init_seed_phrase(&self, password: Uint8Array) -> Result<(), JsValue> {
-        let stored_seed = db::get_encrypted_mnemonic_seed()
-            .await
-            .map_err(|e| e.to_jsvalue())?;
-        if stored_seed.is_some() {
-            debug!("\x1b[37;44m INFO \x1b[0m \x1b[1mkey-vault\x1b[0m: mnemonic phrase exists");
-            return Ok(());
-        }
+    pub fn warmup(variant: SphincsVariant) -> Result<(), KeyVaultError> {
+        let vault = KeyVault::with_variant(variant);
+        let seed = get_random_bytes(variant.bip39_compatible_entropy_size())
+            .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
+        let password = get_random_bytes(32)
+            .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
+        let message = get_random_bytes(32)
+            .map_err(|e| KeyVaultError::Internal(format!("Random generation error: {}", e)))?;
 
-        let size = self.variant.bip39_compatible_entropy_size();
-        let entropy = get_random_bytes(size).unwrap();
-        let password = SecureVec::from_slice(&password.to_vec());
-        let encrypted_seed = encrypt(&password, entropy.as_ref())
-            .map_err(|e| JsValue::from_str(&format!("Encryption error: {}", e)))?;
-
-        db::set_encrypted_mnemonic_seed(encrypted_seed)
-            .await
-            .map_err(|e| e.to_jsvalue())?;
+        let (_, pri_key) = vault
+            .derive_sphincs_key(&seed, 0, DerivationParams::default())
+            .map_err(KeyVaultError::KeyDerivation)?;
+        let _ = encrypt(&password, &pri_key, &[])
+            .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?;
+        let _ = vault.sign_message(&pri_key, &message)?;
         Ok(())
     }
 
-    /// Generates a new SPHINCS+ account - a SPHINCS+ child account derived from the mnemonic phrase,
-    /// encrypts the private key with the password, and stores/appends it in IndexedDB.
+    /// Returns the signature, public key and private key lengths of a SPHINCS+ variant, to size witnesses and plan
+    /// fees without signing. Signatures range from 7856 bytes (128s) to 49856 bytes (256f).
     ///
     /// **Parameters**:
-    /// - `password: Uint8Array` - The password used to decrypt the mnemonic phrase and encrypt the child private key.
+    /// - `variant: SphincsVariant` - The SPHINCS+ parameter set.
     ///
     /// **Returns**:
-    /// - `Result<String, JsValue>` - A String Promise that resolves to the hex-encoded SPHINCS+ lock argument (processed SPHINCS+ public key) of the account on success,
-    ///   or rejects with a JavaScript error on failure.
+    /// - `Result<JsValue, KeyVaultError>` - A `VariantSizes` object `{ sig_len, pk_len, sk_len }` in bytes on success,
+    ///   or a JavaScript error on failure.
     ///
-    /// **Async**: Yes
+    /// **Async**: no
     #[wasm_bindgen]
-    pub async fn gen_new_account(&self, password: Uint8Array) -> Result<String, JsValue> {
-        let password = SecureVec::from_slice(&password.to_vec());
-
-        // Get and decrypt the mnemonic seed phrase
-        let payload = db::get_encrypted_mnemonic_seed()
-            .await
-            .map_err(|e| e.to_jsvalue())?
-            .ok_or_else(|| JsValue::from_str("Mnemonic phrase not found"))?;
-        let seed = decrypt(&password, payload)?;
-
-        let index = Self::get_all_sphincs_lock_args().await?.len() as u32;
-        let (pub_key, pri_key) = self
-            .derive_sphincs_key(&seed, index)
-            .map_err(|e| JsValue::from_str(&format!("Key derivation error: {}", e)))?;
-
-        // Calculate lock script args and encrypt corresponding private key
-        let lock_script_args = self.get_lock_scrip_arg(&pub_key);
-        let encrypted_pri = encrypt(&password, &pri_key)?;
-
-        // Store to DB
-        let account = SphincsPlusAccount {
-            index: 0, // Init to 0; Will be set correctly in add_account
-            lock_args: encode(lock_script_args),
-            pri_enc: encrypted_pri,
-        };
-
-        db::add_account(account).await.map_err(|e| e.to_jsvalue())?;
-
-        Ok(encode(lock_script_args))
+    pub fn variant_sizes(variant: SphincsVariant) -> Result<JsValue, KeyVaultError> {
+        Ok(serde_wasm_bindgen::to_value(&variant.sizes())?)
     }
 
-    /// Imports a mnemonic by encrypting it with the provided password and storing it as the mnemonic phrase.
+    /// Returns a zero-filled placeholder of exactly the size of the witness lock field that signing produces,
+    /// so a transaction serialized for fee estimation has its final size. Replace it with the real signature after signing.
     ///
     /// **Parameters**:
-    /// - `seed_phrase: Uint8Array` - The mnemonic phrase as a UTF-8 encoded Uint8Array to import.
-    /// - `password: Uint8Array` - The password used to encrypt the mnemonic.
+    /// - `variant: SphincsVariant` - The SPHINCS+ parameter set that will sign.
+    /// - `include_pubkey: bool` - Whether the lock field carries the public key, as `sign` output does.
     ///
     /// **Returns**:
-    /// - `Result<(), JsValue>` - A JavaScript Promise that resolves to `undefined` on success,
-    ///   or rejects with a JavaScript error on failure.
+    /// - `Uint8Array` - The zero-filled placeholder.
     ///
-    /// **Async**: Yes
-    ///
-    /// **Warning**: Handle the mnemonic in JavaScript side carefully.
+    /// **Async**: no
     #[wasm_bindgen]
-    pub async fn import_seed_phrase(
-        &self,
-        seed_phrase: Uint8Array,
-        password: Uint8Array,
-    ) -> Result<(), JsValue> {
-        let password = SecureVec::from_slice(&password.to_vec());
-
-        let seed_phrase_bytes = seed_phrase.to_vec();
-        let seed_phrase_str = String::from_utf8(seed_phrase_bytes)
-            .map_err(|e| JsValue::from_str(&format!("Invalid UTF-8: {}", e)))?;
-
-        let words: Vec<&str> = seed_phrase_str.split_whitespace().collect();
-        let word_count = words.len();
-        if word_count != 48 && word_count != 72 {
-            return Err(JsValue::from_str("Mnemonic must have 48 or 72 words"));
-        }
-
-        let mut combined_entropy = Vec::new();
-        for chunk in words.chunks(24) {
-            let chunk_str = chunk.join(" ");
-            let mnemonic = Mnemonic::parse_in(Language::English, &chunk_str)
-                .map_err(|e| JsValue::from_str(&format!("Invalid mnemonic chunk: {}", e)))?;
-            let entropy = mnemonic.to_entropy();
-            combined_entropy.extend_from_slice(&entropy);
-        }
-
-        if combined_entropy.len() < self.variant.bip39_compatible_entropy_size() {
-            return Err(JsValue::from(
-                format!(
-                    "Insufficient entropy: the input seed phrase got {} bytes, but at least {} bytes are required for the chosen SPHINCS+ parameter set {}.",
-                    combined_entropy.len(),
-                    self.variant.bip39_compatible_entropy_size(),
-                    self.variant
-                )
-            ));
-        }
-
-        let encrypted_seed = encrypt(&password, &combined_entropy)?;
-        db::set_encrypted_mnemonic_seed(encrypted_seed)
-            .await
-            .map_err(|e| e.to_jsvalue())?;
-        Ok(())
+    pub fn witness_placeholder(variant: SphincsVariant, include_pubkey: bool) -> Uint8Array {
+        Uint8Array::new_with_length(variant.witness_lock_length(include_pubkey) as u32)
     }
 
-    /// Exports the mnemonic phrase by decrypting it with the provided password.
+    /// Parses a SPHINCS+ variant name, e.g. "Shake192F" from a dropdown keyed by name. Case is ignored.
     ///
     /// **Parameters**:
-    /// - `password: Uint8Array` - The password used to decrypt the mnemonic.
+    /// - `name: String` - The variant name, as `SphincsVariant`'s `Display` writes it.
     ///
     /// **Returns**:
-    /// - `Result<Uint8Array, JsValue>` - A JavaScript Promise that resolves to the mnemonic as a UTF-8 encoded `Uint8Array` on success,
-    ///   or rejects with a JavaScript error on failure.
-    ///
-    /// **Async**: Yes
+    /// - `Result<SphincsVariant, KeyVaultError>` - The variant on success, or a JavaScript error listing the valid names.
     ///
-    /// **Warning**: Exporting the mnemonic exposes it in JavaScript, which may pose a security risk.
-    /// Proper zeroization of exported seed phrase is the responsibility of the caller.
+    /// **Async**: no
     #[wasm_bindgen]
-    pub async fn export_seed_phrase(password: Uint8Array) -> Result<Uint8Array, JsValue> {
-        let password = SecureVec::from_slice(&password.to_vec());
-        let payload = db::get_encrypted_mnemonic_seed()
-            .await
-            .map_err(|e| e.to_jsvalue())?
-            .ok_or_else(|| JsValue::from_str("Mnemonic phrase not found"))?;
-
-        let entropy = decrypt(&password, payload)?;
-        let chunks = entropy.chunks(32);
-        let mut mnemonics = Vec::new();
-        for chunk in chunks {
-            let mnemonic = Mnemonic::from_entropy_in(Language::English, chunk).unwrap();
-            mnemonics.push(mnemonic.to_string());
-        }
-        let combined_mnemonics = mnemonics.join(" ");
-
-        Ok(Uint8Array::from(combined_mnemonics.as_ref()))
+    pub fn parse_variant(name: String) -> Result<SphincsVariant, KeyVaultError> {
+        name.parse::<SphincsVariant>().map_err(KeyVaultError::InvalidInput)
     }
 
-    /// Signs a message using the SPHINCS+ private key after decrypting it with the provided password.
+    /// Lists the SPHINCS+ variants that a given amount of entropy is sufficient for, e.g. to tell the user
+    /// which parameter sets an imported seed supports before they choose one.
     ///
     /// **Parameters**:
-    /// - `password: Uint8Array` - The password used to decrypt the private key.
-    /// - `lock_args: String` - The hex-encoded lock script's arguments corresponding to the SPHINCS+ public key of the account that signs.
-    /// - `message: Uint8Array` - The message to be signed.
+    /// - `entropy_len: usize` - The entropy length in bytes.
     ///
     /// **Returns**:
-    /// - `Result<Uint8Array, JsValue>` - The signature as a `Uint8Array` on success,
-    ///   or a JavaScript error on failure.
+    /// - `Vec<SphincsVariant>` - Every variant whose `bip39_compatible_entropy_size` is at most `entropy_len`.
     ///
-    /// **Async**: Yes
+    /// **Async**: no
     #[wasm_bindgen]
-    pub async fn sign(
-        &self,
-        password: Uint8Array,
-        lock_args: String,
-        message: Uint8Array,
-    ) -> Result<Uint8Array, JsValue> {
-        let password = SecureVec::from_slice(&password.to_vec());
-        let account = db::get_account(&lock_args)
-            .await
-            .map_err(|e| e.to_jsvalue())?
-            .ok_or_else(|| JsValue::from_str("Account not found"))?;
-
-        let pri_key = decrypt(&password, account.pri_enc)?;
-        let message_vec = message.to_vec();
+    pub fn variants_supported_by_entropy(entropy_len: usize) -> Vec<SphincsVariant> {
+        SphincsVariant::supported_by_entropy(entropy_len)
+    }
 
-        match self.variant {
-            SphincsVariant::Sha2128S => sphincs_sign!(slh_dsa_sha2_128s, pri_key, &message_vec, self.variant),
-            SphincsVariant::Sha2128F => sphincs_sign!(slh_dsa_sha2_128f, pri_key, &message_vec, self.variant),
-            SphincsVariant::Shake128S => sphincs_sign!(slh_dsa_shake_128s, pri_key, &message_vec, self.variant),
-            SphincsVariant::Shake128F => sphincs_sign!(slh_dsa_shake_128f, pri_key, &message_vec, self.variant),
-            SphincsVariant::Sha2192S => sphincs_sign!(slh_dsa_sha2_192s, pri_key, &message_vec, self.variant),
-            SphincsVariant::Sha2192F => sphincs_sign!(slh_dsa_sha2_192f, pri_key, &message_vec, self.variant),
-            SphincsVariant::Shake192S => sphincs_sign!(slh_dsa_shake_192s, pri_key, &message_vec, self.variant),
-            SphincsVariant::Shake192F => sphincs_sign!(slh_dsa_shake_192f, pri_key, &message_vec, self.variant),
-            SphincsVariant::Sha2256S => sphincs_sign!(slh_dsa_sha2_256s, pri_key, &message_vec, self.variant),
-            SphincsVariant::Sha2256F => sphincs_sign!(slh_dsa_sha2_256f, pri_key, &message_vec, self.variant),
-            SphincsVariant::Shake256S => sphincs_sign!(slh_dsa_shake_256s, pri_key, &message_vec, self.variant),
-            SphincsVariant::Shake256F => sphincs_sign!(slh_dsa_shake_256f, pri_key, &message_vec, self.variant),
-        }
+    /// Sets the maximum number of accounts `gen_new_account`, `try_gen_account_batch` and `recover_accounts` may reach.
+    /// Defaults to 10,000. Guards the wallet against runaway derivation loops from buggy or malicious input.
+    ///
+    /// **Parameters**:
+    /// - `max: u32` - The maximum number of accounts.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn set_max_accounts(max: u32) {
+        config::set_max_accounts(max);
     }
 
-    /// Supporting wallet recovery - derives a list of lock script arguments (processed public keys) from the seed phrase starting from a given index.
+    /// Sets how long an IndexedDB operation may take before it fails with a "Storage timeout" error, so the UI can
+    /// recover and retry instead of hanging on storage contention or other tabs. Defaults to 10,000 ms.
     ///
     /// **Parameters**:
-    /// - `password: Uint8Array` - The password used to decrypt the mnemonic.
-    /// - `start_index: u32` - The starting index for derivation.
-    /// - `count: u32` - The number of sequential lock scripts arguments to derive.
+    /// - `timeout_ms: u32` - The timeout in milliseconds, at least 1.
     ///
     /// **Returns**:
-    /// - `Result<Vec<String>, JsValue>` - A list of lock script arguments on success,
-    ///   or a JavaScript error on failure.
+    /// - `Result<(), KeyVaultError>` - Ok on success, or a JavaScript error for a timeout of 0.
+    ///
+    /// **Async**: no
     #[wasm_bindgen]
-    pub async fn try_gen_account_batch(
-        &self,
-        password: Uint8Array,
-        start_index: u32,
-        count: u32,
-    ) -> Result<Vec<String>, JsValue> {
-        let password = SecureVec::from_slice(&password.to_vec());
-        // Get and decrypt the mnemonic seed phrase
-        let payload = db::get_encrypted_mnemonic_seed()
-            .await
-            .map_err(|e| e.to_jsvalue())?
-            .ok_or_else(|| JsValue::from_str("Mnemonic phrase not found"))?;
-        let seed = decrypt(&password, payload)?;
-        let mut lock_args_array: Vec<String> = Vec::new();
-        for i in start_index..(start_index + count) {
-            let (pub_key, _) = self
-                .derive_sphincs_key(&seed, i)
-                .map_err(|e| JsValue::from_str(&format!("Key derivation error: {}", e)))?;
+    pub fn set_db_timeout_ms(timeout_ms: u32) -> Result<(), KeyVaultError> {
+        config::set_db_timeout_ms(timeout_ms).map_err(KeyVaultError::InvalidInput)
+    }
 
-            // Calculate lock script args
-            let lock_script_args = self.get_lock_scrip_arg(&pub_key);
-            lock_args_array.push(encode(lock_script_args));
-        }
-        Ok(lock_args_array)
+    /// Sets how many addresses the account descriptor of `KeyVault::account_xpub_equivalent` covers. Defaults to 20.
+    ///
+    /// **Parameters**:
+    /// - `size: u32` - The number of addresses per account.
+    ///
+    /// **Async**: no
+    ///
+    /// **Warning**: Account boundaries depend on the size, so use the same size on every device sharing descriptors.
+    #[wasm_bindgen]
+    pub fn set_descriptor_batch_size(size: u32) {
+        config::set_descriptor_batch_size(size);
     }
 
-    /// Supporting wallet recovery - Recovers the wallet by deriving and storing private keys for the first N accounts.
+    /// Sets the Scrypt parameters that encryptions of the seed phrase, private keys and metadata use from now on, e.g. a
+    /// higher cost on a fast desktop or a lower one on a weak phone. Defaults to log_n 14, r 8, p 1. Existing data keeps
+    /// decrypting, as every payload stores the parameters it was encrypted with.
     ///
     /// **Parameters**:
-    /// - `password: Uint8Array` - The password used to decrypt the seed phrase.
-    /// - `count: u32` - The number of accounts to recover (from index 0 to count-1).
+    /// - `log_n: u8` - The log2 of the Scrypt cost N, from 10 to 20.
+    /// - `r: u32` - The Scrypt block size.
+    /// - `p: u32` - The Scrypt parallelism, at most 16.
     ///
     /// **Returns**:
-    /// - `Result<(), JsValue>` - A list of newly generated sphincs+ lock script arguments (processed public keys) on success, or a JavaScript error on failure.
+    /// - `Result<(), KeyVaultError>` - Ok on success, or a JavaScript error if the parameters are out of bounds, need
+    ///   more than 256 MiB of memory, or Scrypt rejects them.
     ///
-    /// **Async**: Yes
+    /// **Async**: no
+    ///
+    /// **Note**: Data is only re-encrypted with new parameters when it is written again, e.g. by `change_password`.
     #[wasm_bindgen]
-    pub async fn recover_accounts(
-        &self,
-        password: Uint8Array,
-        count: u32,
-    ) -> Result<Vec<String>, JsValue> {
-        let password = SecureVec::from_slice(&password.to_vec());
-        // Get and decrypt the mnemonic seed phrase
-        let payload = db::get_encrypted_mnemonic_seed()
-            .await
-            .map_err(|e| e.to_jsvalue())?
-            .ok_or_else(|| JsValue::from_str("Mnemonic phrase not found"))?;
-        let mut lock_args_array: Vec<String> = Vec::new();
-        let seed = decrypt(&password, payload)?;
-        for i in 0..count {
-            let (pub_key, pri_key) = self
-                .derive_sphincs_key(&seed, i)
-                .map_err(|e| JsValue::from_str(&format!("Key derivation error: {}", e)))?;
+    pub fn set_kdf_params(log_n: u8, r: u32, p: u32) -> Result<(), KeyVaultError> {
+        config::set_enc_scrypt(log_n, r, p).map_err(KeyVaultError::InvalidInput)
+    }
 
-            // Calculate lock script args and encrypt corresponding private key
-            let lock_script_args = self.get_lock_scrip_arg(&pub_key);
-            let encrypted_pri = encrypt(&password, &pri_key)?;
-            // Store to DB
-            let account = SphincsPlusAccount {
-                index: 0, // Init to 0; Will be set correctly in add_account
-                lock_args: encode(lock_script_args),
-                pri_enc: encrypted_pri,
-            };
-            lock_args_array.push(encode(lock_script_args));
+    /// Sets how many wrong passwords in a row lock the operations decrypting with the wallet password, see
+    /// `KeyVault::failed_attempts`. Defaults to 10.
+    ///
+    /// **Parameters**:
+    /// - `max: u32` - The number of wrong passwords. 0 disables the lockout.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn set_max_failed_attempts(max: u32) {
+        config::set_max_failed_attempts(max);
+    }
 
-            db::add_account(account).await.map_err(|e| e.to_jsvalue())?;
-        }
-        Ok(lock_args_array)
+    /// Sets how long the lockout lasts after the last wrong password. Defaults to 300,000 ms (5 minutes).
+    ///
+    /// **Parameters**:
+    /// - `cooldown_ms: u32` - The cooldown in milliseconds. 0 keeps the vault locked until
+    ///   `KeyVault::clear_failed_attempts` is called, as does a host without a clock.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn set_lockout_cooldown_ms(cooldown_ms: u32) {
+        config::set_lockout_cooldown_ms(cooldown_ms);
     }
 
-    /// Building CKB lockscript for SPHINCS+ public key
+    /// Restores every setting changed through the `Util::set_*` functions to its documented default, e.g. to
+    /// troubleshoot a misbehaving configuration. Per-instance `KeyVault` settings are not affected.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn reset_config_defaults() {
+        config::reset_defaults();
+    }
+
+    /// Detects the BIP39 language of a seed phrase by parsing it against every supported wordlist,
+    /// so the UI can pick the import language for the user.
     ///
     /// **Parameters**:
-    /// - `public_key: &SecureVec` - The SPHINCS+ public key to be used in the lock script.
+    /// - `seed_phrase: Uint8Array` - The mnemonic phrase as a UTF-8 encoded Uint8Array.
     ///
     /// **Returns**:
-    /// - `[u8; 32]` - The lock script arguments as a byte array.
-    fn get_lock_scrip_arg(&self, public_key: &SecureVec) -> [u8; 32] {
-        let all_in_one_config: [u8; 4] = [
-            MULTISIG_RESERVED_FIELD_VALUE,
-            REQUIRED_FIRST_N,
-            THRESHOLD,
-            PUBKEY_NUM,
-        ];
-        let sign_flag: u8 = self.variant << 1;
-        let mut script_args_hasher = Hasher::script_args_hasher();
-        script_args_hasher.update(&all_in_one_config);
-        script_args_hasher.update(&[sign_flag]);
-        script_args_hasher.update(&public_key);
-        script_args_hasher.hash()
+    /// - `Result<u8, KeyVaultError>` - The `MnemonicLanguage` id of the matching wordlist on success,
+    ///   or a JavaScript error listing the languages tried.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn detect_mnemonic_language(seed_phrase: Uint8Array) -> Result<u8, KeyVaultError> {
+        let seed_phrase = SecureVec::from_uint8_array(&seed_phrase);
+        let seed_phrase_str = std::str::from_utf8(&seed_phrase)
+            .map_err(|e| KeyVaultError::InvalidInput(format!("Invalid UTF-8: {}", e)))?;
+        let language = utilities::detect_mnemonic_language(seed_phrase_str)
+            .map_err(KeyVaultError::InvalidInput)?;
+        Ok(language as u8)
     }
-}
 
-////////////////////////////////////////////////////////////////////////////////
-///  Key-vault utility functions
-////////////////////////////////////////////////////////////////////////////////
-#[wasm_bindgen]
-pub struct Util;
+    /// Lists the supported BIP39 wordlist languages, for the language picker of seed phrase import and export.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<JsValue>, KeyVaultError>` - `{ id, name, native_name }` objects in id order on success,
+    ///   or a JavaScript error on failure. `id` is the value `detect_mnemonic_language` returns.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn supported_languages() -> Result<Vec<JsValue>, KeyVaultError> {
+        utilities::supported_languages()
+            .iter()
+            .map(|language| serde_wasm_bindgen::to_value(language).map_err(KeyVaultError::from))
+            .collect()
+    }
 
-#[wasm_bindgen]
-impl Util {
-    /// https://github.com/xxuejie/rfcs/blob/cighash-all/rfcs/0000-ckb-tx-message-all/0000-ckb-tx-message-all.md.
+    /// Exports the stored seed as standalone 24-word BIP39 mnemonics, one per 32-byte entropy chunk,
+    /// so it can be backed up with general-purpose tools that only accept standard phrases.
     ///
     /// **Parameters**:
-    /// - `serialized_mock_tx: Uint8Array` - serialized CKB mock transaction.
+    /// - `password: Uint8Array` - The password used to decrypt the seed.
     ///
     /// **Returns**:
-    /// - `Result<Uint8Array, JsValue>` - The CKB transaction message all hash digest as a `Uint8Array` on success,
-    ///   or a JavaScript error on failure.
+    /// - `Result<Vec<Uint8Array>, KeyVaultError>` - A JavaScript Promise that resolves to the UTF-8 encoded mnemonics on success,
+    ///   or rejects with a JavaScript error on failure. Element 0 is chunk 1 of 2 (48 words) or 3 (72 words), and so on.
     ///
-    /// **Async**: no
+    /// **Async**: Yes
+    ///
+    /// **Note**: To reconstruct the QuantumPurse seed phrase, join the chunks with spaces in order 1, 2(, 3).
+    /// Any other order yields a different wallet, so label each backup with its chunk number.
+    ///
+    /// **Warning**: Exporting the mnemonics exposes them in JavaScript, which may pose a security risk.
+    /// Proper zeroization of the exported chunks is the responsibility of the caller.
     #[wasm_bindgen]
-    pub fn get_ckb_tx_message_all(serialized_mock_tx: Uint8Array) -> Result<Uint8Array, JsValue> {
-        let serialized_bytes = serialized_mock_tx.to_vec();
-        let repr_mock_tx: ReprMockTransaction = serde_json::from_slice(&serialized_bytes)
-            .map_err(|e| JsValue::from_str(&format!("Deserialization error: {}", e)))?;
-        let mock_tx: MockTransaction = repr_mock_tx.into();
-        let mut message_hasher = Hasher::message_hasher();
-        let _ = generate_ckb_tx_message_all_from_mock_tx(
-            &mock_tx,
-            ScriptOrIndex::Index(0),
-            &mut message_hasher,
-        )
-        .map_err(|e| JsValue::from_str(&format!("CKB_TX_MESSAGE_ALL error: {:?}", e)))?;
-        let message = message_hasher.hash();
-        Ok(Uint8Array::from(message.as_slice()))
+    pub async fn to_standard_bip39(password: Uint8Array) -> Result<Vec<Uint8Array>, KeyVaultError> {
+        let _guard = operation_lock::begin_operation()?;
+        let password = SecureVec::from_uint8_array(&password);
+        let payload = db::get_encrypted_mnemonic_seed()
+            .await
+            .map_err(KeyVaultError::from)?
+            .ok_or(KeyVaultError::MnemonicNotFound)?;
+
+        let entropy =
+            KeyVault::decrypt_with_lockout(&IndexedDbStorage, &password, payload, &seed_aad())
+                .await?;
+        let chunks = standard_bip39_chunks(&entropy, MnemonicLanguage::English)
+            .map_err(KeyVaultError::Internal)?;
+        Ok(chunks
+            .iter()
+            .map(|chunk| Uint8Array::from(chunk.as_bytes()))
+            .collect())
     }
 
     /// Measure bit strength of a password
@@ -511,15 +3862,18 @@ impl Util {
     /// - `password: Uint8Array` - utf8 serialized password.
     ///
     /// **Returns**:
-    /// - `Result<u16, JsValue>` - The strength of the password measured in bit on success,
+    /// - `Result<u16, KeyVaultError>` - The strength of the password measured in bit on success,
     ///   or a JavaScript error on failure.
     ///
     /// **Async**: no
+    ///
+    /// **Note**: Kept for backward compatibility. Length times charset size overestimates patterned passwords such as
+    /// "Password123!"; prefer `password_score`.
     #[wasm_bindgen]
-    pub fn password_checker(password: Uint8Array) -> Result<u32, JsValue> {
-        let password = SecureVec::from_slice(&password.to_vec());
-        let password_str =
-            std::str::from_utf8(&password).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    pub fn password_checker(password: Uint8Array) -> Result<u32, KeyVaultError> {
+        let password = SecureVec::from_uint8_array(&password);
+        let password_str = std::str::from_utf8(&password)
+            .map_err(|e| KeyVaultError::InvalidInput(e.to_string()))?;
 
         if password_str.is_empty() {
             return Ok(0);
@@ -549,23 +3903,23 @@ impl Util {
         }
 
         if !has_uppercase {
-            return Err(JsValue::from_str(
-                "Password must contain at least one uppercase letter!",
+            return Err(KeyVaultError::InvalidInput(
+                "Password must contain at least one uppercase letter!".to_string(),
             ));
         }
         if !has_lowercase {
-            return Err(JsValue::from_str(
-                "Password must contain at least one lowercase letter!",
+            return Err(KeyVaultError::InvalidInput(
+                "Password must contain at least one lowercase letter!".to_string(),
             ));
         }
         if !has_digit {
-            return Err(JsValue::from_str(
-                "Password must contain at least one digit!",
+            return Err(KeyVaultError::InvalidInput(
+                "Password must contain at least one digit!".to_string(),
             ));
         }
         if !has_punctuation {
-            return Err(JsValue::from_str(
-                "Password must contain at least one symbol!",
+            return Err(KeyVaultError::InvalidInput(
+                "Password must contain at least one symbol!".to_string(),
             ));
         }
 
@@ -599,10 +3953,61 @@ impl Util {
         let rounded_entropy = entropy.round() as u32;
 
         if rounded_entropy < 256 {
-            return Err(JsValue::from_str(
-                "Password entropy must be at least 256 bit. Consider lengthening your password!",
+            return Err(KeyVaultError::InvalidInput(
+                "Password entropy must be at least 256 bit. Consider lengthening your password!"
+                    .to_string(),
             ));
         }
         Ok(rounded_entropy)
     }
+
+    /// Scores a password the way a cracker would guess it, so dictionary words, keyboard walks, repeats and dates
+    /// score low whatever characters they contain.
+    ///
+    /// **Parameters**:
+    /// - `password: Uint8Array` - utf8 serialized password.
+    ///
+    /// **Returns**:
+    /// - `Result<JsValue, KeyVaultError>` - A `PasswordScore` object
+    ///   `{ score, guesses_log10, estimated_crack_time, feedback: { warning, suggestions } }` on success,
+    ///   or a JavaScript error if the password is empty or not UTF-8.
+    ///
+    /// **Async**: no
+    #[wasm_bindgen]
+    pub fn password_score(password: Uint8Array) -> Result<JsValue, KeyVaultError> {
+        let password = SecureVec::from_uint8_array(&password);
+        let password_str = std::str::from_utf8(&password)
+            .map_err(|e| KeyVaultError::InvalidInput(e.to_string()))?;
+        let score = utilities::password_score(password_str).map_err(KeyVaultError::InvalidInput)?;
+        Ok(serde_wasm_bindgen::to_value(&score)?)
+    }
+}
+
+#[cfg(feature = "test-fixtures")]
+#[wasm_bindgen]
+impl Util {
+    /// Derives deterministic accounts from a fixed, public test seed (the 96 bytes 0x00, 0x01, ..., 0x5f),
+    /// giving integration tests stable golden lock script arguments and public keys. Test builds only.
+    ///
+    /// **Parameters**:
+    /// - `variant: SphincsVariant` - The SPHINCS+ parameter set.
+    /// - `count: u32` - The number of accounts, from index 0.
+    ///
+    /// **Returns**:
+    /// - `Result<Vec<JsValue>, KeyVaultError>` - `{ index, lock_args, pub_key }` objects on success, or a JavaScript error on failure.
+    ///
+    /// **Async**: no
+    ///
+    /// **Warning**: The seed is public. Never send funds to fixture accounts.
+    #[wasm_bindgen]
+    pub fn fixture_accounts(
+        variant: SphincsVariant,
+        count: u32,
+    ) -> Result<Vec<JsValue>, KeyVaultError> {
+        fixtures::fixture_accounts(variant, count)
+            .map_err(KeyVaultError::Internal)?
+            .iter()
+            .map(|account| serde_wasm_bindgen::to_value(account).map_err(KeyVaultError::from))
+            .collect()
+    }
 }
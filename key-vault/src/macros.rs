@@ -7,25 +7,44 @@ macro_rules! debug {
 
 #[macro_export]
 macro_rules! sphincs_keygen {
-    ($kg:ty, $n:expr, $seed:expr, $index:expr) => {{
+    ($kg:ty, $n:expr, $seed:expr, $index:expr, $params:expr) => {{
         const N: usize = $n;
         /* The following scrypt param is used together with a very high entropy source - a 512/768 bit
         mnemonic seephrase to serve as QuantumPurse KDF. Security level for the derived keys isn't
-        upgraded with Scrypt, each attacker's guess simply gets longer to run.*/
+        upgraded with Scrypt, each attacker's guess simply gets longer to run. log_n defaults to 10 and
+        can be lowered for slow devices; with a 512/768 bit seed, guessing stays infeasible either way.*/
         let param = ScryptParam {
-            log_n: 10,
+            log_n: $params.log_n,
             r: 8,
             p: 1,
             len: N,
         };
+        // A corrupted or truncated seed must be a catchable error, not a panic aborting the wasm instance
+        if $seed.len() < 3 * N {
+            return Err(format!("Seed too short: need {} bytes, got {}", 3 * N, $seed.len()));
+        }
         let path = format!("{}{}", KDF_PATH_PREFIX, $index);
-        let sk_seed: &[u8; N] = $seed[0..N].try_into().expect("Invalid seed length");
-        let sk_prf: &[u8; N] = $seed[N..2 * N].try_into().expect("Invalid seed length");
-        let pk_seed: &[u8; N] = $seed[2 * N..3 * N].try_into().expect("Invalid seed length");
+        let sk_seed: &[u8; N] = $seed[0..N].try_into().map_err(|_| "Invalid seed length")?;
+        let sk_prf: &[u8; N] = $seed[N..2 * N].try_into().map_err(|_| "Invalid seed length")?;
+        let pk_seed: &[u8; N] = $seed[2 * N..3 * N].try_into().map_err(|_| "Invalid seed length")?;
 
-        let sk_seed_kd: SecureVec = derive_scrypt_key(sk_seed, &path.as_bytes().to_vec(), &param)?;
-        let sk_prf_kd: SecureVec = derive_scrypt_key(sk_prf, &path.as_bytes().to_vec(), &param)?;
-        let pk_seed_kd: SecureVec = derive_scrypt_key(pk_seed, &path.as_bytes().to_vec(), &param)?;
+        let (sk_seed_kd, sk_prf_kd, pk_seed_kd): (SecureVec, SecureVec, SecureVec) = if $params.single_scrypt {
+            // One Scrypt run over the three seed parts, stretched to the three components by Scrypt's final
+            // PBKDF2 step: a third of the KDF cost, but different keys than the three separate runs below.
+            let param = ScryptParam { len: 3 * N, ..param };
+            let master: SecureVec = derive_scrypt_key(&$seed[0..3 * N], &path.as_bytes().to_vec(), &param)?;
+            (
+                SecureVec::from_slice(&master[0..N]),
+                SecureVec::from_slice(&master[N..2 * N]),
+                SecureVec::from_slice(&master[2 * N..3 * N]),
+            )
+        } else {
+            (
+                derive_scrypt_key(sk_seed, &path.as_bytes().to_vec(), &param)?,
+                derive_scrypt_key(sk_prf, &path.as_bytes().to_vec(), &param)?,
+                derive_scrypt_key(pk_seed, &path.as_bytes().to_vec(), &param)?,
+            )
+        };
 
         let sk_seed_kd_ref: &[u8; N] = sk_seed_kd.as_ref().try_into().map_err(|_| "Invalid seed length")?;
         let sk_prf_kd_ref: &[u8; N] = sk_prf_kd.as_ref().try_into().map_err(|_| "Invalid seed length")?;
@@ -33,25 +52,27 @@ macro_rules! sphincs_keygen {
 
         let (pub_key, pri_key) = <$kg>::keygen_with_seeds(sk_seed_kd_ref,sk_prf_kd_ref,pk_seed_kd_ref);
 
-        Ok((
-            SecureVec::from_slice(&pub_key.into_bytes()),
-            SecureVec::from_slice(&pri_key.into_bytes()),
-        ))
+        let mut pri_key_bytes = pri_key.into_bytes();
+        let pri_key = SecureVec::from_slice(&pri_key_bytes);
+        pri_key_bytes.zeroize();
+
+        Ok((SecureVec::from_slice(&pub_key.into_bytes()), pri_key))
     }};
 }
 
 #[macro_export]
 macro_rules! sphincs_sign {
-    ($module:ident, $pri_key:expr, $message_vec:expr, $variant:expr) => {{
+    ($module:ident, $pri_key:expr, $message_vec:expr, $variant:expr, $hedged:expr, $context:expr) => {{
         let mut pri_key_bytes: [u8; $module::SK_LEN] = $pri_key
             .as_ref()
             .try_into()
-            .map_err(|_| JsValue::from_str("Invalid private key length"))?;
-        let signing_key = $module::PrivateKey::try_from_bytes(&pri_key_bytes)
-            .map_err(|e| JsValue::from_str(&format!("Unable to construct private key: {:?}", e)))?;
+            .map_err(|_| KeyVaultError::Internal("Invalid private key length".to_string()))?;
+        let signing_key = $module::PrivateKey::try_from_bytes(&pri_key_bytes).map_err(|e| {
+            KeyVaultError::Internal(format!("Unable to construct private key: {:?}", e))
+        })?;
         let signature = signing_key
-            .try_sign($message_vec, &[], true)
-            .map_err(|e| JsValue::from_str(&format!("Signing error: {:?}", e)))?;
+            .try_sign($message_vec, $context, $hedged)
+            .map_err(|e| KeyVaultError::Internal(format!("Signing error: {:?}", e)))?;
 
         let all_in_one_config: [u8; 4] = [
             MULTISIG_RESERVED_FIELD_VALUE,
@@ -66,7 +87,7 @@ macro_rules! sphincs_sign {
             [$module::PK_LEN..$module::PK_LEN + $module::PK_LEN]
             .as_ref()
             .try_into()
-            .map_err(|_| JsValue::from_str("Invalid public key length"))?;
+            .map_err(|_| KeyVaultError::Internal("Invalid public key length".to_string()))?;
         let ckb_qr_full_signature = [
             &all_in_one_config[..],
             &[param_id_and_sign_flag],
@@ -77,6 +98,29 @@ macro_rules! sphincs_sign {
 
         pri_key_bytes.zeroize();
 
-        Ok(Uint8Array::from(ckb_qr_full_signature.as_slice()))
+        Ok(ckb_qr_full_signature)
+    }};
+}
+
+#[macro_export]
+macro_rules! sphincs_verify {
+    ($module:ident, $pub_key:expr, $message:expr, $signature:expr, $context:expr) => {{
+        let pub_key_bytes: &[u8; $module::PK_LEN] = $pub_key.try_into().map_err(|_| {
+            format!(
+                "Invalid public key length: expected {} bytes for {}, got {}",
+                $module::PK_LEN,
+                stringify!($module),
+                $pub_key.len()
+            )
+        })?;
+        let verifying_key = $module::PublicKey::try_from_bytes(pub_key_bytes)
+            .map_err(|e| format!("Unable to construct public key: {:?}", e))?;
+
+        // A truncated, over-long or empty signature is an invalid signature, not a malformed request,
+        // and is rejected here rather than handed to the library.
+        match <&[u8; $module::SIG_LEN]>::try_from($signature) {
+            Ok(signature) => Ok(verifying_key.verify($message, signature, $context)),
+            Err(_) => Ok(false),
+        }
     }};
 }
@@ -0,0 +1,65 @@
+//! Keeps destructive operations (`clear_database`) from interleaving with other operations across
+//! `await` points. Conflicts are reported as errors rather than waited on, leaving the caller to retry.
+//!
+//! The counters are thread locals, so the lock only covers the `KeyVault`s of one wasm instance, which runs on one
+//! thread. Other instances sharing the IndexedDB database, such as the one `signing_worker.js` starts or the page
+//! in another tab, aren't excluded; against them, each storage operation is only as atomic as its own IndexedDB
+//! transaction.
+
+use super::errors::KeyVaultError;
+use std::cell::Cell;
+#[cfg(test)]
+mod tests;
+
+thread_local! {
+    static ACTIVE_OPERATIONS: Cell<u32> = const { Cell::new(0) };
+    static EXCLUSIVE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Held by a regular operation for its whole duration.
+pub struct OperationGuard(());
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        ACTIVE_OPERATIONS.with(|active| active.set(active.get() - 1));
+    }
+}
+
+/// Held by a destructive operation for its whole duration.
+pub struct ExclusiveGuard(());
+
+impl Drop for ExclusiveGuard {
+    fn drop(&mut self) {
+        EXCLUSIVE.with(|exclusive| exclusive.set(false));
+    }
+}
+
+/// Starts a regular operation.
+///
+/// **Returns**:
+/// - `Result<OperationGuard, KeyVaultError>` - The guard on success, or `Busy` while a destructive operation runs.
+pub fn begin_operation() -> Result<OperationGuard, KeyVaultError> {
+    if EXCLUSIVE.with(Cell::get) {
+        return Err(KeyVaultError::Busy(
+            "The database is being cleared; retry once it completes".to_string(),
+        ));
+    }
+    ACTIVE_OPERATIONS.with(|active| active.set(active.get() + 1));
+    Ok(OperationGuard(()))
+}
+
+/// Starts a destructive operation.
+///
+/// **Returns**:
+/// - `Result<ExclusiveGuard, KeyVaultError>` - The guard on success, or `Busy` while any other operation runs.
+pub fn begin_exclusive() -> Result<ExclusiveGuard, KeyVaultError> {
+    let active = ACTIVE_OPERATIONS.with(Cell::get);
+    if active > 0 || EXCLUSIVE.with(Cell::get) {
+        return Err(KeyVaultError::Busy(format!(
+            "Cannot clear the database while {} other operation(s) are in progress; retry once they complete",
+            active.max(1)
+        )));
+    }
+    EXCLUSIVE.with(|exclusive| exclusive.set(true));
+    Ok(ExclusiveGuard(()))
+}
@@ -0,0 +1,23 @@
+use super::*;
+
+#[test]
+fn test_clear_refused_while_sign_in_flight() {
+  let sign = begin_operation().unwrap();
+  let err = begin_exclusive().err().unwrap();
+  assert_eq!(err.code(), "BUSY");
+  assert!(err.to_string().starts_with("Cannot clear the database while 1 other operation(s)"));
+
+  drop(sign);
+  assert!(begin_exclusive().is_ok());
+}
+
+#[test]
+fn test_sign_refused_while_clear_in_flight() {
+  let clear = begin_exclusive().unwrap();
+  assert!(begin_operation().is_err());
+  assert!(begin_exclusive().is_err());
+
+  drop(clear);
+  let _sign = begin_operation().unwrap();
+  let _gen_account = begin_operation().unwrap();
+}
@@ -0,0 +1,39 @@
+use std::ops::Deref;
+use zeroize::Zeroize;
+
+/// A secure string that zeroizes its contents when dropped.
+#[derive(Debug)]
+pub struct SecureString(String);
+
+impl SecureString {
+    /// Takes ownership of `bytes` without copying. Returns `None` if `bytes` isn't valid UTF-8.
+    pub fn from_utf8(bytes: Vec<u8>) -> Option<Self> {
+        match String::from_utf8(bytes) {
+            Ok(s) => Some(SecureString(s)),
+            Err(e) => {
+                e.into_bytes().zeroize();
+                None
+            }
+        }
+    }
+}
+
+impl Zeroize for SecureString {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecureString {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Deref for SecureString {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
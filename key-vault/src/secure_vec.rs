@@ -1,4 +1,6 @@
+use crate::secure_string::SecureString;
 use std::ops::{Deref, DerefMut};
+use web_sys::js_sys::Uint8Array;
 use zeroize::Zeroize;
 #[cfg(test)]
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -17,6 +19,23 @@ impl SecureVec {
     pub fn from_slice(slice: &[u8]) -> Self {
       SecureVec(slice.to_vec())
     }
+
+    /// Copies a JavaScript array straight into a new `SecureVec`, unlike `from_slice(&array.to_vec())`
+    /// which leaves an intermediate plain `Vec` of the secret on the heap.
+    pub fn from_uint8_array(array: &Uint8Array) -> Self {
+        let mut secure = SecureVec::new_with_length(array.length() as usize);
+        array.copy_to(&mut secure);
+        secure
+    }
+
+    /// Hex-encodes the secret bytes straight into a `SecureString` so no plain `String` copy of the
+    /// secret is left behind. Use this instead of `hex::encode` whenever the bytes are secret.
+    #[allow(dead_code)]
+    pub fn to_hex_secure(&self) -> SecureString {
+        let mut buffer = vec![0u8; self.0.len() * 2];
+        hex::encode_to_slice(&self.0, &mut buffer).expect("Buffer is twice the input length");
+        SecureString::from_utf8(buffer).expect("Hex output is ASCII")
+    }
 }
 
 impl Zeroize for SecureVec {
@@ -0,0 +1,116 @@
+//! In-memory cache of decrypted private keys for an unlocked signing session.
+//! A session is referenced by an unguessable id and ends on `close`, when its timeout passes or on `close_all`.
+
+use super::constants::SESSION_ID_LENGTH;
+use super::secure_vec::SecureVec;
+use super::utilities::get_random_bytes;
+use hex::encode;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+#[cfg(test)]
+mod tests;
+
+thread_local! {
+    /// The caches made by `SessionCache::registered`, so static methods such as `KeyVault::clear_database` reach the
+    /// sessions of every `KeyVault` of this wasm instance.
+    static REGISTERED: RefCell<Vec<Weak<RefCell<SessionCache>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Ends the session of every registered cache, zeroizing their decrypted private keys.
+///
+/// **Panics**: If a cache is borrowed, which the callers rule out by holding `operation_lock::begin_exclusive`.
+pub fn close_all() {
+    REGISTERED.with(|registered| {
+        for cache in registered.borrow().iter().filter_map(Weak::upgrade) {
+            cache.borrow_mut().close();
+        }
+    });
+}
+
+/// An unlocked session: decrypted private keys by lock_args, valid until `expires_at` (ms since epoch).
+struct Session {
+    id: String,
+    keys: HashMap<String, SecureVec>,
+    expires_at: f64,
+}
+
+/// Holds at most one unlocked session. Opening a new session ends the previous one.
+#[derive(Default)]
+pub struct SessionCache {
+    session: Option<Session>,
+}
+
+impl SessionCache {
+    /// Creates an empty cache reached by `close_all` for as long as it lives.
+    pub fn registered() -> Rc<RefCell<SessionCache>> {
+        let cache = Rc::new(RefCell::new(SessionCache::default()));
+        REGISTERED.with(|registered| {
+            let mut registered = registered.borrow_mut();
+            registered.retain(|cache| cache.strong_count() > 0);
+            registered.push(Rc::downgrade(&cache));
+        });
+        cache
+    }
+
+    /// Opens a session over the given decrypted private keys.
+    ///
+    /// **Parameters**:
+    /// - `keys: HashMap<String, SecureVec>` - Decrypted private keys by hex-encoded lock script arguments.
+    /// - `now: f64` - The current time in milliseconds.
+    /// - `timeout_ms: f64` - How long the session stays valid.
+    ///
+    /// **Returns**:
+    /// - `Result<String, String>` - The hex-encoded session id on success, or an error message on failure.
+    pub fn open(
+        &mut self,
+        keys: HashMap<String, SecureVec>,
+        now: f64,
+        timeout_ms: f64,
+    ) -> Result<String, String> {
+        self.close();
+        let id = encode(
+            &*get_random_bytes(SESSION_ID_LENGTH)
+                .map_err(|e| format!("Random generation error: {}", e))?,
+        );
+        self.session = Some(Session {
+            id: id.clone(),
+            keys,
+            expires_at: now + timeout_ms,
+        });
+        Ok(id)
+    }
+
+    /// Looks up a decrypted private key of the session. An expired session is closed on lookup.
+    ///
+    /// **Parameters**:
+    /// - `session_id: &str` - The session id returned by `open`.
+    /// - `lock_args: &str` - The hex-encoded lock script's arguments of the account.
+    /// - `now: f64` - The current time in milliseconds.
+    ///
+    /// **Returns**:
+    /// - `Result<&SecureVec, String>` - The decrypted private key on success, or an error message on failure.
+    pub fn key(
+        &mut self,
+        session_id: &str,
+        lock_args: &str,
+        now: f64,
+    ) -> Result<&SecureVec, String> {
+        if self.session.as_ref().is_some_and(|s| now >= s.expires_at) {
+            self.close();
+            return Err("Session expired".to_string());
+        }
+        match &self.session {
+            Some(session) if session.id == session_id => session
+                .keys
+                .get(lock_args)
+                .ok_or_else(|| "Account not unlocked in this session".to_string()),
+            _ => Err("Invalid session".to_string()),
+        }
+    }
+
+    /// Ends the current session, zeroizing its decrypted private keys.
+    pub fn close(&mut self) {
+        self.session = None;
+    }
+}
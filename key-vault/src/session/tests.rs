@@ -0,0 +1,55 @@
+use super::*;
+
+fn unlocked_keys() -> HashMap<String, SecureVec> {
+  let mut keys = HashMap::new();
+  keys.insert("aa".to_string(), SecureVec::from_slice(&[1, 2, 3]));
+  keys
+}
+
+#[test]
+fn test_session_key_lookup_until_closed() {
+  let mut cache = SessionCache::default();
+  let id = cache.open(unlocked_keys(), 0.0, 1000.0).unwrap();
+  assert_eq!(id.len(), SESSION_ID_LENGTH * 2);
+  assert_eq!(&**cache.key(&id, "aa", 1.0).unwrap(), [1, 2, 3]);
+  assert_eq!(&**cache.key(&id, "aa", 2.0).unwrap(), [1, 2, 3]);
+  assert!(cache.key(&id, "bb", 3.0).is_err());
+
+  cache.close();
+  assert_eq!(cache.key(&id, "aa", 4.0).unwrap_err(), "Invalid session");
+}
+
+#[test]
+fn test_session_expires() {
+  let mut cache = SessionCache::default();
+  let id = cache.open(unlocked_keys(), 0.0, 1000.0).unwrap();
+  assert!(cache.key(&id, "aa", 999.0).is_ok());
+  assert_eq!(cache.key(&id, "aa", 1000.0).unwrap_err(), "Session expired");
+  assert_eq!(cache.key(&id, "aa", 0.0).unwrap_err(), "Invalid session");
+}
+
+#[test]
+fn test_new_session_invalidates_previous() {
+  let mut cache = SessionCache::default();
+  let first = cache.open(unlocked_keys(), 0.0, 1000.0).unwrap();
+  let second = cache.open(unlocked_keys(), 0.0, 1000.0).unwrap();
+  assert_ne!(first, second);
+  assert!(cache.key(&first, "aa", 1.0).is_err());
+  assert!(cache.key(&second, "aa", 1.0).is_ok());
+}
+
+#[test]
+fn test_close_all_ends_every_registered_session() {
+  let first = SessionCache::registered();
+  let second = SessionCache::registered();
+  let first_id = first.borrow_mut().open(unlocked_keys(), 0.0, 1000.0).unwrap();
+  let second_id = second.borrow_mut().open(unlocked_keys(), 0.0, 1000.0).unwrap();
+  drop(SessionCache::registered());
+
+  close_all();
+  assert_eq!(first.borrow_mut().key(&first_id, "aa", 1.0).unwrap_err(), "Invalid session");
+  assert_eq!(second.borrow_mut().key(&second_id, "aa", 1.0).unwrap_err(), "Invalid session");
+  // The dropped cache was pruned when the next one was registered
+  drop(SessionCache::registered());
+  REGISTERED.with(|registered| assert_eq!(registered.borrow().len(), 3));
+}
@@ -0,0 +1,661 @@
+//! Storage backends of a `KeyVault`. `IndexedDbStorage` persists to the browser's IndexedDB through `db`, while
+//! `InMemoryStorage` keeps everything in HashMaps, so the vault logic built on these operations runs under a native
+//! `cargo test`. Every instance method of `KeyVault` reads and writes through its `Storage`; static methods, which
+//! have no vault instance, go to `db` directly.
+
+use super::constants::{CHILD_KEYS_STORE, METADATA_STORE, SEED_PHRASE_KEY, SEED_PHRASE_STORE, VERIFIER_KEY};
+use super::db::{self, KeyVaultDBError};
+use super::types::{CipherPayload, FailedAttempts, SphincsPlusAccount, SphincsVariant, WalletBackup};
+use super::utilities::{release_unlock_attempt, reserve_unlock_attempt};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(test)]
+pub(crate) mod tests;
+
+/// The future of a `Storage` operation. Not `Send`, as IndexedDB futures hold `JsValue`s and wasm runs on one thread.
+pub type StorageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, KeyVaultDBError>> + 'a>>;
+
+/// The storage operations of a `KeyVault`, with the semantics of the `db` functions of the same name.
+pub trait Storage {
+    /// Retrieves the encrypted mnemonic phrase, see `db::get_encrypted_mnemonic_seed`.
+    fn get_encrypted_mnemonic_seed(&self) -> StorageFuture<'_, Option<CipherPayload>>;
+
+    /// Retrieves the encrypted mnemonic phrase even while the vault is sealed,
+    /// see `db::get_sealed_encrypted_mnemonic_seed`.
+    fn get_sealed_encrypted_mnemonic_seed(&self) -> StorageFuture<'_, Option<CipherPayload>>;
+
+    /// Retrieves the password verifier stored with the mnemonic phrase, see `db::get_password_verifier`.
+    fn get_password_verifier(&self) -> StorageFuture<'_, Option<CipherPayload>>;
+
+    /// Stores the encrypted mnemonic phrase with its password verifier, variant and derivation scheme,
+    /// see `db::set_encrypted_mnemonic_seed`.
+    /// Returns false, writing nothing, if a mnemonic phrase is stored and either `overwrite` is false or accounts are
+    /// stored.
+    fn set_encrypted_mnemonic_seed(
+        &self,
+        payload: CipherPayload,
+        verifier: CipherPayload,
+        variant: SphincsVariant,
+        single_scrypt: bool,
+        overwrite: bool,
+    ) -> StorageFuture<'_, bool>;
+
+    /// Stores an encrypted mnemonic phrase restored from a blob, see `db::restore_encrypted_mnemonic_seed`.
+    fn restore_encrypted_mnemonic_seed(
+        &self,
+        payload: CipherPayload,
+        variant: SphincsVariant,
+        single_scrypt: bool,
+        overwrite: bool,
+    ) -> StorageFuture<'_, bool>;
+
+    /// Replaces the mnemonic phrase and all accounts at once, see `db::replace_seed_and_accounts`.
+    fn replace_seed_and_accounts(
+        &self,
+        payload: CipherPayload,
+        verifier: CipherPayload,
+        accounts: Vec<SphincsPlusAccount>,
+        read: Option<(Vec<u8>, Vec<String>)>,
+    ) -> StorageFuture<'_, ()>;
+
+    /// Restores every record of a wallet at once, see `db::restore_vault`.
+    /// Returns false, writing nothing, if the storage isn't empty and `overwrite` is false.
+    fn restore_vault(&self, backup: WalletBackup, overwrite: bool) -> StorageFuture<'_, bool>;
+
+    /// Retrieves the SPHINCS+ variant the wallet was set up with, see `db::get_stored_variant`.
+    fn get_stored_variant(&self) -> StorageFuture<'_, Option<SphincsVariant>>;
+
+    /// Retrieves the derivation scheme the wallet was set up with, see `db::get_stored_single_scrypt`.
+    fn get_stored_single_scrypt(&self) -> StorageFuture<'_, Option<bool>>;
+
+    /// Persists the sealed flag, see `db::set_sealed`.
+    fn set_sealed(&self, sealed: bool) -> StorageFuture<'_, ()>;
+
+    /// Stores a SPHINCS+ account, see `db::add_account`. Returns false if its lock_args are already stored.
+    fn add_account(&self, account: SphincsPlusAccount) -> StorageFuture<'_, bool>;
+
+    /// Stores several SPHINCS+ accounts at once, skipping stored lock_args, see `db::add_accounts`.
+    fn add_accounts(&self, accounts: Vec<SphincsPlusAccount>) -> StorageFuture<'_, u32>;
+
+    /// Overwrites the given accounts, see `db::put_accounts`.
+    fn put_accounts(&self, accounts: Vec<SphincsPlusAccount>) -> StorageFuture<'_, ()>;
+
+    /// Deletes an account, retiring its derivation index, see `db::delete_account`.
+    fn delete_account<'a>(&'a self, lock_args: &'a str) -> StorageFuture<'a, ()>;
+
+    /// Deletes every watch-only account in one go, see `db::purge_watch_only`. Returns the number deleted.
+    fn purge_watch_only(&self) -> StorageFuture<'_, u32>;
+
+    /// Retrieves the account of some lock script arguments, see `db::get_account`.
+    fn get_account<'a>(&'a self, lock_args: &'a str) -> StorageFuture<'a, Option<SphincsPlusAccount>>;
+
+    /// Retrieves the accounts of several lock script arguments, see `db::get_accounts`.
+    fn get_accounts<'a>(
+        &'a self,
+        lock_args_list: &'a [String],
+    ) -> StorageFuture<'a, HashMap<String, SphincsPlusAccount>>;
+
+    /// Retrieves all accounts sorted by index, see `db::get_all_accounts`.
+    fn get_all_accounts(&self) -> StorageFuture<'_, Vec<SphincsPlusAccount>>;
+
+    /// Retrieves the derivation index of the next new account, see `db::next_account_index`.
+    fn next_account_index(&self) -> StorageFuture<'_, u32>;
+
+    /// Reserves an unlock attempt, see `db::reserve_failed_attempt`.
+    fn reserve_failed_attempt(
+        &self,
+        max: u32,
+        cooldown_ms: u32,
+        now: f64,
+    ) -> StorageFuture<'_, Result<FailedAttempts, String>>;
+
+    /// Gives back a reserved attempt that didn't test the password, see `db::release_failed_attempt`.
+    fn release_failed_attempt(&self) -> StorageFuture<'_, ()>;
+
+    /// Clears the recorded wrong-password attempts, see `db::clear_failed_attempts`.
+    fn clear_failed_attempts(&self) -> StorageFuture<'_, ()>;
+
+    /// Clears an object store, see `db::clear_object_store`.
+    fn clear_object_store<'a>(&'a self, store_name: &'a str) -> StorageFuture<'a, ()>;
+}
+
+/// The browser's IndexedDB, the storage of every `KeyVault` built from JavaScript.
+pub struct IndexedDbStorage;
+
+impl Storage for IndexedDbStorage {
+    fn get_encrypted_mnemonic_seed(&self) -> StorageFuture<'_, Option<CipherPayload>> {
+        Box::pin(db::get_encrypted_mnemonic_seed())
+    }
+
+    fn get_sealed_encrypted_mnemonic_seed(&self) -> StorageFuture<'_, Option<CipherPayload>> {
+        Box::pin(db::get_sealed_encrypted_mnemonic_seed())
+    }
+
+    fn get_password_verifier(&self) -> StorageFuture<'_, Option<CipherPayload>> {
+        Box::pin(db::get_password_verifier())
+    }
+
+    fn set_encrypted_mnemonic_seed(
+        &self,
+        payload: CipherPayload,
+        verifier: CipherPayload,
+        variant: SphincsVariant,
+        single_scrypt: bool,
+        overwrite: bool,
+    ) -> StorageFuture<'_, bool> {
+        Box::pin(db::set_encrypted_mnemonic_seed(
+            payload,
+            verifier,
+            variant,
+            single_scrypt,
+            overwrite,
+        ))
+    }
+
+    fn restore_encrypted_mnemonic_seed(
+        &self,
+        payload: CipherPayload,
+        variant: SphincsVariant,
+        single_scrypt: bool,
+        overwrite: bool,
+    ) -> StorageFuture<'_, bool> {
+        Box::pin(db::restore_encrypted_mnemonic_seed(
+            payload,
+            variant,
+            single_scrypt,
+            overwrite,
+        ))
+    }
+
+    fn replace_seed_and_accounts(
+        &self,
+        payload: CipherPayload,
+        verifier: CipherPayload,
+        accounts: Vec<SphincsPlusAccount>,
+        read: Option<(Vec<u8>, Vec<String>)>,
+    ) -> StorageFuture<'_, ()> {
+        Box::pin(db::replace_seed_and_accounts(payload, verifier, accounts, read))
+    }
+
+    fn restore_vault(&self, backup: WalletBackup, overwrite: bool) -> StorageFuture<'_, bool> {
+        Box::pin(db::restore_vault(backup, overwrite))
+    }
+
+    fn get_stored_variant(&self) -> StorageFuture<'_, Option<SphincsVariant>> {
+        Box::pin(db::get_stored_variant())
+    }
+
+    fn get_stored_single_scrypt(&self) -> StorageFuture<'_, Option<bool>> {
+        Box::pin(db::get_stored_single_scrypt())
+    }
+
+    fn set_sealed(&self, sealed: bool) -> StorageFuture<'_, ()> {
+        Box::pin(db::set_sealed(sealed))
+    }
+
+    fn add_account(&self, account: SphincsPlusAccount) -> StorageFuture<'_, bool> {
+        Box::pin(db::add_account(account))
+    }
+
+    fn add_accounts(&self, accounts: Vec<SphincsPlusAccount>) -> StorageFuture<'_, u32> {
+        Box::pin(db::add_accounts(accounts))
+    }
+
+    fn put_accounts(&self, accounts: Vec<SphincsPlusAccount>) -> StorageFuture<'_, ()> {
+        Box::pin(db::put_accounts(accounts))
+    }
+
+    fn delete_account<'a>(&'a self, lock_args: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(db::delete_account(lock_args))
+    }
+
+    fn purge_watch_only(&self) -> StorageFuture<'_, u32> {
+        Box::pin(db::purge_watch_only())
+    }
+
+    fn get_account<'a>(&'a self, lock_args: &'a str) -> StorageFuture<'a, Option<SphincsPlusAccount>> {
+        Box::pin(db::get_account(lock_args))
+    }
+
+    fn get_accounts<'a>(
+        &'a self,
+        lock_args_list: &'a [String],
+    ) -> StorageFuture<'a, HashMap<String, SphincsPlusAccount>> {
+        Box::pin(db::get_accounts(lock_args_list))
+    }
+
+    fn get_all_accounts(&self) -> StorageFuture<'_, Vec<SphincsPlusAccount>> {
+        Box::pin(db::get_all_accounts())
+    }
+
+    fn next_account_index(&self) -> StorageFuture<'_, u32> {
+        Box::pin(db::next_account_index())
+    }
+
+    fn reserve_failed_attempt(
+        &self,
+        max: u32,
+        cooldown_ms: u32,
+        now: f64,
+    ) -> StorageFuture<'_, Result<FailedAttempts, String>> {
+        Box::pin(db::reserve_failed_attempt(max, cooldown_ms, now))
+    }
+
+    fn release_failed_attempt(&self) -> StorageFuture<'_, ()> {
+        Box::pin(db::release_failed_attempt())
+    }
+
+    fn clear_failed_attempts(&self) -> StorageFuture<'_, ()> {
+        Box::pin(db::clear_failed_attempts())
+    }
+
+    fn clear_object_store<'a>(&'a self, store_name: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let db = db::open_db().await?;
+            db::clear_object_store(&db, store_name).await
+        })
+    }
+}
+
+/// A storage living as long as the instance, for tests. The seed store holds the mnemonic phrase and password
+/// verifier under the IndexedDB record keys, the child keys store the accounts by lock_args. The other records
+/// IndexedDB keeps in the seed phrase store (the next derivation index, variant, derivation scheme and sealed flag)
+/// and the metadata store (the failed attempts) have a field each, cleared with their store.
+#[derive(Default)]
+pub struct InMemoryStorage {
+    seed_store: RefCell<HashMap<String, CipherPayload>>,
+    child_keys_store: RefCell<HashMap<String, SphincsPlusAccount>>,
+    next_index: Cell<Option<u32>>,
+    variant: Cell<Option<SphincsVariant>>,
+    single_scrypt: Cell<Option<bool>>,
+    sealed: Cell<bool>,
+    failed_attempts: Cell<FailedAttempts>,
+}
+
+impl InMemoryStorage {
+    /// Creates an empty storage.
+    pub fn new() -> Self {
+        InMemoryStorage::default()
+    }
+
+    /// Refuses with `VaultSealed` while sealed, like `db::open_unsealed_db`.
+    fn check_not_sealed(&self) -> Result<(), KeyVaultDBError> {
+        db::check_not_sealed(self.sealed.get())
+    }
+
+    /// Advances the persisted next derivation index past `indices`, see `db::next_index_after`.
+    fn advance_next_index<I: IntoIterator<Item = u32>>(&self, indices: I) -> Result<(), KeyVaultDBError> {
+        let stored = self.next_index.get().map(f64::from);
+        self.next_index.set(Some(db::next_index_after(stored, indices)?));
+        Ok(())
+    }
+
+    fn stored_indices(&self) -> Vec<u32> {
+        self.child_keys_store.borrow().values().map(|account| account.index).collect()
+    }
+
+    fn seed_store_payload(&self, key: &str) -> Result<Option<CipherPayload>, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        Ok(self.seed_store.borrow().get(key).cloned())
+    }
+
+    fn write_seed(
+        &self,
+        payload: CipherPayload,
+        verifier: CipherPayload,
+        variant: SphincsVariant,
+        single_scrypt: bool,
+        overwrite: bool,
+    ) -> Result<bool, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        let mut seed_store = self.seed_store.borrow_mut();
+        let has_accounts = !self.child_keys_store.borrow().is_empty();
+        if !db::should_restore_seed(seed_store.contains_key(SEED_PHRASE_KEY), has_accounts, overwrite) {
+            return Ok(false);
+        }
+        seed_store.insert(SEED_PHRASE_KEY.to_string(), payload);
+        seed_store.insert(VERIFIER_KEY.to_string(), verifier);
+        self.variant.set(Some(variant));
+        self.single_scrypt.set(Some(single_scrypt));
+        Ok(true)
+    }
+
+    fn restore_seed(
+        &self,
+        payload: CipherPayload,
+        variant: SphincsVariant,
+        single_scrypt: bool,
+        overwrite: bool,
+    ) -> Result<bool, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        let mut seed_store = self.seed_store.borrow_mut();
+        let has_accounts = !self.child_keys_store.borrow().is_empty();
+        if !db::should_restore_seed(seed_store.contains_key(SEED_PHRASE_KEY), has_accounts, overwrite) {
+            return Ok(false);
+        }
+        seed_store.insert(SEED_PHRASE_KEY.to_string(), payload);
+        seed_store.remove(VERIFIER_KEY);
+        self.variant.set(Some(variant));
+        self.single_scrypt.set(Some(single_scrypt));
+        Ok(true)
+    }
+
+    fn replace(
+        &self,
+        payload: CipherPayload,
+        verifier: CipherPayload,
+        accounts: Vec<SphincsPlusAccount>,
+        read: Option<(Vec<u8>, Vec<String>)>,
+    ) -> Result<(), KeyVaultDBError> {
+        self.check_not_sealed()?;
+        if let Some((read_seed, read_lock_args)) = &read {
+            let stored_seed = self.seed_store.borrow().get(SEED_PHRASE_KEY).cloned();
+            let stored_lock_args: Vec<String> = self.child_keys_store.borrow().keys().cloned().collect();
+            db::check_unchanged_since_read(
+                read_seed,
+                stored_seed.as_ref().map(|seed| seed.cipher_text.as_slice()),
+                read_lock_args,
+                &stored_lock_args,
+            )?;
+        }
+        // Checked before any write, as the IndexedDB transaction would roll back
+        self.advance_next_index(accounts.iter().map(|account| account.index))?;
+        let mut seed_store = self.seed_store.borrow_mut();
+        seed_store.insert(SEED_PHRASE_KEY.to_string(), payload);
+        seed_store.insert(VERIFIER_KEY.to_string(), verifier);
+        let mut child_keys_store = self.child_keys_store.borrow_mut();
+        child_keys_store.clear();
+        for account in accounts {
+            child_keys_store.insert(account.lock_args.clone(), account);
+        }
+        Ok(())
+    }
+
+    fn restore(&self, backup: WalletBackup, overwrite: bool) -> Result<bool, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        let exists = self.seed_store.borrow().contains_key(SEED_PHRASE_KEY)
+            || !self.child_keys_store.borrow().is_empty();
+        if !db::should_write_seed(exists, overwrite) {
+            return Ok(false);
+        }
+        // Computed before any write, as the IndexedDB transaction would roll back
+        let next_index = db::next_index_after(
+            backup.next_index.map(f64::from),
+            backup.accounts.iter().map(|account| account.index),
+        )?;
+        let mut seed_store = self.seed_store.borrow_mut();
+        seed_store.insert(SEED_PHRASE_KEY.to_string(), backup.seed);
+        match backup.verifier {
+            Some(verifier) => seed_store.insert(VERIFIER_KEY.to_string(), verifier),
+            None => seed_store.remove(VERIFIER_KEY),
+        };
+        self.variant.set(None);
+        self.single_scrypt.set(None);
+        self.next_index.set(Some(next_index));
+        let mut child_keys_store = self.child_keys_store.borrow_mut();
+        child_keys_store.clear();
+        for account in backup.accounts {
+            child_keys_store.insert(account.lock_args.clone(), account);
+        }
+        Ok(true)
+    }
+
+    fn add(&self, account: SphincsPlusAccount) -> Result<bool, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        if self.child_keys_store.borrow().contains_key(&account.lock_args) {
+            return Ok(false);
+        }
+        self.advance_next_index([account.index])?;
+        self.child_keys_store.borrow_mut().insert(account.lock_args.clone(), account);
+        Ok(true)
+    }
+
+    fn add_all(&self, accounts: Vec<SphincsPlusAccount>) -> Result<u32, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        self.advance_next_index(accounts.iter().map(|account| account.index))?;
+        let mut child_keys_store = self.child_keys_store.borrow_mut();
+        let mut added = 0;
+        for account in accounts {
+            if !child_keys_store.contains_key(&account.lock_args) {
+                child_keys_store.insert(account.lock_args.clone(), account);
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    fn put_all(&self, accounts: Vec<SphincsPlusAccount>) -> Result<(), KeyVaultDBError> {
+        self.check_not_sealed()?;
+        let mut child_keys_store = self.child_keys_store.borrow_mut();
+        for account in accounts {
+            child_keys_store.insert(account.lock_args.clone(), account);
+        }
+        Ok(())
+    }
+
+    fn delete(&self, lock_args: &str) -> Result<(), KeyVaultDBError> {
+        self.check_not_sealed()?;
+        if !self.child_keys_store.borrow().contains_key(lock_args) {
+            return Err(KeyVaultDBError::DatabaseError(format!(
+                "Account {} not found",
+                lock_args
+            )));
+        }
+        self.advance_next_index(self.stored_indices())?;
+        self.child_keys_store.borrow_mut().remove(lock_args);
+        Ok(())
+    }
+
+    fn purge(&self) -> Result<u32, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        let purged = db::watch_only_lock_args(&self.all_accounts()?);
+        let mut child_keys_store = self.child_keys_store.borrow_mut();
+        for lock_args in &purged {
+            child_keys_store.remove(lock_args);
+        }
+        Ok(purged.len() as u32)
+    }
+
+    fn account(&self, lock_args: &str) -> Result<Option<SphincsPlusAccount>, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        Ok(self.child_keys_store.borrow().get(lock_args).cloned())
+    }
+
+    fn accounts(&self, lock_args_list: &[String]) -> Result<HashMap<String, SphincsPlusAccount>, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        let child_keys_store = self.child_keys_store.borrow();
+        Ok(lock_args_list
+            .iter()
+            .filter_map(|lock_args| Some((lock_args.clone(), child_keys_store.get(lock_args)?.clone())))
+            .collect())
+    }
+
+    fn all_accounts(&self) -> Result<Vec<SphincsPlusAccount>, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        let mut accounts: Vec<SphincsPlusAccount> = self.child_keys_store.borrow().values().cloned().collect();
+        accounts.sort_by_key(|account| account.index);
+        Ok(accounts)
+    }
+
+    fn next_index(&self) -> Result<u32, KeyVaultDBError> {
+        self.check_not_sealed()?;
+        db::next_index_after(self.next_index.get().map(f64::from), self.stored_indices())
+    }
+
+    fn reserve_attempt(&self, max: u32, cooldown_ms: u32, now: f64) -> Result<FailedAttempts, String> {
+        let attempts = reserve_unlock_attempt(&self.failed_attempts.get(), max, cooldown_ms, now)?;
+        self.failed_attempts.set(attempts);
+        Ok(attempts)
+    }
+
+    fn clear_store(&self, store_name: &str) -> Result<(), KeyVaultDBError> {
+        match store_name {
+            SEED_PHRASE_STORE => {
+                self.seed_store.borrow_mut().clear();
+                self.next_index.set(None);
+                self.variant.set(None);
+                self.single_scrypt.set(None);
+                self.sealed.set(false);
+                Ok(())
+            }
+            CHILD_KEYS_STORE => {
+                self.child_keys_store.borrow_mut().clear();
+                Ok(())
+            }
+            METADATA_STORE => {
+                self.failed_attempts.set(FailedAttempts::default());
+                Ok(())
+            }
+            _ => Err(KeyVaultDBError::DatabaseError(format!(
+                "Error getting object store {}: no such store",
+                store_name
+            ))),
+        }
+    }
+}
+
+impl Storage for InMemoryStorage {
+    fn get_encrypted_mnemonic_seed(&self) -> StorageFuture<'_, Option<CipherPayload>> {
+        let seed = self.seed_store_payload(SEED_PHRASE_KEY);
+        Box::pin(async move { seed })
+    }
+
+    fn get_sealed_encrypted_mnemonic_seed(&self) -> StorageFuture<'_, Option<CipherPayload>> {
+        let seed = self.seed_store.borrow().get(SEED_PHRASE_KEY).cloned();
+        Box::pin(async move { Ok(seed) })
+    }
+
+    fn get_password_verifier(&self) -> StorageFuture<'_, Option<CipherPayload>> {
+        let verifier = self.seed_store_payload(VERIFIER_KEY);
+        Box::pin(async move { verifier })
+    }
+
+    fn set_encrypted_mnemonic_seed(
+        &self,
+        payload: CipherPayload,
+        verifier: CipherPayload,
+        variant: SphincsVariant,
+        single_scrypt: bool,
+        overwrite: bool,
+    ) -> StorageFuture<'_, bool> {
+        let written = self.write_seed(payload, verifier, variant, single_scrypt, overwrite);
+        Box::pin(async move { written })
+    }
+
+    fn restore_encrypted_mnemonic_seed(
+        &self,
+        payload: CipherPayload,
+        variant: SphincsVariant,
+        single_scrypt: bool,
+        overwrite: bool,
+    ) -> StorageFuture<'_, bool> {
+        let written = self.restore_seed(payload, variant, single_scrypt, overwrite);
+        Box::pin(async move { written })
+    }
+
+    fn replace_seed_and_accounts(
+        &self,
+        payload: CipherPayload,
+        verifier: CipherPayload,
+        accounts: Vec<SphincsPlusAccount>,
+        read: Option<(Vec<u8>, Vec<String>)>,
+    ) -> StorageFuture<'_, ()> {
+        let replaced = self.replace(payload, verifier, accounts, read);
+        Box::pin(async move { replaced })
+    }
+
+    fn restore_vault(&self, backup: WalletBackup, overwrite: bool) -> StorageFuture<'_, bool> {
+        let restored = self.restore(backup, overwrite);
+        Box::pin(async move { restored })
+    }
+
+    fn get_stored_variant(&self) -> StorageFuture<'_, Option<SphincsVariant>> {
+        let variant = self.variant.get();
+        Box::pin(async move { Ok(variant) })
+    }
+
+    fn get_stored_single_scrypt(&self) -> StorageFuture<'_, Option<bool>> {
+        let single_scrypt = self.check_not_sealed().map(|_| self.single_scrypt.get());
+        Box::pin(async move { single_scrypt })
+    }
+
+    fn set_sealed(&self, sealed: bool) -> StorageFuture<'_, ()> {
+        self.sealed.set(sealed);
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn add_account(&self, account: SphincsPlusAccount) -> StorageFuture<'_, bool> {
+        let added = self.add(account);
+        Box::pin(async move { added })
+    }
+
+    fn add_accounts(&self, accounts: Vec<SphincsPlusAccount>) -> StorageFuture<'_, u32> {
+        let added = self.add_all(accounts);
+        Box::pin(async move { added })
+    }
+
+    fn put_accounts(&self, accounts: Vec<SphincsPlusAccount>) -> StorageFuture<'_, ()> {
+        let put = self.put_all(accounts);
+        Box::pin(async move { put })
+    }
+
+    fn delete_account<'a>(&'a self, lock_args: &'a str) -> StorageFuture<'a, ()> {
+        let deleted = self.delete(lock_args);
+        Box::pin(async move { deleted })
+    }
+
+    fn purge_watch_only(&self) -> StorageFuture<'_, u32> {
+        let purged = self.purge();
+        Box::pin(async move { purged })
+    }
+
+    fn get_account<'a>(&'a self, lock_args: &'a str) -> StorageFuture<'a, Option<SphincsPlusAccount>> {
+        let account = self.account(lock_args);
+        Box::pin(async move { account })
+    }
+
+    fn get_accounts<'a>(
+        &'a self,
+        lock_args_list: &'a [String],
+    ) -> StorageFuture<'a, HashMap<String, SphincsPlusAccount>> {
+        let accounts = self.accounts(lock_args_list);
+        Box::pin(async move { accounts })
+    }
+
+    fn get_all_accounts(&self) -> StorageFuture<'_, Vec<SphincsPlusAccount>> {
+        let accounts = self.all_accounts();
+        Box::pin(async move { accounts })
+    }
+
+    fn next_account_index(&self) -> StorageFuture<'_, u32> {
+        let next_index = self.next_index();
+        Box::pin(async move { next_index })
+    }
+
+    fn reserve_failed_attempt(
+        &self,
+        max: u32,
+        cooldown_ms: u32,
+        now: f64,
+    ) -> StorageFuture<'_, Result<FailedAttempts, String>> {
+        let reserved = self.reserve_attempt(max, cooldown_ms, now);
+        Box::pin(async move { Ok(reserved) })
+    }
+
+    fn release_failed_attempt(&self) -> StorageFuture<'_, ()> {
+        self.failed_attempts.set(release_unlock_attempt(&self.failed_attempts.get()));
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn clear_failed_attempts(&self) -> StorageFuture<'_, ()> {
+        self.failed_attempts.set(FailedAttempts::default());
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn clear_object_store<'a>(&'a self, store_name: &'a str) -> StorageFuture<'a, ()> {
+        let cleared = self.clear_store(store_name);
+        Box::pin(async move { cleared })
+    }
+}
@@ -0,0 +1,447 @@
+use super::*;
+use crate::constants::WATCH_ONLY_INDEX_BASE;
+use crate::errors::KeyVaultError;
+use crate::secure_vec::SecureVec;
+use crate::types::{DerivationParams, MnemonicLanguage};
+use crate::utilities::{
+  account_aad, accounts_checksum, decrypt, encode_vault, encrypt, seal_backup, seed_aad, seed_phrase_entropy,
+};
+use crate::KeyVault;
+use hex::encode;
+use std::task::{Context, Poll, Waker};
+
+// The in-memory futures are ready on first poll
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+  let mut cx = Context::from_waker(Waker::noop());
+  match std::pin::pin!(future).poll(&mut cx) {
+    Poll::Ready(output) => output,
+    Poll::Pending => panic!("In-memory storage future is pending"),
+  }
+}
+
+fn payload(byte: u8) -> CipherPayload {
+  CipherPayload { salt: Vec::new(), iv: Vec::new(), cipher_text: vec![byte], version: 0, kdf: None }
+}
+
+fn account(lock_args: &str, index: u32) -> SphincsPlusAccount {
+  SphincsPlusAccount {
+    index,
+    lock_args: lock_args.to_string(),
+    pri_enc: None,
+    account_id: None,
+    pub_key: None,
+    derivation: DerivationParams::default(),
+    has_history: None,
+    label: None,
+  }
+}
+
+#[test]
+fn test_seed_is_only_overwritten_when_asked() {
+  let storage = InMemoryStorage::new();
+  assert!(block_on(storage.get_encrypted_mnemonic_seed()).unwrap().is_none());
+
+  assert!(block_on(storage.set_encrypted_mnemonic_seed(payload(1), payload(2), SphincsVariant::Sha2128F, false, false)).unwrap());
+  assert!(!block_on(storage.set_encrypted_mnemonic_seed(payload(3), payload(4), SphincsVariant::Sha2128F, false, false)).unwrap());
+  assert_eq!(block_on(storage.get_encrypted_mnemonic_seed()).unwrap().unwrap().cipher_text, vec![1]);
+
+  assert!(block_on(storage.set_encrypted_mnemonic_seed(payload(3), payload(4), SphincsVariant::Sha2128F, false, true)).unwrap());
+  assert_eq!(block_on(storage.get_encrypted_mnemonic_seed()).unwrap().unwrap().cipher_text, vec![3]);
+  assert_eq!(storage.seed_store.borrow()[VERIFIER_KEY].cipher_text, vec![4]);
+}
+
+#[test]
+fn test_colliding_account_is_not_stored() {
+  let storage = InMemoryStorage::new();
+  assert!(block_on(storage.add_account(account("aa", 0))).unwrap());
+  assert!(!block_on(storage.add_account(account("aa", 1))).unwrap());
+  assert_eq!(block_on(storage.get_account("aa")).unwrap().unwrap().index, 0);
+  assert!(block_on(storage.get_account("bb")).unwrap().is_none());
+}
+
+#[test]
+fn test_clearing_a_store_leaves_the_others() {
+  let storage = InMemoryStorage::new();
+  block_on(storage.set_encrypted_mnemonic_seed(payload(1), payload(2), SphincsVariant::Sha2128F, false, false)).unwrap();
+  block_on(storage.add_account(account("aa", 0))).unwrap();
+
+  block_on(storage.clear_object_store(CHILD_KEYS_STORE)).unwrap();
+  assert!(block_on(storage.get_account("aa")).unwrap().is_none());
+  assert!(block_on(storage.get_encrypted_mnemonic_seed()).unwrap().is_some());
+
+  block_on(storage.clear_object_store(SEED_PHRASE_STORE)).unwrap();
+  assert!(block_on(storage.get_encrypted_mnemonic_seed()).unwrap().is_none());
+  block_on(storage.clear_object_store(METADATA_STORE)).unwrap();
+  assert!(matches!(
+    block_on(storage.clear_object_store("unknown_store")),
+    Err(KeyVaultDBError::DatabaseError(_))
+  ));
+}
+
+#[test]
+fn test_vault_refuses_a_colliding_account() {
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  block_on(vault.add_new_account(account("aa", 0))).unwrap();
+  let err = block_on(vault.add_new_account(account("aa", 1))).unwrap_err();
+  assert!(matches!(err, KeyVaultError::InvalidInput(message) if message == "Account aa already exists"));
+  assert_eq!(block_on(vault.storage.get_account("aa")).unwrap().unwrap().index, 0);
+}
+
+const PHRASE_48: &str = "uncover behind cargo satoshi tail answer liar success snap explain trigger brush cube mountain friend damp empty nose plastic huge pave enter wolf hazard miracle helmet trend connect bench battle diagram person uniform bike bottom negative glove vague diagram never float peace pride ivory banner say safe mesh";
+const PHRASE_72: &str = "seed boss famous insane stick below swap almost treat suit snake bracket scale kiwi unlock wood repeat cart crawl require duty call fit uncle scale color rhythm please file family example ripple flat embody library usual kind razor erode payment next alpha chapter excuse quote couple easily bus update planet raise treat critic depart river wealth science exchange chief angle wrist second thank razor supply mean nice aware rotate lady repair wine";
+
+fn stored_entropy(vault: &KeyVault, password: &[u8]) -> SecureVec {
+  let payload = block_on(vault.storage.get_encrypted_mnemonic_seed()).unwrap().unwrap();
+  decrypt(password, payload, &seed_aad()).unwrap()
+}
+
+#[test]
+fn test_vault_refuses_to_import_over_a_seed_unless_forced() {
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  let password = SecureVec::from_slice(b"password");
+  let phrase_48 = SecureVec::from_slice(PHRASE_48.as_bytes());
+  let phrase_72 = SecureVec::from_slice(PHRASE_72.as_bytes());
+  let entropy_48 = seed_phrase_entropy(PHRASE_48, MnemonicLanguage::English).unwrap();
+  let entropy_72 = seed_phrase_entropy(PHRASE_72, MnemonicLanguage::English).unwrap();
+
+  block_on(vault.store_seed_phrase(&password, &phrase_48, false, None)).unwrap();
+  let err = block_on(vault.store_seed_phrase(&password, &phrase_72, false, None)).unwrap_err();
+  assert_eq!(err.code(), "INVALID_INPUT");
+  assert_eq!(err.to_string(), "Mnemonic phrase already exists, import with force to overwrite it");
+  assert_eq!(&*stored_entropy(&vault, &password), &*entropy_48);
+
+  block_on(vault.store_seed_phrase(&password, &phrase_72, true, None)).unwrap();
+  assert_eq!(&*stored_entropy(&vault, &password), &*entropy_72);
+}
+
+#[test]
+fn test_vault_refuses_a_forced_import_over_accounts() {
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  let password = SecureVec::from_slice(b"password");
+  block_on(vault.store_seed_phrase(&password, &SecureVec::from_slice(PHRASE_48.as_bytes()), false, None)).unwrap();
+  block_on(vault.derive_next_account(&password)).unwrap();
+
+  let err = block_on(vault.store_seed_phrase(&password, &SecureVec::from_slice(PHRASE_72.as_bytes()), true, None))
+    .unwrap_err();
+  assert_eq!(err.code(), "INVALID_INPUT");
+  assert_eq!(err.to_string(), "Accounts are stored, clear the wallet before importing over its mnemonic phrase");
+  assert_eq!(&*stored_entropy(&vault, &password), &*seed_phrase_entropy(PHRASE_48, MnemonicLanguage::English).unwrap());
+
+  // Once the wallet is cleared, the forced import goes through and derivation starts over
+  block_on(KeyVault::clear_stores(&*vault.storage)).unwrap();
+  block_on(vault.store_seed_phrase(&password, &SecureVec::from_slice(PHRASE_72.as_bytes()), true, None)).unwrap();
+  assert_eq!(&*stored_entropy(&vault, &password), &*seed_phrase_entropy(PHRASE_72, MnemonicLanguage::English).unwrap());
+  block_on(vault.recover_first_accounts(&password, 1, |_, _| Ok(()))).unwrap();
+  assert_eq!(block_on(vault.storage.get_all_accounts()).unwrap()[0].index, 0);
+}
+
+#[test]
+fn test_deleted_indices_are_never_handed_out_again() {
+  let storage = InMemoryStorage::new();
+  assert_eq!(block_on(storage.next_account_index()).unwrap(), 0);
+  assert_eq!(block_on(storage.add_accounts(vec![account("aa", 0), account("bb", 1), account("cc", 2)])).unwrap(), 3);
+
+  block_on(storage.delete_account("cc")).unwrap();
+  assert_eq!(block_on(storage.next_account_index()).unwrap(), 3);
+  block_on(storage.delete_account("bb")).unwrap();
+  assert_eq!(block_on(storage.next_account_index()).unwrap(), 3);
+  assert!(matches!(block_on(storage.delete_account("bb")), Err(KeyVaultDBError::DatabaseError(_))));
+
+  // A replacement keeps the counter, clearing the seed store resets it
+  block_on(storage.replace_seed_and_accounts(payload(1), payload(2), vec![account("dd", 0)], None)).unwrap();
+  assert_eq!(block_on(storage.next_account_index()).unwrap(), 3);
+  let lock_args: Vec<String> = block_on(storage.get_all_accounts()).unwrap().into_iter().map(|a| a.lock_args).collect();
+  assert_eq!(lock_args, vec!["dd"]);
+  block_on(storage.clear_object_store(SEED_PHRASE_STORE)).unwrap();
+  assert_eq!(block_on(storage.next_account_index()).unwrap(), 1);
+}
+
+#[test]
+fn test_purge_deletes_only_watch_only_accounts() {
+  let storage = InMemoryStorage::new();
+  let signing = |lock_args: &str, index: u32| SphincsPlusAccount { pri_enc: Some(payload(1)), ..account(lock_args, index) };
+  let accounts = vec![
+    signing("aa", 0),
+    account("bb", WATCH_ONLY_INDEX_BASE),
+    signing("cc", 1),
+    account("dd", WATCH_ONLY_INDEX_BASE + 1),
+  ];
+  assert_eq!(block_on(storage.add_accounts(accounts)).unwrap(), 4);
+
+  assert_eq!(block_on(storage.purge_watch_only()).unwrap(), 2);
+  let lock_args: Vec<String> = block_on(storage.get_all_accounts()).unwrap().into_iter().map(|a| a.lock_args).collect();
+  assert_eq!(lock_args, vec!["aa", "cc"]);
+  assert_eq!(block_on(storage.next_account_index()).unwrap(), 2);
+  assert_eq!(block_on(storage.purge_watch_only()).unwrap(), 0);
+
+  block_on(storage.set_sealed(true)).unwrap();
+  assert!(matches!(block_on(storage.purge_watch_only()), Err(KeyVaultDBError::VaultSealed(_))));
+}
+
+#[test]
+fn test_sealed_storage_only_serves_the_seed_for_unsealing() {
+  let storage = InMemoryStorage::new();
+  block_on(storage.set_encrypted_mnemonic_seed(payload(1), payload(2), SphincsVariant::Sha2128F, false, false)).unwrap();
+  block_on(storage.add_account(account("aa", 0))).unwrap();
+  block_on(storage.set_sealed(true)).unwrap();
+
+  assert!(matches!(block_on(storage.get_encrypted_mnemonic_seed()), Err(KeyVaultDBError::VaultSealed(_))));
+  assert!(matches!(block_on(storage.get_all_accounts()), Err(KeyVaultDBError::VaultSealed(_))));
+  assert!(matches!(block_on(storage.add_account(account("bb", 1))), Err(KeyVaultDBError::VaultSealed(_))));
+  assert!(block_on(storage.get_sealed_encrypted_mnemonic_seed()).unwrap().is_some());
+  assert_eq!(block_on(storage.get_stored_variant()).unwrap(), Some(SphincsVariant::Sha2128F));
+
+  block_on(storage.set_sealed(false)).unwrap();
+  assert_eq!(block_on(storage.get_all_accounts()).unwrap().len(), 1);
+}
+
+#[test]
+fn test_vault_refuses_a_storage_of_another_variant() {
+  let storage = InMemoryStorage::new();
+  block_on(storage.set_encrypted_mnemonic_seed(payload(1), payload(2), SphincsVariant::Sha2256S, true, false)).unwrap();
+  assert_eq!(block_on(storage.get_stored_single_scrypt()).unwrap(), Some(true));
+
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(storage));
+  let err = block_on(vault.check_stored_variant()).unwrap_err();
+  assert_eq!(err.code(), "VARIANT_MISMATCH");
+  assert!(block_on(vault.new_key_params()).unwrap().single_scrypt);
+}
+
+#[test]
+fn test_clearing_the_vault_stores_resets_the_storage() {
+  let storage = InMemoryStorage::new();
+  block_on(storage.set_encrypted_mnemonic_seed(payload(1), payload(2), SphincsVariant::Sha2128F, false, false)).unwrap();
+  block_on(storage.add_account(account("aa", 4))).unwrap();
+  block_on(storage.reserve_failed_attempt(5, 1000, 0.0)).unwrap().unwrap();
+
+  block_on(KeyVault::clear_stores(&storage)).unwrap();
+  assert!(block_on(storage.get_encrypted_mnemonic_seed()).unwrap().is_none());
+  assert!(block_on(storage.get_all_accounts()).unwrap().is_empty());
+  assert_eq!(block_on(storage.get_stored_variant()).unwrap(), None);
+  assert_eq!(block_on(storage.next_account_index()).unwrap(), 0);
+  assert_eq!(storage.failed_attempts.get(), FailedAttempts::default());
+}
+
+#[test]
+fn test_vault_recovers_accounts_into_its_storage() {
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  let password = SecureVec::from_slice(b"password");
+  block_on(vault.store_seed_phrase(&password, &SecureVec::from_slice(PHRASE_48.as_bytes()), false, None)).unwrap();
+
+  let mut progress = Vec::new();
+  let recovered = block_on(vault.recover_first_accounts(&password, 2, |done, count| {
+    progress.push((done, count));
+    Ok(())
+  }))
+  .unwrap();
+  assert_eq!(progress, vec![(1, 2), (2, 2)]);
+
+  let entropy = seed_phrase_entropy(PHRASE_48, MnemonicLanguage::English).unwrap();
+  let stored = block_on(vault.storage.get_all_accounts()).unwrap();
+  assert_eq!(stored.len(), 2);
+  for (index, account) in stored.iter().enumerate() {
+    let (pub_key, _) = vault.derive_sphincs_key(&entropy, index as u32, account.derivation).unwrap();
+    assert_eq!(account.index, index as u32);
+    assert_eq!(account.lock_args, encode(vault.get_lock_scrip_arg(&pub_key)));
+    assert_eq!(account.lock_args, recovered[index]);
+  }
+  assert_eq!(block_on(vault.storage.next_account_index()).unwrap(), 2);
+
+  // A failing progress callback stores nothing more
+  let err = block_on(vault.recover_first_accounts(&password, 3, |_, _| Err(KeyVaultError::Internal("stop".to_string()))))
+    .unwrap_err();
+  assert_eq!(err.code(), "INTERNAL");
+  assert_eq!(block_on(vault.storage.get_all_accounts()).unwrap().len(), 2);
+}
+
+#[test]
+fn test_vault_changes_the_password_of_its_storage() {
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  let old_password = SecureVec::from_slice(b"old password");
+  let new_password = SecureVec::from_slice(b"new password");
+  block_on(vault.store_seed_phrase(&old_password, &SecureVec::from_slice(PHRASE_48.as_bytes()), false, None)).unwrap();
+  block_on(vault.recover_first_accounts(&old_password, 2, |_, _| Ok(()))).unwrap();
+
+  let err = block_on(vault.reencrypt_wallet(&new_password, &old_password, |_, _| Ok(()))).unwrap_err();
+  assert_eq!(err.code(), "WRONG_PASSWORD");
+  assert_eq!(&*stored_entropy(&vault, &old_password), &*seed_phrase_entropy(PHRASE_48, MnemonicLanguage::English).unwrap());
+
+  let mut progress = Vec::new();
+  block_on(vault.reencrypt_wallet(&old_password, &new_password, |done, total| {
+    progress.push((done, total));
+    Ok(())
+  }))
+  .unwrap();
+  assert_eq!(progress, vec![(1, 2), (2, 2)]);
+
+  let seed = block_on(vault.storage.get_encrypted_mnemonic_seed()).unwrap().unwrap();
+  assert!(decrypt(&old_password, seed, &seed_aad()).is_err());
+  assert_eq!(&*stored_entropy(&vault, &new_password), &*seed_phrase_entropy(PHRASE_48, MnemonicLanguage::English).unwrap());
+  for account in block_on(vault.storage.get_all_accounts()).unwrap() {
+    let pri_enc = account.pri_enc.clone().unwrap();
+    assert!(decrypt(&old_password, pri_enc.clone(), &account_aad(&account.lock_args)).is_err());
+    assert!(decrypt(&new_password, pri_enc, &account_aad(&account.lock_args)).is_ok());
+  }
+  // The successful decryption cleared the wrong-password attempt, so the next one is the first
+  assert_eq!(block_on(vault.storage.reserve_failed_attempt(0, 0, 0.0)).unwrap().unwrap().count, 1);
+}
+
+#[test]
+fn test_failed_restore_writes_nothing() {
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  let password = SecureVec::from_slice(b"password");
+  let entropy_48 = seed_phrase_entropy(PHRASE_48, MnemonicLanguage::English).unwrap();
+  let entropy_72 = seed_phrase_entropy(PHRASE_72, MnemonicLanguage::English).unwrap();
+  block_on(vault.store_seed_phrase(&password, &SecureVec::from_slice(PHRASE_48.as_bytes()), false, None)).unwrap();
+  let original = block_on(vault.recover_first_accounts(&password, 2, |_, _| Ok(()))).unwrap();
+
+  let accounts = block_on(vault.storage.get_all_accounts()).unwrap();
+  let mut backup = WalletBackup {
+    seed: encrypt(&password, &entropy_72, &seed_aad()).unwrap(),
+    checksum: accounts_checksum(&accounts),
+    accounts,
+    verifier: None,
+    // Passes the backup validation but fails the restore once it has started writing
+    next_index: Some(WATCH_ONLY_INDEX_BASE),
+  };
+  let sealed = serde_json::to_string(&seal_backup(&password, &backup, true).unwrap()).unwrap();
+  let err = block_on(KeyVault::restore_backup(&*vault.storage, &password, &sealed, true)).unwrap_err();
+  assert_eq!(err.code(), "DATABASE");
+
+  assert_eq!(&*stored_entropy(&vault, &password), &*entropy_48);
+  let lock_args: Vec<String> = block_on(vault.storage.get_all_accounts()).unwrap().into_iter().map(|a| a.lock_args).collect();
+  assert_eq!(lock_args, original);
+  assert_eq!(block_on(vault.storage.get_stored_variant()).unwrap(), Some(SphincsVariant::Sha2128F));
+  assert_eq!(block_on(vault.storage.next_account_index()).unwrap(), 2);
+
+  backup.next_index = None;
+  let sealed = serde_json::to_string(&seal_backup(&password, &backup, true).unwrap()).unwrap();
+  let err = block_on(KeyVault::restore_backup(&*vault.storage, &password, &sealed, false)).unwrap_err();
+  assert_eq!(err.code(), "INVALID_INPUT");
+  assert_eq!(&*stored_entropy(&vault, &password), &*entropy_48);
+
+  block_on(KeyVault::restore_backup(&*vault.storage, &password, &sealed, true)).unwrap();
+  assert_eq!(&*stored_entropy(&vault, &password), &*entropy_72);
+  assert_eq!(block_on(vault.storage.get_stored_variant()).unwrap(), None);
+  assert_eq!(block_on(vault.storage.next_account_index()).unwrap(), 2);
+}
+
+#[test]
+fn test_encrypting_entry_points_reject_an_empty_password() {
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  let empty = SecureVec::from_slice(b"");
+  let password = SecureVec::from_slice(b"password");
+  let phrase = SecureVec::from_slice(PHRASE_48.as_bytes());
+  let assert_rejected = |err: KeyVaultError| {
+    assert_eq!(err.code(), "INVALID_INPUT");
+    assert_eq!(err.to_string(), "Password must not be empty");
+  };
+
+  // init_seed_phrase and import_seed_phrase
+  assert_rejected(block_on(vault.create_seed_phrase(&empty)).unwrap_err());
+  assert_rejected(block_on(vault.store_seed_phrase(&empty, &phrase, false, None)).unwrap_err());
+  assert!(block_on(vault.storage.get_encrypted_mnemonic_seed()).unwrap().is_none());
+
+  // gen_new_account
+  block_on(vault.store_seed_phrase(&password, &phrase, false, None)).unwrap();
+  assert_rejected(block_on(vault.derive_next_account(&empty)).unwrap_err());
+  assert!(block_on(vault.storage.get_all_accounts()).unwrap().is_empty());
+
+  // change_password
+  assert_rejected(block_on(vault.reencrypt_wallet(&password, &empty, |_, _| Ok(()))).unwrap_err());
+  assert_eq!(&*stored_entropy(&vault, &password), &*seed_phrase_entropy(PHRASE_48, MnemonicLanguage::English).unwrap());
+}
+
+#[test]
+fn test_deleted_indices_stay_retired_across_password_change_and_vault_import() {
+  fn create(vault: &KeyVault, password: &SecureVec) -> u32 {
+    let lock_args = block_on(vault.derive_next_account(password)).unwrap();
+    block_on(vault.storage.get_account(&lock_args)).unwrap().unwrap().index
+  }
+  fn delete_index(vault: &KeyVault, index: u32) {
+    let accounts = block_on(vault.storage.get_all_accounts()).unwrap();
+    let lock_args = &accounts.iter().find(|a| a.index == index).unwrap().lock_args;
+    block_on(vault.storage.delete_account(lock_args)).unwrap();
+  }
+
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  let old_password = SecureVec::from_slice(b"old password");
+  let new_password = SecureVec::from_slice(b"new password");
+  block_on(vault.store_seed_phrase(&old_password, &SecureVec::from_slice(PHRASE_48.as_bytes()), false, None)).unwrap();
+  let created: Vec<u32> = (0..3).map(|_| create(&vault, &old_password)).collect();
+  assert_eq!(created, vec![0, 1, 2]);
+  delete_index(&vault, 1);
+  assert_eq!(create(&vault, &old_password), 3);
+
+  block_on(vault.reencrypt_wallet(&old_password, &new_password, |_, _| Ok(()))).unwrap();
+  delete_index(&vault, 3);
+  assert_eq!(create(&vault, &new_password), 4);
+
+  let blob = block_on(KeyVault::encode_stored_vault(&*vault.storage)).unwrap();
+  let imported = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  block_on(KeyVault::decode_into_storage(&*imported.storage, &blob, false)).unwrap();
+  delete_index(&imported, 4);
+  assert_eq!(create(&imported, &new_password), 5);
+  let indices: Vec<u32> = block_on(imported.storage.get_all_accounts()).unwrap().iter().map(|a| a.index).collect();
+  assert_eq!(indices, vec![0, 2, 5]);
+}
+
+#[test]
+fn test_rotated_accounts_keep_their_id_and_label() {
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  let password = SecureVec::from_slice(b"password");
+  block_on(vault.store_seed_phrase(&password, &SecureVec::from_slice(PHRASE_48.as_bytes()), false, None)).unwrap();
+  let old_lock_args = block_on(vault.recover_first_accounts(&password, 2, |_, _| Ok(()))).unwrap();
+
+  let mut labelled = block_on(vault.storage.get_account(&old_lock_args[1])).unwrap().unwrap();
+  labelled.account_id = Some("savings".to_string());
+  labelled.label = Some("Savings".to_string());
+  labelled.has_history = Some(true);
+  block_on(vault.storage.put_accounts(vec![labelled])).unwrap();
+  let generated_id = block_on(vault.storage.get_account(&old_lock_args[0])).unwrap().unwrap().account_id;
+  assert!(generated_id.is_some());
+
+  let new_lock_args = block_on(vault.rotate_to_fresh_seed(&password)).unwrap();
+  assert_eq!(new_lock_args.len(), 2);
+  assert!(new_lock_args.iter().all(|lock_args| !old_lock_args.contains(lock_args)));
+
+  let rotated = block_on(vault.storage.get_account(&new_lock_args[1])).unwrap().unwrap();
+  assert_eq!(rotated.index, 1);
+  assert_eq!(rotated.account_id.as_deref(), Some("savings"));
+  assert_eq!(rotated.label.as_deref(), Some("Savings"));
+  assert_eq!(rotated.has_history, Some(true));
+  let unlabelled = block_on(vault.storage.get_account(&new_lock_args[0])).unwrap().unwrap();
+  assert_eq!(unlabelled.account_id, generated_id);
+  assert_eq!((unlabelled.label, unlabelled.has_history), (None, None));
+}
+
+#[test]
+fn test_every_stored_account_gets_an_id() {
+  let vault = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  let password = SecureVec::from_slice(b"password");
+  block_on(vault.store_seed_phrase(&password, &SecureVec::from_slice(PHRASE_48.as_bytes()), false, None)).unwrap();
+  block_on(vault.recover_first_accounts(&password, 2, |_, _| Ok(()))).unwrap();
+  block_on(vault.derive_next_account(&password)).unwrap();
+
+  let ids: std::collections::HashSet<String> =
+    block_on(vault.storage.get_all_accounts()).unwrap().into_iter().map(|a| a.account_id.unwrap()).collect();
+  assert_eq!(ids.len(), 3);
+  assert!(ids.iter().all(|id| id.len() == 32));
+
+  // An export made before ids existed is completed on import
+  let mut backup = WalletBackup {
+    seed: block_on(vault.storage.get_encrypted_mnemonic_seed()).unwrap().unwrap(),
+    verifier: None,
+    accounts: block_on(vault.storage.get_all_accounts()).unwrap(),
+    checksum: String::new(),
+    next_index: None,
+  };
+  backup.accounts.iter_mut().for_each(|account| account.account_id = None);
+  backup.checksum = accounts_checksum(&backup.accounts);
+  let blob = encode_vault(&backup).unwrap();
+  let imported = KeyVault::with_storage(SphincsVariant::Sha2128F, Box::new(InMemoryStorage::new()));
+  block_on(KeyVault::decode_into_storage(&*imported.storage, &blob, false)).unwrap();
+  let accounts = block_on(imported.storage.get_all_accounts()).unwrap();
+  assert_eq!(accounts.len(), 3);
+  assert!(accounts.iter().all(|account| account.account_id.as_ref().is_some_and(|id| !ids.contains(id))));
+}
@@ -0,0 +1,518 @@
+use super::*;
+use crate::constants::{DEFAULT_DERIVATION_LOG_N, MAX_DERIVATION_LOG_N, MAX_SIGNING_CONTEXT_LENGTH, MIN_DERIVATION_LOG_N};
+use crate::storage::tests::block_on;
+use crate::storage::InMemoryStorage;
+
+/// A vault of `vault_variant` on an in-memory storage holding one account derived under `account_variant`.
+fn vault_with_account_of(vault_variant: SphincsVariant, account_variant: SphincsVariant) -> (KeyVault, String) {
+  let (lock_args, pri_key) = derived_account(account_variant, 0);
+  let storage = InMemoryStorage::new();
+  let pri_enc = encrypt(b"password", &pri_key, &account_aad(&lock_args)).unwrap();
+  let account = SphincsPlusAccount {
+    index: 0,
+    lock_args: lock_args.clone(),
+    pri_enc: Some(pri_enc),
+    account_id: None,
+    pub_key: Some(encode(&pri_key[pri_key.len() / 2..])),
+    derivation: DerivationParams::default(),
+    has_history: None,
+    label: None,
+  };
+  assert!(block_on(storage.add_account(account)).unwrap());
+  (KeyVault::with_storage(vault_variant, Box::new(storage)), lock_args)
+}
+
+#[test]
+fn test_sign_128_variant_account_with_256_variant_vault() {
+  let password = SecureVec::from_slice(b"password");
+  let (vault, lock_args) = vault_with_account_of(SphincsVariant::Sha2256S, SphincsVariant::Sha2128S);
+  let err = block_on(vault.sign_with_password(&password, &lock_args, &[7u8; 32], true, &[])).unwrap_err();
+  assert_eq!(err.code(), "VARIANT_MISMATCH");
+  assert!(err.to_string().contains("Sha2128S"));
+
+  // Same key length, told apart by the lock script arguments
+  let (vault, lock_args) = vault_with_account_of(SphincsVariant::Sha2128F, SphincsVariant::Sha2128S);
+  let err = block_on(vault.sign_with_password(&password, &lock_args, &[7u8; 32], true, &[])).unwrap_err();
+  assert_eq!(err.code(), "VARIANT_MISMATCH");
+  assert!(err.to_string().contains("configure the wallet with variant Sha2128S"));
+
+  let (vault, lock_args) = vault_with_account_of(SphincsVariant::Sha2128S, SphincsVariant::Sha2128S);
+  let signature = block_on(vault.sign_with_password(&password, &lock_args, &[7u8; 32], true, &[])).unwrap();
+  let account = block_on(vault.storage.get_account(&lock_args)).unwrap().unwrap();
+  assert!(vault.verify_for_account(&account, &[7u8; 32], &signature, &[]).unwrap());
+}
+
+fn derived_account(variant: SphincsVariant, index: u32) -> (String, SecureVec) {
+  let seed = [index as u8 + 1; 96];
+  let vault = KeyVault::with_variant(variant);
+  let (pub_key, pri_key) = vault.derive_sphincs_key(&seed, index, DerivationParams::default()).unwrap();
+  (encode(vault.get_lock_scrip_arg(&pub_key)), pri_key)
+}
+
+fn detect_all(accounts: &[(String, SecureVec)]) -> Result<SphincsVariant, String> {
+  let detected: Vec<(String, Option<SphincsVariant>)> = accounts
+    .iter()
+    .map(|(lock_args, pri_key)| (lock_args.clone(), KeyVault::detect_account_variant(pri_key, lock_args)))
+    .collect();
+  KeyVault::common_variant(&detected)
+}
+
+#[test]
+fn test_key_of_a_variant_sharing_the_key_length_is_refused() {
+  // Sha2128S and Sha2128F keys are both 64 bytes long
+  let (lock_args, pri_key) = derived_account(SphincsVariant::Sha2128S, 0);
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  assert_eq!(pri_key.len(), SphincsVariant::Sha2128F.private_key_length());
+  let err = vault.check_key_variant(&pri_key, &lock_args).unwrap_err();
+  assert_eq!(err.code(), "VARIANT_MISMATCH");
+  assert!(err.to_string().contains("configure the wallet with variant Sha2128S"));
+
+  assert!(KeyVault::with_variant(SphincsVariant::Sha2128S).check_key_variant(&pri_key, &lock_args).is_ok());
+  let unchecked = KeyVault { check_variant: false, ..KeyVault::with_variant(SphincsVariant::Sha2128F) };
+  assert!(unchecked.check_key_variant(&pri_key, &lock_args).is_ok());
+}
+
+#[test]
+fn test_unhedged_signing_is_deterministic() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let (_, pri_key) = derived_account(SphincsVariant::Sha2128F, 0);
+  let message = [7u8; 32];
+
+  let deterministic = vault.sign_message_bytes(&pri_key, &message, false, &[]).unwrap();
+  assert_eq!(deterministic, vault.sign_message_bytes(&pri_key, &message, false, &[]).unwrap());
+
+  let hedged = vault.sign_message_bytes(&pri_key, &message, true, &[]).unwrap();
+  assert_ne!(hedged, vault.sign_message_bytes(&pri_key, &message, true, &[]).unwrap());
+  assert_ne!(hedged, deterministic);
+  // Only the SPHINCS+ signature differs, not the lock config and public key in front of it
+  let prefix = 5 + slh_dsa_sha2_128f::PK_LEN;
+  assert_eq!(hedged[..prefix], deterministic[..prefix]);
+}
+
+#[test]
+fn test_signing_context_separates_domains() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let (pub_key, pri_key) = vault.derive_sphincs_key(&[5u8; 48], 0, DerivationParams::default()).unwrap();
+  let message = [7u8; 32];
+  let full = vault.sign_message_bytes(&pri_key, &message, true, b"app-a").unwrap();
+  let raw = &full[5 + slh_dsa_sha2_128f::PK_LEN..];
+
+  assert!(verify_signature_with_context(vault.variant, &pub_key, &message, raw, b"app-a").unwrap());
+  assert!(!verify_signature_with_context(vault.variant, &pub_key, &message, raw, b"app-b").unwrap());
+  assert!(!verify_signature(vault.variant, &pub_key, &message, raw).unwrap());
+
+  let too_long = [0u8; MAX_SIGNING_CONTEXT_LENGTH + 1];
+  let err = vault.sign_message_bytes(&pri_key, &message, true, &too_long).unwrap_err();
+  assert!(err.to_string().contains("Signing context must be at most 255 bytes"));
+  assert!(verify_signature_with_context(vault.variant, &pub_key, &message, raw, &too_long).is_err());
+  assert!(vault.sign_message_bytes(&pri_key, &message, true, &too_long[1..]).is_ok());
+}
+
+#[test]
+fn test_from_store_detection_on_empty_store() {
+  assert!(detect_all(&[]).unwrap_err().starts_with("No accounts stored"));
+}
+
+#[test]
+fn test_from_store_detection_on_single_variant_store() {
+  // Sha2128F and Shake128S keys have the same length; the lock_args tell them apart
+  let accounts = vec![derived_account(SphincsVariant::Shake128S, 0), derived_account(SphincsVariant::Shake128S, 1)];
+  assert_eq!(detect_all(&accounts).unwrap(), SphincsVariant::Shake128S);
+}
+
+#[test]
+fn test_from_store_detection_on_mixed_variant_store() {
+  let accounts = vec![derived_account(SphincsVariant::Sha2128F, 0), derived_account(SphincsVariant::Shake128S, 1)];
+  let err = detect_all(&accounts).unwrap_err();
+  assert_eq!(err, "Stored accounts use mixed SPHINCS+ variants: Sha2128F, Shake128S");
+}
+
+#[test]
+fn test_from_store_detection_on_foreign_account() {
+  let (_, pri_key) = derived_account(SphincsVariant::Sha2128F, 0);
+  let (other_lock_args, _) = derived_account(SphincsVariant::Sha2128F, 1);
+  assert_eq!(KeyVault::detect_account_variant(&pri_key, &other_lock_args), None);
+}
+
+#[test]
+fn test_lock_args_personalization_changes_lock_args() {
+  let pub_key = SecureVec::from_slice(&[3; 32]);
+  let mut vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let default_lock_args = vault.get_lock_scrip_arg(&pub_key);
+
+  vault.lock_args_hash_config.personalization = Some(*b"ckb-sphincs-v2..");
+  let personalized_lock_args = vault.get_lock_scrip_arg(&pub_key);
+  vault.lock_args_hash_config.personalization = Some(*b"another-lock-scr");
+  assert_ne!(personalized_lock_args, vault.get_lock_scrip_arg(&pub_key));
+  assert_ne!(personalized_lock_args, default_lock_args);
+
+  vault.lock_args_hash_config = LockArgsHashConfig { personalization: None, prefix: vec![1] };
+  assert_ne!(vault.get_lock_scrip_arg(&pub_key), default_lock_args);
+  vault.lock_args_hash_config = LockArgsHashConfig::default();
+  assert_eq!(vault.get_lock_scrip_arg(&pub_key), default_lock_args);
+}
+
+#[test]
+fn test_audit_lock_args_flags_corrupted_account() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let seed = [5u8; 64];
+  let mut accounts: Vec<SphincsPlusAccount> = (0..3)
+    .map(|index| {
+      let (pub_key, _) = vault.derive_sphincs_key(&seed, index, DerivationParams::default()).unwrap();
+      SphincsPlusAccount {
+        index,
+        lock_args: encode(vault.get_lock_scrip_arg(&pub_key)),
+        pri_enc: Some(CipherPayload { salt: Vec::new(), iv: Vec::new(), cipher_text: Vec::new(), version: 0, kdf: None }),
+        account_id: None,
+        pub_key: None,
+        derivation: DerivationParams::default(),
+        has_history: None,
+        label: None,
+      }
+    })
+    .collect();
+  let corrupted = "00".repeat(32);
+  accounts[1].lock_args = corrupted.clone();
+
+  let audit = vault.audit_range(&seed, &accounts, 0, 4).unwrap();
+  let matches: Vec<bool> = audit.iter().map(|entry| entry.matches).collect();
+  assert_eq!(matches, vec![true, false, true, false]);
+  assert_eq!(audit[1].stored.as_deref(), Some(corrupted.as_str()));
+  assert_ne!(audit[1].rederived, corrupted);
+  assert_eq!(audit[3].index, 3);
+  assert!(audit[3].stored.is_none());
+}
+
+#[test]
+fn test_backfill_only_fills_missing_pub_keys() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let seed = [5u8; 64];
+  let mut accounts: Vec<SphincsPlusAccount> = (0..3)
+    .map(|index| {
+      let (pub_key, _) = vault.derive_sphincs_key(&seed, index, DerivationParams::default()).unwrap();
+      SphincsPlusAccount {
+        index,
+        lock_args: encode(vault.get_lock_scrip_arg(&pub_key)),
+        pri_enc: Some(CipherPayload { salt: Vec::new(), iv: Vec::new(), cipher_text: Vec::new(), version: 0, kdf: None }),
+        account_id: None,
+        pub_key: Some(encode(&*pub_key)),
+        derivation: DerivationParams::default(),
+        has_history: None,
+        label: None,
+      }
+    })
+    .collect();
+  let expected = accounts[0].pub_key.clone();
+  accounts[0].pub_key = None;
+  accounts[2].pub_key = None;
+
+  let backfilled = vault.fill_missing_pub_keys(&seed, accounts.clone()).unwrap();
+  let indices: Vec<u32> = backfilled.iter().map(|account| account.index).collect();
+  assert_eq!(indices, vec![0, 2]);
+  assert_eq!(backfilled[0].pub_key, expected);
+  assert!(backfilled[1].pub_key.is_some());
+
+  accounts[2].lock_args = "00".repeat(32);
+  assert!(vault.fill_missing_pub_keys(&seed, accounts).is_err());
+}
+
+#[test]
+fn test_derivation_cost_levels_are_consistent() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let seed = [5u8; 64];
+  let default = DerivationParams::default();
+  let fast = DerivationParams { log_n: MIN_DERIVATION_LOG_N, ..DerivationParams::default() };
+
+  let (default_pub_key, _) = vault.derive_sphincs_key_uncached(&seed, 0, default).unwrap();
+  let (fast_pub_key, _) = vault.derive_sphincs_key_uncached(&seed, 0, fast).unwrap();
+  assert_eq!(&*default_pub_key, &*vault.derive_sphincs_key_uncached(&seed, 0, default).unwrap().0);
+  assert_eq!(&*fast_pub_key, &*vault.derive_sphincs_key_uncached(&seed, 0, fast).unwrap().0);
+  assert_ne!(&*default_pub_key, &*fast_pub_key);
+}
+
+#[test]
+fn test_derivation_log_n_setter_bounds() {
+  let mut vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  assert!(vault.set_derivation_log_n(MIN_DERIVATION_LOG_N).is_ok());
+  assert!(vault.set_derivation_log_n(MAX_DERIVATION_LOG_N).is_ok());
+  assert_eq!(vault.derivation_params.log_n, MAX_DERIVATION_LOG_N);
+  assert_eq!(vault.set_derivation_log_n(MAX_DERIVATION_LOG_N + 1).unwrap_err().code(), "INVALID_INPUT");
+  assert_eq!(vault.set_derivation_log_n(MIN_DERIVATION_LOG_N - 1).unwrap_err().code(), "INVALID_INPUT");
+  assert_eq!(vault.derivation_params.log_n, MAX_DERIVATION_LOG_N);
+}
+
+#[test]
+fn test_cached_derivation_follows_variant_and_cost() {
+  let mut vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  vault.set_derivation_cache_capacity(8);
+  let seed = [5u8; 64];
+  let fast = DerivationParams { log_n: MIN_DERIVATION_LOG_N, ..DerivationParams::default() };
+
+  let (sha2_pub_key, _) = vault.derive_sphincs_key(&seed, 0, fast).unwrap();
+  vault.variant = SphincsVariant::Shake128F;
+  let (shake_pub_key, _) = vault.derive_sphincs_key(&seed, 0, fast).unwrap();
+  assert_eq!(&*shake_pub_key, &*vault.derive_sphincs_key_uncached(&seed, 0, fast).unwrap().0);
+  assert_ne!(&*sha2_pub_key, &*shake_pub_key);
+
+  let slower = DerivationParams { log_n: MIN_DERIVATION_LOG_N + 1, ..fast };
+  assert_eq!(&*vault.derive_sphincs_key(&seed, 0, slower).unwrap().0, &*vault.derive_sphincs_key_uncached(&seed, 0, slower).unwrap().0);
+}
+
+#[test]
+fn test_short_seed_is_an_error_not_a_panic() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2256S);
+  let err = vault.derive_sphincs_key_uncached(&[5u8; 64], 0, DerivationParams::default()).unwrap_err();
+  assert_eq!(err, "Seed too short: need 96 bytes, got 64");
+  assert!(vault.derive_sphincs_key(&[], 0, DerivationParams::default()).is_err());
+}
+
+// Benchmark: cargo test --release bench_derivation_cost -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_derivation_cost() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let seed = [5u8; 64];
+  for log_n in [MIN_DERIVATION_LOG_N, 8, DEFAULT_DERIVATION_LOG_N, 12] {
+    let start = std::time::Instant::now();
+    for index in 0..10 {
+      vault.derive_sphincs_key_uncached(&seed, index, DerivationParams { log_n, ..DerivationParams::default() }).unwrap();
+    }
+    println!("log_n {}: {:?} per key", log_n, start.elapsed() / 10);
+  }
+}
+
+#[test]
+fn test_single_scrypt_derivation_is_opt_in() {
+  let vault = KeyVault::with_variant(SphincsVariant::Shake128F);
+  let seed = [5u8; 64];
+  let separate = DerivationParams { log_n: MIN_DERIVATION_LOG_N, single_scrypt: false };
+  let single = DerivationParams { single_scrypt: true, ..separate };
+
+  let (separate_pub_key, _) = vault.derive_sphincs_key_uncached(&seed, 0, separate).unwrap();
+  let (single_pub_key, single_pri_key) = vault.derive_sphincs_key_uncached(&seed, 0, single).unwrap();
+  assert_ne!(&*separate_pub_key, &*single_pub_key);
+  assert_eq!(&*single_pri_key, &*vault.derive_sphincs_key_uncached(&seed, 0, single).unwrap().1);
+  assert_ne!(&*single_pub_key, &*vault.derive_sphincs_key_uncached(&seed, 1, single).unwrap().0);
+  assert!(!DerivationParams::default().single_scrypt);
+}
+
+// Benchmark: cargo test --release bench_derivation_schemes -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_derivation_schemes() {
+  let vault = KeyVault::with_variant(SphincsVariant::Shake256S);
+  let seed = [5u8; 96];
+  for single_scrypt in [false, true] {
+    let params = DerivationParams { single_scrypt, ..DerivationParams::default() };
+    let start = std::time::Instant::now();
+    for index in 0..5 {
+      vault.derive_sphincs_key_uncached(&seed, index, params).unwrap();
+    }
+    println!("shake_256s, single_scrypt {}: {:?} per key", single_scrypt, start.elapsed() / 5);
+  }
+}
+
+// Benchmark: cargo test --release bench_variant_check_cost -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_variant_check_cost() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let (pub_key, pri_key) = vault.derive_sphincs_key_uncached(&[5u8; 64], 0, DerivationParams::default()).unwrap();
+  let lock_args = encode(vault.get_lock_scrip_arg(&pub_key));
+  let message = [7u8; 32];
+
+  let start = std::time::Instant::now();
+  for _ in 0..100_000 {
+    std::hint::black_box(vault.check_key_variant(std::hint::black_box(&pri_key), &lock_args)).unwrap();
+  }
+  let check = start.elapsed() / 100_000;
+  let start = std::time::Instant::now();
+  for _ in 0..10 {
+    std::hint::black_box(vault.sign_message_bytes(&pri_key, &message, true, &[]).unwrap());
+  }
+  let sign = start.elapsed() / 10;
+  println!("sha2_128f: variant check {:?}, signature {:?} ({:.1e} of a signature)", check, sign, check.as_secs_f64() / sign.as_secs_f64());
+}
+
+#[test]
+fn test_recovery_refused_for_accounts_of_another_seed() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let seed_a = [5u8; 64];
+  let seed_b = [6u8; 64];
+  let (pub_key, _) = vault.derive_sphincs_key(&seed_a, 0, DerivationParams::default()).unwrap();
+  let accounts = vec![SphincsPlusAccount {
+    index: 0,
+    lock_args: encode(vault.get_lock_scrip_arg(&pub_key)),
+    pri_enc: Some(CipherPayload { salt: Vec::new(), iv: Vec::new(), cipher_text: Vec::new(), version: 0, kdf: None }),
+    account_id: None,
+    pub_key: None,
+    derivation: DerivationParams::default(),
+    has_history: None,
+    label: None,
+  }];
+
+  assert!(vault.check_accounts_match_seed(&seed_a, &accounts).is_ok());
+  let err = vault.check_accounts_match_seed(&seed_b, &accounts).unwrap_err();
+  assert!(err.contains("does not derive from the current seed phrase"));
+  assert!(vault.check_accounts_match_seed(&seed_b, &[]).is_ok());
+}
+
+#[test]
+fn test_account_descriptor_matches_derived_addresses() {
+  let seed = [7u8; 96];
+  let vault = KeyVault::with_variant(SphincsVariant::Shake128F);
+  let (start, end) = config::descriptor_index_range(1, 3).unwrap();
+  let descriptor = vault.account_descriptor(&seed, 1, start, end, DerivationParams::default()).unwrap();
+  assert_eq!(descriptor.account, 1);
+  assert_eq!(descriptor.path_prefix, KDF_PATH_PREFIX);
+  let indices: Vec<u32> = descriptor.pubkeys.iter().map(|key| key.index).collect();
+  assert_eq!(indices, vec![3, 4, 5]);
+  for key in &descriptor.pubkeys {
+    let (pub_key, _) = vault.derive_sphincs_key(&seed, key.index, DerivationParams::default()).unwrap();
+    assert_eq!(key.pub_key, encode(&*pub_key));
+    assert_eq!(key.lock_args, encode(vault.get_lock_scrip_arg(&pub_key)));
+  }
+}
+
+#[test]
+fn test_seed_zeroized_after_last_derivation() {
+  let vault = KeyVault::with_variant(SphincsVariant::Shake128F);
+  let mut seed = SecureVec::from_slice(&[9u8; 96]);
+  let lock_args = vault
+    .derive_range_then_zeroize(&mut seed, 0..2, DerivationParams::default(), |_, pub_key, _| {
+      Ok(encode(vault.get_lock_scrip_arg(&pub_key)))
+    })
+    .unwrap();
+  assert_eq!(lock_args.len(), 2);
+  assert!(seed.iter().all(|byte| *byte == 0));
+
+  // Also zeroized when a derivation step fails
+  let mut seed = SecureVec::from_slice(&[9u8; 96]);
+  let err = vault
+    .derive_range_then_zeroize(&mut seed, 0..2, DerivationParams::default(), |index, _, _| {
+      if index == 1 { Err(KeyVaultError::Internal("boom".to_string())) } else { Ok(index) }
+    })
+    .unwrap_err();
+  assert_eq!(err.to_string(), "boom");
+  assert!(seed.iter().all(|byte| *byte == 0));
+}
+
+#[test]
+fn test_recovery_time_estimate_is_plausible() {
+  let vault = KeyVault::with_variant(SphincsVariant::Shake128F);
+  let start = std::time::Instant::now();
+  let clock = || start.elapsed().as_secs_f64() * 1000.0;
+  let cycle_ms = vault.time_recovery_cycle(clock).unwrap();
+  // Four Scrypt runs and a keygen: well above zero, and nowhere near a minute even in debug builds
+  assert!(cycle_ms > 0.0);
+  assert!(cycle_ms * 3.0 < 180_000.0);
+}
+
+#[test]
+fn test_verify_for_account_accepts_raw_and_full_signatures() {
+  let vault = KeyVault::with_variant(SphincsVariant::Shake128F);
+  let (pub_key, pri_key) = vault.derive_sphincs_key(&[3u8; 64], 0, DerivationParams::default()).unwrap();
+  let mut account = SphincsPlusAccount {
+    index: 0,
+    lock_args: encode(vault.get_lock_scrip_arg(&pub_key)),
+    pri_enc: Some(CipherPayload { salt: Vec::new(), iv: Vec::new(), cipher_text: Vec::new(), version: 0, kdf: None }),
+    account_id: None,
+    pub_key: Some(encode(&*pub_key)),
+    derivation: DerivationParams::default(),
+    has_history: None,
+    label: None,
+  };
+  let message = [0x42u8; 32];
+  let pri_key_bytes: &[u8; slh_dsa_shake_128f::SK_LEN] = pri_key.as_ref().try_into().unwrap();
+  let signing_key = slh_dsa_shake_128f::PrivateKey::try_from_bytes(pri_key_bytes).unwrap();
+  let raw = signing_key.try_sign(&message, &[], true).unwrap();
+  let full = [&[MULTISIG_RESERVED_FIELD_VALUE, REQUIRED_FIRST_N, THRESHOLD, PUBKEY_NUM][..], &[(vault.variant << 1) | 1], &pub_key[..], &raw[..]].concat();
+
+  assert!(vault.verify_for_account(&account, &message, &raw, &[]).unwrap());
+  assert!(vault.verify_for_account(&account, &message, &full, &[]).unwrap());
+  assert!(!vault.verify_for_account(&account, &[0x43u8; 32], &raw, &[]).unwrap());
+  assert!(!vault.verify_for_account(&account, &[0x43u8; 32], &full, &[]).unwrap());
+  assert!(vault.verify_for_account(&account, &message, &raw[1..], &[]).unwrap_err().starts_with("Invalid signature length"));
+
+  // A full signature carrying another account's public key is invalid for this one
+  let mut foreign = account.clone();
+  foreign.lock_args = "00".repeat(32);
+  assert!(!vault.verify_for_account(&foreign, &message, &full, &[]).unwrap());
+
+  // Raw signatures need the stored public key, full ones carry their own
+  account.pub_key = None;
+  assert!(vault.verify_for_account(&account, &message, &raw, &[]).unwrap_err().contains("no stored public key"));
+  assert!(vault.verify_for_account(&account, &message, &full, &[]).unwrap());
+}
+
+#[test]
+fn test_watch_only_account_cannot_sign() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let seed_a = [5u8; 64];
+  let seed_b = [6u8; 64];
+  let (pub_key, _) = vault.derive_sphincs_key(&seed_b, 0, DerivationParams::default()).unwrap();
+  let account = SphincsPlusAccount {
+    index: 0,
+    lock_args: encode(vault.get_lock_scrip_arg(&pub_key)),
+    pri_enc: None,
+    account_id: None,
+    pub_key: Some(encode(&*pub_key)),
+    derivation: DerivationParams::default(),
+    has_history: None,
+    label: None,
+  };
+
+  assert!(account.is_watch_only());
+  let err = block_on(KeyVault::decrypt_private_key(&InMemoryStorage::new(), &[1, 2, 3], &account)).unwrap_err();
+  assert_eq!(err.code(), "WATCH_ONLY");
+  assert!(AccountSummary::from(&account).watch_only);
+  // A watch-only key of another seed doesn't fail the seed check
+  assert!(vault.check_accounts_match_seed(&seed_a, &[account]).is_ok());
+}
+
+#[test]
+fn test_imported_account_must_match_its_key_variant_and_index() {
+  let vault = KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let seed = [9u8; 64];
+  let account_at = |vault: &KeyVault, index: u32| {
+    let (pub_key, _) = vault.derive_sphincs_key(&seed, index, DerivationParams::default()).unwrap();
+    SphincsPlusAccount {
+      index,
+      lock_args: encode(vault.get_lock_scrip_arg(&pub_key)),
+      pri_enc: Some(CipherPayload { salt: Vec::new(), iv: Vec::new(), cipher_text: Vec::new(), version: 0, kdf: None }),
+      account_id: None,
+      pub_key: Some(encode(&*pub_key)),
+      derivation: DerivationParams::default(),
+      has_history: None,
+      label: None,
+    }
+  };
+  let stored = vec![account_at(&vault, 0)];
+  assert!(vault.check_imported_account(&account_at(&vault, 1), &stored).is_ok());
+  assert!(vault.check_imported_account(&stored[0], &stored).is_ok());
+
+  let mut mismatched = account_at(&vault, 1);
+  mismatched.lock_args = "00".repeat(32);
+  assert_eq!(vault.check_imported_account(&mismatched, &stored).unwrap_err().code(), "INVALID_INPUT");
+
+  let mut no_key = account_at(&vault, 1);
+  no_key.pub_key = None;
+  assert_eq!(vault.check_imported_account(&no_key, &stored).unwrap_err().code(), "INVALID_INPUT");
+
+  // Same key length, different sign flag in the lock_args
+  let shake = account_at(&KeyVault::with_variant(SphincsVariant::Shake128F), 1);
+  assert_eq!(vault.check_imported_account(&shake, &stored).unwrap_err().code(), "INVALID_INPUT");
+  let wide = account_at(&KeyVault::with_variant(SphincsVariant::Sha2256F), 1);
+  assert_eq!(vault.check_imported_account(&wide, &stored).unwrap_err().code(), "VARIANT_MISMATCH");
+
+  let mut colliding = account_at(&vault, 1);
+  colliding.index = 0;
+  let err = vault.check_imported_account(&colliding, &stored).unwrap_err();
+  assert!(err.to_string().contains("already used"));
+
+  let mut out_of_range = account_at(&vault, 1);
+  out_of_range.index = WATCH_ONLY_INDEX_BASE;
+  assert_eq!(vault.check_imported_account(&out_of_range, &stored).unwrap_err().code(), "INVALID_INPUT");
+  out_of_range.pri_enc = None;
+  assert!(vault.check_imported_account(&out_of_range, &stored).is_ok());
+}
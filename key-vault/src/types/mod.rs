@@ -0,0 +1,800 @@
+use crate::constants::DEFAULT_DERIVATION_LOG_N;
+use bip39::Language;
+use fips205::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Shl, Shr};
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+#[cfg(test)]
+mod tests;
+
+/// Scrypt param structure.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ScryptParam {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub len: usize,
+}
+
+/// Parameters of the key derivation KDF. Stored with each account so re-derivation uses the matching ones.
+///
+/// **Fields**:
+/// - `log_n: u8` - Scrypt cost of each derivation. Defaults to `DEFAULT_DERIVATION_LOG_N`.
+/// - `single_scrypt: bool` - Whether the three SPHINCS+ seed components come from one Scrypt run stretched to all
+///   three, instead of one run each. Derives different keys. Off by default and for legacy records.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DerivationParams {
+    pub log_n: u8,
+    #[serde(default)]
+    pub single_scrypt: bool,
+}
+
+impl Default for DerivationParams {
+    fn default() -> Self {
+        DerivationParams {
+            log_n: DEFAULT_DERIVATION_LOG_N,
+            single_scrypt: false,
+        }
+    }
+}
+
+/// Represents an encrypted payload containing salt, IV, and ciphertext.
+///
+/// **Fields**:
+/// - `salt: Vec<u8>` - Salt used for key derivation with Scrypt.
+/// - `iv: Vec<u8>` - Initialization vector (nonce) for AES-GCM encryption.
+/// - `cipher_text: Vec<u8>` - Encrypted data produced by AES-GCM.
+/// - `version: u8` - Payload format version. 0 (legacy records) carries no associated data,
+///   1 is bound to its DB record through AES-GCM associated data, 2 is version 1 stored as raw bytes.
+/// - `kdf: Option<ScryptParam>` - The Scrypt parameters the key was derived with. Payloads written before they were
+///   stored have none and use `ENC_SCRYPT`.
+///
+/// The byte fields serialize as raw bytes (a `Uint8Array` in JavaScript), and deserialize from raw bytes or from the
+/// hex strings payloads before version 2 were written with.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CipherPayload {
+    #[serde(with = "payload_bytes")]
+    pub salt: Vec<u8>,
+    #[serde(with = "payload_bytes")]
+    pub iv: Vec<u8>,
+    #[serde(with = "payload_bytes")]
+    pub cipher_text: Vec<u8>,
+    #[serde(default)]
+    pub version: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<ScryptParam>,
+}
+
+/// Serde of the `CipherPayload` byte fields: written as raw bytes, read from raw bytes, a byte array or a legacy
+/// hex string.
+mod payload_bytes {
+    use serde::de::{self, SeqAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_any(PayloadBytesVisitor)
+    }
+
+    struct PayloadBytesVisitor;
+
+    impl<'de> Visitor<'de> for PayloadBytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("bytes or a hex string")
+        }
+
+        fn visit_str<E: de::Error>(self, hex: &str) -> Result<Vec<u8>, E> {
+            hex::decode(hex).map_err(|e| E::custom(format!("Hex decode error: {:?}", e)))
+        }
+
+        fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(bytes.to_vec())
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(bytes)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+/// Represents a SPHINCS+ key pair with the lock script argument (processed public key) and an encrypted private key.
+///
+/// **Fields**:
+/// - `index: u32` - The derivation index of the key.
+/// - `lock_args: String` - The lock script's argument calculated from the SPHINCS+ public key.
+/// - `pri_enc: Option<CipherPayload>` - Encrypted SPHINCS+ private key, stored as a `CipherPayload`. `None` for
+///   watch-only accounts imported from a public key.
+/// - `account_id: Option<String>` - Local id that stays stable when lock_args change (e.g. variant migration). A random one
+///   is assigned when the account is stored, and legacy records get one on the upgrade to DB_VERSION 4.
+/// - `pub_key: Option<String>` - The hex-encoded SPHINCS+ public key. Missing on legacy records until `backfill_pubkeys` runs.
+/// - `derivation: DerivationParams` - The KDF cost the key was derived with. Legacy records default to the original cost.
+/// - `has_history: Option<bool>` - Whether on-chain discovery found history for the account, `None` if never checked.
+/// - `label: Option<String>` - Optional user-editable name shown for the account, e.g. "Savings".
+/// TODO improve size
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SphincsPlusAccount {
+    pub index: u32,
+    pub lock_args: String,
+    #[serde(default)]
+    pub pri_enc: Option<CipherPayload>,
+    #[serde(default)]
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub pub_key: Option<String>,
+    #[serde(default)]
+    pub derivation: DerivationParams,
+    #[serde(default)]
+    pub has_history: Option<bool>,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl SphincsPlusAccount {
+    /// Whether the account was imported from a public key only and holds no private key.
+    pub fn is_watch_only(&self) -> bool {
+        self.pri_enc.is_none()
+    }
+}
+
+/// The whole-wallet backup: the stored records, still encrypted under the wallet password.
+///
+/// **Fields**:
+/// - `seed: CipherPayload` - The encrypted mnemonic seed phrase.
+/// - `accounts: Vec<SphincsPlusAccount>` - All accounts, sorted by index.
+/// - `checksum: String` - The accounts checksum, see `utilities::accounts_checksum`.
+/// - `verifier: Option<CipherPayload>` - The stored password verifier. Only `export_vault` carries it, as
+///   `import_backup` makes a fresh one from the password.
+/// - `next_index: Option<u32>` - The next derivation index, so indices of deleted accounts stay retired after a
+///   restore. Older exports and backups lack it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WalletBackup {
+    pub seed: CipherPayload,
+    pub accounts: Vec<SphincsPlusAccount>,
+    pub checksum: String,
+    #[serde(default)]
+    pub verifier: Option<CipherPayload>,
+    #[serde(default)]
+    pub next_index: Option<u32>,
+}
+
+/// Consecutive wrong-password attempts, persisted so reloading the page doesn't reset them.
+///
+/// **Fields**:
+/// - `count: u32` - The number of wrong passwords since the last successful unlock.
+/// - `last_failure_ms: f64` - When the last wrong password was entered, in milliseconds since the Unix epoch.
+///   0 if no clock was available then.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct FailedAttempts {
+    pub count: u32,
+    pub last_failure_ms: f64,
+}
+
+/// How lock script arguments are hashed from the lock script config, sign flag and public key.
+///
+/// **Fields**:
+/// - `personalization: Option<[u8; 16]>` - Blake2b personalization. `None` uses the one of the deployed
+///   quantum resistant lock script (`Hasher::script_args_hasher`).
+/// - `prefix: Vec<u8>` - Extra bytes hashed before the lock script config. Empty by default.
+#[derive(Debug, Clone, Default)]
+pub struct LockArgsHashConfig {
+    pub personalization: Option<[u8; 16]>,
+    pub prefix: Vec<u8>,
+}
+
+/// A supported BIP39 wordlist language, for language pickers.
+///
+/// **Fields**:
+/// - `id: u8` - The `MnemonicLanguage` id.
+/// - `name: String` - The English name, as in `MnemonicLanguage`'s `Display`.
+/// - `native_name: &'static str` - The name in the language itself.
+#[derive(Serialize, Debug)]
+pub struct LanguageInfo {
+    pub id: u8,
+    pub name: String,
+    pub native_name: &'static str,
+}
+
+/// The lock script parameters the key vault builds lock_args and witnesses with, for integrators to check
+/// against the deployed lock script.
+///
+/// **Fields**:
+/// - `code_hash: &'static str` - Code hash of the compatible lock script.
+/// - `hash_type: &'static str` - Hash type of the compatible lock script.
+/// - `multisig_reserved_field_value: u8`, `required_first_n: u8`, `threshold: u8`, `pubkey_num: u8` - The all-in-one config.
+/// - `param_ids: Vec<(SphincsVariant, u8)>` - Each variant's param id. lock_args hash `param_id << 1`,
+///   witnesses carry `(param_id << 1) | 1`.
+#[derive(Serialize, Debug)]
+pub struct LockScriptConfig {
+    pub code_hash: &'static str,
+    pub hash_type: &'static str,
+    pub multisig_reserved_field_value: u8,
+    pub required_first_n: u8,
+    pub threshold: u8,
+    pub pubkey_num: u8,
+    pub param_ids: Vec<(SphincsVariant, u8)>,
+}
+
+/// Result of re-deriving the lock script arguments of one account index, see `KeyVault::audit_lock_args`.
+///
+/// **Fields**:
+/// - `index: u32` - The account index.
+/// - `stored: Option<String>` - The lock_args stored at the index, or `None` if no account is stored there.
+/// - `rederived: String` - The lock_args re-derived from the seed at the index.
+/// - `matches: bool` - Whether the stored lock_args equal the re-derived ones.
+#[derive(Serialize, Debug)]
+pub struct LockArgsAudit {
+    pub index: u32,
+    pub stored: Option<String>,
+    pub rederived: String,
+    pub matches: bool,
+}
+
+/// Account listing that tolerates unreadable records, see `KeyVault::get_account_listing`.
+///
+/// **Fields**:
+/// - `lock_args: Vec<String>` - Lock script arguments of the readable accounts, sorted by index.
+/// - `unreadable: Vec<String>` - Deserialization errors of the records that couldn't be read.
+#[derive(Serialize, Debug)]
+pub struct AccountListing {
+    pub lock_args: Vec<String>,
+    pub unreadable: Vec<String>,
+}
+
+/// The public details of a stored account, without its encrypted private key.
+///
+/// **Fields**:
+/// - `index: u32` - The derivation index.
+/// - `lock_args: String` - The hex-encoded lock script arguments.
+/// - `account_id: Option<String>` - The app-chosen local id, if any.
+/// - `pub_key: Option<String>` - The hex-encoded SPHINCS+ public key, if stored.
+/// - `has_history: Option<bool>` - Whether on-chain discovery found history for the account, if checked.
+/// - `label: Option<String>` - The user-editable name, if any.
+/// - `watch_only: bool` - Whether the account holds no private key and so can't sign.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct AccountSummary {
+    pub index: u32,
+    pub lock_args: String,
+    pub account_id: Option<String>,
+    pub pub_key: Option<String>,
+    pub has_history: Option<bool>,
+    pub label: Option<String>,
+    pub watch_only: bool,
+}
+
+impl From<&SphincsPlusAccount> for AccountSummary {
+    fn from(account: &SphincsPlusAccount) -> Self {
+        AccountSummary {
+            index: account.index,
+            lock_args: account.lock_args.clone(),
+            account_id: account.account_id.clone(),
+            pub_key: account.pub_key.clone(),
+            has_history: account.has_history,
+            label: account.label.clone(),
+            watch_only: account.is_watch_only(),
+        }
+    }
+}
+
+/// The display details of a stored account, for account pickers.
+///
+/// **Fields**:
+/// - `index: u32` - The derivation index.
+/// - `lock_args: String` - The hex-encoded lock script arguments.
+/// - `label: Option<String>` - The user-editable name, if any.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct AccountMeta {
+    pub index: u32,
+    pub lock_args: String,
+    pub label: Option<String>,
+}
+
+impl From<&SphincsPlusAccount> for AccountMeta {
+    fn from(account: &SphincsPlusAccount) -> Self {
+        AccountMeta {
+            index: account.index,
+            lock_args: account.lock_args.clone(),
+            label: account.label.clone(),
+        }
+    }
+}
+
+/// A watch-only public descriptor of one account, see `KeyVault::account_xpub_equivalent`.
+///
+/// **Fields**:
+/// - `account: u32` - The descriptor account number.
+/// - `variant: SphincsVariant` - The SPHINCS+ variant the public keys belong to.
+/// - `path_prefix: String` - The KDF path prefix each address index is appended to.
+/// - `derivation: DerivationParams` - The derivation cost the public keys were derived with.
+/// - `pubkeys: Vec<DescriptorKey>` - The public keys of the addresses covered by the account, in index order.
+#[derive(Serialize, Debug)]
+pub struct AccountDescriptor {
+    pub account: u32,
+    pub variant: SphincsVariant,
+    pub path_prefix: String,
+    pub derivation: DerivationParams,
+    pub pubkeys: Vec<DescriptorKey>,
+}
+
+/// One address of an `AccountDescriptor`.
+///
+/// **Fields**:
+/// - `index: u32` - The derivation index.
+/// - `pub_key: String` - The hex-encoded SPHINCS+ public key.
+/// - `lock_args: String` - The hex-encoded lock script arguments of the public key.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DescriptorKey {
+    pub index: u32,
+    pub pub_key: String,
+    pub lock_args: String,
+}
+
+/// A decoded CKB address, see `Util::validate_ckb_address`.
+///
+/// **Fields**:
+/// - `network: String` - `"mainnet"` or `"testnet"`.
+/// - `code_hash: String` - The `0x`-prefixed code hash of the lock script.
+/// - `hash_type: String` - The hash type of the lock script: `"data"`, `"type"`, `"data1"` or `"data2"`.
+/// - `args: String` - The `0x`-prefixed lock script arguments.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct CkbAddress {
+    pub network: String,
+    pub code_hash: String,
+    pub hash_type: String,
+    pub args: String,
+}
+
+/// A pattern-aware password strength estimate, see `Util::password_score`.
+///
+/// **Fields**:
+/// - `score: u8` - 0 (too guessable) to 4 (very unguessable).
+/// - `guesses_log10: f64` - Base 10 logarithm of the estimated number of guesses to find the password.
+/// - `estimated_crack_time: String` - Human readable time to crack it offline against a slow hash, e.g. "3 hours".
+/// - `feedback: PasswordFeedback` - Why the password is weak and how to improve it. Empty for strong passwords.
+#[derive(Serialize, Debug)]
+pub struct PasswordScore {
+    pub score: u8,
+    pub guesses_log10: f64,
+    pub estimated_crack_time: String,
+    pub feedback: PasswordFeedback,
+}
+
+/// **Fields**:
+/// - `warning: Option<String>` - What makes the password weak, e.g. "This is a top-10 common password".
+/// - `suggestions: Vec<String>` - How to make it stronger.
+#[derive(Serialize, Debug, Default)]
+pub struct PasswordFeedback {
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+/// Key and signature sizes of a SPHINCS+ variant, see `Util::variant_sizes`.
+///
+/// **Fields**:
+/// - `sig_len: usize` - Signature length in bytes (`SIG_LEN`).
+/// - `pk_len: usize` - Public key length in bytes (`PK_LEN`).
+/// - `sk_len: usize` - Private key length in bytes (`SK_LEN`).
+#[derive(Serialize, Debug, PartialEq)]
+pub struct VariantSizes {
+    pub sig_len: usize,
+    pub pk_len: usize,
+    pub sk_len: usize,
+}
+
+/// One entry of a transaction signing plan: which account signs the script group of which input.
+///
+/// **Fields**:
+/// - `input_index: usize` - Index of an input locked by the script group to sign.
+/// - `lock_args: String` - The hex-encoded lock script's arguments of the account that signs.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SigningPlanEntry {
+    pub input_index: usize,
+    pub lock_args: String,
+}
+
+/// Non-secret structural state of the wallet, safe to paste into a bug report.
+/// Holds no mnemonic, private key, ciphertext, salt or IV - only counts, lengths and versions.
+///
+/// **Fields**:
+/// - `db_version: u8` - The IndexedDB schema version.
+/// - `account_count: usize` - Number of stored accounts.
+/// - `indices: Vec<u32>` - Sorted derivation indices of the stored accounts.
+/// - `variants_detected: Vec<KeyLengthGroup>` - Accounts grouped by private key length, with the variants each length fits.
+/// - `mnemonic_present: bool` - Whether an encrypted mnemonic seed phrase is stored.
+/// - `encryption_metadata: EncryptionMetadata` - The cipher and KDF parameters in use.
+/// - `schema_versions: BTreeMap<u8, usize>` - Number of stored payloads (seed and accounts) per `CipherPayload` version.
+#[derive(Serialize, Debug)]
+pub struct DiagnosticReport {
+    pub db_version: u8,
+    pub account_count: usize,
+    pub indices: Vec<u32>,
+    pub variants_detected: Vec<KeyLengthGroup>,
+    pub mnemonic_present: bool,
+    pub encryption_metadata: EncryptionMetadata,
+    pub schema_versions: BTreeMap<u8, usize>,
+}
+
+/// Accounts sharing a private key length. The length only reveals the security level,
+/// so `candidates` lists every variant of that length.
+#[derive(Serialize, Debug)]
+pub struct KeyLengthGroup {
+    pub private_key_length: usize,
+    pub account_count: usize,
+    pub candidates: Vec<SphincsVariant>,
+}
+
+/// Cipher and KDF parameters used to encrypt newly stored data.
+#[derive(Serialize, Debug)]
+pub struct EncryptionMetadata {
+    pub cipher: &'static str,
+    pub kdf: &'static str,
+    pub scrypt_log_n: u8,
+    pub scrypt_r: u32,
+    pub scrypt_p: u32,
+    pub salt_length: usize,
+    pub iv_length: usize,
+}
+
+/// ID of all 12 SPHINCS+ variants.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SphincsVariant {
+    Sha2128F = 48,
+    Sha2128S,
+    Sha2192F,
+    Sha2192S,
+    Sha2256F,
+    Sha2256S,
+    Shake128F,
+    Shake128S,
+    Shake192F,
+    Shake192S,
+    Shake256F,
+    Shake256S,
+}
+
+impl SphincsVariant {
+    /// All 12 SPHINCS+ variants in id order.
+    pub const ALL: [SphincsVariant; 12] = [
+        Self::Sha2128F,
+        Self::Sha2128S,
+        Self::Sha2192F,
+        Self::Sha2192S,
+        Self::Sha2256F,
+        Self::Sha2256S,
+        Self::Shake128F,
+        Self::Shake128S,
+        Self::Shake192F,
+        Self::Shake192S,
+        Self::Shake256F,
+        Self::Shake256S,
+    ];
+
+    /// BIP39 accepts entropy levels that is a multiple of 32 bytes.
+    /// Here're the entropy levels Quantum Purse chooses for all SPHINCS+ param sets that's BIP39 compatible:
+    ///     - For 128* variant, 48 bytes entropy required so 64(2*32) bytes is chosen (~ 48 words).
+    ///     - For 192* variant, 72 bytes entropy required so 96(3*32) bytes is chosen (~ 72 words).
+    ///     - For 256* variant, 96 bytes entropy required so 96(3*32) bytes is chosen (~ 72 words).
+    /// Extra bytes are truncated in case of 128* and 192* variants.
+    ///
+    pub fn bip39_compatible_entropy_size(&self) -> usize {
+        match self {
+            Self::Sha2128F | Self::Sha2128S | Self::Shake128F | Self::Shake128S => 2 * 32,
+            _ => 3 * 32,
+        }
+    }
+
+    /// SPHINCS+ private key length in bytes (`SK_LEN`) of the variant.
+    pub fn private_key_length(&self) -> usize {
+        match self {
+            Self::Sha2128F => slh_dsa_sha2_128f::SK_LEN,
+            Self::Sha2128S => slh_dsa_sha2_128s::SK_LEN,
+            Self::Sha2192F => slh_dsa_sha2_192f::SK_LEN,
+            Self::Sha2192S => slh_dsa_sha2_192s::SK_LEN,
+            Self::Sha2256F => slh_dsa_sha2_256f::SK_LEN,
+            Self::Sha2256S => slh_dsa_sha2_256s::SK_LEN,
+            Self::Shake128F => slh_dsa_shake_128f::SK_LEN,
+            Self::Shake128S => slh_dsa_shake_128s::SK_LEN,
+            Self::Shake192F => slh_dsa_shake_192f::SK_LEN,
+            Self::Shake192S => slh_dsa_shake_192s::SK_LEN,
+            Self::Shake256F => slh_dsa_shake_256f::SK_LEN,
+            Self::Shake256S => slh_dsa_shake_256s::SK_LEN,
+        }
+    }
+
+    /// SPHINCS+ public key length in bytes (`PK_LEN`) of the variant.
+    pub fn public_key_length(&self) -> usize {
+        match self {
+            Self::Sha2128F => slh_dsa_sha2_128f::PK_LEN,
+            Self::Sha2128S => slh_dsa_sha2_128s::PK_LEN,
+            Self::Sha2192F => slh_dsa_sha2_192f::PK_LEN,
+            Self::Sha2192S => slh_dsa_sha2_192s::PK_LEN,
+            Self::Sha2256F => slh_dsa_sha2_256f::PK_LEN,
+            Self::Sha2256S => slh_dsa_sha2_256s::PK_LEN,
+            Self::Shake128F => slh_dsa_shake_128f::PK_LEN,
+            Self::Shake128S => slh_dsa_shake_128s::PK_LEN,
+            Self::Shake192F => slh_dsa_shake_192f::PK_LEN,
+            Self::Shake192S => slh_dsa_shake_192s::PK_LEN,
+            Self::Shake256F => slh_dsa_shake_256f::PK_LEN,
+            Self::Shake256S => slh_dsa_shake_256s::PK_LEN,
+        }
+    }
+
+    /// SPHINCS+ signature length in bytes (`SIG_LEN`) of the variant.
+    pub fn signature_length(&self) -> usize {
+        match self {
+            Self::Sha2128F => slh_dsa_sha2_128f::SIG_LEN,
+            Self::Sha2128S => slh_dsa_sha2_128s::SIG_LEN,
+            Self::Sha2192F => slh_dsa_sha2_192f::SIG_LEN,
+            Self::Sha2192S => slh_dsa_sha2_192s::SIG_LEN,
+            Self::Sha2256F => slh_dsa_sha2_256f::SIG_LEN,
+            Self::Sha2256S => slh_dsa_sha2_256s::SIG_LEN,
+            Self::Shake128F => slh_dsa_shake_128f::SIG_LEN,
+            Self::Shake128S => slh_dsa_shake_128s::SIG_LEN,
+            Self::Shake192F => slh_dsa_shake_192f::SIG_LEN,
+            Self::Shake192S => slh_dsa_shake_192s::SIG_LEN,
+            Self::Shake256F => slh_dsa_shake_256f::SIG_LEN,
+            Self::Shake256S => slh_dsa_shake_256s::SIG_LEN,
+        }
+    }
+
+    /// Signature, public key and private key lengths of the variant.
+    pub fn sizes(&self) -> VariantSizes {
+        VariantSizes {
+            sig_len: self.signature_length(),
+            pk_len: self.public_key_length(),
+            sk_len: self.private_key_length(),
+        }
+    }
+
+    /// Length in bytes of the CKB quantum resistant lock witness field signed with this variant:
+    /// 4 bytes of multisig config, 1 byte of param id and sign flag, the optional public key, then the signature.
+    pub fn witness_lock_length(&self, include_pubkey: bool) -> usize {
+        let pub_key_length = if include_pubkey {
+            self.public_key_length()
+        } else {
+            0
+        };
+        4 + 1 + pub_key_length + self.signature_length()
+    }
+
+    /// Checks that a decrypted private key of `len` bytes belongs to this variant.
+    /// On mismatch, the error names the variants the key length does fit so the user knows which one to configure.
+    pub fn check_private_key_length(&self, len: usize) -> Result<(), String> {
+        if len == self.private_key_length() {
+            return Ok(());
+        }
+
+        let candidates: Vec<String> = Self::ALL
+            .iter()
+            .filter(|variant| variant.private_key_length() == len)
+            .map(|variant| variant.to_string())
+            .collect();
+        if candidates.is_empty() {
+            Err(format!(
+                "Invalid private key length: expected {} bytes for {}, got {}",
+                self.private_key_length(),
+                self,
+                len
+            ))
+        } else {
+            Err(format!(
+                "This account was created with a different SPHINCS+ variant; configure the wallet with variant {} to sign",
+                candidates.join(" or ")
+            ))
+        }
+    }
+
+    /// Checks that an imported seed of `entropy_len` bytes is enough for this variant.
+    /// On shortfall, the error names the variants the seed phrase does support, composing `supported_by_entropy`.
+    pub fn check_seed_entropy(&self, entropy_len: usize) -> Result<(), String> {
+        let required = self.bip39_compatible_entropy_size();
+        if entropy_len >= required {
+            return Ok(());
+        }
+
+        // BIP39 encodes every 32 bytes of entropy in 24 words
+        let word_count = entropy_len / 32 * 24;
+        let required_word_count = required / 32 * 24;
+        let supported: Vec<String> = Self::supported_by_entropy(entropy_len)
+            .iter()
+            .map(|variant| variant.to_string())
+            .collect();
+        if supported.is_empty() {
+            Err(format!(
+                "Insufficient entropy: the input seed phrase got {} bytes, but at least {} bytes are required for the chosen SPHINCS+ parameter set {}.",
+                entropy_len, required, self
+            ))
+        } else {
+            Err(format!(
+                "Your {}-word phrase supports SPHINCS+ variants {}. Select one of those, or provide a {}-word phrase for {}.",
+                word_count,
+                supported.join(", "),
+                required_word_count,
+                self
+            ))
+        }
+    }
+
+    /// Lists every variant whose BIP39 compatible entropy size is satisfied by `entropy_len` bytes.
+    pub fn supported_by_entropy(entropy_len: usize) -> Vec<SphincsVariant> {
+        Self::ALL
+            .iter()
+            .copied()
+            .filter(|variant| variant.bip39_compatible_entropy_size() <= entropy_len)
+            .collect()
+    }
+}
+
+impl fmt::Display for SphincsVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            SphincsVariant::Sha2128F => "Sha2128F",
+            SphincsVariant::Sha2128S => "Sha2128S",
+            SphincsVariant::Sha2192F => "Sha2192F",
+            SphincsVariant::Sha2192S => "Sha2192S",
+            SphincsVariant::Sha2256F => "Sha2256F",
+            SphincsVariant::Sha2256S => "Sha2256S",
+            SphincsVariant::Shake128F => "Shake128F",
+            SphincsVariant::Shake128S => "Shake128S",
+            SphincsVariant::Shake192F => "Shake192F",
+            SphincsVariant::Shake192S => "Shake192S",
+            SphincsVariant::Shake256F => "Shake256F",
+            SphincsVariant::Shake256S => "Shake256S",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl Shr<u8> for SphincsVariant {
+    type Output = u8;
+    fn shr(self, rhs: u8) -> u8 {
+        (self as u8) >> rhs
+    }
+}
+
+impl TryFrom<u8> for SphincsVariant {
+    type Error = String;
+
+    /// Maps a variant id to the variant, refusing ids outside the 12 known ones.
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        Self::ALL
+            .iter()
+            .find(|variant| **variant as u8 == id)
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "Invalid SPHINCS+ variant id {}, expected {} to {}",
+                    id,
+                    Self::ALL[0] as u8,
+                    Self::ALL[Self::ALL.len() - 1] as u8
+                )
+            })
+    }
+}
+
+impl FromStr for SphincsVariant {
+    type Err = String;
+
+    /// Parses a variant name as `Display` writes it, e.g. "Shake192F", ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        Self::ALL
+            .iter()
+            .find(|variant| variant.to_string().eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<String> = Self::ALL.iter().map(|v| v.to_string()).collect();
+                format!(
+                    "Unknown SPHINCS+ variant \"{}\", expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl Shl<u8> for SphincsVariant {
+    type Output = u8;
+    fn shl(self, rhs: u8) -> u8 {
+        (self as u8) << rhs
+    }
+}
+
+/// BIP39 wordlist languages, with stable ids.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum MnemonicLanguage {
+    English = 0,
+    SimplifiedChinese,
+    TraditionalChinese,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Portuguese,
+    Spanish,
+}
+
+impl MnemonicLanguage {
+    /// All supported BIP39 languages in id order.
+    pub const ALL: [MnemonicLanguage; 10] = [
+        Self::English,
+        Self::SimplifiedChinese,
+        Self::TraditionalChinese,
+        Self::Czech,
+        Self::French,
+        Self::Italian,
+        Self::Japanese,
+        Self::Korean,
+        Self::Portuguese,
+        Self::Spanish,
+    ];
+
+    /// The language's name in the language itself, for language pickers.
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::SimplifiedChinese => "简体中文",
+            Self::TraditionalChinese => "繁體中文",
+            Self::Czech => "Čeština",
+            Self::French => "Français",
+            Self::Italian => "Italiano",
+            Self::Japanese => "日本語",
+            Self::Korean => "한국어",
+            Self::Portuguese => "Português",
+            Self::Spanish => "Español",
+        }
+    }
+
+    /// The matching `bip39` wordlist.
+    pub fn to_bip39(&self) -> Language {
+        match self {
+            Self::English => Language::English,
+            Self::SimplifiedChinese => Language::SimplifiedChinese,
+            Self::TraditionalChinese => Language::TraditionalChinese,
+            Self::Czech => Language::Czech,
+            Self::French => Language::French,
+            Self::Italian => Language::Italian,
+            Self::Japanese => Language::Japanese,
+            Self::Korean => Language::Korean,
+            Self::Portuguese => Language::Portuguese,
+            Self::Spanish => Language::Spanish,
+        }
+    }
+}
+
+impl fmt::Display for MnemonicLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            MnemonicLanguage::English => "English",
+            MnemonicLanguage::SimplifiedChinese => "SimplifiedChinese",
+            MnemonicLanguage::TraditionalChinese => "TraditionalChinese",
+            MnemonicLanguage::Czech => "Czech",
+            MnemonicLanguage::French => "French",
+            MnemonicLanguage::Italian => "Italian",
+            MnemonicLanguage::Japanese => "Japanese",
+            MnemonicLanguage::Korean => "Korean",
+            MnemonicLanguage::Portuguese => "Portuguese",
+            MnemonicLanguage::Spanish => "Spanish",
+        };
+        write!(f, "{}", s)
+    }
+}
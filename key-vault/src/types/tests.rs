@@ -0,0 +1,129 @@
+use super::*;
+
+#[test]
+fn test_variants_supported_by_64_bytes_entropy() {
+  let supported: Vec<String> = SphincsVariant::supported_by_entropy(64)
+    .iter()
+    .map(|v| v.to_string())
+    .collect();
+  assert_eq!(supported, vec!["Sha2128F", "Sha2128S", "Shake128F", "Shake128S"]);
+}
+
+#[test]
+fn test_variants_supported_by_96_bytes_entropy() {
+  let supported = SphincsVariant::supported_by_entropy(96);
+  assert_eq!(supported.len(), SphincsVariant::ALL.len());
+}
+
+#[test]
+fn test_variants_supported_by_insufficient_entropy() {
+  assert!(SphincsVariant::supported_by_entropy(32).is_empty());
+}
+
+#[test]
+fn test_witness_lock_length_per_variant() {
+  // (variant, public key length, signature length) from FIPS 205, table 2
+  let expected = [
+    (SphincsVariant::Sha2128F, 32, 17088),
+    (SphincsVariant::Sha2128S, 32, 7856),
+    (SphincsVariant::Sha2192F, 48, 35664),
+    (SphincsVariant::Sha2192S, 48, 16224),
+    (SphincsVariant::Sha2256F, 64, 49856),
+    (SphincsVariant::Sha2256S, 64, 29792),
+    (SphincsVariant::Shake128F, 32, 17088),
+    (SphincsVariant::Shake128S, 32, 7856),
+    (SphincsVariant::Shake192F, 48, 35664),
+    (SphincsVariant::Shake192S, 48, 16224),
+    (SphincsVariant::Shake256F, 64, 49856),
+    (SphincsVariant::Shake256S, 64, 29792),
+  ];
+  for (variant, pub_key_length, signature_length) in expected {
+    assert_eq!(variant.witness_lock_length(true), 5 + pub_key_length + signature_length);
+    assert_eq!(variant.witness_lock_length(false), 5 + signature_length);
+    let sizes = variant.sizes();
+    assert_eq!((sizes.pk_len, sizes.sig_len), (pub_key_length, signature_length));
+    // FIPS 205 private keys are SK.seed, SK.prf, PK.seed and PK.root: twice the public key
+    assert_eq!(sizes.sk_len, 2 * pub_key_length);
+  }
+}
+
+#[test]
+fn test_short_seed_phrase_error_suggests_supported_variants() {
+  let err = SphincsVariant::Sha2256S.check_seed_entropy(64).unwrap_err();
+  assert_eq!(
+    err,
+    "Your 48-word phrase supports SPHINCS+ variants Sha2128F, Sha2128S, Shake128F, Shake128S. \
+     Select one of those, or provide a 72-word phrase for Sha2256S."
+  );
+  assert!(SphincsVariant::Sha2128F.check_seed_entropy(64).is_ok());
+  assert!(SphincsVariant::Sha2256S.check_seed_entropy(96).is_ok());
+}
+
+#[test]
+fn test_insufficient_entropy_without_supported_variant() {
+  let err = SphincsVariant::Sha2128F.check_seed_entropy(32).unwrap_err();
+  assert!(err.starts_with("Insufficient entropy"));
+}
+
+#[test]
+fn test_variant_from_id() {
+  for variant in SphincsVariant::ALL {
+    assert_eq!(SphincsVariant::try_from(variant as u8), Ok(variant));
+  }
+  assert_eq!(SphincsVariant::try_from(12), Err("Invalid SPHINCS+ variant id 12, expected 48 to 59".to_string()));
+  assert!(SphincsVariant::try_from(60).is_err());
+  assert!(SphincsVariant::try_from(u8::MAX).is_err());
+}
+
+#[test]
+fn test_variant_from_name() {
+  for variant in SphincsVariant::ALL {
+    assert_eq!(variant.to_string().parse::<SphincsVariant>(), Ok(variant));
+  }
+  assert_eq!("shake192f".parse::<SphincsVariant>(), Ok(SphincsVariant::Shake192F));
+  assert_eq!(" SHA2256S ".parse::<SphincsVariant>(), Ok(SphincsVariant::Sha2256S));
+  let err = "Shake512F".parse::<SphincsVariant>().unwrap_err();
+  assert!(err.starts_with("Unknown SPHINCS+ variant \"Shake512F\", expected one of Sha2128F, "));
+}
+
+#[test]
+fn test_account_history_flag_defaults_and_is_listed() {
+  let legacy = serde_json::json!({
+    "index": 0,
+    "lock_args": "aa",
+    "pri_enc": { "salt": "00", "iv": "00", "cipher_text": "00", "version": 1 }
+  });
+  let mut account: SphincsPlusAccount = serde_json::from_value(legacy).unwrap();
+  assert_eq!(account.has_history, None);
+  assert_eq!(AccountSummary::from(&account).has_history, None);
+
+  account.has_history = Some(true);
+  let stored: SphincsPlusAccount = serde_json::from_value(serde_json::to_value(&account).unwrap()).unwrap();
+  assert_eq!(stored.has_history, Some(true));
+  assert_eq!(AccountSummary::from(&stored).has_history, Some(true));
+}
+
+#[test]
+fn test_account_label_defaults_and_is_listed() {
+  let legacy = serde_json::json!({
+    "index": 3,
+    "lock_args": "aa",
+    "pri_enc": { "salt": "00", "iv": "00", "cipher_text": "00", "version": 1 }
+  });
+  let mut account: SphincsPlusAccount = serde_json::from_value(legacy).unwrap();
+  assert_eq!(account.label, None);
+
+  account.label = Some("Savings".to_string());
+  let stored: SphincsPlusAccount = serde_json::from_value(serde_json::to_value(&account).unwrap()).unwrap();
+  assert_eq!(
+    AccountMeta::from(&stored),
+    AccountMeta { index: 3, lock_args: "aa".to_string(), label: Some("Savings".to_string()) }
+  );
+  assert_eq!(AccountSummary::from(&stored).label, Some("Savings".to_string()));
+}
+
+#[test]
+fn test_legacy_derivation_params_use_separate_scrypt_runs() {
+  let params: DerivationParams = serde_json::from_value(serde_json::json!({ "log_n": 12 })).unwrap();
+  assert_eq!(params, DerivationParams { log_n: 12, single_scrypt: false });
+}
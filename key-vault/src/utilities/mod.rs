@@ -1,12 +1,45 @@
-use super::constants::{ENC_SCRYPT, IV_LENGTH, SALT_LENGTH};
-use super::types::{CipherPayload, ScryptParam};
+use super::constants::{
+    AES_GCM_TAG_LENGTH, BACKUP_FORMAT_DEFLATE, BACKUP_FORMAT_PLAIN, BACKUP_KEY, BLOB_KEY,
+    CHILD_KEYS_STORE, CIPHER_PAYLOAD_VERSION, DB_NAME, ENC_SCRYPT, FAILED_ATTEMPTS_KEY, IV_LENGTH,
+    LOCK_SCRIPT_CODE_HASH, LOCK_SCRIPT_HASH_TYPE, MAX_ACCOUNT_LABEL_LENGTH,
+    MAX_BACKUP_INFLATED_SIZE, MAX_DERIVATION_LOG_N, MAX_METADATA_KEY_LENGTH,
+    MAX_METADATA_VALUE_LENGTH, MAX_SIGNING_CONTEXT_LENGTH, MIN_DERIVATION_LOG_N,
+    MULTISIG_RESERVED_FIELD_VALUE, PUBKEY_NUM, REQUIRED_FIRST_N, SALT_LENGTH, SEED_PHRASE_KEY,
+    SEED_PHRASE_STORE, THRESHOLD, VAULT_FORMAT_VERSION, VAULT_MAGIC, VERIFIER_KEY,
+    VERIFIER_PLAINTEXT, WATCH_ONLY_INDEX_BASE,
+};
+use super::types::{
+    AccountSummary, CipherPayload, DiagnosticReport, EncryptionMetadata, FailedAttempts,
+    KeyLengthGroup, LanguageInfo, LockArgsHashConfig, LockScriptConfig, MnemonicLanguage,
+    PasswordFeedback, PasswordScore, ScryptParam, SphincsPlusAccount, SphincsVariant, WalletBackup,
+};
+use crate::config;
+use crate::errors::{DecryptError, KeyVaultError};
+use crate::kdf_cache;
+use crate::secure_string::SecureString;
 use crate::secure_vec::SecureVec;
+use crate::sphincs_verify;
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Key, Nonce,
 };
+use bip39::Mnemonic;
+use blake2b_ref::Blake2bBuilder;
+use ckb_fips205_utils::{
+    ckb_tx_message_all_from_mock_tx::{generate_ckb_tx_message_all_from_mock_tx, ScriptOrIndex},
+    Hasher,
+};
+use ckb_mock_tx_types::{MockTransaction, ReprMockTransaction};
+use fips205::{
+    traits::{SerDes, Verifier},
+    *,
+};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 use hex::{decode, encode};
 use scrypt::{scrypt, Params};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
 use zeroize::Zeroize;
 #[cfg(test)]
 mod tests;
@@ -24,6 +57,33 @@ pub fn get_random_bytes(length: usize) -> Result<SecureVec, getrandom_v03::Error
     Ok(buffer)
 }
 
+/// Generates a random local account id, for accounts the app hasn't named with `KeyVault::set_account_id`.
+///
+/// **Returns**:
+/// - `Result<String, getrandom_v03::Error>` - 16 random bytes hex-encoded on success, or an error on failure.
+pub fn new_account_id() -> Result<String, getrandom_v03::Error> {
+    Ok(encode(&*get_random_bytes(16)?))
+}
+
+/// Gives every account without a local id a random one, e.g. the accounts of a backup exported before ids existed.
+///
+/// **Parameters**:
+/// - `accounts: &mut [SphincsPlusAccount]` - The accounts to complete.
+///
+/// **Returns**:
+/// - `Result<(), getrandom_v03::Error>` - Ok on success, or an error if random generation fails.
+pub fn assign_missing_account_ids(
+    accounts: &mut [SphincsPlusAccount],
+) -> Result<(), getrandom_v03::Error> {
+    for account in accounts
+        .iter_mut()
+        .filter(|account| account.account_id.is_none())
+    {
+        account.account_id = Some(new_account_id()?);
+    }
+    Ok(())
+}
+
 /// Derive scrypt key.
 ///
 /// **Parameters**:
@@ -39,42 +99,242 @@ pub fn derive_scrypt_key(
     salt: &Vec<u8>,
     param: &ScryptParam,
 ) -> Result<SecureVec, String> {
+    let scrypt_param = Params::new(param.log_n, param.r, param.p, param.len)
+        .map_err(|e| format!("Invalid scrypt parameters: {:?}", e))?;
     let mut scrypt_key = SecureVec::new_with_length(param.len);
-    let scrypt_param = Params::new(param.log_n, param.r, param.p, param.len).unwrap();
-    scrypt(password, &salt, &scrypt_param, &mut scrypt_key)
-        .map_err(|e| format!("Scrypt error: {:?}", e))?;
+    if let Err(e) = scrypt(password, &salt, &scrypt_param, &mut scrypt_key) {
+        scrypt_key.zeroize();
+        return Err(format!("Scrypt error: {:?}", e));
+    }
     Ok(scrypt_key)
 }
 
+/// Associated data binding a ciphertext to the DB record that holds it, so a ciphertext relocated to
+/// another record (e.g. an account key moved into the mnemonic slot) fails to decrypt.
+///
+/// **Parameters**:
+/// - `store_name: &str` - The object store holding the record.
+/// - `record_key: &str` - The key of the record.
+///
+/// **Returns**:
+/// - `Vec<u8>` - The AES-GCM associated data.
+pub fn record_aad(store_name: &str, record_key: &str) -> Vec<u8> {
+    format!("{}/{}/{}", DB_NAME, store_name, record_key).into_bytes()
+}
+
+/// Associated data of the encrypted mnemonic seed phrase.
+pub fn seed_aad() -> Vec<u8> {
+    record_aad(SEED_PHRASE_STORE, SEED_PHRASE_KEY)
+}
+
+/// Associated data of the encrypted password verifier.
+pub fn verifier_aad() -> Vec<u8> {
+    record_aad(SEED_PHRASE_STORE, VERIFIER_KEY)
+}
+
+/// Associated data of an account's encrypted private key.
+pub fn account_aad(lock_args: &str) -> Vec<u8> {
+    record_aad(CHILD_KEYS_STORE, lock_args)
+}
+
+/// Associated data of an encrypted whole-wallet backup.
+pub fn backup_aad() -> Vec<u8> {
+    format!("{}/{}", DB_NAME, BACKUP_KEY).into_bytes()
+}
+
+/// Associated data of an app secret encrypted with `Util::encrypt_blob`, so it can't pass for a vault record.
+pub fn blob_aad() -> Vec<u8> {
+    format!("{}/{}", DB_NAME, BLOB_KEY).into_bytes()
+}
+
+/// Rejects an empty password, which would make encryption trivially breakable.
+///
+/// **Parameters**:
+/// - `password: &[u8]` - The password to check.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if the password is not empty, or an error message otherwise.
+pub fn check_password_not_empty(password: &[u8]) -> Result<(), String> {
+    if password.is_empty() {
+        return Err("Password must not be empty".to_string());
+    }
+    Ok(())
+}
+
+/// Estimates password strength from the patterns an attacker tries first - dictionary words, names, keyboard walks,
+/// repeats, sequences and dates - rather than from the character set alone.
+///
+/// **Parameters**:
+/// - `password: &str` - The password to score.
+///
+/// **Returns**:
+/// - `Result<PasswordScore, String>` - The estimate on success, or an error message if the password is empty.
+pub fn password_score(password: &str) -> Result<PasswordScore, String> {
+    check_password_not_empty(password.as_bytes())?;
+    let entropy = zxcvbn::zxcvbn(password, &[]).map_err(|e| e.to_string())?;
+    let feedback = entropy
+        .feedback()
+        .as_ref()
+        .map(|feedback| PasswordFeedback {
+            warning: feedback.warning().map(|warning| warning.to_string()),
+            suggestions: feedback
+                .suggestions()
+                .iter()
+                .map(|suggestion| suggestion.to_string())
+                .collect(),
+        })
+        .unwrap_or_default();
+    Ok(PasswordScore {
+        score: entropy.score(),
+        guesses_log10: entropy.guesses_log10(),
+        estimated_crack_time: entropy
+            .crack_times()
+            .offline_slow_hashing_1e4_per_second()
+            .to_string(),
+        feedback,
+    })
+}
+
+/// Encrypts the known verifier plaintext with the password, stored alongside the mnemonic phrase so the password
+/// can be checked without decrypting key material.
+///
+/// **Parameters**:
+/// - `password: &[u8]` - The password of the mnemonic phrase.
+///
+/// **Returns**:
+/// - `Result<CipherPayload, String>` - The encrypted verifier on success, or an error message on failure.
+pub fn make_password_verifier(password: &[u8]) -> Result<CipherPayload, String> {
+    encrypt(password, VERIFIER_PLAINTEXT, &verifier_aad())
+}
+
+/// Checks whether unlocking is allowed after a run of wrong passwords. Once `max` wrong passwords in a row are
+/// recorded, unlocking is refused until `cooldown_ms` have passed since the last one or the attempts are cleared.
+///
+/// **Parameters**:
+/// - `attempts: &FailedAttempts` - The recorded wrong passwords.
+/// - `max: u32` - How many wrong passwords in a row lock unlocking. 0 disables the lockout.
+/// - `cooldown_ms: u32` - How long unlocking stays locked after the last wrong password. 0 keeps it locked
+///   until the attempts are cleared.
+/// - `now: f64` - The current time in milliseconds, see `now_ms`. Without a clock (0), the cooldown can't be
+///   timed, so unlocking stays locked until the attempts are cleared.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if unlocking is allowed, or an error message saying how to resume otherwise.
+pub fn check_unlock_allowed(
+    attempts: &FailedAttempts,
+    max: u32,
+    cooldown_ms: u32,
+    now: f64,
+) -> Result<(), String> {
+    if max == 0 || attempts.count < max {
+        return Ok(());
+    }
+    if cooldown_ms > 0 && now > 0.0 && attempts.last_failure_ms > 0.0 {
+        let elapsed = (now - attempts.last_failure_ms).max(0.0);
+        if elapsed >= cooldown_ms as f64 {
+            return Ok(());
+        }
+        return Err(format!(
+            "{} wrong passwords in a row, retry in {} seconds or clear the failed attempts",
+            attempts.count,
+            ((cooldown_ms as f64 - elapsed) / 1000.0).ceil()
+        ));
+    }
+    Err(format!(
+        "{} wrong passwords in a row, clear the failed attempts to retry",
+        attempts.count
+    ))
+}
+
+/// Reserves an unlock attempt: checks that unlocking is allowed, see `check_unlock_allowed`, and counts the attempt as
+/// failed up front. A successful unlock then clears the attempts, so concurrent attempts can't all pass the check
+/// before any failure is recorded.
+///
+/// **Parameters**:
+/// - `attempts: &FailedAttempts` - The recorded wrong passwords.
+/// - `max: u32` - How many wrong passwords in a row lock unlocking. 0 disables the lockout.
+/// - `cooldown_ms: u32` - How long unlocking stays locked after the last wrong password.
+/// - `now: f64` - The current time in milliseconds, see `now_ms`.
+///
+/// **Returns**:
+/// - `Result<FailedAttempts, String>` - The attempts to record, including this one, if unlocking is allowed,
+///   or an error message saying how to resume otherwise.
+pub fn reserve_unlock_attempt(
+    attempts: &FailedAttempts,
+    max: u32,
+    cooldown_ms: u32,
+    now: f64,
+) -> Result<FailedAttempts, String> {
+    check_unlock_allowed(attempts, max, cooldown_ms, now)?;
+    Ok(FailedAttempts {
+        count: attempts.count.saturating_add(1),
+        last_failure_ms: now,
+    })
+}
+
+/// Gives back an attempt reserved by `reserve_unlock_attempt` that didn't test the password, e.g. a corrupted payload.
+///
+/// **Parameters**:
+/// - `attempts: &FailedAttempts` - The recorded attempts, including the reserved one.
+///
+/// **Returns**:
+/// - `FailedAttempts` - The attempts without the reserved one.
+pub fn release_unlock_attempt(attempts: &FailedAttempts) -> FailedAttempts {
+    FailedAttempts {
+        count: attempts.count.saturating_sub(1),
+        last_failure_ms: attempts.last_failure_ms,
+    }
+}
+
+/// Checks a password against a stored password verifier.
+///
+/// **Parameters**:
+/// - `password: &[u8]` - The password to check.
+/// - `verifier: CipherPayload` - The stored verifier, see `make_password_verifier`.
+///
+/// **Returns**:
+/// - `Result<bool, DecryptError>` - Whether the verifier decrypts with the password to the known plaintext, or the
+///   decryption error, `WrongPassword` if the password is wrong.
+pub fn check_password_verifier(password: &[u8], verifier: CipherPayload) -> Result<bool, DecryptError> {
+    decrypt(password, verifier, &verifier_aad()).map(|plaintext| &plaintext[..] == VERIFIER_PLAINTEXT)
+}
+
 /// Encrypts data using AES-GCM with a password-derived key.
 ///
 /// **Parameters**:
 /// - `password: &[u8]` - The password used to derive the encryption key.
 /// - `input: &[u8]` - The plaintext data to encrypt.
+/// - `aad: &[u8]` - Associated data authenticated along with the ciphertext, see `record_aad`.
 ///
 /// **Returns**:
 /// - `Result<CipherPayload, String>` - A `CipherPayload` containing the encrypted data, salt, and IV on success, or an error message on failure.
+///   An empty password is rejected.
 ///
 /// Warning: Proper zeroization of passwords and inputs is the responsibility of the caller.
-pub fn encrypt(password: &[u8], input: &[u8]) -> Result<CipherPayload, String> {
+pub fn encrypt(password: &[u8], input: &[u8], aad: &[u8]) -> Result<CipherPayload, String> {
+    check_password_not_empty(password)?;
     let mut salt = vec![0u8; SALT_LENGTH];
     let mut iv = vec![0u8; IV_LENGTH];
     let random_bytes = get_random_bytes(SALT_LENGTH + IV_LENGTH).map_err(|e| e.to_string())?;
     salt.copy_from_slice(&random_bytes[0..SALT_LENGTH]);
     iv.copy_from_slice(&random_bytes[SALT_LENGTH..]);
 
-    let scrypt_key = derive_scrypt_key(password, &salt, &ENC_SCRYPT)?;
+    let kdf = config::enc_scrypt();
+    // The salt is fresh, so the key cache could never hit; it only serves `decrypt`
+    let scrypt_key = derive_scrypt_key(password, &salt, &kdf)?;
     let aes_key: &Key<Aes256Gcm> = Key::<Aes256Gcm>::from_slice(&scrypt_key);
     let cipher = Aes256Gcm::new(aes_key);
     let nonce = Nonce::from_slice(&iv);
     let cipher_text = cipher
-        .encrypt(nonce, input)
+        .encrypt(nonce, Payload { msg: input, aad })
         .map_err(|e| format!("Encryption error: {:?}", e))?;
 
     Ok(CipherPayload {
-        salt: encode(salt),
-        iv: encode(iv),
-        cipher_text: encode(cipher_text),
+        salt,
+        iv,
+        cipher_text,
+        version: CIPHER_PAYLOAD_VERSION,
+        kdf: Some(kdf),
     })
 }
 
@@ -83,26 +343,1159 @@ pub fn encrypt(password: &[u8], input: &[u8]) -> Result<CipherPayload, String> {
 /// **Parameters**:
 /// - `password: &[u8]` - The password used to derive the decryption key.
 /// - `payload: CipherPayload` - The encrypted data payload containing salt, IV, and ciphertext.
+/// - `aad: &[u8]` - Associated data the payload was encrypted with. Ignored for legacy (version 0) payloads.
 ///
 /// **Returns**:
-/// - `Result<Vec<u8>, String>` - The decrypted plaintext on success, or an error message on failure.
+/// - `Result<SecureVec, DecryptError>` - The decrypted plaintext on success. A payload whose salt, IV, ciphertext
+///   or KDF parameters are malformed fails with `CorruptedPayload` before any key is derived; a well-formed payload
+///   that doesn't authenticate fails with `WrongPassword`.
 ///
 /// Warning: Proper zeroization of passwords and inputs is the responsibility of the caller.
-pub fn decrypt(password: &[u8], payload: CipherPayload) -> Result<SecureVec, String> {
-    let salt = decode(payload.salt).map_err(|e| format!("Salt decode error: {:?}", e))?;
-    let iv = decode(payload.iv).map_err(|e| format!("IV decode error: {:?}", e))?;
-    let cipher_text =
-        decode(payload.cipher_text).map_err(|e| format!("Ciphertext decode error: {:?}", e))?;
+pub fn decrypt(password: &[u8], payload: CipherPayload, aad: &[u8]) -> Result<SecureVec, DecryptError> {
+    check_payload_well_formed(&payload).map_err(DecryptError::CorruptedPayload)?;
+    // Legacy payloads were encrypted without associated data
+    let aad: &[u8] = if payload.version == 0 { &[] } else { aad };
 
-    let scrypt_key = derive_scrypt_key(password, &salt, &ENC_SCRYPT)?;
+    let kdf = payload.kdf.unwrap_or(ENC_SCRYPT);
+    let scrypt_key = kdf_cache::get_or_derive(password, &payload.salt, &kdf, || {
+        derive_scrypt_key(password, &payload.salt, &kdf)
+    })
+    .map_err(DecryptError::CorruptedPayload)?;
     let aes_key: &Key<Aes256Gcm> = Key::<Aes256Gcm>::from_slice(&scrypt_key);
     let cipher = Aes256Gcm::new(aes_key);
-    let nonce = Nonce::from_slice(&iv);
+    let nonce = Nonce::from_slice(&payload.iv);
     let mut decipher = cipher
-        .decrypt(nonce, cipher_text.as_ref())
-        .map_err(|e| format!("Decryption error: {:?}", e))?;
+        .decrypt(
+            nonce,
+            Payload {
+                msg: payload.cipher_text.as_ref(),
+                aad,
+            },
+        )
+        .map_err(|e| DecryptError::WrongPassword(format!("Decryption error: {:?}", e)))?;
 
     let secure_decipher = SecureVec::from_slice(&decipher);
     decipher.zeroize();
     Ok(secure_decipher)
 }
+
+/// Checks the field lengths of an encrypted payload against what `encrypt` produces.
+///
+/// **Parameters**:
+/// - `payload: &CipherPayload` - The payload to check.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if the payload is well-formed, or an error message naming the malformed field.
+fn check_payload_well_formed(payload: &CipherPayload) -> Result<(), String> {
+    if payload.salt.len() != SALT_LENGTH {
+        return Err(format!(
+            "Invalid salt length: expected {} bytes, got {}",
+            SALT_LENGTH,
+            payload.salt.len()
+        ));
+    }
+    if payload.iv.len() != IV_LENGTH {
+        return Err(format!(
+            "Invalid IV length: expected {} bytes, got {}",
+            IV_LENGTH,
+            payload.iv.len()
+        ));
+    }
+    if payload.cipher_text.len() < AES_GCM_TAG_LENGTH {
+        return Err(format!(
+            "Ciphertext too short: expected at least {} bytes, got {}",
+            AES_GCM_TAG_LENGTH,
+            payload.cipher_text.len()
+        ));
+    }
+    let kdf = payload.kdf.unwrap_or(ENC_SCRYPT);
+    if kdf.len != ENC_SCRYPT.len {
+        return Err(format!(
+            "Invalid key length: expected {} bytes, got {}",
+            ENC_SCRYPT.len, kdf.len
+        ));
+    }
+    // The parameters come with the payload, e.g. from an imported backup; bound them before deriving
+    config::check_scrypt_cost(kdf.log_n, kdf.r, kdf.p)
+}
+
+/// Checks an encrypted mnemonic seed blob before it replaces the stored one, see `KeyVault::set_encrypted_seed_blob`.
+/// A blob failing here could never be decrypted, and restoring it would lock the user out of their wallet.
+///
+/// **Parameters**:
+/// - `payload: &CipherPayload` - The blob.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if the blob is a payload `encrypt` could have produced, or an error message otherwise.
+pub fn check_seed_blob(payload: &CipherPayload) -> Result<(), String> {
+    if payload.version > CIPHER_PAYLOAD_VERSION {
+        return Err(format!(
+            "Unsupported payload version: {}, this app reads up to {}",
+            payload.version, CIPHER_PAYLOAD_VERSION
+        ));
+    }
+    check_payload_well_formed(payload)
+}
+
+/// Checks the length of a FIPS 205 context string, which binds a signature to an app or protocol.
+///
+/// **Parameters**:
+/// - `context: &[u8]` - The context string.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if at most `MAX_SIGNING_CONTEXT_LENGTH` bytes long, or an error message otherwise.
+pub fn check_signing_context(context: &[u8]) -> Result<(), String> {
+    if context.len() > MAX_SIGNING_CONTEXT_LENGTH {
+        return Err(format!(
+            "Signing context must be at most {} bytes long, got {}",
+            MAX_SIGNING_CONTEXT_LENGTH,
+            context.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Verifies a raw SPHINCS+ signature against a public key of the given variant.
+///
+/// **Parameters**:
+/// - `variant: SphincsVariant` - The SPHINCS+ parameter set of the key and signature.
+/// - `pub_key: &[u8]` - The SPHINCS+ public key.
+/// - `message: &[u8]` - The signed message.
+/// - `signature: &[u8]` - The raw SPHINCS+ signature.
+///
+/// **Returns**:
+/// - `Result<bool, String>` - Whether the signature is valid, or an error message if the public key is malformed.
+///   A signature that isn't exactly `SIG_LEN` bytes long is reported as invalid (`Ok(false)`), not as an error.
+pub fn verify_signature(
+    variant: SphincsVariant,
+    pub_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, String> {
+    verify_signature_with_context(variant, pub_key, message, signature, &[])
+}
+
+/// Verifies a raw SPHINCS+ signature made under a context string, see `verify_signature`. A signature only
+/// verifies under the context it was made with.
+///
+/// **Parameters**:
+/// - `variant: SphincsVariant` - The SPHINCS+ parameter set of the key and signature.
+/// - `pub_key: &[u8]` - The SPHINCS+ public key.
+/// - `message: &[u8]` - The signed message.
+/// - `signature: &[u8]` - The raw SPHINCS+ signature.
+/// - `context: &[u8]` - The FIPS 205 context string, checked with `check_signing_context`.
+///
+/// **Returns**:
+/// - `Result<bool, String>` - Whether the signature is valid, or an error message if the public key or the context
+///   is malformed.
+pub fn verify_signature_with_context(
+    variant: SphincsVariant,
+    pub_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+    context: &[u8],
+) -> Result<bool, String> {
+    check_signing_context(context)?;
+    match variant {
+        SphincsVariant::Sha2128S => sphincs_verify!(slh_dsa_sha2_128s, pub_key, message, signature, context),
+        SphincsVariant::Sha2128F => sphincs_verify!(slh_dsa_sha2_128f, pub_key, message, signature, context),
+        SphincsVariant::Sha2192S => sphincs_verify!(slh_dsa_sha2_192s, pub_key, message, signature, context),
+        SphincsVariant::Sha2192F => sphincs_verify!(slh_dsa_sha2_192f, pub_key, message, signature, context),
+        SphincsVariant::Sha2256S => sphincs_verify!(slh_dsa_sha2_256s, pub_key, message, signature, context),
+        SphincsVariant::Sha2256F => sphincs_verify!(slh_dsa_sha2_256f, pub_key, message, signature, context),
+        SphincsVariant::Shake128S => sphincs_verify!(slh_dsa_shake_128s, pub_key, message, signature, context),
+        SphincsVariant::Shake128F => sphincs_verify!(slh_dsa_shake_128f, pub_key, message, signature, context),
+        SphincsVariant::Shake192S => sphincs_verify!(slh_dsa_shake_192s, pub_key, message, signature, context),
+        SphincsVariant::Shake192F => sphincs_verify!(slh_dsa_shake_192f, pub_key, message, signature, context),
+        SphincsVariant::Shake256S => sphincs_verify!(slh_dsa_shake_256s, pub_key, message, signature, context),
+        SphincsVariant::Shake256F => sphincs_verify!(slh_dsa_shake_256f, pub_key, message, signature, context),
+    }
+}
+
+/// Verifies a raw SPHINCS+ signature and checks that the public key hashes to the expected lock_args,
+/// so a signature can't be passed off under an address its key doesn't belong to.
+///
+/// **Parameters**:
+/// - `variant: SphincsVariant` - The SPHINCS+ parameter set of the key and signature.
+/// - `pub_key: &[u8]` - The claimed SPHINCS+ public key.
+/// - `message: &[u8]` - The signed message.
+/// - `signature: &[u8]` - The raw SPHINCS+ signature.
+/// - `expected_lock_args: &str` - The hex-encoded lock script arguments, `0x` prefix optional.
+///
+/// **Returns**:
+/// - `Result<bool, String>` - True if both checks pass, or an error message if the public key is malformed.
+pub fn verify_and_check_lock_args(
+    variant: SphincsVariant,
+    pub_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+    expected_lock_args: &str,
+) -> Result<bool, String> {
+    let expected_lock_args = expected_lock_args.trim_start_matches("0x");
+    let lock_args = lock_args_from_pub_key(variant, &LockArgsHashConfig::default(), pub_key);
+    let lock_args_match = encode(lock_args).eq_ignore_ascii_case(expected_lock_args);
+    Ok(verify_signature(variant, pub_key, message, signature)? && lock_args_match)
+}
+
+/// Parses a JSON serialized CKB mock transaction.
+///
+/// **Parameters**:
+/// - `serialized_mock_tx: &[u8]` - serialized CKB mock transaction.
+///
+/// **Returns**:
+/// - `Result<MockTransaction, String>` - The mock transaction on success, or an error message on failure.
+pub fn parse_mock_tx(serialized_mock_tx: &[u8]) -> Result<MockTransaction, String> {
+    Ok(parse_repr_mock_tx(serialized_mock_tx)?.into())
+}
+
+/// Parses a JSON serialized CKB mock transaction into its JSON representation, whose scripts can be compared.
+///
+/// **Parameters**:
+/// - `serialized_mock_tx: &[u8]` - serialized CKB mock transaction.
+///
+/// **Returns**:
+/// - `Result<ReprMockTransaction, String>` - The mock transaction on success, or an error message on failure.
+pub fn parse_repr_mock_tx(serialized_mock_tx: &[u8]) -> Result<ReprMockTransaction, String> {
+    serde_json::from_slice(serialized_mock_tx).map_err(|e| format!("Deserialization error: {}", e))
+}
+
+/// Finds the lock script groups of a mock transaction: the index of the first input locked by each distinct lock
+/// script, in input order.
+///
+/// **Parameters**:
+/// - `repr_mock_tx: &ReprMockTransaction` - The CKB mock transaction.
+///
+/// **Returns**:
+/// - `Vec<usize>` - One input index per lock script group.
+pub fn lock_group_inputs(repr_mock_tx: &ReprMockTransaction) -> Vec<usize> {
+    let mut locks = Vec::new();
+    let mut group_inputs = Vec::new();
+    for (index, input) in repr_mock_tx.mock_info.inputs.iter().enumerate() {
+        if !locks.contains(&&input.output.lock) {
+            locks.push(&input.output.lock);
+            group_inputs.push(index);
+        }
+    }
+    group_inputs
+}
+
+/// Checks that an input of a mock transaction is locked by an account's lock script, so an account is never asked
+/// to sign the script group of another lock.
+///
+/// **Parameters**:
+/// - `repr_mock_tx: &ReprMockTransaction` - The CKB mock transaction.
+/// - `input_index: usize` - Index of the input to be signed.
+/// - `lock_args: &str` - The hex-encoded lock script's arguments of the signing account.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if the input's lock script arguments are `lock_args`, or an error message otherwise.
+pub fn check_input_lock_args(
+    repr_mock_tx: &ReprMockTransaction,
+    input_index: usize,
+    lock_args: &str,
+) -> Result<(), String> {
+    let inputs = &repr_mock_tx.mock_info.inputs;
+    let input = inputs.get(input_index).ok_or_else(|| {
+        format!(
+            "Input index {} out of range: the transaction has {} inputs",
+            input_index,
+            inputs.len()
+        )
+    })?;
+    let input_lock_args = encode(input.output.lock.args.as_bytes());
+    if !input_lock_args.eq_ignore_ascii_case(lock_args.trim_start_matches("0x")) {
+        return Err(format!(
+            "Input {} is not locked by account {}",
+            input_index, lock_args
+        ));
+    }
+    Ok(())
+}
+
+/// Computes the CKB transaction message all digest for the script group of an input.
+/// https://github.com/xxuejie/rfcs/blob/cighash-all/rfcs/0000-ckb-tx-message-all/0000-ckb-tx-message-all.md.
+///
+/// **Parameters**:
+/// - `mock_tx: &MockTransaction` - The CKB mock transaction.
+/// - `input_index: usize` - Index of an input locked by the script group being signed.
+///
+/// **Returns**:
+/// - `Result<[u8; 32], String>` - The message digest on success, or an error message on failure.
+pub fn ckb_tx_message_all(
+    mock_tx: &MockTransaction,
+    input_index: usize,
+) -> Result<[u8; 32], String> {
+    let input_count = mock_tx.mock_info.inputs.len();
+    if input_index >= input_count {
+        return Err(format!(
+            "Input index {} out of range: the transaction has {} inputs",
+            input_index, input_count
+        ));
+    }
+    let mut message_hasher = Hasher::message_hasher();
+    generate_ckb_tx_message_all_from_mock_tx(
+        mock_tx,
+        ScriptOrIndex::Index(input_index),
+        &mut message_hasher,
+    )
+    .map_err(|e| format!("CKB_TX_MESSAGE_ALL error: {:?}", e))?;
+    Ok(message_hasher.hash())
+}
+
+/// Fingerprints an account set: SHA-256 over the (index, lock_args) pairs sorted by index.
+/// Two wallets holding the same accounts produce the same checksum, whatever the storage order.
+///
+/// **Parameters**:
+/// - `accounts: &[SphincsPlusAccount]` - The account set.
+///
+/// **Returns**:
+/// - `String` - The hex-encoded checksum.
+pub fn accounts_checksum(accounts: &[SphincsPlusAccount]) -> String {
+    let mut entries: Vec<(u32, &str)> = accounts
+        .iter()
+        .map(|account| (account.index, account.lock_args.as_str()))
+        .collect();
+    entries.sort();
+
+    let mut hasher = Sha256::new();
+    for (index, lock_args) in entries {
+        hasher.update(index.to_be_bytes());
+        hasher.update((lock_args.len() as u32).to_be_bytes());
+        hasher.update(lock_args.as_bytes());
+    }
+    encode(hasher.finalize())
+}
+
+/// Hashes lock script arguments according to a `LockArgsHashConfig`.
+///
+/// **Parameters**:
+/// - `config: &LockArgsHashConfig` - The personalization and prefix to hash with.
+/// - `fields: &[&[u8]]` - The fields hashed after the prefix, in order.
+///
+/// **Returns**:
+/// - `[u8; 32]` - The lock script arguments.
+pub fn lock_args_hash(config: &LockArgsHashConfig, fields: &[&[u8]]) -> [u8; 32] {
+    match config.personalization {
+        None => {
+            let mut hasher = Hasher::script_args_hasher();
+            hasher.update(&config.prefix);
+            for field in fields {
+                hasher.update(field);
+            }
+            hasher.hash()
+        }
+        Some(personalization) => {
+            let mut hasher = Blake2bBuilder::new(32).personal(&personalization).build();
+            hasher.update(&config.prefix);
+            for field in fields {
+                hasher.update(field);
+            }
+            let mut lock_args = [0u8; 32];
+            hasher.finalize(&mut lock_args);
+            lock_args
+        }
+    }
+}
+
+/// Computes the lock script arguments of a SPHINCS+ public key: the hash of the all-in-one config,
+/// the variant's sign flag and the public key.
+///
+/// **Parameters**:
+/// - `variant: SphincsVariant` - The SPHINCS+ parameter set of the key.
+/// - `config: &LockArgsHashConfig` - The hash personalization and prefix.
+/// - `pub_key: &[u8]` - The SPHINCS+ public key.
+///
+/// **Returns**:
+/// - `[u8; 32]` - The lock script arguments.
+pub fn lock_args_from_pub_key(
+    variant: SphincsVariant,
+    config: &LockArgsHashConfig,
+    pub_key: &[u8],
+) -> [u8; 32] {
+    let all_in_one_config: [u8; 4] = [
+        MULTISIG_RESERVED_FIELD_VALUE,
+        REQUIRED_FIRST_N,
+        THRESHOLD,
+        PUBKEY_NUM,
+    ];
+    let sign_flag: u8 = variant << 1;
+    lock_args_hash(config, &[&all_in_one_config, &[sign_flag], pub_key])
+}
+
+/// Computes the hex-encoded lock script arguments of an externally supplied SPHINCS+ public key, e.g. a co-signer's,
+/// with the default lock script hash.
+///
+/// **Parameters**:
+/// - `variant: SphincsVariant` - The SPHINCS+ parameter set of the key.
+/// - `pub_key: &[u8]` - The SPHINCS+ public key.
+///
+/// **Returns**:
+/// - `Result<String, String>` - The hex-encoded lock script arguments on success, or an error message if the key
+///   length doesn't fit the variant.
+pub fn compute_lock_args(variant: SphincsVariant, pub_key: &[u8]) -> Result<String, String> {
+    let pk_len = variant.public_key_length();
+    if pub_key.len() != pk_len {
+        return Err(format!(
+            "Invalid public key length: expected {} bytes for {}, got {}",
+            pk_len,
+            variant,
+            pub_key.len()
+        ));
+    }
+    Ok(encode(lock_args_from_pub_key(
+        variant,
+        &LockArgsHashConfig::default(),
+        pub_key,
+    )))
+}
+
+/// Computes the lock script arguments of an m-of-n multisig over SPHINCS+ public keys: the hash of the all-in-one
+/// config, then the sign flag and public key of every signer, in order.
+///
+/// **Parameters**:
+/// - `variant: SphincsVariant` - The SPHINCS+ parameter set of all keys.
+/// - `config: &LockArgsHashConfig` - The hash personalization and prefix.
+/// - `pub_keys: &[Vec<u8>]` - The SPHINCS+ public keys of the signers.
+/// - `require_first_n: u8` - How many of the first keys must sign.
+/// - `threshold: u8` - How many signatures unlock the script.
+///
+/// **Returns**:
+/// - `Result<[u8; 32], String>` - The lock script arguments on success, or an error message if the multisig
+///   parameters are inconsistent or a key has the wrong length.
+pub fn multisig_lock_args(
+    variant: SphincsVariant,
+    config: &LockArgsHashConfig,
+    pub_keys: &[Vec<u8>],
+    require_first_n: u8,
+    threshold: u8,
+) -> Result<[u8; 32], String> {
+    let preimage = lock_args_preimage(variant, pub_keys, require_first_n, threshold)?;
+    Ok(lock_args_hash(config, &[&preimage]))
+}
+
+/// Builds the bytes hashed into the lock script arguments of an m-of-n multisig over SPHINCS+ public keys, after
+/// any `LockArgsHashConfig` prefix. The layout is:
+/// - 1 byte: the reserved field, always 0x80;
+/// - 1 byte: `require_first_n`;
+/// - 1 byte: `threshold`;
+/// - 1 byte: the number of public keys;
+/// - per public key, in order: 1 byte sign flag (`variant << 1`), then the public key.
+///
+/// **Parameters**:
+/// - `variant: SphincsVariant` - The SPHINCS+ parameter set of all keys.
+/// - `pub_keys: &[Vec<u8>]` - The SPHINCS+ public keys of the signers.
+/// - `require_first_n: u8` - How many of the first keys must sign.
+/// - `threshold: u8` - How many signatures unlock the script.
+///
+/// **Returns**:
+/// - `Result<Vec<u8>, String>` - The preimage on success, or an error message if the multisig parameters are
+///   inconsistent or a key has the wrong length.
+pub fn lock_args_preimage(
+    variant: SphincsVariant,
+    pub_keys: &[Vec<u8>],
+    require_first_n: u8,
+    threshold: u8,
+) -> Result<Vec<u8>, String> {
+    let pubkey_num = u8::try_from(pub_keys.len())
+        .map_err(|_| format!("Too many public keys: {}, at most 255", pub_keys.len()))?;
+    if threshold == 0 || threshold > pubkey_num {
+        return Err(format!(
+            "Threshold must be between 1 and the number of public keys ({}), got {}",
+            pubkey_num, threshold
+        ));
+    }
+    if require_first_n > threshold {
+        return Err(format!(
+            "require_first_n ({}) must not exceed the threshold ({})",
+            require_first_n, threshold
+        ));
+    }
+    let pk_len = variant.public_key_length();
+    if let Some(position) = pub_keys.iter().position(|key| key.len() != pk_len) {
+        return Err(format!(
+            "Invalid public key length at position {}: expected {} bytes, got {}",
+            position,
+            pk_len,
+            pub_keys[position].len()
+        ));
+    }
+
+    let all_in_one_config: [u8; 4] = [
+        MULTISIG_RESERVED_FIELD_VALUE,
+        require_first_n,
+        threshold,
+        pubkey_num,
+    ];
+    let sign_flag: u8 = variant << 1;
+    let mut preimage = Vec::with_capacity(all_in_one_config.len() + pub_keys.len() * (1 + pk_len));
+    preimage.extend_from_slice(&all_in_one_config);
+    for pub_key in pub_keys {
+        preimage.push(sign_flag);
+        preimage.extend_from_slice(pub_key);
+    }
+    Ok(preimage)
+}
+
+/// Lines found accounts up with the lock script arguments they were requested by.
+///
+/// **Parameters**:
+/// - `requested: &[String]` - The requested lock script arguments.
+/// - `found: &HashMap<String, SphincsPlusAccount>` - The accounts found, by lock_args.
+///
+/// **Returns**:
+/// - `Vec<Option<AccountSummary>>` - One entry per requested lock_args, in order, `None` where no account was found.
+pub fn accounts_in_requested_order(
+    requested: &[String],
+    found: &HashMap<String, SphincsPlusAccount>,
+) -> Vec<Option<AccountSummary>> {
+    requested
+        .iter()
+        .map(|lock_args| found.get(lock_args).map(AccountSummary::from))
+        .collect()
+}
+
+/// Picks the accounts of every requested lock script argument, in order, failing if any is missing.
+///
+/// **Parameters**:
+/// - `requested: &[String]` - The requested lock script arguments.
+/// - `found: &HashMap<String, SphincsPlusAccount>` - The accounts found, by lock_args.
+///
+/// **Returns**:
+/// - `Result<Vec<&SphincsPlusAccount>, String>` - One account per requested lock_args on success, or an error
+///   message naming the missing lock_args.
+pub fn require_accounts<'a>(
+    requested: &[String],
+    found: &'a HashMap<String, SphincsPlusAccount>,
+) -> Result<Vec<&'a SphincsPlusAccount>, String> {
+    let missing: Vec<&str> = requested
+        .iter()
+        .filter(|lock_args| !found.contains_key(*lock_args))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Accounts not found: {}", missing.join(", ")));
+    }
+    Ok(requested
+        .iter()
+        .map(|lock_args| &found[lock_args])
+        .collect())
+}
+
+/// Collects the derivation indices held by an account set.
+///
+/// **Parameters**:
+/// - `accounts: &[SphincsPlusAccount]` - The account set.
+///
+/// **Returns**:
+/// - `Vec<u32>` - The distinct indices in ascending order.
+pub fn stored_indices(accounts: &[SphincsPlusAccount]) -> Vec<u32> {
+    let mut indices: Vec<u32> = accounts.iter().map(|account| account.index).collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+/// Validates the size of an encrypted metadata entry, and that its key isn't the vault's own `FAILED_ATTEMPTS_KEY`.
+///
+/// **Parameters**:
+/// - `key: &str` - The metadata key.
+/// - `value_len: usize` - The plaintext value length in bytes.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if within limits, or an error message otherwise.
+pub fn check_metadata_entry(key: &str, value_len: usize) -> Result<(), String> {
+    if key.is_empty() || key.len() > MAX_METADATA_KEY_LENGTH {
+        return Err(format!(
+            "Metadata key must be 1 to {} bytes long, got {}",
+            MAX_METADATA_KEY_LENGTH,
+            key.len()
+        ));
+    }
+    if key == FAILED_ATTEMPTS_KEY {
+        return Err(format!("Metadata key {} is reserved", key));
+    }
+    if value_len > MAX_METADATA_VALUE_LENGTH {
+        return Err(format!(
+            "Metadata value must be at most {} bytes long, got {}",
+            MAX_METADATA_VALUE_LENGTH, value_len
+        ));
+    }
+    Ok(())
+}
+
+/// Normalizes a user-entered account label: surrounding whitespace is trimmed and a blank label clears it.
+///
+/// **Parameters**:
+/// - `label: &str` - The label as entered.
+///
+/// **Returns**:
+/// - `Result<Option<String>, String>` - The label to store, `None` to clear it, or an error message if it is longer
+///   than `MAX_ACCOUNT_LABEL_LENGTH` characters.
+pub fn normalize_account_label(label: &str) -> Result<Option<String>, String> {
+    let label = label.trim();
+    let length = label.chars().count();
+    if length > MAX_ACCOUNT_LABEL_LENGTH {
+        return Err(format!(
+            "Account label must be at most {} characters long, got {}",
+            MAX_ACCOUNT_LABEL_LENGTH, length
+        ));
+    }
+    Ok((!label.is_empty()).then(|| label.to_string()))
+}
+
+/// Re-encrypts every account's private key from `old_password` to `new_password` in memory, leaving watch-only
+/// accounts as they are. Decrypted keys are zeroized as soon as they are re-encrypted.
+///
+/// **Parameters**:
+/// - `accounts: &mut [SphincsPlusAccount]` - The accounts to re-encrypt in place.
+/// - `old_password: &[u8]` - The current password.
+/// - `new_password: &[u8]` - The new password.
+/// - `on_progress: F` - Called with `(done, total)` after each account; an error aborts the re-encryption.
+///
+/// **Returns**:
+/// - `Result<(), KeyVaultError>` - Ok on success, `InvalidInput` if `new_password` is empty, the decryption error
+///   of the first account that doesn't open (`WrongPassword` or `CorruptedPayload`), or the first encryption or
+///   progress callback error.
+///
+/// Warning: Proper zeroization of passwords is the responsibility of the caller.
+pub fn reencrypt_accounts<F>(
+    accounts: &mut [SphincsPlusAccount],
+    old_password: &[u8],
+    new_password: &[u8],
+    mut on_progress: F,
+) -> Result<(), KeyVaultError>
+where
+    F: FnMut(u32, u32) -> Result<(), KeyVaultError>,
+{
+    check_password_not_empty(new_password).map_err(KeyVaultError::InvalidInput)?;
+    let total = accounts.len() as u32;
+    for (done, account) in accounts.iter_mut().enumerate() {
+        // Watch-only accounts hold nothing encrypted
+        if let Some(pri_enc) = account.pri_enc.clone() {
+            let aad = account_aad(&account.lock_args);
+            let pri_key = decrypt(old_password, pri_enc, &aad)?;
+            account.pri_enc = Some(
+                encrypt(new_password, &pri_key, &aad)
+                    .map_err(|e| KeyVaultError::Internal(format!("Encryption error: {}", e)))?,
+            );
+        }
+        on_progress(done as u32 + 1, total)?;
+    }
+    Ok(())
+}
+
+/// Detects the BIP39 wordlist language of a QuantumPurse seed phrase - a concatenation of 24-word mnemonics.
+///
+/// **Parameters**:
+/// - `seed_phrase: &str` - The seed phrase.
+///
+/// **Returns**:
+/// - `Result<MnemonicLanguage, String>` - The first language every 24-word chunk parses in, or an error message
+///   listing the languages tried.
+pub fn detect_mnemonic_language(seed_phrase: &str) -> Result<MnemonicLanguage, String> {
+    let words: Vec<&str> = seed_phrase.split_whitespace().collect();
+    if words.is_empty() {
+        return Err("Mnemonic is empty".to_string());
+    }
+
+    for language in MnemonicLanguage::ALL {
+        let parses = words.chunks(24).all(|chunk| {
+            Mnemonic::parse_in(language.to_bip39(), chunk.join(" ").as_str()).is_ok()
+        });
+        if parses {
+            return Ok(language);
+        }
+    }
+
+    let tried: Vec<String> = MnemonicLanguage::ALL
+        .iter()
+        .map(|language| language.to_string())
+        .collect();
+    Err(format!(
+        "Unable to detect the mnemonic language, tried: {}",
+        tried.join(", ")
+    ))
+}
+
+/// Parses a QuantumPurse seed phrase - 48 or 72 words, a concatenation of 24-word mnemonics - into its seed entropy.
+/// Every intermediate copy of the words and the entropy is zeroized before it is dropped.
+///
+/// **Parameters**:
+/// - `seed_phrase: &str` - The seed phrase.
+/// - `language: MnemonicLanguage` - The BIP39 wordlist of the seed phrase.
+///
+/// **Returns**:
+/// - `Result<SecureVec, String>` - The combined entropy of the chunks in order on success, or an error message
+///   on failure.
+pub fn seed_phrase_entropy(seed_phrase: &str, language: MnemonicLanguage) -> Result<SecureVec, String> {
+    let words: Vec<&str> = seed_phrase.split_whitespace().collect();
+    if words.len() != 48 && words.len() != 72 {
+        return Err("Mnemonic must have 48 or 72 words".to_string());
+    }
+
+    let mut combined_entropy = SecureVec::new_with_length(words.len() / 24 * 32);
+    for (chunk_index, (chunk, out)) in words.chunks(24).zip(combined_entropy.chunks_mut(32)).enumerate() {
+        let chunk_str = SecureString::from_utf8(chunk.join(" ").into_bytes()).expect("Words are valid UTF-8");
+        let mnemonic = Mnemonic::parse_in(language.to_bip39(), &chunk_str)
+            .map_err(|e| format!("Invalid {} mnemonic chunk {}: {}", language, chunk_index + 1, e))?;
+        let mut entropy = mnemonic.to_entropy();
+        out.copy_from_slice(&entropy);
+        entropy.zeroize();
+    }
+    Ok(combined_entropy)
+}
+
+/// Lists every supported BIP39 wordlist language in id order, so adding a wordlist updates language pickers.
+///
+/// **Returns**:
+/// - `Vec<LanguageInfo>` - The id, English name and native name of each language.
+pub fn supported_languages() -> Vec<LanguageInfo> {
+    MnemonicLanguage::ALL
+        .iter()
+        .map(|language| LanguageInfo {
+            id: *language as u8,
+            name: language.to_string(),
+            native_name: language.native_name(),
+        })
+        .collect()
+}
+
+/// The current time in milliseconds since the Unix epoch, from `Date.now`, falling back to
+/// `performance.timeOrigin + performance.now()`. Returns 0 when the host has neither (non-browser wasm runtimes,
+/// some workers) instead of trapping, so callers must treat 0 as "no clock" and skip time-based behavior.
+///
+/// **Returns**:
+/// - `f64` - The current time in milliseconds, or 0 if no clock is available.
+pub fn now_ms() -> f64 {
+    js_now_ms().unwrap_or(0.0)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn js_now_ms() -> Option<f64> {
+    use wasm_bindgen::{JsCast, JsValue};
+    use web_sys::js_sys::{global, Function, Reflect};
+
+    // Looked up dynamically: a missing API is then `None` rather than a thrown exception
+    let call = |object: &JsValue, name: &str| -> Option<f64> {
+        let function = Reflect::get(object, &JsValue::from_str(name))
+            .ok()?
+            .dyn_into::<Function>()
+            .ok()?;
+        function.call0(object).ok()?.as_f64()
+    };
+    let global = global();
+    if let Ok(date) = Reflect::get(&global, &JsValue::from_str("Date")) {
+        if let Some(now) = call(&date, "now") {
+            return Some(now);
+        }
+    }
+    let performance = Reflect::get(&global, &JsValue::from_str("performance")).ok()?;
+    let time_origin = Reflect::get(&performance, &JsValue::from_str("timeOrigin"))
+        .ok()
+        .and_then(|time_origin| time_origin.as_f64())
+        .unwrap_or(0.0);
+    call(&performance, "now").map(|now| time_origin + now)
+}
+
+/// Outside wasm there is no JS host to ask, which exercises the fallback.
+#[cfg(not(target_arch = "wasm32"))]
+fn js_now_ms() -> Option<f64> {
+    None
+}
+
+/// Decides whether a gap-limit scan can stop: the run of unused accounts since the last used one, or since the
+/// start if none was used, has reached the gap limit.
+///
+/// **Parameters**:
+/// - `next_index: u32` - The index the scan would check next.
+/// - `last_used: Option<u32>` - The highest index found to be used so far.
+/// - `gap_limit: u32` - The number of consecutive unused accounts that ends the scan.
+///
+/// **Returns**:
+/// - `bool` - Whether the scan is done.
+pub fn gap_scan_done(next_index: u32, last_used: Option<u32>, gap_limit: u32) -> bool {
+    let unused_run = match last_used {
+        Some(used) => next_index - used - 1,
+        None => next_index,
+    };
+    unused_run >= gap_limit
+}
+
+/// Checks that a key derivation KDF cost is within `MIN_DERIVATION_LOG_N..=MAX_DERIVATION_LOG_N`.
+///
+/// **Parameters**:
+/// - `log_n: u8` - The Scrypt cost.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if in range, or an error message otherwise.
+pub fn check_derivation_log_n(log_n: u8) -> Result<(), String> {
+    if !(MIN_DERIVATION_LOG_N..=MAX_DERIVATION_LOG_N).contains(&log_n) {
+        return Err(format!(
+            "Derivation cost log_n must be between {} and {}, got {}",
+            MIN_DERIVATION_LOG_N, MAX_DERIVATION_LOG_N, log_n
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that decrypted seed entropy has a length some SPHINCS+ variant stores (64 or 96 bytes, whole
+/// 32-byte BIP39 chunks), so corrupted seeds fail with an explicit error instead of a panic when encoded as words.
+///
+/// **Parameters**:
+/// - `entropy_len: usize` - The length of the decrypted seed entropy in bytes.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if the length is valid, or an error message naming the expected and actual lengths.
+pub fn check_seed_entropy_length(entropy_len: usize) -> Result<(), String> {
+    let mut expected: Vec<usize> = SphincsVariant::ALL
+        .iter()
+        .map(|variant| variant.bip39_compatible_entropy_size())
+        .collect();
+    expected.sort_unstable();
+    expected.dedup();
+    if expected.contains(&entropy_len) {
+        return Ok(());
+    }
+
+    let expected: Vec<String> = expected.iter().map(|len| len.to_string()).collect();
+    Err(format!(
+        "Corrupted seed: expected {} bytes of entropy, got {}",
+        expected.join(" or "),
+        entropy_len
+    ))
+}
+
+/// Splits QuantumPurse seed entropy into standalone 24-word BIP39 mnemonics, one per 32-byte entropy chunk.
+/// Each mnemonic carries its own checksum, so it is valid on its own in any BIP39 tool.
+///
+/// **Parameters**:
+/// - `entropy: &[u8]` - The decrypted seed entropy, checked with `check_seed_entropy_length`.
+/// - `language: MnemonicLanguage` - The BIP39 wordlist to render the mnemonics with.
+///
+/// **Returns**:
+/// - `Result<Vec<String>, String>` - The mnemonics in chunk order on success, or an error message on failure.
+///   Joining them with spaces, in order, gives back the QuantumPurse seed phrase.
+pub fn standard_bip39_chunks(
+    entropy: &[u8],
+    language: MnemonicLanguage,
+) -> Result<Vec<String>, String> {
+    check_seed_entropy_length(entropy.len())?;
+    entropy
+        .chunks(32)
+        .map(|chunk| {
+            Mnemonic::from_entropy_in(language.to_bip39(), chunk)
+                .map(|mnemonic| mnemonic.to_string())
+                .map_err(|e| format!("Invalid seed entropy: {}", e))
+        })
+        .collect()
+}
+
+/// Seals a whole-wallet backup under the password. The backup is serialized, optionally deflated,
+/// then encrypted. The format flag is the first plaintext byte so it is authenticated along with the data.
+///
+/// **Parameters**:
+/// - `password: &[u8]` - The password used to encrypt the backup.
+/// - `backup: &WalletBackup` - The backup to seal.
+/// - `compress: bool` - Whether to deflate the serialized backup before encryption.
+///
+/// **Returns**:
+/// - `Result<CipherPayload, String>` - The encrypted backup on success, or an error message on failure.
+pub fn seal_backup(
+    password: &[u8],
+    backup: &WalletBackup,
+    compress: bool,
+) -> Result<CipherPayload, String> {
+    let serialized =
+        serde_json::to_vec(backup).map_err(|e| format!("Serialization error: {}", e))?;
+    let mut plain = Vec::with_capacity(serialized.len() + 1);
+    if compress {
+        plain.push(BACKUP_FORMAT_DEFLATE);
+        let mut encoder = DeflateEncoder::new(plain, Compression::best());
+        encoder
+            .write_all(&serialized)
+            .map_err(|e| format!("Compression error: {}", e))?;
+        plain = encoder
+            .finish()
+            .map_err(|e| format!("Compression error: {}", e))?;
+    } else {
+        plain.push(BACKUP_FORMAT_PLAIN);
+        plain.extend_from_slice(&serialized);
+    }
+    encrypt(password, &plain, &backup_aad())
+}
+
+/// Opens a backup sealed by `seal_backup` and validates it: the accounts checksum and `check_backup_accounts`.
+///
+/// **Parameters**:
+/// - `password: &[u8]` - The password used to decrypt the backup.
+/// - `payload: CipherPayload` - The encrypted backup.
+///
+/// **Returns**:
+/// - `Result<WalletBackup, KeyVaultError>` - The backup on success. `WrongPassword` if the backup doesn't decrypt
+///   under the password, `CorruptedPayload` if it decrypts to something `seal_backup` didn't produce, or
+///   `InvalidInput` if its accounts fail `check_backup_accounts`.
+pub fn open_backup(password: &[u8], payload: CipherPayload) -> Result<WalletBackup, KeyVaultError> {
+    let plain = decrypt(password, payload, &backup_aad())?;
+    let (format, body) = plain
+        .split_first()
+        .ok_or_else(|| KeyVaultError::CorruptedPayload("Empty backup".to_string()))?;
+    let serialized = match *format {
+        BACKUP_FORMAT_PLAIN => body.to_vec(),
+        BACKUP_FORMAT_DEFLATE => inflate_capped(body, MAX_BACKUP_INFLATED_SIZE)?,
+        other => {
+            return Err(KeyVaultError::CorruptedPayload(format!(
+                "Unsupported backup format: {}",
+                other
+            )))
+        }
+    };
+    let backup: WalletBackup = serde_json::from_slice(&serialized)
+        .map_err(|e| KeyVaultError::CorruptedPayload(format!("Invalid backup: {}", e)))?;
+    if accounts_checksum(&backup.accounts) != backup.checksum {
+        return Err(KeyVaultError::CorruptedPayload(
+            "Backup checksum mismatch".to_string(),
+        ));
+    }
+    check_backup_accounts(&backup.accounts).map_err(KeyVaultError::InvalidInput)?;
+    Ok(backup)
+}
+
+/// Inflates a deflate stream, refusing to produce more than `max` bytes so a small crafted backup can't expand into
+/// gigabytes of memory.
+///
+/// **Parameters**:
+/// - `body: &[u8]` - The deflate stream.
+/// - `max: usize` - The most bytes the stream may inflate to.
+///
+/// **Returns**:
+/// - `Result<Vec<u8>, KeyVaultError>` - The inflated bytes, or `CorruptedPayload` if the stream is invalid or inflates
+///   past `max`.
+pub fn inflate_capped(body: &[u8], max: usize) -> Result<Vec<u8>, KeyVaultError> {
+    let mut inflated = Vec::new();
+    DeflateDecoder::new(body)
+        .take(max as u64 + 1)
+        .read_to_end(&mut inflated)
+        .map_err(|e| KeyVaultError::CorruptedPayload(format!("Decompression error: {}", e)))?;
+    if inflated.len() > max {
+        return Err(KeyVaultError::CorruptedPayload(format!(
+            "Backup inflates past {} bytes",
+            max
+        )));
+    }
+    Ok(inflated)
+}
+
+/// Serializes the stored records into a portable vault blob: `VAULT_MAGIC`, the format version, the JSON serialized
+/// backup, then a SHA-256 digest of everything before it. The records stay encrypted under the wallet password, so
+/// the digest only needs to catch corruption and truncation.
+///
+/// **Parameters**:
+/// - `backup: &WalletBackup` - The stored records.
+///
+/// **Returns**:
+/// - `Result<Vec<u8>, String>` - The blob on success, or an error message on failure.
+pub fn encode_vault(backup: &WalletBackup) -> Result<Vec<u8>, String> {
+    let mut blob = VAULT_MAGIC.to_vec();
+    blob.push(VAULT_FORMAT_VERSION);
+    serde_json::to_writer(&mut blob, backup).map_err(|e| format!("Serialization error: {}", e))?;
+    let digest = Sha256::digest(&blob);
+    blob.extend_from_slice(&digest);
+    Ok(blob)
+}
+
+/// Parses a vault blob made by `encode_vault` and validates it: header, digest, the accounts checksum and
+/// `check_backup_accounts`.
+///
+/// **Parameters**:
+/// - `blob: &[u8]` - The vault blob.
+///
+/// **Returns**:
+/// - `Result<WalletBackup, String>` - The stored records on success, or an error message if the blob is invalid.
+pub fn decode_vault(blob: &[u8]) -> Result<WalletBackup, String> {
+    let header_len = VAULT_MAGIC.len() + 1;
+    let digest_len = Sha256::output_size();
+    if blob.len() < header_len + digest_len || blob[..VAULT_MAGIC.len()] != VAULT_MAGIC {
+        return Err("Not a QuantumPurse vault export".to_string());
+    }
+    if blob[VAULT_MAGIC.len()] != VAULT_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported vault format version: {}",
+            blob[VAULT_MAGIC.len()]
+        ));
+    }
+    let (content, digest) = blob.split_at(blob.len() - digest_len);
+    if Sha256::digest(content).as_slice() != digest {
+        return Err("Vault checksum mismatch: the export is corrupted or truncated".to_string());
+    }
+    let backup: WalletBackup = serde_json::from_slice(&content[header_len..])
+        .map_err(|e| format!("Invalid vault export: {}", e))?;
+    if accounts_checksum(&backup.accounts) != backup.checksum {
+        return Err("Vault accounts checksum mismatch".to_string());
+    }
+    check_backup_accounts(&backup.accounts)?;
+    Ok(backup)
+}
+
+/// Checks that backup accounts can be stored as they are: every lock_args is a 32-byte hex string, no lock_args or
+/// index appears twice and every signing account leaves a next derivation index below `WATCH_ONLY_INDEX_BASE`. A duplicate would otherwise silently overwrite another
+/// account on restore.
+///
+/// **Parameters**:
+/// - `accounts: &[SphincsPlusAccount]` - The backup accounts.
+///
+/// **Returns**:
+/// - `Result<(), String>` - Ok if the accounts are consistent, or an error message otherwise.
+pub fn check_backup_accounts(accounts: &[SphincsPlusAccount]) -> Result<(), String> {
+    let mut lock_args_seen = std::collections::HashSet::new();
+    let mut indices_seen = std::collections::HashSet::new();
+    let mut ids_seen = std::collections::HashSet::new();
+    for account in accounts {
+        if decode(&account.lock_args).map(|bytes| bytes.len()) != Ok(32) {
+            return Err(format!(
+                "Invalid lock_args in backup: {}",
+                account.lock_args
+            ));
+        }
+        if !lock_args_seen.insert(account.lock_args.as_str()) {
+            return Err(format!(
+                "Duplicate account in backup: {}",
+                account.lock_args
+            ));
+        }
+        if !indices_seen.insert(account.index) {
+            return Err(format!(
+                "Duplicate account index in backup: {}",
+                account.index
+            ));
+        }
+        // Account ids are a unique index of the store
+        if let Some(account_id) = account.account_id.as_deref() {
+            if !ids_seen.insert(account_id) {
+                return Err(format!("Duplicate account id in backup: {}", account_id));
+            }
+        }
+        if !account.is_watch_only() && account.index >= WATCH_ONLY_INDEX_BASE - 1 {
+            return Err(format!(
+                "Account index out of range in backup: {}",
+                account.index
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Builds the non-secret diagnostic report of the stored wallet. Private key lengths are inferred from the
+/// ciphertext lengths (AES-GCM adds a 16-byte tag), so nothing is decrypted. Watch-only accounts are counted
+/// but hold no key length.
+///
+/// **Parameters**:
+/// - `db_version: u8` - The IndexedDB schema version.
+/// - `seed: Option<&CipherPayload>` - The encrypted mnemonic seed phrase, if stored.
+/// - `accounts: &[SphincsPlusAccount]` - The stored accounts.
+///
+/// **Returns**:
+/// - `DiagnosticReport` - The report.
+pub fn diagnostic_report(
+    db_version: u8,
+    seed: Option<&CipherPayload>,
+    accounts: &[SphincsPlusAccount],
+) -> DiagnosticReport {
+    let enc_scrypt = config::enc_scrypt();
+    let mut key_lengths: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut schema_versions: BTreeMap<u8, usize> = BTreeMap::new();
+    for pri_enc in accounts.iter().filter_map(|account| account.pri_enc.as_ref()) {
+        let private_key_length = pri_enc.cipher_text.len().saturating_sub(AES_GCM_TAG_LENGTH);
+        *key_lengths.entry(private_key_length).or_default() += 1;
+        *schema_versions.entry(pri_enc.version).or_default() += 1;
+    }
+    if let Some(seed) = seed {
+        *schema_versions.entry(seed.version).or_default() += 1;
+    }
+
+    DiagnosticReport {
+        db_version,
+        account_count: accounts.len(),
+        indices: stored_indices(accounts),
+        variants_detected: key_lengths
+            .into_iter()
+            .map(|(private_key_length, account_count)| KeyLengthGroup {
+                private_key_length,
+                account_count,
+                candidates: SphincsVariant::ALL
+                    .iter()
+                    .copied()
+                    .filter(|variant| variant.private_key_length() == private_key_length)
+                    .collect(),
+            })
+            .collect(),
+        mnemonic_present: seed.is_some(),
+        encryption_metadata: EncryptionMetadata {
+            cipher: "AES-256-GCM",
+            kdf: "scrypt",
+            scrypt_log_n: enc_scrypt.log_n,
+            scrypt_r: enc_scrypt.r,
+            scrypt_p: enc_scrypt.p,
+            salt_length: SALT_LENGTH,
+            iv_length: IV_LENGTH,
+        },
+        schema_versions,
+    }
+}
+
+/// The lock script parameters the key vault builds lock_args and witnesses with.
+///
+/// **Returns**:
+/// - `LockScriptConfig` - The config, including the code hash of the compatible lock script.
+pub fn lock_script_config() -> LockScriptConfig {
+    LockScriptConfig {
+        code_hash: LOCK_SCRIPT_CODE_HASH,
+        hash_type: LOCK_SCRIPT_HASH_TYPE,
+        multisig_reserved_field_value: MULTISIG_RESERVED_FIELD_VALUE,
+        required_first_n: REQUIRED_FIRST_N,
+        threshold: THRESHOLD,
+        pubkey_num: PUBKEY_NUM,
+        param_ids: SphincsVariant::ALL
+            .iter()
+            .map(|variant| (*variant, *variant as u8))
+            .collect(),
+    }
+}
+
+/// Molecule-serializes a CKB `WitnessArgs`: a table of three `BytesOpt` fields (lock, input_type, output_type).
+/// A table is its total size and the field offsets as u32 little endian, followed by the fields; an absent
+/// `BytesOpt` is empty and a present one is a `Bytes` fixvec - its length as u32 little endian, then the bytes.
+///
+/// **Parameters**:
+/// - `lock: Option<&[u8]>` - The lock field, e.g. the signature blob from `sign`.
+/// - `input_type: Option<&[u8]>` - The optional input_type field.
+/// - `output_type: Option<&[u8]>` - The optional output_type field.
+///
+/// **Returns**:
+/// - `Vec<u8>` - The serialized `WitnessArgs`.
+pub fn witness_args(
+    lock: Option<&[u8]>,
+    input_type: Option<&[u8]>,
+    output_type: Option<&[u8]>,
+) -> Vec<u8> {
+    let fields: Vec<Vec<u8>> = [lock, input_type, output_type]
+        .iter()
+        .map(|field| match field {
+            Some(bytes) => [&(bytes.len() as u32).to_le_bytes()[..], *bytes].concat(),
+            None => Vec::new(),
+        })
+        .collect();
+
+    let header_size = 4 * (1 + fields.len());
+    let total_size = header_size + fields.iter().map(Vec::len).sum::<usize>();
+    let mut serialized = Vec::with_capacity(total_size);
+    serialized.extend_from_slice(&(total_size as u32).to_le_bytes());
+    let mut offset = header_size;
+    for field in &fields {
+        serialized.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += field.len();
+    }
+    for field in fields {
+        serialized.extend_from_slice(&field);
+    }
+    serialized
+}
@@ -5,8 +5,8 @@ use std::sync::atomic::Ordering;
 fn test_pass_encrypt_decrypt() {
   let password = vec![1, 2, 3];
   let data = b"test";
-  let payload = encrypt(&password, data).unwrap();
-  let decrypted = decrypt(&password, payload).unwrap();
+  let payload = encrypt(&password, data, b"aad").unwrap();
+  let decrypted = decrypt(&password, payload, b"aad").unwrap();
   assert_eq!(decrypted.as_ref(), data);
 }
 
@@ -14,12 +14,79 @@ fn test_pass_encrypt_decrypt() {
 fn test_fail_encrypt_decrypt() {
   let password = vec![1, 2, 3];
   let data = b"test";
-  let payload = encrypt(&password, data).unwrap();
+  let payload = encrypt(&password, data, b"aad").unwrap();
   let password1 = vec![2, 2, 3];
-  let result = decrypt(&password1, payload);
+  let result = decrypt(&password1, payload, b"aad");
   assert!(result.is_err());
 }
 
+#[test]
+fn test_corrupted_payload_is_not_a_wrong_password() {
+  use crate::errors::KeyVaultError;
+  let password = vec![1, 2, 3];
+  let payload = encrypt(&password, b"test", b"aad").unwrap();
+
+  let mut short_salt = payload.clone();
+  short_salt.salt.pop();
+  let err = decrypt(&password, short_salt, b"aad").unwrap_err();
+  assert!(matches!(err, DecryptError::CorruptedPayload(_)));
+  assert_eq!(KeyVaultError::from(err).code(), "CORRUPTED_PAYLOAD");
+
+  let mut long_iv = payload.clone();
+  long_iv.iv.push(0);
+  assert!(matches!(decrypt(&password, long_iv, b"aad"), Err(DecryptError::CorruptedPayload(_))));
+
+  let mut flipped = payload.clone();
+  flipped.cipher_text[0] ^= 1;
+  let err = decrypt(&password, flipped, b"aad").unwrap_err();
+  assert!(matches!(err, DecryptError::WrongPassword(_)));
+  assert_eq!(KeyVaultError::from(err).code(), "WRONG_PASSWORD");
+
+  let err = decrypt(&[4, 5, 6], payload, b"aad").unwrap_err();
+  assert_eq!(KeyVaultError::from(err).code(), "WRONG_PASSWORD");
+}
+
+#[test]
+fn test_decrypt_rejects_excessive_kdf_params() {
+  let password = vec![1, 2, 3];
+  let payload = encrypt(&password, b"test", b"aad").unwrap();
+  for kdf in [
+    ScryptParam { log_n: 40, ..ENC_SCRYPT },
+    ScryptParam { r: 1 << 20, ..ENC_SCRYPT },
+    ScryptParam { p: u32::MAX, ..ENC_SCRYPT },
+  ] {
+    let crafted = CipherPayload { kdf: Some(kdf), ..payload.clone() };
+    assert!(matches!(decrypt(&password, crafted, b"aad"), Err(DecryptError::CorruptedPayload(_))));
+  }
+}
+
+#[test]
+fn test_decrypt_uses_payload_kdf_params() {
+  let password = vec![1, 2, 3];
+  let mut payload = encrypt(&password, b"test", b"aad").unwrap();
+  let kdf = payload.kdf.expect("New payloads record their KDF params");
+  assert!(decrypt(&password, payload.clone(), b"aad").is_ok());
+  payload.kdf = Some(ScryptParam { log_n: kdf.log_n - 1, ..kdf });
+  assert!(decrypt(&password, payload, b"aad").is_err());
+}
+
+#[test]
+fn test_empty_password_rejected() {
+  assert_eq!(check_password_not_empty(&[]).unwrap_err(), "Password must not be empty");
+  assert!(check_password_not_empty(&[0]).is_ok());
+  assert_eq!(encrypt(&[], b"seed", &seed_aad()).unwrap_err(), "Password must not be empty");
+}
+
+#[test]
+fn test_reencrypt_accounts_rejects_empty_new_password() {
+  let old_password = vec![1, 2, 3];
+  let mut account = dummy_account(0, "aa");
+  account.pri_enc = Some(encrypt(&old_password, &[9; 4], &account_aad("aa")).unwrap());
+  let mut accounts = vec![account];
+  let err = reencrypt_accounts(&mut accounts, &old_password, &[], |_, _| Ok(())).unwrap_err();
+  assert_eq!(err.code(), "INVALID_INPUT");
+  assert_eq!(err.to_string(), "Password must not be empty");
+}
 
 #[test]
 fn test_zeroize_on_drop_decrypt_output() {
@@ -27,9 +94,849 @@ fn test_zeroize_on_drop_decrypt_output() {
   ZEROIZED.store(false, Ordering::SeqCst);
   let password = vec![1, 2, 3];
   let data = b"test";
-  let payload = encrypt(&password, data).unwrap();
+  let payload = encrypt(&password, data, b"aad").unwrap();
   {
-      let _decrypted = decrypt(&password, payload).unwrap();
+      let _decrypted = decrypt(&password, payload, b"aad").unwrap();
   } // decrypted is dropped here
   assert!(ZEROIZED.load(Ordering::SeqCst));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_seed_phrase_entropy_is_zeroized_on_drop() {
+  use crate::secure_vec::ZEROIZED;
+  let entropy: Vec<u8> = (0..64).collect();
+  let phrase = standard_bip39_chunks(&entropy, MnemonicLanguage::English).unwrap().join(" ");
+  ZEROIZED.store(false, Ordering::SeqCst);
+  {
+    let combined_entropy = seed_phrase_entropy(&phrase, MnemonicLanguage::English).unwrap();
+    assert_eq!(&*combined_entropy, entropy.as_slice());
+  }
+  assert!(ZEROIZED.load(Ordering::SeqCst));
+
+  assert_eq!(
+    seed_phrase_entropy("abandon ability", MnemonicLanguage::English).unwrap_err(),
+    "Mnemonic must have 48 or 72 words"
+  );
+  assert!(seed_phrase_entropy(&phrase, MnemonicLanguage::Spanish)
+    .unwrap_err()
+    .starts_with("Invalid Spanish mnemonic chunk 1"));
+}
+
+#[test]
+fn test_secure_vec_to_hex_secure() {
+  let secret = SecureVec::from_slice(&[0x00, 0xab, 0xff, 0x10]);
+  let hex_secret = secret.to_hex_secure();
+  assert_eq!(&*hex_secret, encode([0x00, 0xab, 0xff, 0x10]));
+}
+
+fn sha2_128f_signature(message: &[u8]) -> (Vec<u8>, Vec<u8>) {
+  use fips205::traits::{KeyGen, Signer};
+  let (pub_key, pri_key) = slh_dsa_sha2_128f::KG::keygen_with_seeds(&[1u8; 16], &[2u8; 16], &[3u8; 16]);
+  let signature = pri_key.try_sign(message, &[], false).unwrap();
+  (pub_key.into_bytes().to_vec(), signature.to_vec())
+}
+
+#[test]
+fn test_verify_signature() {
+  let message = b"message";
+  let (pub_key, signature) = sha2_128f_signature(message);
+  assert!(verify_signature(SphincsVariant::Sha2128F, &pub_key, message, &signature).unwrap());
+  assert!(!verify_signature(SphincsVariant::Sha2128F, &pub_key, b"other message", &signature).unwrap());
+}
+
+#[test]
+fn test_verify_with_wrong_public_key_length() {
+  let message = b"message";
+  let (pub_key, signature) = sha2_128f_signature(message);
+  let err = verify_signature(SphincsVariant::Sha2256S, &pub_key, message, &signature).unwrap_err();
+  assert_eq!(err, "Invalid public key length: expected 64 bytes for slh_dsa_sha2_256s, got 32");
+}
+
+#[test]
+fn test_verify_truncated_signature() {
+  let message = b"message";
+  let (pub_key, signature) = sha2_128f_signature(message);
+  let truncated = &signature[..signature.len() - 1];
+  assert_eq!(verify_signature(SphincsVariant::Sha2128F, &pub_key, message, truncated), Ok(false));
+}
+
+#[test]
+fn test_verify_over_long_signature() {
+  let message = b"message";
+  let (pub_key, mut signature) = sha2_128f_signature(message);
+  signature.push(0);
+  assert_eq!(verify_signature(SphincsVariant::Sha2128F, &pub_key, message, &signature), Ok(false));
+}
+
+#[test]
+fn test_verify_empty_signature() {
+  let message = b"message";
+  let (pub_key, _) = sha2_128f_signature(message);
+  assert_eq!(verify_signature(SphincsVariant::Sha2128F, &pub_key, message, &[]), Ok(false));
+}
+
+#[test]
+fn test_derive_scrypt_key_invalid_params() {
+  let param = ScryptParam { log_n: 14, r: 0, p: 0, len: 32 };
+  let result = derive_scrypt_key(&[1, 2, 3], &vec![0u8; SALT_LENGTH], &param);
+  assert!(result.unwrap_err().starts_with("Invalid scrypt parameters"));
+}
+
+fn dummy_account(index: u32, lock_args: &str) -> SphincsPlusAccount {
+  SphincsPlusAccount {
+    index,
+    lock_args: lock_args.to_string(),
+    pri_enc: Some(CipherPayload {
+      salt: Vec::new(),
+      iv: Vec::new(),
+      cipher_text: Vec::new(),
+      version: 0,
+      kdf: None,
+    }),
+    account_id: None,
+    pub_key: None,
+    derivation: crate::types::DerivationParams::default(),
+    has_history: None,
+    label: None,
+  }
+}
+
+#[test]
+fn test_accounts_checksum() {
+  let mut accounts = vec![dummy_account(1, "bb"), dummy_account(0, "aa")];
+  let checksum = accounts_checksum(&accounts);
+  assert_eq!(checksum, accounts_checksum(&[dummy_account(0, "aa"), dummy_account(1, "bb")]));
+
+  accounts.push(dummy_account(2, "cc"));
+  let extended_checksum = accounts_checksum(&accounts);
+  assert_ne!(checksum, extended_checksum);
+  assert_eq!(extended_checksum, accounts_checksum(&accounts));
+}
+
+#[test]
+fn test_stored_indices_with_gaps() {
+  let accounts = vec![
+    dummy_account(5, "ee"),
+    dummy_account(0, "aa"),
+    dummy_account(2, "cc"),
+    dummy_account(1, "bb"),
+  ];
+  assert_eq!(stored_indices(&accounts), vec![0, 1, 2, 5]);
+  assert!(stored_indices(&[]).is_empty());
+}
+
+#[test]
+fn test_metadata_entry_limits() {
+  assert!(check_metadata_entry("network", MAX_METADATA_VALUE_LENGTH).is_ok());
+  assert!(check_metadata_entry("", 1).is_err());
+  assert!(check_metadata_entry(&"k".repeat(MAX_METADATA_KEY_LENGTH + 1), 1).is_err());
+  assert!(check_metadata_entry("network", MAX_METADATA_VALUE_LENGTH + 1).is_err());
+  assert!(check_metadata_entry(FAILED_ATTEMPTS_KEY, 1).unwrap_err().contains("reserved"));
+}
+
+#[test]
+fn test_metadata_value_round_trip() {
+  let password = vec![1, 2, 3];
+  let value = b"{\"network\":\"testnet\"}";
+  check_metadata_entry("settings", value.len()).unwrap();
+  let aad = record_aad(crate::constants::METADATA_STORE, "settings");
+  let payload = encrypt(&password, value, &aad).unwrap();
+  assert_eq!(decrypt(&password, payload, &aad).unwrap().as_ref(), value);
+}
+
+#[test]
+fn test_reencrypt_accounts_reports_progress() {
+  let old_password = vec![1, 2, 3];
+  let new_password = vec![4, 5, 6];
+  let mut accounts: Vec<SphincsPlusAccount> = (0..3)
+    .map(|i| {
+      let mut account = dummy_account(i, &format!("{:02x}", i));
+      account.pri_enc = Some(encrypt(&old_password, &[i as u8; 8], &account_aad(&account.lock_args)).unwrap());
+      account
+    })
+    .collect();
+
+  let mut calls: Vec<(u32, u32)> = Vec::new();
+  reencrypt_accounts(&mut accounts, &old_password, &new_password, |done, total| {
+    calls.push((done, total));
+    Ok(())
+  })
+  .unwrap();
+
+  assert_eq!(calls, vec![(1, 3), (2, 3), (3, 3)]);
+  for (i, account) in accounts.iter().enumerate() {
+    let pri_key = decrypt(&new_password, account.pri_enc.clone().unwrap(), &account_aad(&account.lock_args)).unwrap();
+    assert_eq!(pri_key.as_ref(), &[i as u8; 8]);
+  }
+}
+
+#[test]
+fn test_reencrypt_accounts_aborts_on_progress_error() {
+  let old_password = vec![1, 2, 3];
+  let mut accounts = vec![dummy_account(0, "00"), dummy_account(1, "01")];
+  for account in accounts.iter_mut() {
+    account.pri_enc = Some(encrypt(&old_password, b"key", &account_aad(&account.lock_args)).unwrap());
+  }
+
+  let result = reencrypt_accounts(&mut accounts, &old_password, &[4, 5, 6], |_, _| {
+    Err(KeyVaultError::Internal("cancelled".to_string()))
+  });
+  assert_eq!(result.unwrap_err().to_string(), "cancelled");
+}
+
+#[test]
+fn test_detect_english_mnemonic() {
+  let seed_phrase = "uncover behind cargo satoshi tail answer liar success snap explain trigger brush cube mountain friend damp empty nose plastic huge pave enter wolf hazard miracle helmet trend connect bench battle diagram person uniform bike bottom negative glove vague diagram never float peace pride ivory banner say safe mesh";
+  assert_eq!(detect_mnemonic_language(seed_phrase), Ok(MnemonicLanguage::English));
+}
+
+#[test]
+fn test_detect_spanish_mnemonic() {
+  let chunks: Vec<String> = [[7u8; 32], [9u8; 32]]
+    .iter()
+    .map(|entropy| Mnemonic::from_entropy_in(bip39::Language::Spanish, entropy).unwrap().to_string())
+    .collect();
+  assert_eq!(detect_mnemonic_language(&chunks.join(" ")), Ok(MnemonicLanguage::Spanish));
+}
+
+#[test]
+fn test_detect_unknown_mnemonic_language() {
+  let err = detect_mnemonic_language("not a mnemonic").unwrap_err();
+  assert!(err.contains("English"));
+  assert!(err.contains("Spanish"));
+}
+
+#[test]
+fn test_relocated_payload_fails_decryption() {
+  let password = vec![1, 2, 3];
+  let payload = encrypt(&password, b"private key", &account_aad("aa")).unwrap();
+  assert!(decrypt(&password, payload.clone(), &account_aad("bb")).is_err());
+  assert!(decrypt(&password, payload.clone(), &seed_aad()).is_err());
+  assert!(decrypt(&password, payload.clone(), &blob_aad()).is_err());
+  assert!(decrypt(&password, payload, &account_aad("aa")).is_ok());
+
+  let blob = encrypt(&password, b"app secret", &blob_aad()).unwrap();
+  assert!(decrypt(&password, blob.clone(), &seed_aad()).is_err());
+  assert_eq!(decrypt(&password, blob, &blob_aad()).unwrap().as_ref(), b"app secret");
+}
+
+#[test]
+fn test_hex_payload_reads_as_raw_bytes() {
+  let password = vec![1, 2, 3];
+  let payload = encrypt(&password, b"private key", &account_aad("aa")).unwrap();
+  assert_eq!(payload.version, CIPHER_PAYLOAD_VERSION);
+  let hex = serde_json::json!({
+    "salt": encode(&payload.salt),
+    "iv": encode(&payload.iv),
+    "cipher_text": encode(&payload.cipher_text),
+    "version": 1,
+  });
+  let legacy: CipherPayload = serde_json::from_value(hex).unwrap();
+  assert_eq!(legacy.cipher_text, payload.cipher_text);
+  assert_eq!(decrypt(&password, legacy, &account_aad("aa")).unwrap().as_ref(), b"private key");
+
+  let raw: CipherPayload = serde_json::from_value(serde_json::to_value(&payload).unwrap()).unwrap();
+  assert_eq!(raw.salt, payload.salt);
+  assert_eq!(raw.iv, payload.iv);
+  assert!(serde_json::from_value::<CipherPayload>(serde_json::json!({ "salt": "zz", "iv": "00", "cipher_text": "00" })).is_err());
+}
+
+#[test]
+fn test_legacy_payload_decrypts_without_aad() {
+  let password = vec![1, 2, 3];
+  let mut payload = encrypt(&password, b"seed", &[]).unwrap();
+  payload.version = 0;
+  assert_eq!(decrypt(&password, payload, &seed_aad()).unwrap().as_ref(), b"seed");
+}
+
+#[test]
+fn test_seed_blob_round_trip() {
+  let password = vec![1, 2, 3];
+  let seed = encrypt(&password, &[7u8; 48], &seed_aad()).unwrap();
+  let blob = serde_json::to_string(&seed).unwrap();
+  let restored: CipherPayload = serde_json::from_str(&blob).unwrap();
+  assert_eq!(check_seed_blob(&restored), Ok(()));
+  assert_eq!(decrypt(&password, restored, &seed_aad()).unwrap().as_ref(), &[7u8; 48]);
+}
+
+#[test]
+fn test_malformed_seed_blob_is_rejected() {
+  let seed = encrypt(&[1, 2, 3], &[7u8; 48], &seed_aad()).unwrap();
+  assert!(check_seed_blob(&CipherPayload { version: CIPHER_PAYLOAD_VERSION + 1, ..seed.clone() })
+    .unwrap_err()
+    .contains("Unsupported payload version"));
+  assert!(check_seed_blob(&CipherPayload { salt: vec![0; 8], ..seed.clone() }).unwrap_err().contains("salt"));
+  assert!(check_seed_blob(&CipherPayload { iv: Vec::new(), ..seed.clone() }).unwrap_err().contains("IV"));
+  let costly = ScryptParam { log_n: 30, ..ENC_SCRYPT };
+  assert!(check_seed_blob(&CipherPayload { kdf: Some(costly), ..seed.clone() }).unwrap_err().contains("log_n"));
+  assert!(check_seed_blob(&CipherPayload { cipher_text: vec![0; 4], ..seed }).unwrap_err().contains("Ciphertext"));
+}
+
+fn dummy_backup(count: u32) -> WalletBackup {
+  // Random ciphertexts the size of an encrypted 256f private key (128 bytes + 16 byte tag)
+  let accounts: Vec<SphincsPlusAccount> = (0..count)
+    .map(|i| {
+      let mut account = dummy_account(i, &encode(&*get_random_bytes(32).unwrap()));
+      account.pri_enc.as_mut().unwrap().cipher_text = get_random_bytes(144).unwrap().to_vec();
+      account
+    })
+    .collect();
+  WalletBackup {
+    seed: dummy_account(0, "").pri_enc.unwrap(),
+    checksum: accounts_checksum(&accounts),
+    accounts,
+    verifier: None,
+    next_index: None,
+  }
+}
+
+#[test]
+fn test_backup_round_trip() {
+  let password = vec![1, 2, 3];
+  let backup = dummy_backup(3);
+  for compress in [false, true] {
+    let payload = seal_backup(&password, &backup, compress).unwrap();
+    let restored = open_backup(&password, payload).unwrap();
+    assert_eq!(restored.checksum, backup.checksum);
+    assert_eq!(restored.accounts.len(), 3);
+    assert_eq!(restored.accounts[2].pri_enc.as_ref().unwrap().cipher_text, backup.accounts[2].pri_enc.as_ref().unwrap().cipher_text);
+  }
+  let payload = seal_backup(&password, &backup, true).unwrap();
+  assert_eq!(open_backup(&[4, 5, 6], payload).unwrap_err().code(), "WRONG_PASSWORD");
+}
+
+#[test]
+fn test_backup_compression_shrinks_payloads() {
+  // JSON spells random ciphertext bytes out as number arrays, deflate wins most of it back.
+  let password = vec![1, 2, 3];
+  let backup = dummy_backup(50);
+  let plain = seal_backup(&password, &backup, false).unwrap();
+  let compressed = seal_backup(&password, &backup, true).unwrap();
+  assert!(compressed.cipher_text.len() * 10 < plain.cipher_text.len() * 7);
+}
+
+#[test]
+fn test_backup_inflation_is_capped() {
+  let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+  encoder.write_all(&[0u8; 4096]).unwrap();
+  let bomb = encoder.finish().unwrap();
+  assert!(bomb.len() < 100);
+
+  assert_eq!(inflate_capped(&bomb, 4096).unwrap().len(), 4096);
+  let err = inflate_capped(&bomb, 4095).unwrap_err();
+  assert_eq!(err.code(), "CORRUPTED_PAYLOAD");
+  assert!(err.to_string().ends_with("Backup inflates past 4095 bytes"));
+}
+
+#[test]
+fn test_backup_checksum_mismatch_is_rejected() {
+  let password = vec![1, 2, 3];
+  let mut backup = dummy_backup(2);
+  backup.accounts.pop();
+  let payload = seal_backup(&password, &backup, true).unwrap();
+  let err = open_backup(&password, payload).unwrap_err();
+  assert_eq!(err.code(), "CORRUPTED_PAYLOAD");
+  assert!(err.to_string().ends_with("Backup checksum mismatch"));
+}
+
+#[test]
+fn test_inconsistent_backup_is_rejected_before_restore() {
+  let password = vec![1, 2, 3];
+  let mut backup = dummy_backup(3);
+  backup.accounts[2].lock_args = backup.accounts[0].lock_args.clone();
+  backup.checksum = accounts_checksum(&backup.accounts);
+  let payload = seal_backup(&password, &backup, false).unwrap();
+  let err = open_backup(&password, payload).unwrap_err();
+  assert_eq!(err.code(), "INVALID_INPUT");
+  assert!(err.to_string().starts_with("Duplicate account in backup"));
+
+  let mut accounts = dummy_backup(2).accounts;
+  accounts[1].index = 0;
+  assert!(check_backup_accounts(&accounts).unwrap_err().starts_with("Duplicate account index"));
+
+  accounts[1].index = WATCH_ONLY_INDEX_BASE - 1;
+  assert!(check_backup_accounts(&accounts).unwrap_err().starts_with("Account index out of range"));
+
+  accounts[1].index = 1;
+  assert!(check_backup_accounts(&accounts).is_ok());
+  accounts[0].account_id = Some("main".to_string());
+  accounts[1].account_id = Some("main".to_string());
+  assert!(check_backup_accounts(&accounts).unwrap_err().starts_with("Duplicate account id"));
+  accounts[1].account_id = None;
+  assert!(check_backup_accounts(&accounts).is_ok());
+  accounts[1].lock_args = "abcd".to_string();
+  assert!(check_backup_accounts(&accounts).unwrap_err().starts_with("Invalid lock_args"));
+}
+
+#[test]
+fn test_vault_blob_round_trip() {
+  let backup = dummy_backup(3);
+  let blob = encode_vault(&backup).unwrap();
+  assert_eq!(&blob[..4], b"QPVT");
+  let restored = decode_vault(&blob).unwrap();
+  assert_eq!(restored.checksum, backup.checksum);
+  assert_eq!(restored.accounts.len(), 3);
+  assert_eq!(restored.next_index, None);
+}
+
+#[test]
+fn test_vault_blob_keeps_next_index() {
+  // Accounts 0..=2 stored, indices 3 and 4 deleted
+  let backup = WalletBackup { next_index: Some(5), ..dummy_backup(3) };
+  let restored = decode_vault(&encode_vault(&backup).unwrap()).unwrap();
+  assert_eq!(restored.next_index, Some(5));
+}
+
+#[test]
+fn test_corrupted_vault_blob_is_rejected() {
+  let blob = encode_vault(&dummy_backup(2)).unwrap();
+
+  let mut flipped = blob.clone();
+  flipped[10] ^= 1;
+  assert!(decode_vault(&flipped).unwrap_err().contains("checksum mismatch"));
+  assert!(decode_vault(&blob[..blob.len() - 1]).is_err());
+
+  let mut wrong_magic = blob.clone();
+  wrong_magic[0] = b'X';
+  assert!(decode_vault(&wrong_magic).unwrap_err().contains("Not a QuantumPurse vault"));
+
+  let mut newer = blob;
+  newer[4] = VAULT_FORMAT_VERSION + 1;
+  assert!(decode_vault(&newer).unwrap_err().contains("Unsupported vault format"));
+}
+
+#[test]
+fn test_diagnostic_report_holds_no_secrets() {
+  let password = vec![1, 2, 3];
+  let seed = encrypt(&password, &[7; 64], &seed_aad()).unwrap();
+  let mut accounts: Vec<SphincsPlusAccount> = [0, 1, 5]
+    .iter()
+    .map(|&i| {
+      let mut account = dummy_account(i, &format!("{:02x}", i));
+      account.pri_enc = Some(encrypt(&password, &[9; 64], &account_aad(&account.lock_args)).unwrap());
+      account
+    })
+    .collect();
+  accounts[2].pri_enc.as_mut().unwrap().version = 0;
+
+  let report = diagnostic_report(2, Some(&seed), &accounts);
+  assert_eq!(report.account_count, 3);
+  assert_eq!(report.indices, vec![0, 1, 5]);
+  assert!(report.mnemonic_present);
+  assert_eq!(report.variants_detected.len(), 1);
+  assert_eq!(report.variants_detected[0].private_key_length, 64);
+  assert_eq!(report.variants_detected[0].account_count, 3);
+  assert_eq!(report.variants_detected[0].candidates.len(), 4);
+  assert_eq!(report.schema_versions.get(&0), Some(&1));
+  assert_eq!(report.schema_versions.get(&CIPHER_PAYLOAD_VERSION), Some(&3));
+
+  let serialized = serde_json::to_string(&report).unwrap();
+  for payload in accounts.iter().filter_map(|account| account.pri_enc.as_ref()).chain([&seed]) {
+    assert!(!serialized.contains(&encode(&payload.cipher_text)));
+    assert!(!serialized.contains(&encode(&payload.salt)));
+    assert!(!serialized.contains(&encode(&payload.iv)));
+  }
+}
+
+#[test]
+fn test_message_all_input_index_out_of_range() {
+  let err = ckb_tx_message_all(&MockTransaction::default(), 0).unwrap_err();
+  assert!(err.contains("out of range"));
+}
+
+#[test]
+fn test_lock_script_config() {
+  let config = lock_script_config();
+  let code_hash = config.code_hash.strip_prefix("0x").unwrap();
+  assert_eq!(decode(code_hash).unwrap().len(), 32);
+  assert_eq!(config.hash_type, "data1");
+  assert_eq!(
+    [config.multisig_reserved_field_value, config.required_first_n, config.threshold, config.pubkey_num],
+    [0x80, 0x00, 0x01, 0x01]
+  );
+  assert_eq!(config.param_ids.len(), 12);
+  assert_eq!(config.param_ids[0], (SphincsVariant::Sha2128F, 48));
+  assert_eq!(config.param_ids[11], (SphincsVariant::Shake256S, 59));
+}
+
+#[test]
+fn test_witness_args_serialization() {
+  // The well-known secp256k1 placeholder witness: WitnessArgs { lock: Some([0; 65]), None, None }
+  let serialized = witness_args(Some(&[0; 65]), None, None);
+  assert_eq!(serialized.len(), 85);
+  assert_eq!(encode(&serialized[..20]), "5500000010000000550000005500000041000000");
+
+  let empty = witness_args(None, None, None);
+  assert_eq!(encode(empty), "10000000100000001000000010000000");
+
+  let serialized = witness_args(Some(&[0xaa]), Some(&[]), Some(&[0xbb, 0xcc]));
+  assert_eq!(
+    encode(serialized),
+    "1f000000100000001500000019000000\
+     01000000aa\
+     00000000\
+     02000000bbcc"
+  );
+}
+
+#[test]
+fn test_standard_bip39_chunks_round_trip() {
+  let entropy: Vec<u8> = (0..96).collect();
+  let chunks = standard_bip39_chunks(&entropy, MnemonicLanguage::English).unwrap();
+  assert_eq!(chunks.len(), 3);
+
+  let mut reassembled = Vec::new();
+  for chunk in &chunks {
+    assert_eq!(chunk.split_whitespace().count(), 24);
+    reassembled.extend(Mnemonic::parse_in(bip39::Language::English, chunk.as_str()).unwrap().to_entropy());
+  }
+  assert_eq!(reassembled, entropy);
+  assert_eq!(detect_mnemonic_language(&chunks.join(" ")), Ok(MnemonicLanguage::English));
+}
+
+#[test]
+fn test_standard_bip39_chunks_in_other_language() {
+  let entropy: Vec<u8> = (0..64).collect();
+  let chunks = standard_bip39_chunks(&entropy, MnemonicLanguage::Spanish).unwrap();
+  let phrase = chunks.join(" ");
+  assert_eq!(detect_mnemonic_language(&phrase), Ok(MnemonicLanguage::Spanish));
+  assert_ne!(chunks, standard_bip39_chunks(&entropy, MnemonicLanguage::English).unwrap());
+}
+
+#[test]
+fn test_standard_bip39_chunks_rejects_partial_chunk() {
+  assert!(standard_bip39_chunks(&[0u8; 48], MnemonicLanguage::English).is_err());
+  assert!(standard_bip39_chunks(&[], MnemonicLanguage::English).is_err());
+}
+
+#[test]
+fn test_corrupted_seed_entropy_is_a_clean_error() {
+  let err = standard_bip39_chunks(&[0u8; 33], MnemonicLanguage::English).unwrap_err();
+  assert_eq!(err, "Corrupted seed: expected 64 or 96 bytes of entropy, got 33");
+  assert!(check_seed_entropy_length(64).is_ok());
+  assert!(check_seed_entropy_length(96).is_ok());
+  assert!(check_seed_entropy_length(128).is_err());
+}
+
+#[test]
+fn test_supported_languages() {
+  let languages = supported_languages();
+  assert_eq!(languages.len(), MnemonicLanguage::ALL.len());
+  assert_eq!(languages[0].id, 0);
+  assert_eq!(languages[0].name, "English");
+  assert_eq!(languages[0].native_name, "English");
+  let spanish = languages.iter().find(|language| language.name == "Spanish").unwrap();
+  assert_eq!(spanish.id, MnemonicLanguage::Spanish as u8);
+  assert_eq!(spanish.native_name, "Español");
+  for (id, language) in languages.iter().enumerate() {
+    assert_eq!(language.id as usize, id);
+  }
+}
+
+#[test]
+fn test_verify_and_check_lock_args() {
+  let message = b"message";
+  let (pub_key, signature) = sha2_128f_signature(message);
+  let lock_args = encode(lock_args_from_pub_key(SphincsVariant::Sha2128F, &LockArgsHashConfig::default(), &pub_key));
+  let check = |pub_key: &[u8], signature: &[u8], lock_args: &str| {
+    verify_and_check_lock_args(SphincsVariant::Sha2128F, pub_key, message, signature, lock_args).unwrap()
+  };
+
+  assert!(check(&pub_key, &signature, &lock_args));
+  assert!(check(&pub_key, &signature, &format!("0x{}", lock_args.to_uppercase())));
+
+  let mut wrong_signature = signature.clone();
+  wrong_signature[100] ^= 1;
+  assert!(!check(&pub_key, &wrong_signature, &lock_args));
+
+  // A valid signature from another key, claimed under this key's address
+  use fips205::traits::{KeyGen, Signer};
+  let (other_pub_key, other_pri_key) = slh_dsa_sha2_128f::KG::keygen_with_seeds(&[4u8; 16], &[5u8; 16], &[6u8; 16]);
+  let other_signature = other_pri_key.try_sign(message, &[], false).unwrap();
+  assert!(!check(&other_pub_key.into_bytes(), &other_signature, &lock_args));
+}
+
+#[test]
+fn test_derivation_log_n_range() {
+  assert!(check_derivation_log_n(4).is_ok());
+  assert!(check_derivation_log_n(10).is_ok());
+  assert!(check_derivation_log_n(20).is_ok());
+  assert!(check_derivation_log_n(3).is_err());
+  assert!(check_derivation_log_n(21).is_err());
+}
+
+#[test]
+fn test_now_ms_falls_back_without_a_clock() {
+  // Native tests have no JS host, like a wasm runtime without Date and performance
+  assert_eq!(now_ms(), 0.0);
+}
+
+#[test]
+fn test_accounts_in_requested_order() {
+  let mut found = HashMap::new();
+  found.insert("aa".to_string(), dummy_account(0, "aa"));
+  found.insert("cc".to_string(), dummy_account(2, "cc"));
+  let requested = vec!["cc".to_string(), "bb".to_string(), "aa".to_string(), "cc".to_string()];
+
+  let accounts = accounts_in_requested_order(&requested, &found);
+  let indices: Vec<Option<u32>> = accounts.iter().map(|account| account.as_ref().map(|account| account.index)).collect();
+  assert_eq!(indices, vec![Some(2), None, Some(0), Some(2)]);
+  assert_eq!(accounts[2].as_ref().unwrap().lock_args, "aa");
+}
+
+#[test]
+fn test_require_accounts_names_missing_lock_args() {
+  let mut found = HashMap::new();
+  found.insert("aa".to_string(), dummy_account(0, "aa"));
+  found.insert("cc".to_string(), dummy_account(2, "cc"));
+
+  let requested = vec!["cc".to_string(), "aa".to_string()];
+  let indices: Vec<u32> = require_accounts(&requested, &found).unwrap().iter().map(|account| account.index).collect();
+  assert_eq!(indices, vec![2, 0]);
+
+  let requested = vec!["aa".to_string(), "bb".to_string(), "dd".to_string()];
+  assert_eq!(require_accounts(&requested, &found).unwrap_err(), "Accounts not found: bb, dd");
+}
+
+#[test]
+fn test_one_of_one_multisig_matches_single_sig_lock_args() {
+  let variant = SphincsVariant::Sha2128F;
+  let config = LockArgsHashConfig::default();
+  let pub_key = vec![7u8; variant.public_key_length()];
+  let multisig = multisig_lock_args(variant, &config, &[pub_key.clone()], REQUIRED_FIRST_N, THRESHOLD).unwrap();
+  assert_eq!(multisig, lock_args_from_pub_key(variant, &config, &pub_key));
+}
+
+#[test]
+fn test_lock_args_preimage_layout() {
+  let variant = SphincsVariant::Sha2128F;
+  let keys = vec![vec![1u8; variant.public_key_length()], vec![2u8; variant.public_key_length()]];
+  let preimage = lock_args_preimage(variant, &keys, 1, 2).unwrap();
+  assert_eq!(preimage.len(), 4 + 2 * (1 + variant.public_key_length()));
+  assert_eq!(preimage[..4], [MULTISIG_RESERVED_FIELD_VALUE, 1, 2, 2]);
+  assert_eq!(preimage[4], variant << 1);
+  assert_eq!(preimage[5..5 + keys[0].len()], keys[0][..]);
+  assert_eq!(preimage[5 + keys[0].len()], variant << 1);
+
+  let mut hasher = Hasher::script_args_hasher();
+  hasher.update(&preimage);
+  let config = LockArgsHashConfig::default();
+  assert_eq!(hasher.hash(), multisig_lock_args(variant, &config, &keys, 1, 2).unwrap());
+  assert!(lock_args_preimage(variant, &keys, 0, 3).is_err());
+}
+
+#[test]
+fn test_multisig_lock_args_depend_on_keys_and_params() {
+  let variant = SphincsVariant::Sha2128F;
+  let config = LockArgsHashConfig::default();
+  let keys = vec![vec![1u8; variant.public_key_length()], vec![2u8; variant.public_key_length()]];
+  let swapped = vec![keys[1].clone(), keys[0].clone()];
+  let two_of_two = multisig_lock_args(variant, &config, &keys, 0, 2).unwrap();
+  assert_ne!(two_of_two, multisig_lock_args(variant, &config, &keys, 0, 1).unwrap());
+  assert_ne!(two_of_two, multisig_lock_args(variant, &config, &keys, 1, 2).unwrap());
+  assert_ne!(two_of_two, multisig_lock_args(variant, &config, &swapped, 0, 2).unwrap());
+}
+
+#[test]
+fn test_multisig_lock_args_rejects_invalid_params() {
+  let variant = SphincsVariant::Sha2128F;
+  let config = LockArgsHashConfig::default();
+  let keys = vec![vec![1u8; variant.public_key_length()], vec![2u8; variant.public_key_length()]];
+  assert!(multisig_lock_args(variant, &config, &keys, 0, 0).is_err());
+  assert!(multisig_lock_args(variant, &config, &keys, 0, 3).is_err());
+  assert!(multisig_lock_args(variant, &config, &keys, 2, 1).is_err());
+  assert!(multisig_lock_args(variant, &config, &[], 0, 1).is_err());
+  let short = vec![keys[0].clone(), vec![2u8; 3]];
+  assert!(multisig_lock_args(variant, &config, &short, 0, 1).unwrap_err().contains("position 1"));
+}
+
+#[test]
+fn test_password_score_sees_through_patterns() {
+  // Both pass the charset rules of `password_checker`
+  assert!(password_score("Password123!").unwrap().score <= 1);
+  let walk = password_score("Qwerty123!@#").unwrap();
+  assert!(walk.score <= 2);
+  assert!(!walk.feedback.suggestions.is_empty() || walk.feedback.warning.is_some());
+
+  assert_eq!(password_score("vL8#qZ2!mW9$xR4@tY7&").unwrap().score, 4);
+  assert!(password_score("").is_err());
+}
+
+#[test]
+fn test_gap_scan_stops_after_gap_limit_unused() {
+  assert!(!gap_scan_done(0, None, 3));
+  assert!(!gap_scan_done(2, None, 3));
+  assert!(gap_scan_done(3, None, 3));
+  // Index 4 used: 5, 6 and 7 must all be unused before stopping
+  assert!(!gap_scan_done(7, Some(4), 3));
+  assert!(gap_scan_done(8, Some(4), 3));
+}
+
+#[test]
+fn test_unlock_locks_after_max_failed_attempts() {
+  let attempts = |count| FailedAttempts { count, last_failure_ms: 1_000_000.0 };
+  assert!(check_unlock_allowed(&attempts(0), 3, 60_000, 1_000_000.0).is_ok());
+  assert!(check_unlock_allowed(&attempts(2), 3, 60_000, 1_000_000.0).is_ok());
+  let err = check_unlock_allowed(&attempts(3), 3, 60_000, 1_030_000.0).unwrap_err();
+  assert!(err.contains("retry in 30 seconds"));
+  // The cooldown ends the lockout; the next wrong password starts another
+  assert!(check_unlock_allowed(&attempts(3), 3, 60_000, 1_060_000.0).is_ok());
+  // 0 disables the lockout
+  assert!(check_unlock_allowed(&attempts(100), 0, 60_000, 1_000_000.0).is_ok());
+}
+
+#[test]
+fn test_lockout_without_cooldown_needs_clearing() {
+  let attempts = FailedAttempts { count: 3, last_failure_ms: 1_000_000.0 };
+  let err = check_unlock_allowed(&attempts, 3, 0, 9_000_000.0).unwrap_err();
+  assert!(err.contains("clear the failed attempts"));
+  // Without a clock the cooldown can't be timed
+  assert!(check_unlock_allowed(&attempts, 3, 60_000, 0.0).is_err());
+  // A clock running backwards doesn't lengthen the lockout beyond the cooldown
+  let err = check_unlock_allowed(&attempts, 3, 60_000, 500_000.0).unwrap_err();
+  assert!(err.contains("retry in 60 seconds"));
+}
+
+#[test]
+fn test_reserved_attempts_lock_and_clear() {
+  let mut attempts = FailedAttempts::default();
+  // Each reservation counts before decrypting, so three concurrent attempts use up a limit of 3
+  for count in 1..=3 {
+    attempts = reserve_unlock_attempt(&attempts, 3, 60_000, 1_000_000.0).unwrap();
+    assert_eq!(attempts, FailedAttempts { count, last_failure_ms: 1_000_000.0 });
+  }
+  assert!(reserve_unlock_attempt(&attempts, 3, 60_000, 1_000_000.0).unwrap_err().contains("3 wrong passwords"));
+
+  // An attempt that didn't test the password is given back
+  let released = release_unlock_attempt(&attempts);
+  assert_eq!(released.count, 2);
+  assert_eq!(reserve_unlock_attempt(&released, 3, 60_000, 1_000_000.0).unwrap().count, 3);
+  assert_eq!(release_unlock_attempt(&FailedAttempts::default()).count, 0);
+
+  // Clearing on success unlocks again
+  assert_eq!(reserve_unlock_attempt(&FailedAttempts::default(), 3, 60_000, 1_000_000.0).unwrap().count, 1);
+}
+
+#[test]
+fn test_password_verifier() {
+  let verifier = make_password_verifier(&[1, 2, 3]).unwrap();
+  assert_eq!(check_password_verifier(&[1, 2, 3], verifier.clone()), Ok(true));
+  assert!(matches!(check_password_verifier(&[1, 2, 4], verifier), Err(DecryptError::WrongPassword(_))));
+}
+
+#[test]
+fn test_seed_payload_does_not_pass_as_verifier() {
+  let password = vec![1, 2, 3];
+  let seed = encrypt(&password, VERIFIER_PLAINTEXT, &seed_aad()).unwrap();
+  assert!(!matches!(check_password_verifier(&password, seed), Ok(true)));
+}
+
+#[test]
+fn test_normalize_account_label() {
+  assert_eq!(normalize_account_label("  Savings "), Ok(Some("Savings".to_string())));
+  assert_eq!(normalize_account_label("   "), Ok(None));
+  assert_eq!(normalize_account_label(&"é".repeat(MAX_ACCOUNT_LABEL_LENGTH)).unwrap().unwrap().chars().count(), MAX_ACCOUNT_LABEL_LENGTH);
+  assert!(normalize_account_label(&"a".repeat(MAX_ACCOUNT_LABEL_LENGTH + 1)).is_err());
+}
+
+#[test]
+fn test_compute_lock_args() {
+  let (pub_key, _) = sha2_128f_signature(b"message");
+  let expected = encode(lock_args_from_pub_key(SphincsVariant::Sha2128F, &LockArgsHashConfig::default(), &pub_key));
+  assert_eq!(compute_lock_args(SphincsVariant::Sha2128F, &pub_key).unwrap(), expected);
+  assert_ne!(compute_lock_args(SphincsVariant::Shake128F, &pub_key).unwrap(), expected);
+
+  let err = compute_lock_args(SphincsVariant::Sha2256S, &pub_key).unwrap_err();
+  assert!(err.contains("Invalid public key length"));
+  assert!(compute_lock_args(SphincsVariant::Sha2128F, &pub_key[1..]).is_err());
+}
+
+fn mock_tx_with_input_locks(lock_args: &[&str]) -> ReprMockTransaction {
+  let out_point = serde_json::json!({ "tx_hash": format!("0x{}", "00".repeat(32)), "index": "0x0" });
+  let inputs: Vec<serde_json::Value> = lock_args
+    .iter()
+    .map(|args| {
+      serde_json::json!({
+        "input": { "since": "0x0", "previous_output": out_point },
+        "output": {
+          "capacity": "0x0",
+          "lock": { "code_hash": LOCK_SCRIPT_CODE_HASH, "hash_type": LOCK_SCRIPT_HASH_TYPE, "args": format!("0x{}", args) },
+          "type": null
+        },
+        "data": "0x",
+        "header": null
+      })
+    })
+    .collect();
+  let tx_inputs: Vec<serde_json::Value> =
+    lock_args.iter().map(|_| serde_json::json!({ "since": "0x0", "previous_output": out_point })).collect();
+  // An empty WitnessArgs per input
+  let witnesses: Vec<&str> = lock_args.iter().map(|_| "0x10000000100000001000000010000000").collect();
+  serde_json::from_value(serde_json::json!({
+    "mock_info": { "inputs": inputs, "cell_deps": [], "header_deps": [] },
+    "tx": {
+      "version": "0x0",
+      "cell_deps": [],
+      "header_deps": [],
+      "inputs": tx_inputs,
+      "outputs": [],
+      "outputs_data": [],
+      "witnesses": witnesses
+    }
+  }))
+  .unwrap()
+}
+
+#[test]
+fn test_input_lock_args_must_match_signing_account() {
+  let own = "11".repeat(32);
+  let foreign = "22".repeat(32);
+  let mock_tx = mock_tx_with_input_locks(&[&own, &foreign]);
+
+  assert!(check_input_lock_args(&mock_tx, 0, &own).is_ok());
+  assert!(check_input_lock_args(&mock_tx, 0, &format!("0x{}", own.to_uppercase())).is_ok());
+  assert_eq!(
+    check_input_lock_args(&mock_tx, 1, &own).unwrap_err(),
+    format!("Input 1 is not locked by account {}", own)
+  );
+  assert_eq!(
+    check_input_lock_args(&mock_tx, 2, &own).unwrap_err(),
+    "Input index 2 out of range: the transaction has 2 inputs"
+  );
+}
+
+#[test]
+fn test_two_accounts_sign_their_own_inputs() {
+  use crate::types::SigningPlanEntry;
+  let vault = crate::KeyVault::with_variant(SphincsVariant::Sha2128F);
+  let seed = [6u8; 64];
+  let mut pri_keys = HashMap::new();
+  let mut lock_args = Vec::new();
+  for index in 0..2 {
+    let (pub_key, pri_key) = vault.derive_sphincs_key(&seed, index, crate::types::DerivationParams::default()).unwrap();
+    let args = encode(vault.get_lock_scrip_arg(&pub_key));
+    pri_keys.insert(args.clone(), pri_key);
+    lock_args.push(args);
+  }
+  let mock_tx: MockTransaction = mock_tx_with_input_locks(&[&lock_args[0], &lock_args[1]]).into();
+  // Plan order needn't follow input order
+  let plan: Vec<SigningPlanEntry> = lock_args
+    .iter()
+    .enumerate()
+    .rev()
+    .map(|(input_index, lock_args)| SigningPlanEntry { input_index, lock_args: lock_args.clone() })
+    .collect();
+
+  let signatures = vault.sign_with_plan(&mock_tx, &plan, &pri_keys).unwrap();
+  assert_eq!(signatures.len(), 2);
+  for (entry, signature) in plan.iter().zip(&signatures) {
+    let message = ckb_tx_message_all(&mock_tx, entry.input_index).unwrap();
+    // Multisig config, then the sign flag, the public key and the raw signature
+    let (pub_key, raw_signature) = signature[5..].split_at(slh_dsa_sha2_128f::PK_LEN);
+    assert!(verify_and_check_lock_args(SphincsVariant::Sha2128F, pub_key, &message, raw_signature, &entry.lock_args).unwrap());
+  }
+  assert_ne!(ckb_tx_message_all(&mock_tx, 0).unwrap(), ckb_tx_message_all(&mock_tx, 1).unwrap());
+
+  let unknown = [SigningPlanEntry { input_index: 0, lock_args: "33".repeat(32) }];
+  assert_eq!(vault.sign_with_plan(&mock_tx, &unknown, &pri_keys).unwrap_err().code(), "ACCOUNT_NOT_FOUND");
+}
@@ -5,7 +5,7 @@ import { scriptToAddress } from "@nervosnetwork/ckb-sdk-utils";
 import { Script, HashType, Address, Transaction, DepType, Cell } from "@ckb-lumos/base";
 import { TransactionSkeletonType, TransactionSkeleton, sealTransaction, addressToScript } from "@ckb-lumos/helpers";
 import { insertWitnessPlaceHolder, prepareSigningEntries, hexToByteArray } from "./utils";
-import __wbg_init, { KeyVault, Util as KeyVaultUtil, SphincsVariant } from "../../key-vault/pkg/key_vault";
+import __wbg_init, { KeyVault, Util as KeyVaultUtil, SphincsVariant, MnemonicLanguage } from "../../key-vault/pkg/key_vault";
 import { LightClient, randomSecretKey, LightClientSetScriptsCommand, CellWithBlockNumAndTxIndex, ScriptStatus } from "ckb-light-client-js";
 import Worker from "worker-loader!../../light-client/status_worker.js";
 import testnetConfig from "../../light-client/network.test.toml";
@@ -408,7 +408,8 @@ export default class QuantumPurse {
   /* Clears all local data of the wallet. */
   public async deleteWallet(): Promise<void> {
     // localStorage.removeItem(QuantumPurse.CLIENT_SECRET);
-    const spxLockArgsList = await this.getAllLockScriptArgs();
+    // A sealed vault refuses to list accounts but can still be cleared
+    const spxLockArgsList = (await KeyVault.is_sealed()) ? [] : await this.getAllLockScriptArgs();
     spxLockArgsList.forEach((lockArgs) => {
       localStorage.removeItem(QuantumPurse.START_BLOCK + "-" + lockArgs);
     });
@@ -418,6 +419,37 @@ export default class QuantumPurse {
     ]);
   }
 
+  /**
+   * Seals the vault: every wallet operation is refused, across reloads, until `unsealWallet` is called.
+   * @throws Error if the key vault is not initialized.
+   */
+  public async sealWallet(): Promise<void> {
+    if (!this.keyVault) throw new Error("KeyVault not initialized!");
+    await this.keyVault.seal();
+  }
+
+  /**
+   * Checks whether a seed phrase is stored, sealed or not.
+   * @returns A promise resolving to true if a wallet exists, false if one must be created or imported.
+   */
+  public static async hasSeedPhrase(): Promise<boolean> {
+    return KeyVault.has_mnemonic();
+  }
+
+  /**
+   * Unseals a sealed vault after checking the password.
+   * @param password - The password to decrypt the seed phrase (will be zeroed out).
+   * @throws Error if the key vault is not initialized or the password is wrong.
+   */
+  public async unsealWallet(password: Uint8Array): Promise<void> {
+    if (!this.keyVault) throw new Error("KeyVault not initialized!");
+    try {
+      await this.keyVault.unseal(password);
+    } finally {
+      password.fill(0);
+    }
+  }
+
   /**
    * Generates a new account derived from the master seed; Set sellective sync filter for the account on the ckb light client;
    * For the first account generation (index 0), sellectice sync filter will replace the previous sync filters.
@@ -466,19 +498,25 @@ export default class QuantumPurse {
   }
 
   /**
-   * Imports a seed phrase and stores it encrypted in IndexedDB, overwriting any existing seed.
+   * Imports a seed phrase and stores it encrypted in IndexedDB.
    * @param seedPhrase - The seed phrase as a Uint8Array (UTF-8 encoded).
    * @param password - The password to encrypt the seed phrase (will be zeroed out).
+   * @param force - Whether to overwrite an existing seed; by default, importing over an existing seed throws. Even when
+   *   forced, importing over stored accounts throws, as they would stay derived from the replaced seed; call
+   *   `deleteWallet` first.
+   * @param language - The BIP39 wordlist of the seed phrase, English if not given.
    * @returns A promise that resolves when the seed is imported.
    * @remark SeedPhrase, password and sensitive data are overwritten with zeros after use.
    */
   public async importSeedPhrase(
     seedPhrase: Uint8Array,
-    password: Uint8Array
+    password: Uint8Array,
+    force: boolean = false,
+    language?: MnemonicLanguage
   ): Promise<void> {
     try {
       if (!this.keyVault) throw new Error("KeyVault not initialized!");
-      await this.keyVault.import_seed_phrase(seedPhrase, password);
+      await this.keyVault.import_seed_phrase(seedPhrase, password, force, language);
     } finally {
       password.fill(0);
       seedPhrase.fill(0);
@@ -488,13 +526,14 @@ export default class QuantumPurse {
   /**
    * Exports the wallet's seed phrase.
    * @param password - The password to decrypt the seed (will be zeroed out).
+   * @param language - The BIP39 wordlist to render the seed phrase with, English if not given.
    * @returns A promise resolving to the seed phrase as a Uint8Array.
    * @throws Error if the master seed is not found or decryption fails.
    * @remark The password is overwritten with zeros after use. Handle the returned seed carefully to avoid leakage.
    */
-  public async exportSeedPhrase(password: Uint8Array): Promise<Uint8Array> {
+  public async exportSeedPhrase(password: Uint8Array, language?: MnemonicLanguage): Promise<Uint8Array> {
     try {
-      const seed = await KeyVault.export_seed_phrase(password);
+      const seed = await KeyVault.export_seed_phrase(password, language);
       return seed;
     } finally {
       password.fill(0);
@@ -549,13 +588,18 @@ export default class QuantumPurse {
    * Generate and settle accounts to the DB.
    * @param password - The password to decrypt the master seed (will be zeroed out).
    * @param count - The number of keys to search for.
+   * @param onProgress - Optional callback invoked with (done, total) after each account is prepared.
    * @remark The password is overwritten with zeros after use.
    * TODO test set sellective sync
    */
-  public async recoverAccounts(password: Uint8Array, count: number): Promise<void> {
+  public async recoverAccounts(
+    password: Uint8Array,
+    count: number,
+    onProgress?: (done: number, total: number) => void
+  ): Promise<void> {
     try {
       if (!this.keyVault) throw new Error("KeyVault not initialized!");
-      const spxLockArgsList = await this.keyVault.recover_accounts(password, count);
+      const spxLockArgsList = await this.keyVault.recover_accounts(password, count, onProgress);
 
       if (!this.client) {
         console.error("Light client not initialized");
@@ -112,9 +112,10 @@ export function insertWitnessPlaceHolder(
 /**
  * https://github.com/xxuejie/rfcs/blob/cighash-all/rfcs/0000-ckb-tx-message-all/0000-ckb-tx-message-all.md
  * @param tx - The transaction skeleton to process.
+ * @param inputIndex - Index of an input locked by the script group being signed.
  * @returns An Uint8Array representing the transaction message all hash.
  */
-function get_ckb_tx_message_all_hash(tx: TransactionSkeletonType): Uint8Array {
+function get_ckb_tx_message_all_hash(tx: TransactionSkeletonType, inputIndex: number): Uint8Array {
   // Prepare mock transaction
   const tx_mock = {
     version: "0x0",
@@ -211,7 +212,7 @@ function get_ckb_tx_message_all_hash(tx: TransactionSkeletonType): Uint8Array {
 
   // Serialize to JSON string and call the rust tool
   const serializedTx = new TextEncoder().encode(JSON.stringify(reprMockTx));
-  return KeyVaultUtil.get_ckb_tx_message_all(new Uint8Array(serializedTx));
+  return KeyVaultUtil.get_ckb_tx_message_all(new Uint8Array(serializedTx), inputIndex);
 }
 
 /**
@@ -232,7 +233,7 @@ export function prepareSigningEntries(
       const signingEntry = {
         type: "witness_args_lock",
         index: i,
-        message: byteArrayToHex(get_ckb_tx_message_all_hash(txSkeleton)),
+        message: byteArrayToHex(get_ckb_tx_message_all_hash(txSkeleton, i)),
       };
       signingEntries = signingEntries.push(signingEntry);
     }
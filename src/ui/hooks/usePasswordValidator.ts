@@ -1,5 +1,6 @@
 import QuantumPurse from "../../core/quantum_purse";
 import { utf8ToBytes } from "../../core/utils";
+import { formatError, keyVaultErrorCode } from "../utils/methods";
 
 const usePasswordValidator = () => {
   const entropyValidator = (password: string) => {
@@ -11,7 +12,11 @@ const usePasswordValidator = () => {
       QuantumPurse.checkPassword(passwordBytes);
       return Promise.resolve();
     } catch (error) {
-      return Promise.reject(new Error(error as string));
+      // An invalid input error tells which character class the password lacks
+      if (keyVaultErrorCode(error) === "INVALID_INPUT") {
+        return Promise.reject(new Error((error as Error).message));
+      }
+      return Promise.reject(new Error(formatError(error)));
     }
   };
 
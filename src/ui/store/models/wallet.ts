@@ -283,7 +283,10 @@ export const wallet = createModel<RootModel>()({
     },
     async importWallet({ srp, password }) {
       try {
-        await quantum.importSeedPhrase(utf8ToBytes(srp), utf8ToBytes(password));
+        // Importing replaces whatever an earlier, unfinished wallet setup stored. The key vault refuses to import
+        // over stored accounts, as they'd stay derived from the replaced seed, so clear them first
+        await quantum.deleteWallet();
+        await quantum.importSeedPhrase(utf8ToBytes(srp), utf8ToBytes(password), true);
 
         let accountsLength = 1;
 
@@ -27,10 +27,15 @@ export const formatBalance = (balance: string | bigint | undefined) => {
     .replace(/\B(?=(\d{3})+(?!\d))/g, ",")} ${CKB_UNIT}`;
 };
 
+/* The stable code of a key vault error, see key-vault/src/errors/mod.rs; undefined for any other error. */
+export const keyVaultErrorCode = (error: any): string | undefined => {
+  return error instanceof Error ? (error as Error & { code?: string }).code : undefined;
+};
+
 export const formatError = (error: any) => {
   let description = "Something went wrong";
 
-  if (String(error) === "Decryption error: Error") {
+  if (keyVaultErrorCode(error) === "WRONG_PASSWORD") {
     description = "Invalid password";
   } else if (String(error) === "Error: Insufficient balance!") {
     description = "Insufficient balance";
@@ -0,0 +1,56 @@
+import { expect } from "chai";
+import { utf8ToBytes } from "../src/core/utils";
+import { formatError, keyVaultErrorCode } from "../src/ui/utils/methods";
+import usePasswordValidator from "../src/ui/hooks/usePasswordValidator";
+import __wbg_init, { KeyVault, SphincsVariant } from "../key-vault/pkg/key_vault";
+
+describe("UI Error Formatting", () => {
+  let keyVault: KeyVault;
+  let passwordStr: string = "my password is easy to crack. D0n't use this!";
+  let seedPhrase48: string =
+    "uncover behind cargo satoshi tail answer liar success snap explain trigger brush cube mountain friend damp empty nose plastic huge pave enter wolf hazard miracle helmet trend connect bench battle diagram person uniform bike bottom negative glove vague diagram never float peace pride ivory banner say safe mesh";
+
+  before(async () => {
+    // Manually initialize Wasm with Karma-served file
+    const wasmResponse = await fetch("/base/key-vault/pkg/key_vault_bg.wasm");
+    const wasmBuffer = await wasmResponse.arrayBuffer();
+    await __wbg_init(wasmBuffer);
+    keyVault = new KeyVault(SphincsVariant.Shake128F);
+    await KeyVault.clear_database();
+    await keyVault.import_seed_phrase(utf8ToBytes(seedPhrase48), utf8ToBytes(passwordStr), false, undefined);
+  });
+
+  after(async () => {
+    await KeyVault.clear_database();
+  });
+
+  it("Should report a wrong password by its error code", async () => {
+    let error: any;
+    try {
+      await KeyVault.export_seed_phrase(utf8ToBytes("Not the password 1!"), undefined);
+    } catch (e) {
+      error = e;
+    }
+    expect(keyVaultErrorCode(error)).to.eq("WRONG_PASSWORD");
+    expect(formatError(error)).to.eq("Invalid password");
+  });
+
+  it("Should leave errors without a code to their own handling", () => {
+    expect(keyVaultErrorCode(new Error("Insufficient balance!"))).to.be.undefined;
+    expect(formatError(new Error("Insufficient balance!"))).to.eq("Insufficient balance");
+    expect(formatError("Decryption error: Error")).to.eq("Something went wrong");
+  });
+
+  it("Should reject a weak password with the reason the key vault gives", async () => {
+    const { rules } = usePasswordValidator();
+    let error: any;
+    try {
+      await (rules[1] as any).validator(undefined, "all lowercase 1!");
+    } catch (e) {
+      error = e;
+    }
+    expect(error).to.be.instanceOf(Error);
+    expect(error.message).to.eq("Password must contain at least one uppercase letter!");
+    await (rules[1] as any).validator(undefined, passwordStr);
+  });
+});
@@ -0,0 +1,53 @@
+import { expect } from "chai";
+import { utf8ToBytes } from "../src/core/utils";
+import __wbg_init, { KeyVault, SphincsVariant } from "../key-vault/pkg/key_vault";
+
+describe("Lock Args Paging", () => {
+  let keyVault: KeyVault;
+  let passwordStr: string = "my password is easy to crack. D0n't use this!";
+  let seedPhrase48: string =
+    "uncover behind cargo satoshi tail answer liar success snap explain trigger brush cube mountain friend damp empty nose plastic huge pave enter wolf hazard miracle helmet trend connect bench battle diagram person uniform bike bottom negative glove vague diagram never float peace pride ivory banner say safe mesh";
+  let remaining: string[];
+
+  before(async () => {
+    // Manually initialize Wasm with Karma-served file
+    const wasmResponse = await fetch("/base/key-vault/pkg/key_vault_bg.wasm");
+    const wasmBuffer = await wasmResponse.arrayBuffer();
+    await __wbg_init(wasmBuffer);
+    keyVault = new KeyVault(SphincsVariant.Shake128F);
+    await KeyVault.clear_database();
+    await keyVault.import_seed_phrase(utf8ToBytes(seedPhrase48), utf8ToBytes(passwordStr), true, undefined);
+    const recovered: string[] = await keyVault.recover_accounts(utf8ToBytes(passwordStr), 5, undefined);
+
+    // Leave gaps at indices 1 and 3
+    await KeyVault.delete_account(recovered[1]);
+    await KeyVault.delete_account(recovered[3]);
+    remaining = [recovered[0], recovered[2], recovered[4]];
+  });
+
+  after(async () => {
+    await KeyVault.clear_database();
+  });
+
+  it("Should page through a gapped index set in index order", async () => {
+    expect(await KeyVault.get_lock_args_page(0, 2)).to.deep.equal([remaining[0], remaining[1]]);
+    expect(await KeyVault.get_lock_args_page(2, 2)).to.deep.equal([remaining[2]]);
+    expect(await KeyVault.get_lock_args_page(1, 1)).to.deep.equal([remaining[1]]);
+  });
+
+  it("Should return an empty page past the last account", async () => {
+    expect(await KeyVault.get_lock_args_page(3, 2)).to.deep.equal([]);
+    expect(await KeyVault.get_lock_args_page(100, 10)).to.deep.equal([]);
+  });
+
+  it("Should list the same lock args page by page as all at once", async () => {
+    const paged: string[] = [];
+    for (let offset = 0; ; offset += 2) {
+      const page: string[] = await KeyVault.get_lock_args_page(offset, 2);
+      if (page.length === 0) break;
+      paged.push(...page);
+    }
+    expect(paged).to.deep.equal(remaining);
+    expect(await KeyVault.get_all_sphincs_lock_args()).to.deep.equal(remaining);
+  });
+});
@@ -33,9 +33,11 @@ describe("Quantum Purse Basics", () => {
   });
 
   it("Should export the exact seed imported", async () => {
+    await wallet.deleteWallet();
     await wallet.importSeedPhrase(
       utf8ToBytes(seedPhrase48),
-      utf8ToBytes(passwordStr)
+      utf8ToBytes(passwordStr),
+      true
     );
     const exportedSeedPhrase = await wallet.exportSeedPhrase(
       utf8ToBytes(passwordStr)
@@ -61,9 +63,11 @@ describe("Quantum Purse Basics", () => {
   });
 
   it("Should zeroize seed phrase and password after importing a new seed phrase", async () => {
+    // Importing over the accounts of earlier tests is refused even when forced
+    await wallet.deleteWallet();
     const seedPhraseHandler = utf8ToBytes(seedPhrase48);
     const passwordStrHandler = utf8ToBytes(passwordStr);
-    await wallet.importSeedPhrase(seedPhraseHandler, passwordStrHandler);
+    await wallet.importSeedPhrase(seedPhraseHandler, passwordStrHandler, true);
     expect(seedPhraseHandler.every((byte) => byte === 0)).to.be.true;
     expect(passwordStrHandler.every((byte) => byte === 0)).to.be.true;
   });
@@ -78,9 +82,10 @@ describe("Quantum Purse Basics", () => {
   });
 
   it("Should zeroize password after signing a transaction", async () => {
+    await wallet.deleteWallet();
     let passwordStrHandler = utf8ToBytes(passwordStr);
     const seedPhraseHandler = utf8ToBytes(seedPhrase48);
-    await wallet.importSeedPhrase(seedPhraseHandler, passwordStrHandler);
+    await wallet.importSeedPhrase(seedPhraseHandler, passwordStrHandler, true);
 
     // Mocking lightClient related function
     sinon.stub(wallet as any, "setSellectiveSyncFilterInternal").resolves();
@@ -109,9 +114,11 @@ describe("Quantum Purse Basics", () => {
   });
 
   it("Should zeroize password after recovering accounts", async () => {
+    // Accounts of earlier tests belong to other seeds, which recovery refuses to mix with
+    await wallet.deleteWallet();
     const seedPhraseHandler = utf8ToBytes(seedPhrase72);
     let passwordStrHandler = utf8ToBytes(passwordStr);
-    await wallet.importSeedPhrase(seedPhraseHandler, passwordStrHandler);
+    await wallet.importSeedPhrase(seedPhraseHandler, passwordStrHandler, true);
 
     // Mock `this.client`
     const mockClient = {
@@ -127,12 +134,98 @@ describe("Quantum Purse Basics", () => {
     expect(passwordStrHandler.every((byte) => byte === 0)).to.be.true;
   });
 
+  it("Should report recovery progress and store nothing if the callback throws", async () => {
+    await wallet.deleteWallet();
+    await wallet.importSeedPhrase(utf8ToBytes(seedPhrase48), utf8ToBytes(passwordStr));
+    (wallet as any).client = {
+      getTransactions: sinon.stub().resolves({ transactions: [] }),
+      setScripts: sinon.stub().resolves(),
+    };
+
+    const progress: Array<[number, number]> = [];
+    await wallet.recoverAccounts(utf8ToBytes(passwordStr), 2, (done, total) =>
+      progress.push([done, total])
+    );
+    expect(progress).to.deep.equal([[1, 2], [2, 2]]);
+
+    await wallet.deleteWallet();
+    await wallet.importSeedPhrase(utf8ToBytes(seedPhrase48), utf8ToBytes(passwordStr));
+    try {
+      await wallet.recoverAccounts(utf8ToBytes(passwordStr), 2, (done) => {
+        if (done === 2) throw new Error("cancelled");
+      });
+      expect.fail("Expected an error to be thrown");
+    } catch (error) {
+      expect(error).to.have.property("message").that.includes("cancelled");
+    }
+    expect(await wallet.getAllLockScriptArgs()).to.deep.equal([]);
+  });
+
+  it("Should yield to the event loop between recovered accounts", async () => {
+    await wallet.deleteWallet();
+    await wallet.importSeedPhrase(utf8ToBytes(seedPhrase48), utf8ToBytes(passwordStr));
+    (wallet as any).client = {
+      getTransactions: sinon.stub().resolves({ transactions: [] }),
+      setScripts: sinon.stub().resolves(),
+    };
+
+    // A timer queued when the first account is reported fires before the second one is
+    let timerFired = false;
+    const firedAt: boolean[] = [];
+    await wallet.recoverAccounts(utf8ToBytes(passwordStr), 2, (done) => {
+      firedAt.push(timerFired);
+      if (done === 1) setTimeout(() => (timerFired = true), 0);
+    });
+    expect(firedAt).to.deep.equal([false, true]);
+  });
+
+  it("Should report whether a seed phrase is stored", async () => {
+    await wallet.deleteWallet();
+    expect(await QuantumPurse.hasSeedPhrase()).to.be.false;
+    await wallet.importSeedPhrase(utf8ToBytes(seedPhrase48), utf8ToBytes(passwordStr));
+    expect(await QuantumPurse.hasSeedPhrase()).to.be.true;
+  });
+
   it("Should zeroize password after checking password", async () => {
     let passwordStrHandler = utf8ToBytes(passwordStr);
     await QuantumPurse.checkPassword(passwordStrHandler);
     expect(passwordStrHandler.every((byte) => byte === 0)).to.be.true;
   });
 
+  it("Should refuse to import over an existing seed phrase unless forced", async () => {
+    await wallet.deleteWallet();
+    await wallet.importSeedPhrase(utf8ToBytes(seedPhrase48), utf8ToBytes(passwordStr));
+    try {
+      await wallet.importSeedPhrase(utf8ToBytes(seedPhrase72), utf8ToBytes(passwordStr));
+      expect.fail("Expected an error to be thrown");
+    } catch (error) {
+      expect(error).to.have.property("code", "INVALID_INPUT");
+      expect((error as Error).message).to.contain("Mnemonic phrase already exists");
+    }
+    expect(bytesToUtf8(await wallet.exportSeedPhrase(utf8ToBytes(passwordStr)))).to.eq(seedPhrase48);
+  });
+
+  it("Should block operations while sealed and resume after unseal", async () => {
+    await wallet.importSeedPhrase(utf8ToBytes(seedPhrase48), utf8ToBytes(passwordStr), true);
+    await wallet.sealWallet();
+    try {
+      await wallet.getAllLockScriptArgs();
+      expect.fail("Expected an error to be thrown");
+    } catch (error) {
+      expect(error).to.have.property("code", "VAULT_SEALED");
+    }
+    try {
+      await wallet.exportSeedPhrase(utf8ToBytes(passwordStr));
+      expect.fail("Expected an error to be thrown");
+    } catch (error) {
+      expect(error).to.have.property("code", "VAULT_SEALED");
+    }
+
+    await wallet.unsealWallet(utf8ToBytes(passwordStr));
+    const exportedSeedPhrase = await wallet.exportSeedPhrase(utf8ToBytes(passwordStr));
+    expect(bytesToUtf8(exportedSeedPhrase)).to.eq(seedPhrase48);
+  });
+
   it("Should throw when importing seedphrase that's of different length than 48/72", async () => {
     const seedPhraseHandler = utf8ToBytes(seedPhraseInvalidLength24);
     const passwordStrHandler = utf8ToBytes(passwordStr);
@@ -141,7 +234,8 @@ describe("Quantum Purse Basics", () => {
       expect.fail("Expected an error to be thrown");
     } catch (error) {
       console.error(error)
-      expect(error).to.equal('Mnemonic must have 48 or 72 words');
+      expect(error).to.have.property("code", "INVALID_INPUT");
+      expect(error).to.have.property("message", "Mnemonic must have 48 or 72 words");
     }
   });
 
@@ -153,7 +247,7 @@ describe("Quantum Purse Basics", () => {
       expect.fail("Expected an error to be thrown");
     } catch (error) {
       console.error(error)
-      expect(error).to.equal("Invalid mnemonic chunk: the mnemonic has an invalid checksum");
+      expect((error as Error).message).to.contain("Invalid English mnemonic chunk");
     }
   });
 
@@ -165,7 +259,7 @@ describe("Quantum Purse Basics", () => {
       expect.fail("Expected an error to be thrown");
     } catch (error) {
       console.error(error)
-      expect(error).to.contain("Invalid mnemonic chunk: mnemonic contains an unknown word");
+      expect((error as Error).message).to.contain("Invalid English mnemonic chunk 3: mnemonic contains an unknown word");
     }
   });
 
@@ -178,7 +272,7 @@ describe("Quantum Purse Basics", () => {
       expect.fail("Expected an error to be thrown");
     } catch (error) {
       console.error(error)
-      expect(error).to.contain("Insufficient entropy: the input seed phrase got");
+      expect((error as Error).message).to.contain("Insufficient entropy: the input seed phrase got");
     }
   });
 });
\ No newline at end of file
@@ -0,0 +1,68 @@
+import { expect } from "chai";
+import QuantumPurse, { SphincsVariant } from "../src/core/quantum_purse";
+import sinon from "sinon";
+import { utf8ToBytes } from "../src/core/utils";
+import __wbg_init from "../key-vault/pkg/key_vault";
+
+describe("Signing Worker", () => {
+  let wallet: QuantumPurse;
+  let worker: Worker;
+  let lockArgs: string;
+  let passwordStr: string = "my password is easy to crack. D0n't use this!";
+
+  before(async () => {
+    // Manually initialize Wasm with Karma-served file
+    const wasmResponse = await fetch("/base/key-vault/pkg/key_vault_bg.wasm");
+    const wasmBuffer = await wasmResponse.arrayBuffer();
+    await __wbg_init(wasmBuffer);
+    wallet = await QuantumPurse.getInstance();
+    wallet.initKeyVault(SphincsVariant.Shake128F);
+    await wallet.deleteWallet();
+    await wallet.initSeedPhrase(utf8ToBytes(passwordStr));
+    // Mocking lightClient related function
+    sinon.stub(wallet as any, "setSellectiveSyncFilterInternal").resolves();
+    lockArgs = await wallet.genAccount(utf8ToBytes(passwordStr));
+    sinon.restore();
+
+    worker = new Worker(new URL("../key-vault/signing_worker.js", import.meta.url), { type: "module" });
+  });
+
+  after(() => {
+    worker.terminate();
+  });
+
+  const signInWorker = (requestId: number, password: Uint8Array): Promise<any> =>
+    new Promise((resolve) => {
+      const onMessage = (event: MessageEvent) => {
+        if (event.data.requestId !== requestId) return;
+        worker.removeEventListener("message", onMessage);
+        resolve(event.data);
+      };
+      worker.addEventListener("message", onMessage);
+      const message = new Uint8Array(32).fill(7);
+      worker.postMessage(
+        { requestId, variant: SphincsVariant.Shake128F, password, lockArgs, message },
+        [password.buffer]
+      );
+    });
+
+  it("Should sign in the worker while the main thread keeps running", async () => {
+    // Scrypt and signing take well over a timer tick; on the main thread they would keep this timer from firing
+    let ticks = 0;
+    const timer = setInterval(() => ticks++, 0);
+    const response = await signInWorker(1, utf8ToBytes(passwordStr));
+    clearInterval(timer);
+
+    expect(response.error).to.be.undefined;
+    expect(response.signature).to.be.instanceOf(Uint8Array);
+    expect(response.signature.length).to.be.greaterThan(0);
+    expect(ticks).to.be.greaterThan(0);
+  });
+
+  it("Should post the error message and code of a failed signing", async () => {
+    const response = await signInWorker(2, utf8ToBytes("not the password"));
+    expect(response.signature).to.be.undefined;
+    expect(response.error.code).to.eq("WRONG_PASSWORD");
+    expect(response.error.message).to.be.a("string").that.is.not.empty;
+  });
+});